@@ -7,16 +7,23 @@ use std::ops::{Deref, DerefMut};
 use crate::actor_id::{to_actor_id, AMactorId};
 use crate::byte_span::{to_str, AMbyteSpan};
 use crate::cursor::{to_cursor, AMcursor};
+use crate::index::AMindex;
+use crate::item::AMitem;
 use crate::items::AMitems;
 use crate::obj::{to_obj_id, AMobjId, AMobjType};
 use crate::result::{to_result, AMresult};
 use crate::sync::{to_sync_message, AMsyncMessage, AMsyncState};
 
+pub mod block;
 pub mod list;
 pub mod map;
 pub mod mark;
+pub mod observer;
+pub mod options;
 pub mod utils;
 
+use crate::doc::observer::{notify, Observer};
+use crate::doc::options::{AMloadOptions, AMsaveOptions};
 use crate::doc::utils::{clamp, to_doc, to_doc_mut, to_items};
 
 macro_rules! to_sync_state_mut {
@@ -32,22 +39,89 @@ macro_rules! to_sync_state_mut {
 /// \struct AMdoc
 /// \installed_headerfile
 /// \brief A JSON-like CRDT.
-#[derive(Clone)]
-pub struct AMdoc(am::AutoCommit);
+pub struct AMdoc {
+    body: am::AutoCommit,
+    observer: Option<Observer>,
+}
 
 impl AMdoc {
     pub fn new(auto_commit: am::AutoCommit) -> Self {
-        Self(auto_commit)
+        Self {
+            body: auto_commit,
+            observer: None,
+        }
     }
 
     fn is_equal_to(&mut self, other: &mut Self) -> bool {
         self.document().get_heads() == other.document().get_heads()
     }
+
+    /// Applies `f` to the document and gets the patches describing the changes it made
+    fn log_patches<T, F>(&mut self, f: F) -> Result<Vec<am::Patch>, am::AutomergeError>
+    where
+        F: FnOnce(&mut am::AutoCommit, &mut am::PatchLog) -> Result<T, am::AutomergeError>,
+    {
+        let mut patch_log = am::PatchLog::active(self.text_encoding().into());
+        f(&mut self.body, &mut patch_log)?;
+        Ok(self.make_patches(&mut patch_log))
+    }
+
+    /// Gets the entries of an object as items nesting the entries of the objects within it
+    fn hydrate_items(
+        &self,
+        obj_id: &am::ObjId,
+        heads: Option<&[am::ChangeHash]>,
+    ) -> Result<Vec<AMitem>, am::AutomergeError> {
+        let props: Vec<am::Prop> = match self.object_type(obj_id)? {
+            am::ObjType::Map | am::ObjType::Table => match heads {
+                None => self.keys(obj_id).map(am::Prop::from).collect(),
+                Some(heads) => self.keys_at(obj_id, heads).map(am::Prop::from).collect(),
+            },
+            am::ObjType::List => {
+                let len = match heads {
+                    None => self.length(obj_id),
+                    Some(heads) => self.length_at(obj_id, heads),
+                };
+                (0..len).map(am::Prop::from).collect()
+            }
+            am::ObjType::Text => {
+                let text = match heads {
+                    None => self.text(obj_id)?,
+                    Some(heads) => self.text_at(obj_id, heads)?,
+                };
+                return Ok(vec![text.into()]);
+            }
+        };
+        let mut items = Vec::with_capacity(props.len());
+        for prop in props {
+            let entry = match heads {
+                None => self.get(obj_id, prop.clone())?,
+                Some(heads) => self.get_at(obj_id, prop.clone(), heads)?,
+            };
+            if let Some((value, id)) = entry {
+                let children = match value {
+                    am::Value::Object(_) => self.hydrate_items(&id, heads)?,
+                    am::Value::Scalar(_) => Vec::new(),
+                };
+                let index = match prop {
+                    am::Prop::Map(key) => AMindex::Key(key.into()),
+                    am::Prop::Seq(pos) => AMindex::Pos(pos),
+                };
+                items.push(AMitem::nested(
+                    Some(index),
+                    id,
+                    value.into_owned().into(),
+                    children,
+                ));
+            }
+        }
+        Ok(items)
+    }
 }
 
 impl AsRef<am::AutoCommit> for AMdoc {
     fn as_ref(&self) -> &am::AutoCommit {
-        &self.0
+        &self.body
     }
 }
 
@@ -55,13 +129,13 @@ impl Deref for AMdoc {
     type Target = am::AutoCommit;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.body
     }
 }
 
 impl DerefMut for AMdoc {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.body
     }
 }
 
@@ -85,10 +159,12 @@ impl DerefMut for AMdoc {
 pub unsafe extern "C" fn AMapplyChanges(doc: *mut AMdoc, items: *const AMitems) -> *mut AMresult {
     let doc = to_doc_mut!(doc);
     let items = to_items!(items);
-    match Vec::<am::Change>::try_from(items) {
+    let result = match Vec::<am::Change>::try_from(items) {
         Ok(changes) => to_result(doc.apply_changes(changes)),
         Err(e) => AMresult::error(&e.to_string()).into(),
-    }
+    };
+    notify(doc);
+    result
 }
 
 /// \memberof AMdoc
@@ -161,7 +237,77 @@ pub unsafe extern "C" fn AMcommit(
     if let Some(timestamp) = timestamp.as_ref() {
         options.set_time(*timestamp);
     }
-    to_result(doc.commit_with(options))
+    let result = to_result(doc.commit_with(options));
+    notify(doc);
+    result
+}
+
+/// \memberof AMdoc
+/// \brief Gets the patches describing the differences between two points in a
+///        document's history.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \param[in] before A pointer to an `AMitems` struct with
+///                   `AM_VAL_TYPE_CHANGE_HASH` items to select the historical
+///                   point to start from or `NULL` to start from an empty
+///                   document.
+/// \param[in] after A pointer to an `AMitems` struct with
+///                  `AM_VAL_TYPE_CHANGE_HASH` items to select the historical
+///                  point to end at or `NULL` to end at its current point.
+/// \return A pointer to an `AMresult` struct with `AM_VAL_TYPE_PATCH` items.
+/// \pre \p doc `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+/// before must be a valid pointer to an AMitems or std::ptr::null()
+/// after must be a valid pointer to an AMitems or std::ptr::null()
+#[no_mangle]
+pub unsafe extern "C" fn AMdiff(
+    doc: *mut AMdoc,
+    before: *const AMitems,
+    after: *const AMitems,
+) -> *mut AMresult {
+    let doc = to_doc_mut!(doc);
+    let before = match before.as_ref() {
+        None => Vec::new(),
+        Some(before) => match <Vec<am::ChangeHash>>::try_from(before) {
+            Ok(before) => before,
+            Err(e) => return AMresult::error(&e.to_string()).into(),
+        },
+    };
+    let after = match after.as_ref() {
+        None => doc.get_heads(),
+        Some(after) => match <Vec<am::ChangeHash>>::try_from(after) {
+            Ok(after) => after,
+            Err(e) => return AMresult::error(&e.to_string()).into(),
+        },
+    };
+    to_result(doc.diff(&before, &after))
+}
+
+/// \memberof AMdoc
+/// \brief Gets the patches describing the changes made to a document since
+///        the last call to this function.
+///
+/// \details The first call gets the patches describing the document's entire
+///          current state.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \return A pointer to an `AMresult` struct with `AM_VAL_TYPE_PATCH` items.
+/// \pre \p doc `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+#[no_mangle]
+pub unsafe extern "C" fn AMdiffIncremental(doc: *mut AMdoc) -> *mut AMresult {
+    let doc = to_doc_mut!(doc);
+    to_result(doc.diff_incremental())
 }
 
 /// \memberof AMdoc
@@ -204,7 +350,9 @@ pub unsafe extern "C" fn AMemptyChange(
     if let Some(timestamp) = timestamp.as_ref() {
         options.set_time(*timestamp);
     }
-    to_result(doc.empty_change(options))
+    let result = to_result(doc.empty_change(options));
+    notify(doc);
+    result
 }
 
 /// \memberof AMdoc
@@ -542,6 +690,64 @@ pub unsafe extern "C" fn AMgetLastLocalChange(doc: *mut AMdoc) -> *mut AMresult
     to_result(doc.get_last_local_change())
 }
 
+/// \memberof AMdoc
+/// \brief Gets the current or historical content of an object as a whole.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \param[in] obj_id A pointer to an `AMobjId` struct or `AM_ROOT`.
+/// \param[in] heads A pointer to an `AMitems` struct with `AM_VAL_TYPE_CHANGE_HASH`
+///                  items to select historical content or `NULL` to select
+///                  its current content.
+/// \param[in] json `true` to get the content as a JSON string, `false` to get
+///                 it as a tree of items.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_STR` item
+///         holding the JSON representation of the object (with the keys of
+///         its maps sorted) if \p json is `true`. Otherwise, an item for each
+///         entry of a map object or element of a list object whose index is
+///         the entry's key or the element's position and whose nested object
+///         content can be gotten with `AMitemChildren()`, or an
+///         `AM_VAL_TYPE_STR` item for the string of a text object.
+/// \pre \p doc `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+/// obj_id must be a valid pointer to an AMobjId or std::ptr::null()
+/// heads must be a valid pointer to an AMitems or std::ptr::null()
+#[no_mangle]
+pub unsafe extern "C" fn AMhydrate(
+    doc: *const AMdoc,
+    obj_id: *const AMobjId,
+    heads: *const AMitems,
+    json: bool,
+) -> *mut AMresult {
+    let doc = to_doc!(doc);
+    let obj_id = to_obj_id!(obj_id);
+    let heads = match heads.as_ref() {
+        None => None,
+        Some(heads) => match <Vec<am::ChangeHash>>::try_from(heads) {
+            Ok(heads) => Some(heads),
+            Err(e) => return AMresult::error(&e.to_string()).into(),
+        },
+    };
+    if !json {
+        return match doc.hydrate_items(obj_id, heads.as_deref()) {
+            Ok(items) => AMresult::items(items).into(),
+            Err(e) => AMresult::error(&e.to_string()).into(),
+        };
+    }
+    // Going through `serde_json::Value` sorts the keys of the maps.
+    match doc.hydrate(obj_id, heads.as_deref()) {
+        Ok(value) => match serde_json::to_value(value) {
+            Ok(json) => AMresult::item(json.to_string().into()).into(),
+            Err(e) => AMresult::error(&e.to_string()).into(),
+        },
+        Err(e) => AMresult::error(&e.to_string()).into(),
+    }
+}
+
 /// \memberof AMdoc
 /// \brief Gets the current or historical keys of a map object.
 ///
@@ -600,6 +806,43 @@ pub unsafe extern "C" fn AMload(src: *const u8, count: usize) -> *mut AMresult {
     to_result(am::AutoCommit::load(data))
 }
 
+/// \memberof AMdoc
+/// \brief Allocates storage for a document and initializes it with the compact
+///        form of an incremental save according to the given options.
+///
+/// \param[in] src A pointer to an array of bytes.
+/// \param[in] count The count of bytes to load from the array pointed to by
+///                  \p src.
+/// \param[in] options A pointer to an `AMloadOptions` struct or `NULL` for the
+///                    default options.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_DOC` item.
+/// \pre \p src `!= NULL`
+/// \pre `sizeof(`\p src `) > 0`
+/// \pre \p count `<= sizeof(`\p src `)`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// src must be a byte array of length `>= count`
+/// options must be a valid pointer to an AMloadOptions or std::ptr::null()
+#[no_mangle]
+pub unsafe extern "C" fn AMloadWith(
+    src: *const u8,
+    count: usize,
+    options: *const AMloadOptions,
+) -> *mut AMresult {
+    let data = std::slice::from_raw_parts(src, count);
+    let options = match options.as_ref() {
+        Some(options) => match am::LoadOptions::try_from(options) {
+            Ok(options) => options,
+            Err(e) => return AMresult::error(&e).into(),
+        },
+        None => am::LoadOptions::new(),
+    };
+    to_result(am::AutoCommit::load_with_options(data, options))
+}
+
 /// \memberof AMdoc
 /// \brief Loads the compact form of an incremental save into a document.
 ///
@@ -627,7 +870,44 @@ pub unsafe extern "C" fn AMloadIncremental(
 ) -> *mut AMresult {
     let doc = to_doc_mut!(doc);
     let data = std::slice::from_raw_parts(src, count);
-    to_result(doc.load_incremental(data))
+    let result = to_result(doc.load_incremental(data));
+    notify(doc);
+    result
+}
+
+/// \memberof AMdoc
+/// \brief Loads the compact form of an incremental save into a document and
+///        gets the patches describing the changes it made.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \param[in] src A pointer to an array of bytes.
+/// \param[in] count The count of bytes to load from the array pointed to by
+///                  \p src.
+/// \return A pointer to an `AMresult` struct with `AM_VAL_TYPE_PATCH` items.
+/// \pre \p doc `!= NULL`
+/// \pre \p src `!= NULL`
+/// \pre `sizeof(`\p src `) > 0`
+/// \pre \p count `<= sizeof(`\p src `)`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+/// src must be a byte array of length `>= count`
+#[no_mangle]
+pub unsafe extern "C" fn AMloadIncrementalLogPatches(
+    doc: *mut AMdoc,
+    src: *const u8,
+    count: usize,
+) -> *mut AMresult {
+    let doc = to_doc_mut!(doc);
+    let data = std::slice::from_raw_parts(src, count);
+    let result = to_result(
+        doc.log_patches(|doc, patch_log| doc.load_incremental_log_patches(data, patch_log)),
+    );
+    notify(doc);
+    result
 }
 
 /// \memberof AMdoc
@@ -649,7 +929,36 @@ pub unsafe extern "C" fn AMloadIncremental(
 #[no_mangle]
 pub unsafe extern "C" fn AMmerge(dest: *mut AMdoc, src: *mut AMdoc) -> *mut AMresult {
     let dest = to_doc_mut!(dest);
-    to_result(dest.merge(to_doc_mut!(src)))
+    let result = to_result(dest.merge(to_doc_mut!(src)));
+    notify(dest);
+    result
+}
+
+/// \memberof AMdoc
+/// \brief Applies all of the changes in \p src which are not in \p dest to
+///        \p dest and gets the patches describing the changes made to
+///        \p dest.
+///
+/// \param[in] dest A pointer to an `AMdoc` struct.
+/// \param[in] src A pointer to an `AMdoc` struct.
+/// \return A pointer to an `AMresult` struct with `AM_VAL_TYPE_PATCH` items.
+/// \pre \p dest `!= NULL`
+/// \pre \p src `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// dest must be a valid pointer to an AMdoc
+/// src must be a valid pointer to an AMdoc
+#[no_mangle]
+pub unsafe extern "C" fn AMmergeAndLogPatches(dest: *mut AMdoc, src: *mut AMdoc) -> *mut AMresult {
+    let dest = to_doc_mut!(dest);
+    let src = to_doc_mut!(src);
+    let result =
+        to_result(dest.log_patches(|dest, patch_log| dest.merge_and_log_patches(src, patch_log)));
+    notify(dest);
+    result
 }
 
 /// \memberof AMdoc
@@ -796,10 +1105,52 @@ pub unsafe extern "C" fn AMreceiveSyncMessage(
     let doc = to_doc_mut!(doc);
     let sync_state = to_sync_state_mut!(sync_state);
     let sync_message = to_sync_message!(sync_message);
-    to_result(
+    let result = to_result(
         doc.sync()
             .receive_sync_message(sync_state.as_mut(), sync_message.as_ref().clone()),
-    )
+    );
+    notify(doc);
+    result
+}
+
+/// \memberof AMdoc
+/// \brief Receives a synchronization message from a peer based upon a given
+///        synchronization state and gets the patches describing the changes
+///        it made.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \param[in] sync_state A pointer to an `AMsyncState` struct.
+/// \param[in] sync_message A pointer to an `AMsyncMessage` struct.
+/// \return A pointer to an `AMresult` struct with `AM_VAL_TYPE_PATCH` items.
+/// \pre \p doc `!= NULL`
+/// \pre \p sync_state `!= NULL`
+/// \pre \p sync_message `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+/// sync_state must be a valid pointer to an AMsyncState
+/// sync_message must be a valid pointer to an AMsyncMessage
+#[no_mangle]
+pub unsafe extern "C" fn AMreceiveSyncMessageLogPatches(
+    doc: *mut AMdoc,
+    sync_state: *mut AMsyncState,
+    sync_message: *const AMsyncMessage,
+) -> *mut AMresult {
+    let doc = to_doc_mut!(doc);
+    let sync_state = to_sync_state_mut!(sync_state);
+    let sync_message = to_sync_message!(sync_message);
+    let result = to_result(doc.log_patches(|doc, patch_log| {
+        doc.sync().receive_sync_message_log_patches(
+            sync_state.as_mut(),
+            sync_message.as_ref().clone(),
+            patch_log,
+        )
+    }));
+    notify(doc);
+    result
 }
 
 /// \memberof AMdoc
@@ -836,7 +1187,9 @@ pub unsafe extern "C" fn AMrollback(doc: *mut AMdoc) -> usize {
 #[no_mangle]
 pub unsafe extern "C" fn AMsave(doc: *mut AMdoc) -> *mut AMresult {
     let doc = to_doc_mut!(doc);
-    to_result(Ok(doc.save()))
+    let result = to_result(Ok(doc.save()));
+    notify(doc);
+    result
 }
 
 /// \memberof AMdoc
@@ -855,7 +1208,43 @@ pub unsafe extern "C" fn AMsave(doc: *mut AMdoc) -> *mut AMresult {
 #[no_mangle]
 pub unsafe extern "C" fn AMsaveIncremental(doc: *mut AMdoc) -> *mut AMresult {
     let doc = to_doc_mut!(doc);
-    to_result(Ok(doc.save_incremental()))
+    let result = to_result(Ok(doc.save_incremental()));
+    notify(doc);
+    result
+}
+
+/// \memberof AMdoc
+/// \brief Saves the entirety of a document into a compact form according to
+///        the given options.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \param[in] options A pointer to an `AMsaveOptions` struct or `NULL` for the
+///                    default options.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_BYTES` item.
+/// \pre \p doc `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+/// options must be a valid pointer to an AMsaveOptions or std::ptr::null()
+#[no_mangle]
+pub unsafe extern "C" fn AMsaveWith(
+    doc: *mut AMdoc,
+    options: *const AMsaveOptions,
+) -> *mut AMresult {
+    let doc = to_doc_mut!(doc);
+    let options = match options.as_ref() {
+        Some(options) => match am::SaveOptions::try_from(options) {
+            Ok(options) => options,
+            Err(e) => return AMresult::error(&e).into(),
+        },
+        None => am::SaveOptions::default(),
+    };
+    let result = to_result(Ok(doc.save_with_options(options)));
+    notify(doc);
+    result
 }
 
 /// \memberof AMdoc
@@ -1007,3 +1396,34 @@ pub unsafe extern "C" fn AMtext(
         },
     }
 }
+
+/// \memberof AMdoc
+/// \brief Replaces the string represented by a text object with a new one.
+///
+/// \details The differences between the current and the new strings are
+///          converted into a reasonably minimal set of splices.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \param[in] obj_id A pointer to an `AMobjId` struct or `AM_ROOT`.
+/// \param[in] text A UTF-8 string view as an `AMbyteSpan` struct.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_VOID` item.
+/// \pre \p doc `!= NULL`
+/// \pre \p text.src `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+/// obj_id must be a valid pointer to an AMobjId or std::ptr::null()
+/// text.src must be a byte array of length >= text.count
+#[no_mangle]
+pub unsafe extern "C" fn AMupdateText(
+    doc: *mut AMdoc,
+    obj_id: *const AMobjId,
+    text: AMbyteSpan,
+) -> *mut AMresult {
+    let doc = to_doc_mut!(doc);
+    let obj_id = to_obj_id!(obj_id);
+    to_result(doc.update_text(obj_id, to_str!(text)))
+}
@@ -0,0 +1,458 @@
+use automerge as am;
+
+use std::borrow::Cow;
+
+use am::hydrate;
+use am::iter::Span;
+use am::marks::Mark;
+use am::patches::TextRepresentation;
+use am::transaction::Transactable;
+use am::{BlockOrText, ReadDoc};
+
+use crate::byte_span::{to_str, AMbyteSpan};
+use crate::doc::utils::{clamp, to_doc, to_doc_mut};
+use crate::doc::AMdoc;
+use crate::item::{AMitem, Item};
+use crate::items::AMitems;
+use crate::obj::{to_obj_id, AMobjId};
+use crate::result::{to_result, AMresult};
+
+macro_rules! to_span {
+    ($handle:expr) => {{
+        match $handle.as_ref() {
+            Some(b) => b,
+            None => return AMresult::error("Invalid `AMspan*`").into(),
+        }
+    }};
+}
+
+/// \struct AMspan
+/// \installed_headerfile
+/// \brief A run of text with the same marks or a block marker within a text
+///        object.
+#[derive(PartialEq)]
+pub struct AMspan {
+    body: Span,
+    start: usize,
+    end: usize,
+    obj_id: Option<AMobjId>,
+}
+
+impl AMspan {
+    pub fn new(span: Span, start: usize, end: usize, obj_id: Option<am::ObjId>) -> Self {
+        Self {
+            body: span,
+            start,
+            end,
+            obj_id: obj_id.map(AMobjId::new),
+        }
+    }
+
+    /// Parses a block from the JSON representation of its map.
+    pub fn block(json: &str) -> Result<Self, String> {
+        match serde_json::from_str::<hydrate::Value>(json) {
+            Ok(hydrate::Value::Map(map)) => Ok(Self::new(Span::Block(map), 0, 1, None)),
+            Ok(_) => Err("A block must be a JSON object".to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+impl AsRef<Span> for AMspan {
+    fn as_ref(&self) -> &Span {
+        &self.body
+    }
+}
+
+impl<'a> From<&'a AMspan> for BlockOrText<'a> {
+    fn from(span: &'a AMspan) -> Self {
+        match &span.body {
+            Span::Block(map) => BlockOrText::Block(map.clone()),
+            Span::Text(text, _) => BlockOrText::Text(Cow::Borrowed(text)),
+        }
+    }
+}
+
+/// \memberof AMdoc
+/// \brief Inserts a block marker into a text object.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \param[in] obj_id A pointer to an `AMobjId` struct or `AM_ROOT`.
+/// \param[in] pos A position in the text object identified by \p obj_id or
+///                `SIZE_MAX` to indicate one past its end.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_OBJ_TYPE`
+///         item identifying the block marker's map object.
+/// \pre \p doc `!= NULL`
+/// \pre `0 <=` \p pos `<= AMobjSize(`\p obj_id `)` or \p pos `== SIZE_MAX`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+/// obj_id must be a valid pointer to an AMobjId or std::ptr::null()
+#[no_mangle]
+pub unsafe extern "C" fn AMsplitBlock(
+    doc: *mut AMdoc,
+    obj_id: *const AMobjId,
+    pos: usize,
+) -> *mut AMresult {
+    let doc = to_doc_mut!(doc);
+    let obj_id = to_obj_id!(obj_id);
+    let len = doc.length(obj_id);
+    let pos = clamp!(pos, len, "pos");
+    to_result((doc.split_block(obj_id, pos), pos, am::ObjType::Map))
+}
+
+/// \memberof AMdoc
+/// \brief Deletes a block marker from a text object.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \param[in] obj_id A pointer to an `AMobjId` struct or `AM_ROOT`.
+/// \param[in] pos The position of a block marker in the text object
+///                identified by \p obj_id.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_VOID` item.
+/// \pre \p doc `!= NULL`
+/// \pre `0 <=` \p pos `< AMobjSize(`\p obj_id `)`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+/// obj_id must be a valid pointer to an AMobjId or std::ptr::null()
+#[no_mangle]
+pub unsafe extern "C" fn AMjoinBlock(
+    doc: *mut AMdoc,
+    obj_id: *const AMobjId,
+    pos: usize,
+) -> *mut AMresult {
+    let doc = to_doc_mut!(doc);
+    let obj_id = to_obj_id!(obj_id);
+    to_result(doc.join_block(obj_id, pos))
+}
+
+/// \memberof AMdoc
+/// \brief Replaces a block marker within a text object with a new one.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \param[in] obj_id A pointer to an `AMobjId` struct or `AM_ROOT`.
+/// \param[in] pos The position of a block marker in the text object
+///                identified by \p obj_id.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_OBJ_TYPE`
+///         item identifying the new block marker's map object.
+/// \pre \p doc `!= NULL`
+/// \pre `0 <=` \p pos `< AMobjSize(`\p obj_id `)`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+/// obj_id must be a valid pointer to an AMobjId or std::ptr::null()
+#[no_mangle]
+pub unsafe extern "C" fn AMreplaceBlock(
+    doc: *mut AMdoc,
+    obj_id: *const AMobjId,
+    pos: usize,
+) -> *mut AMresult {
+    let doc = to_doc_mut!(doc);
+    let obj_id = to_obj_id!(obj_id);
+    to_result((doc.replace_block(obj_id, pos), pos, am::ObjType::Map))
+}
+
+/// \memberof AMdoc
+/// \brief Gets the current or historical runs of text and block markers of a
+///        text object.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \param[in] obj_id A pointer to an `AMobjId` struct or `AM_ROOT`.
+/// \param[in] heads A pointer to an `AMitems` struct with `AM_VAL_TYPE_CHANGE_HASH`
+///                  items to select historical spans or `NULL` to select its
+///                  current spans.
+/// \return A pointer to an `AMresult` struct with `AM_VAL_TYPE_SPAN` items.
+/// \pre \p doc `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+/// obj_id must be a valid pointer to an AMobjId or std::ptr::null()
+/// heads must be a valid pointer to an AMitems or std::ptr::null()
+#[no_mangle]
+pub unsafe extern "C" fn AMspans(
+    doc: *const AMdoc,
+    obj_id: *const AMobjId,
+    heads: *const AMitems,
+) -> *mut AMresult {
+    let doc = to_doc!(doc);
+    let obj_id = to_obj_id!(obj_id);
+    let heads = match heads.as_ref() {
+        None => None,
+        Some(heads) => match <Vec<am::ChangeHash>>::try_from(heads) {
+            Ok(heads) => Some(heads),
+            Err(e) => return AMresult::error(&e.to_string()).into(),
+        },
+    };
+    let spans = match &heads {
+        None => doc.spans(obj_id),
+        Some(heads) => doc.spans_at(obj_id, heads),
+    };
+    let spans = match spans {
+        Ok(spans) => spans,
+        Err(e) => return AMresult::error(&e.to_string()).into(),
+    };
+    let text_rep = TextRepresentation::String(doc.text_encoding());
+    let mut start = 0;
+    let mut items = Vec::new();
+    for span in spans {
+        let (end, block_id) = match &span {
+            Span::Text(text, _) => (
+                start + am::ConcreteTextValue::new(text, text_rep).len(),
+                None,
+            ),
+            Span::Block(_) => {
+                let block = match &heads {
+                    None => doc.get(obj_id, start),
+                    Some(heads) => doc.get_at(obj_id, start, heads),
+                };
+                // The block marker is a single character whose width
+                // depends on the text encoding.
+                let width = am::ConcreteTextValue::new("\u{fffc}", text_rep).len();
+                match block {
+                    Ok(block) => (start + width, block.map(|(_, id)| id)),
+                    Err(e) => return AMresult::error(&e.to_string()).into(),
+                }
+            }
+        };
+        items.push(AMspan::new(span, start, end, block_id).into());
+        start = end;
+    }
+    AMresult::items(items).into()
+}
+
+/// \memberof AMdoc
+/// \brief Updates the text and block markers of a text object to match a
+///        given sequence of runs of text and block markers.
+///
+/// \details The differences between the current and the given sequences are
+///          converted into a reasonably minimal set of operations.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \param[in] obj_id A pointer to an `AMobjId` struct or `AM_ROOT`.
+/// \param[in] spans A copy of an `AMitems` struct with `AM_VAL_TYPE_STR`
+///                  items for runs of text and `AM_VAL_TYPE_SPAN` items for
+///                  runs of text or block markers <b>starting at its current
+///                  position</b>; call `AMitemsRewound()` on a used `AMitems`
+///                  first to ensure that all of its items are used.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_VOID` item.
+/// \pre \p doc `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+/// obj_id must be a valid pointer to an AMobjId or std::ptr::null()
+#[no_mangle]
+pub unsafe extern "C" fn AMupdateSpans(
+    doc: *mut AMdoc,
+    obj_id: *const AMobjId,
+    spans: AMitems,
+) -> *mut AMresult {
+    let doc = to_doc_mut!(doc);
+    let obj_id = to_obj_id!(obj_id);
+    let mut update = Vec::new();
+    for item in spans.as_ref().iter() {
+        let item: &Item = item.as_ref();
+        if let Ok(span) = <&AMspan>::try_from(item) {
+            update.push(span.into());
+        } else if let Ok(am::ScalarValue::Str(text)) = <&am::ScalarValue>::try_from(item) {
+            update.push(BlockOrText::Text(Cow::Borrowed(text.as_str())));
+        } else {
+            return AMresult::error("Expected `AM_VAL_TYPE_SPAN` or `AM_VAL_TYPE_STR` items")
+                .into();
+        }
+    }
+    to_result(doc.update_spans(obj_id, update))
+}
+
+/// \memberof AMspan
+/// \brief Tests whether a span is a block marker.
+///
+/// \param[in] span A pointer to an `AMspan` struct.
+/// \return `true` if \p span is a block marker, `false` if it's a run of text.
+/// \pre \p span `!= NULL`
+/// \post `(`\p span `== NULL) -> false`
+/// \internal
+///
+/// # Safety
+/// span must be a valid pointer to an AMspan
+#[no_mangle]
+pub unsafe extern "C" fn AMspanIsBlock(span: *const AMspan) -> bool {
+    matches!(span.as_ref().map(|span| &span.body), Some(Span::Block(_)))
+}
+
+/// \memberof AMspan
+/// \brief Gets the text of a span.
+///
+/// \param[in] span A pointer to an `AMspan` struct.
+/// \return A UTF-8 string view as an `AMbyteSpan` struct for a run of text or
+///         for the object replacement character (U+FFFC) of a block marker.
+/// \pre \p span `!= NULL`
+/// \post `(`\p span `== NULL) -> (AMbyteSpan){NULL, 0}`
+/// \internal
+///
+/// # Safety
+/// span must be a valid pointer to an AMspan
+#[no_mangle]
+pub unsafe extern "C" fn AMspanText(span: *const AMspan) -> AMbyteSpan {
+    if let Some(span) = span.as_ref() {
+        return span.body.as_str().as_bytes().into();
+    }
+    Default::default()
+}
+
+/// \memberof AMspan
+/// \brief Gets the offset at which a span starts within its text object.
+///
+/// \param[in] span A pointer to an `AMspan` struct.
+/// \return The offset at which the span starts.
+///         If `AUTOMERGE_C_UTF8` is defined then the offset is in units of
+///         bytes but if `AUTOMERGE_C_UTF32` is defined then it is in units of
+///         Unicode code points.
+/// \pre \p span `!= NULL`
+/// \post `(`\p span `== NULL) -> 0`
+/// \internal
+///
+/// # Safety
+/// span must be a valid pointer to an AMspan
+#[no_mangle]
+pub unsafe extern "C" fn AMspanStart(span: *const AMspan) -> usize {
+    if let Some(span) = span.as_ref() {
+        return span.start;
+    }
+    0
+}
+
+/// \memberof AMspan
+/// \brief Gets the offset at which a span ends within its text object.
+///
+/// \param[in] span A pointer to an `AMspan` struct.
+/// \return The offset one past the span's end.
+///         If `AUTOMERGE_C_UTF8` is defined then the offset is in units of
+///         bytes but if `AUTOMERGE_C_UTF32` is defined then it is in units of
+///         Unicode code points.
+/// \pre \p span `!= NULL`
+/// \post `(`\p span `== NULL) -> 0`
+/// \internal
+///
+/// # Safety
+/// span must be a valid pointer to an AMspan
+#[no_mangle]
+pub unsafe extern "C" fn AMspanEnd(span: *const AMspan) -> usize {
+    if let Some(span) = span.as_ref() {
+        return span.end;
+    }
+    0
+}
+
+/// \memberof AMspan
+/// \brief Gets the marks active for the whole of a span.
+///
+/// \param[in] span A pointer to an `AMspan` struct.
+/// \return A pointer to an `AMresult` struct with `AM_VAL_TYPE_MARK` items
+///         whose offsets are the span's.
+/// \pre \p span `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// span must be a valid pointer to an AMspan
+#[no_mangle]
+pub unsafe extern "C" fn AMspanMarks(span: *const AMspan) -> *mut AMresult {
+    let span = to_span!(span);
+    let marks = match &span.body {
+        Span::Text(_, Some(marks)) => marks
+            .iter()
+            .map(|(name, value)| {
+                Mark::new(name.to_string(), value.clone(), span.start, span.end).into()
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    AMresult::items(marks).into()
+}
+
+/// \memberof AMspan
+/// \brief Gets the properties of a block marker.
+///
+/// \param[in] span A pointer to an `AMspan` struct.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_STR` item
+///         holding the JSON representation of the block marker's map object
+///         (with its keys sorted) or an `AM_VAL_TYPE_VOID` item for a run of
+///         text.
+/// \pre \p span `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// span must be a valid pointer to an AMspan
+#[no_mangle]
+pub unsafe extern "C" fn AMspanBlock(span: *const AMspan) -> *mut AMresult {
+    let span = to_span!(span);
+    match &span.body {
+        // Going through `serde_json::Value` sorts the keys of the maps.
+        Span::Block(map) => match serde_json::to_value(hydrate::Value::Map(map.clone())) {
+            Ok(json) => AMresult::item(json.to_string().into()).into(),
+            Err(e) => AMresult::error(&e.to_string()).into(),
+        },
+        Span::Text(..) => AMresult::item(Default::default()).into(),
+    }
+}
+
+/// \memberof AMspan
+/// \brief Gets the identifier of a block marker's map object.
+///
+/// \param[in] span A pointer to an `AMspan` struct.
+/// \return A pointer to an `AMobjId` struct for a block marker gotten from
+///         `AMspans()` or `NULL`.
+/// \pre \p span `!= NULL`
+/// \post `(`\p span `== NULL) -> NULL`
+/// \internal
+///
+/// # Safety
+/// span must be a valid pointer to an AMspan
+#[no_mangle]
+pub unsafe extern "C" fn AMspanObjId(span: *const AMspan) -> *const AMobjId {
+    match span.as_ref().and_then(|span| span.obj_id.as_ref()) {
+        Some(obj_id) => obj_id,
+        None => std::ptr::null(),
+    }
+}
+
+/// \memberof AMitem
+/// \brief Allocates a new item and initializes it from a block marker.
+///
+/// \param[in] block A UTF-8 string view as an `AMbyteSpan` struct holding the
+///                  JSON representation of the block marker's map object,
+///                  e.g. `{"type": "paragraph", "parents": [], "attrs": {}}`.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_SPAN` item.
+/// \pre \p block.src `!= NULL`
+/// \pre `0 <` \p block.count `<= sizeof(`\p block.src `)`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// block.src must be a byte array of length >= block.count
+#[no_mangle]
+pub unsafe extern "C" fn AMitemFromBlock(block: AMbyteSpan) -> *mut AMresult {
+    match AMspan::block(to_str!(block)) {
+        Ok(span) => AMresult::item(AMitem::from(span)).into(),
+        Err(e) => AMresult::error(&e).into(),
+    }
+}
@@ -0,0 +1,141 @@
+use automerge as am;
+use automerge::ReadDoc;
+
+use std::ffi::c_void;
+
+use crate::result::AMresult;
+
+use super::{utils::to_doc_mut, AMdoc};
+
+/// \brief A function that's notified of the changes made to a document.
+///
+/// \param[in] doc A pointer to the `AMdoc` struct that was changed.
+/// \param[in] heads A pointer to an `AMresult` struct with the
+///                  `AM_VAL_TYPE_CHANGE_HASH` items of the document's new
+///                  heads.
+/// \param[in] patches A pointer to an `AMresult` struct with the
+///                    `AM_VAL_TYPE_PATCH` items describing the changes made
+///                    to the document since the previous notification.
+/// \param[in] user_data The pointer given to `AMsetChangeCallback()`.
+/// \note Ownership of \p heads and \p patches is transferred to the function
+///       so they must be passed to `AMresultFree()` (or pushed onto an
+///       `AMstack`) in order to avoid a memory leak.
+pub type AMchangeCallback = Option<
+    unsafe extern "C" fn(
+        doc: *mut AMdoc,
+        heads: *mut AMresult,
+        patches: *mut AMresult,
+        user_data: *mut c_void,
+    ),
+>;
+
+pub(crate) struct Observer {
+    callback: unsafe extern "C" fn(*mut AMdoc, *mut AMresult, *mut AMresult, *mut c_void),
+    user_data: *mut c_void,
+    /// The heads of the document as of the latest notification.
+    heads: Vec<am::ChangeHash>,
+    is_notifying: bool,
+}
+
+/// Invokes a document's change callback if the document's heads have changed
+/// since the callback was last invoked.
+///
+/// Changes made by the callback itself don't invoke it again; they're reported
+/// by its next invocation instead.
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+pub(crate) unsafe fn notify(doc: *mut AMdoc) {
+    let Some(am_doc) = doc.as_mut() else {
+        return;
+    };
+    let Some(observer) = am_doc.observer.as_mut() else {
+        return;
+    };
+    if observer.is_notifying {
+        return;
+    }
+    let heads = am_doc.body.get_heads();
+    if heads == observer.heads {
+        return;
+    }
+    let patches = am_doc.body.take_observed_patches();
+    observer.heads.clone_from(&heads);
+    observer.is_notifying = true;
+    let (callback, user_data) = (observer.callback, observer.user_data);
+    callback(
+        doc,
+        AMresult::from(heads).into(),
+        AMresult::from(patches).into(),
+        user_data,
+    );
+    // The callback may have replaced or removed itself.
+    if let Some(observer) = (*doc).observer.as_mut() {
+        observer.is_notifying = false;
+    }
+}
+
+/// \memberof AMdoc
+/// \brief Sets the function to notify of the changes made to a document by
+///        `AMapplyChanges()`, `AMcommit()`, `AMemptyChange()`,
+///        `AMloadIncremental()`, `AMmerge()` and `AMreceiveSyncMessage()`
+///        (including their variants that log patches) and by the pending
+///        operations that `AMsave()`, `AMsaveIncremental()` and `AMsaveWith()`
+///        commit.
+///
+/// \details Every notification reports all of the changes made to the
+///          document since the previous one (or since the function was set),
+///          including those made by other functions in the meantime. The
+///          function isn't notified when the document's heads are unchanged.
+///
+///          The function is invoked after the modifying function has
+///          finished with the document, so it may call any function on
+///          the document except `AMresultFree()` on the result that owns it.
+///          Changes that it makes to the document don't notify it again;
+///          they're reported by its next notification instead. Replacing or
+///          removing the function from within itself takes effect for the
+///          next notification.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \param[in] callback A pointer to an `AMchangeCallback` function or `NULL`
+///                     to stop notifying.
+/// \param[in] user_data A pointer that's passed to \p callback unchanged.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_VOID` item.
+/// \pre \p doc `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+/// callback must be a valid function pointer or std::ptr::null()
+#[no_mangle]
+pub unsafe extern "C" fn AMsetChangeCallback(
+    doc: *mut AMdoc,
+    callback: AMchangeCallback,
+    user_data: *mut c_void,
+) -> *mut AMresult {
+    let doc = to_doc_mut!(doc);
+    let is_notifying = doc
+        .observer
+        .as_ref()
+        .is_some_and(|observer| observer.is_notifying);
+    doc.observer = match callback {
+        Some(callback) => {
+            let text_rep = doc.body.text_encoding().into();
+            doc.body
+                .set_observer_log(Some(am::PatchLog::active(text_rep)));
+            Some(Observer {
+                callback,
+                user_data,
+                heads: doc.body.get_heads(),
+                is_notifying,
+            })
+        }
+        None => {
+            doc.body.set_observer_log(None);
+            None
+        }
+    };
+    AMresult::item(Default::default()).into()
+}
@@ -0,0 +1,270 @@
+use automerge as am;
+
+/// \ingroup enumerations
+/// \enum AMcompression
+/// \installed_headerfile
+/// \brief Whether to compress the columns of a saved document.
+#[derive(Eq, PartialEq)]
+#[repr(C)]
+pub enum AMcompression {
+    /// The default tag, which selects `AM_COMPRESSION_DEFLATE`.
+    Default = 0,
+    /// Apply DEFLATE compression.
+    Deflate = 1,
+    /// Don't compress anything.
+    None = 2,
+    /// Apply zstd compression, which requires `AUTOMERGE_C_ZSTD` to be
+    /// defined. Only builds of automerge which support zstd can load the
+    /// document.
+    Zstd = 3,
+}
+
+impl Default for AMcompression {
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+/// \ingroup enumerations
+/// \enum AMonPartialLoad
+/// \installed_headerfile
+/// \brief What to do when loading a document partially succeeds.
+#[derive(Eq, PartialEq)]
+#[repr(C)]
+pub enum AMonPartialLoad {
+    /// The default tag, which selects `AM_ON_PARTIAL_LOAD_ERROR`.
+    Default = 0,
+    /// Fail the entire load.
+    Error = 1,
+    /// Ignore the error and keep the changes that were loaded.
+    Ignore = 2,
+}
+
+impl Default for AMonPartialLoad {
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+impl From<&AMonPartialLoad> for am::OnPartialLoad {
+    fn from(on_partial_load: &AMonPartialLoad) -> Self {
+        match on_partial_load {
+            AMonPartialLoad::Default | AMonPartialLoad::Error => Self::Error,
+            AMonPartialLoad::Ignore => Self::Ignore,
+        }
+    }
+}
+
+/// \ingroup enumerations
+/// \enum AMorphans
+/// \installed_headerfile
+/// \brief Whether to save the changes whose dependencies are missing.
+#[derive(Eq, PartialEq)]
+#[repr(C)]
+pub enum AMorphans {
+    /// Leave the changes out.
+    Discard = 1,
+    /// The default tag, which selects `AM_ORPHANS_RETAIN`.
+    Default = 0,
+    /// Save the changes.
+    Retain = 2,
+}
+
+impl Default for AMorphans {
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+/// \ingroup enumerations
+/// \enum AMstringMigration
+/// \installed_headerfile
+/// \brief Whether to convert the string values in a loaded document into text
+///        objects.
+#[derive(Eq, PartialEq)]
+#[repr(C)]
+pub enum AMstringMigration {
+    /// Convert every string value into a text object.
+    ConvertToText = 1,
+    /// The default tag, which selects `AM_STRING_MIGRATION_NO_MIGRATION`.
+    Default = 0,
+    /// Don't convert anything.
+    NoMigration = 2,
+}
+
+impl Default for AMstringMigration {
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+impl From<&AMstringMigration> for am::StringMigration {
+    fn from(string_migration: &AMstringMigration) -> Self {
+        match string_migration {
+            AMstringMigration::ConvertToText => Self::ConvertToText,
+            AMstringMigration::Default | AMstringMigration::NoMigration => Self::NoMigration,
+        }
+    }
+}
+
+/// \ingroup enumerations
+/// \enum AMtextEncoding
+/// \installed_headerfile
+/// \brief The units in which positions and lengths within text objects are
+///        measured.
+#[derive(Eq, PartialEq)]
+#[repr(C)]
+pub enum AMtextEncoding {
+    /// The default tag, which selects `AM_TEXT_ENCODING_UTF8_CODE_UNIT` if
+    /// `AUTOMERGE_C_UTF8` is defined or `AM_TEXT_ENCODING_UNICODE_CODE_POINT`
+    /// if `AUTOMERGE_C_UTF32` is defined. No other tag is supported.
+    Default = 0,
+    /// Grapheme clusters.
+    GraphemeCluster = 1,
+    /// Unicode code points.
+    UnicodeCodePoint = 2,
+    /// UTF-16 code units.
+    Utf16CodeUnit = 3,
+    /// UTF-8 code units (bytes).
+    Utf8CodeUnit = 4,
+}
+
+impl Default for AMtextEncoding {
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+impl TryFrom<&AMtextEncoding> for am::TextEncoding {
+    type Error = String;
+
+    /// The positions and lengths that the C API exchanges are in the units of
+    /// the encoding it was built for so no other encoding is supported.
+    fn try_from(text_encoding: &AMtextEncoding) -> Result<Self, Self::Error> {
+        let text_encoding = match text_encoding {
+            AMtextEncoding::Default => return Ok(Self::default()),
+            AMtextEncoding::GraphemeCluster => Self::GraphemeCluster,
+            AMtextEncoding::UnicodeCodePoint => Self::UnicodeCodePoint,
+            AMtextEncoding::Utf16CodeUnit => Self::Utf16CodeUnit,
+            AMtextEncoding::Utf8CodeUnit => Self::Utf8CodeUnit,
+        };
+        if text_encoding == Self::default() {
+            Ok(text_encoding)
+        } else {
+            Err(format!(
+                "Text encoding {:?} isn't supported by this build, which uses {:?}",
+                text_encoding,
+                Self::default()
+            ))
+        }
+    }
+}
+
+/// \ingroup enumerations
+/// \enum AMverificationMode
+/// \installed_headerfile
+/// \brief Whether to verify the head hashes of a loaded document.
+#[derive(Eq, PartialEq)]
+#[repr(C)]
+pub enum AMverificationMode {
+    /// Verify the head hashes.
+    Check = 1,
+    /// The default tag, which selects `AM_VERIFICATION_MODE_CHECK`.
+    Default = 0,
+    /// Don't verify the head hashes.
+    DontCheck = 2,
+}
+
+impl Default for AMverificationMode {
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+impl From<&AMverificationMode> for am::VerificationMode {
+    fn from(verification_mode: &AMverificationMode) -> Self {
+        match verification_mode {
+            AMverificationMode::Check | AMverificationMode::Default => Self::Check,
+            AMverificationMode::DontCheck => Self::DontCheck,
+        }
+    }
+}
+
+/// \struct AMloadOptions
+/// \installed_headerfile
+/// \brief The options for loading a document.
+///
+/// \note A zero-initialized `AMloadOptions` struct selects the default for
+///       every option.
+#[derive(Default, Eq, PartialEq)]
+#[repr(C)]
+pub struct AMloadOptions {
+    /// What to do when loading the document partially succeeds.
+    pub on_partial_load: AMonPartialLoad,
+    /// Whether to convert the document's string values into text objects.
+    pub string_migration: AMstringMigration,
+    /// The units of the document's text indices.
+    pub text_encoding: AMtextEncoding,
+    /// Whether to verify the document's head hashes.
+    pub verification_mode: AMverificationMode,
+}
+
+impl TryFrom<&AMloadOptions> for am::LoadOptions<'static> {
+    type Error = String;
+
+    fn try_from(options: &AMloadOptions) -> Result<Self, Self::Error> {
+        Ok(am::LoadOptions::new()
+            .on_partial_load((&options.on_partial_load).into())
+            .migrate_strings((&options.string_migration).into())
+            .text_encoding((&options.text_encoding).try_into()?)
+            .verification_mode((&options.verification_mode).into()))
+    }
+}
+
+/// \struct AMsaveOptions
+/// \installed_headerfile
+/// \brief The options for saving a document.
+///
+/// \note A zero-initialized `AMsaveOptions` struct selects the default for
+///       every option, which are the options of `AMsave()`.
+#[derive(Default, Eq, PartialEq)]
+#[repr(C)]
+pub struct AMsaveOptions {
+    /// Whether to compress the document's columns.
+    pub compression: AMcompression,
+    /// Whether to save changes whose dependencies are missing.
+    pub orphans: AMorphans,
+    /// The zstd compression level when `compression` is
+    /// `AM_COMPRESSION_ZSTD`, `0` selects zstd's default level.
+    pub compression_level: i32,
+    /// The size in bytes below which columns are left uncompressed, `0`
+    /// selects the default of 256.
+    pub compression_threshold: usize,
+}
+
+impl TryFrom<&AMsaveOptions> for am::SaveOptions {
+    type Error = String;
+
+    fn try_from(options: &AMsaveOptions) -> Result<Self, Self::Error> {
+        let mut save_options = am::SaveOptions::new()
+            .deflate(options.compression != AMcompression::None)
+            .retain_orphans(options.orphans != AMorphans::Discard);
+        if options.compression == AMcompression::Zstd {
+            save_options = save_options.compression(zstd(options.compression_level)?);
+        }
+        if options.compression_threshold != 0 {
+            save_options = save_options.compression_threshold(options.compression_threshold);
+        }
+        Ok(save_options)
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn zstd(level: i32) -> Result<am::Compression, String> {
+    Ok(am::Compression::Zstd { level })
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd(_level: i32) -> Result<am::Compression, String> {
+    Err("zstd compression isn't supported by this build".to_string())
+}
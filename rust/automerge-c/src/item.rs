@@ -10,10 +10,13 @@ use crate::actor_id::AMactorId;
 use crate::byte_span::{to_str, AMbyteSpan};
 use crate::change::AMchange;
 use crate::cursor::AMcursor;
+use crate::doc::block::AMspan;
 use crate::doc::mark::AMmark;
 use crate::doc::AMdoc;
 use crate::index::{AMidxType, AMindex};
+use crate::items::AMitems;
 use crate::obj::AMobjId;
+use crate::patch::AMpatch;
 use crate::result::{to_result, AMresult};
 use crate::sync::{AMsyncHave, AMsyncMessage, AMsyncState};
 
@@ -36,6 +39,8 @@ pub enum Value {
     Cursor(AMcursor),
     Doc(RefCell<AMdoc>),
     Mark(AMmark),
+    Patch(AMpatch),
+    Span(AMspan),
     SyncHave(AMsyncHave),
     SyncMessage(AMsyncMessage),
     SyncState(RefCell<AMsyncState>),
@@ -209,6 +214,18 @@ impl From<Mark> for Value {
     }
 }
 
+impl From<am::Patch> for Value {
+    fn from(patch: am::Patch) -> Self {
+        Self::Patch(AMpatch::new(patch))
+    }
+}
+
+impl From<AMspan> for Value {
+    fn from(span: AMspan) -> Self {
+        Self::Span(span)
+    }
+}
+
 impl From<String> for Value {
     fn from(string: String) -> Self {
         Self::Value(am::Value::Scalar(Cow::Owned(am::ScalarValue::Str(
@@ -496,6 +513,40 @@ impl<'a> TryFrom<&'a Value> for &'a AMmark {
     }
 }
 
+impl<'a> TryFrom<&'a Value> for &'a AMpatch {
+    type Error = am::AutomergeError;
+
+    fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+        use self::Value::*;
+        use am::AutomergeError::InvalidValueType;
+
+        match value {
+            Patch(patch) => Ok(patch),
+            _ => Err(InvalidValueType {
+                expected: type_name::<Self>().to_string(),
+                unexpected: type_name::<self::Value>().to_string(),
+            }),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a Value> for &'a AMspan {
+    type Error = am::AutomergeError;
+
+    fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+        use self::Value::*;
+        use am::AutomergeError::InvalidValueType;
+
+        match value {
+            Span(span) => Ok(span),
+            _ => Err(InvalidValueType {
+                expected: type_name::<Self>().to_string(),
+                unexpected: type_name::<self::Value>().to_string(),
+            }),
+        }
+    }
+}
+
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         use self::Value::*;
@@ -505,6 +556,8 @@ impl PartialEq for Value {
             (Change(lhs, _), Change(rhs, _)) => lhs == rhs,
             (ChangeHash(lhs), ChangeHash(rhs)) => lhs == rhs,
             (Doc(lhs), Doc(rhs)) => lhs.as_ptr() == rhs.as_ptr(),
+            (Patch(lhs), Patch(rhs)) => lhs == rhs,
+            (Span(lhs), Span(rhs)) => lhs == rhs,
             (SyncMessage(lhs), SyncMessage(rhs)) => *lhs == *rhs,
             (SyncState(lhs), SyncState(rhs)) => *lhs == *rhs,
             (Value(lhs), Value(rhs)) => lhs == rhs,
@@ -515,6 +568,8 @@ impl PartialEq for Value {
 
 #[derive(Default)]
 pub struct Item {
+    /// The items nested within the item's value.
+    children: Vec<AMitem>,
     /// The item's index.
     index: Option<AMindex>,
     /// The item's identifier.
@@ -630,6 +685,7 @@ impl From<am::Cursor> for Item {
 impl From<(am::ObjId, am::ObjType)> for Item {
     fn from((obj_id, obj_type): (am::ObjId, am::ObjType)) -> Self {
         Self {
+            children: Vec::new(),
             index: None,
             obj_id: Some(AMobjId::new(obj_id)),
             value: Some(am::Value::Object(obj_type).into()),
@@ -673,6 +729,18 @@ impl From<Mark> for Item {
     }
 }
 
+impl From<am::Patch> for Item {
+    fn from(patch: am::Patch) -> Self {
+        Value::from(patch).into()
+    }
+}
+
+impl From<AMspan> for Item {
+    fn from(span: AMspan) -> Self {
+        Value::from(span).into()
+    }
+}
+
 impl From<String> for Item {
     fn from(string: String) -> Self {
         Value::from(string).into()
@@ -682,6 +750,7 @@ impl From<String> for Item {
 impl From<Value> for Item {
     fn from(value: Value) -> Self {
         Self {
+            children: Vec::new(),
             index: None,
             obj_id: None,
             value: Some(value),
@@ -691,7 +760,10 @@ impl From<Value> for Item {
 
 impl PartialEq for Item {
     fn eq(&self, other: &Self) -> bool {
-        self.index == other.index && self.obj_id == other.obj_id && self.value == other.value
+        self.index == other.index
+            && self.obj_id == other.obj_id
+            && self.value == other.value
+            && self.children == other.children
     }
 }
 
@@ -890,6 +962,40 @@ impl<'a> TryFrom<&'a Item> for &'a AMmark {
     }
 }
 
+impl<'a> TryFrom<&'a Item> for &'a AMpatch {
+    type Error = am::AutomergeError;
+
+    fn try_from(item: &'a Item) -> Result<Self, Self::Error> {
+        use am::AutomergeError::InvalidValueType;
+
+        if let Some(value) = &item.value {
+            value.try_into()
+        } else {
+            Err(InvalidValueType {
+                expected: type_name::<Self>().to_string(),
+                unexpected: type_name::<Option<am::Patch>>().to_string(),
+            })
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a Item> for &'a AMspan {
+    type Error = am::AutomergeError;
+
+    fn try_from(item: &'a Item) -> Result<Self, Self::Error> {
+        use am::AutomergeError::InvalidValueType;
+
+        if let Some(value) = &item.value {
+            value.try_into()
+        } else {
+            Err(InvalidValueType {
+                expected: type_name::<Self>().to_string(),
+                unexpected: type_name::<Option<AMspan>>().to_string(),
+            })
+        }
+    }
+}
+
 impl TryFrom<&Item> for bool {
     type Error = am::AutomergeError;
 
@@ -998,6 +1104,14 @@ impl TryFrom<&Item> for (am::Value<'static>, am::ObjId) {
                     expected,
                     unexpected: type_name::<AMmark>().to_string(),
                 }),
+                Patch(_) => Err(InvalidValueType {
+                    expected,
+                    unexpected: type_name::<AMpatch>().to_string(),
+                }),
+                Span(_) => Err(InvalidValueType {
+                    expected,
+                    unexpected: type_name::<AMspan>().to_string(),
+                }),
                 SyncHave(_) => Err(InvalidValueType {
                     expected,
                     unexpected: type_name::<AMsyncHave>().to_string(),
@@ -1025,19 +1139,44 @@ pub struct AMitem(Rc<Item>);
 impl AMitem {
     pub fn exact(obj_id: am::ObjId, value: Value) -> Self {
         Self(Rc::new(Item {
+            children: Vec::new(),
             index: None,
             obj_id: Some(AMobjId::new(obj_id)),
             value: Some(value),
         }))
     }
 
+    pub fn located(index: Option<AMindex>, obj_id: am::ObjId) -> Self {
+        Self(Rc::new(Item {
+            children: Vec::new(),
+            index,
+            obj_id: Some(AMobjId::new(obj_id)),
+            value: None,
+        }))
+    }
+
     pub fn indexed(index: AMindex, obj_id: am::ObjId, value: Value) -> Self {
         Self(Rc::new(Item {
+            children: Vec::new(),
             index: Some(index),
             obj_id: Some(AMobjId::new(obj_id)),
             value: Some(value),
         }))
     }
+
+    pub fn nested(
+        index: Option<AMindex>,
+        obj_id: am::ObjId,
+        value: Value,
+        children: Vec<AMitem>,
+    ) -> Self {
+        Self(Rc::new(Item {
+            children,
+            index,
+            obj_id: Some(AMobjId::new(obj_id)),
+            value: Some(value),
+        }))
+    }
 }
 
 impl AsRef<Item> for AMitem {
@@ -1049,6 +1188,7 @@ impl AsRef<Item> for AMitem {
 impl Default for AMitem {
     fn default() -> Self {
         Self(Rc::new(Item {
+            children: Vec::new(),
             index: None,
             obj_id: None,
             value: None,
@@ -1128,6 +1268,18 @@ impl From<Mark> for AMitem {
     }
 }
 
+impl From<am::Patch> for AMitem {
+    fn from(patch: am::Patch) -> Self {
+        Value::from(patch).into()
+    }
+}
+
+impl From<AMspan> for AMitem {
+    fn from(span: AMspan) -> Self {
+        Value::from(span).into()
+    }
+}
+
 impl From<String> for AMitem {
     fn from(string: String) -> Self {
         Value::from(string).into()
@@ -1218,6 +1370,22 @@ impl<'a> TryFrom<&'a AMitem> for &'a AMmark {
     }
 }
 
+impl<'a> TryFrom<&'a AMitem> for &'a AMpatch {
+    type Error = am::AutomergeError;
+
+    fn try_from(item: &'a AMitem) -> Result<Self, Self::Error> {
+        item.as_ref().try_into()
+    }
+}
+
+impl<'a> TryFrom<&'a AMitem> for &'a AMspan {
+    type Error = am::AutomergeError;
+
+    fn try_from(item: &'a AMitem) -> Result<Self, Self::Error> {
+        item.as_ref().try_into()
+    }
+}
+
 impl<'a> TryFrom<&'a AMitem> for &'a AMsyncHave {
     type Error = am::AutomergeError;
 
@@ -1321,6 +1489,10 @@ pub enum AMvalType {
     Null = 1 << 12,
     /// An object type value.
     ObjType = 1 << 13,
+    /// A patch.
+    Patch = 1 << 21,
+    /// A span of text or a block marker.
+    Span = 1 << 22,
     /// A UTF-8 string view value.
     Str = 1 << 14,
     /// A synchronization have value.
@@ -1379,6 +1551,8 @@ impl From<&Value> for AMvalType {
             Cursor(_) => Self::Cursor,
             Doc(_) => Self::Doc,
             Mark(_) => Self::Mark,
+            Patch(_) => Self::Patch,
+            Span(_) => Self::Span,
             SyncHave(_) => Self::SyncHave,
             SyncMessage(_) => Self::SyncMessage,
             SyncState(_) => Self::SyncState,
@@ -1396,6 +1570,29 @@ impl From<&Item> for AMvalType {
     }
 }
 
+/// \memberof AMitem
+/// \brief Gets the items nested within an item of a hydrated object.
+///
+/// \param[in] item A pointer to an `AMitem` struct.
+/// \return An `AMitems` struct with the entries of a map object, the elements
+///         of a list object or the string of a text object when \p item came
+///         from `AMhydrate()` and an empty `AMitems` struct otherwise.
+/// \pre \p item `!= NULL`
+/// \internal
+///
+/// # Safety
+/// item must be a valid pointer to an AMitem
+#[no_mangle]
+pub unsafe extern "C" fn AMitemChildren<'a>(item: *const AMitem) -> AMitems<'a> {
+    if let Some(item) = item.as_ref() {
+        let children = &item.as_ref().children;
+        if !children.is_empty() {
+            return AMitems::new(children);
+        }
+    }
+    Default::default()
+}
+
 /// \memberof AMitem
 /// \brief Tests the equality of two items.
 ///
@@ -1972,6 +2169,56 @@ pub unsafe extern "C" fn AMitemToMark(item: *const AMitem, value: *mut *const AM
     false
 }
 
+/// \memberof AMitem
+/// \brief Gets the patch value of an item.
+///
+/// \param[in] item A pointer to an `AMitem` struct.
+/// \param[out] value A pointer to an `AMpatch` struct pointer.
+/// \return `true` if `AMitemValType(`\p item `) == AM_VAL_TYPE_PATCH` and
+///         \p *value has been reassigned, `false` otherwise.
+/// \pre \p item `!= NULL`
+/// \internal
+///
+/// # Safety
+/// item must be a valid pointer to an AMitem
+#[no_mangle]
+pub unsafe extern "C" fn AMitemToPatch(item: *const AMitem, value: *mut *const AMpatch) -> bool {
+    if let Some(item) = item.as_ref() {
+        if let Ok(patch) = <&AMpatch>::try_from(item) {
+            if !value.is_null() {
+                *value = patch;
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// \memberof AMitem
+/// \brief Gets the span value of an item.
+///
+/// \param[in] item A pointer to an `AMitem` struct.
+/// \param[out] value A pointer to an `AMspan` struct pointer.
+/// \return `true` if `AMitemValType(`\p item `) == AM_VAL_TYPE_SPAN` and
+///         \p *value has been reassigned, `false` otherwise.
+/// \pre \p item `!= NULL`
+/// \internal
+///
+/// # Safety
+/// item must be a valid pointer to an AMitem
+#[no_mangle]
+pub unsafe extern "C" fn AMitemToSpan(item: *const AMitem, value: *mut *const AMspan) -> bool {
+    if let Some(item) = item.as_ref() {
+        if let Ok(span) = <&AMspan>::try_from(item) {
+            if !value.is_null() {
+                *value = span;
+                return true;
+            }
+        }
+    }
+    false
+}
+
 /// \memberof AMitem
 /// \brief Gets the UTF-8 string view value of an item.
 ///
@@ -7,5 +7,6 @@ mod index;
 mod item;
 mod items;
 mod obj;
+mod patch;
 mod result;
 mod sync;
@@ -0,0 +1,340 @@
+use automerge as am;
+
+use am::marks::Mark;
+use am::{Patch, PatchAction};
+
+use crate::index::AMindex;
+use crate::item::AMitem;
+use crate::obj::AMobjId;
+use crate::result::{to_result, AMresult};
+
+macro_rules! to_patch {
+    ($handle:expr) => {{
+        match $handle.as_ref() {
+            Some(b) => b,
+            None => return AMresult::error("Invalid `AMpatch*`").into(),
+        }
+    }};
+}
+
+/// \ingroup enumerations
+/// \enum AMactionType
+/// \installed_headerfile
+/// \brief The kind of modification described by a patch.
+#[derive(Eq, PartialEq)]
+#[repr(C)]
+pub enum AMactionType {
+    /// A conflict arose for a property whose value didn't change.
+    Conflict = 1,
+    /// The default tag, not an action type signifier.
+    Default = 0,
+    /// A key was deleted from a map object.
+    DelMap = 2,
+    /// A range of items was deleted from a list object or text object.
+    DelSeq = 3,
+    /// A counter was incremented.
+    Increment = 4,
+    /// A range of items was inserted into a list object.
+    Insert = 5,
+    /// Marks were created or cleared within a text object.
+    Mark = 6,
+    /// A value was put into a map object.
+    PutMap = 7,
+    /// A value was put into a list object.
+    PutSeq = 8,
+    /// Text was inserted into a text object.
+    SpliceText = 9,
+}
+
+impl Default for AMactionType {
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+impl From<&PatchAction> for AMactionType {
+    fn from(action: &PatchAction) -> Self {
+        match action {
+            PatchAction::Conflict { .. } => Self::Conflict,
+            PatchAction::DeleteMap { .. } => Self::DelMap,
+            PatchAction::DeleteSeq { .. } => Self::DelSeq,
+            PatchAction::Increment { .. } => Self::Increment,
+            PatchAction::Insert { .. } => Self::Insert,
+            PatchAction::Mark { .. } => Self::Mark,
+            PatchAction::PutMap { .. } => Self::PutMap,
+            PatchAction::PutSeq { .. } => Self::PutSeq,
+            PatchAction::SpliceText { .. } => Self::SpliceText,
+        }
+    }
+}
+
+/// \struct AMpatch
+/// \installed_headerfile
+/// \brief A description of a modification to an object within a document.
+#[derive(PartialEq)]
+pub struct AMpatch {
+    body: Patch,
+    obj_id: AMobjId,
+}
+
+impl AMpatch {
+    pub fn new(patch: Patch) -> Self {
+        Self {
+            obj_id: AMobjId::new(patch.obj.clone()),
+            body: patch,
+        }
+    }
+
+    fn index(&self) -> Option<AMindex> {
+        match &self.body.action {
+            PatchAction::PutMap { key, .. } | PatchAction::DeleteMap { key } => {
+                Some(AMindex::Key(key.into()))
+            }
+            PatchAction::PutSeq { index, .. }
+            | PatchAction::Insert { index, .. }
+            | PatchAction::SpliceText { index, .. }
+            | PatchAction::DeleteSeq { index, .. } => Some(AMindex::Pos(*index)),
+            PatchAction::Increment { prop, .. } | PatchAction::Conflict { prop } => {
+                Some(prop.into())
+            }
+            PatchAction::Mark { .. } => None,
+        }
+    }
+}
+
+impl AsRef<Patch> for AMpatch {
+    fn as_ref(&self) -> &Patch {
+        &self.body
+    }
+}
+
+impl From<&am::Prop> for AMindex {
+    fn from(prop: &am::Prop) -> Self {
+        match prop {
+            am::Prop::Map(key) => Self::Key(key.into()),
+            am::Prop::Seq(pos) => Self::Pos(*pos),
+        }
+    }
+}
+
+/// \memberof AMpatch
+/// \brief Gets the kind of modification described by a patch.
+///
+/// \param[in] patch A pointer to an `AMpatch` struct.
+/// \return An `AMactionType` enum tag.
+/// \pre \p patch `!= NULL`
+/// \post `(`\p patch `== NULL) -> AM_ACTION_TYPE_DEFAULT`
+/// \internal
+///
+/// # Safety
+/// patch must be a valid pointer to an AMpatch
+#[no_mangle]
+pub unsafe extern "C" fn AMpatchAction(patch: *const AMpatch) -> AMactionType {
+    if let Some(patch) = patch.as_ref() {
+        return (&patch.body.action).into();
+    }
+    Default::default()
+}
+
+/// \memberof AMpatch
+/// \brief Gets the identifier of the object modified by a patch.
+///
+/// \param[in] patch A pointer to an `AMpatch` struct.
+/// \return A pointer to an `AMobjId` struct.
+/// \pre \p patch `!= NULL`
+/// \post `(`\p patch `== NULL) -> NULL`
+/// \internal
+///
+/// # Safety
+/// patch must be a valid pointer to an AMpatch
+#[no_mangle]
+pub unsafe extern "C" fn AMpatchObjId(patch: *const AMpatch) -> *const AMobjId {
+    if let Some(patch) = patch.as_ref() {
+        return &patch.obj_id;
+    }
+    std::ptr::null()
+}
+
+/// \memberof AMpatch
+/// \brief Gets the path from the root object to the object modified by a patch.
+///
+/// \param[in] patch A pointer to an `AMpatch` struct.
+/// \return A pointer to an `AMresult` struct with `AM_VAL_TYPE_VOID` items
+///         whose object identifiers are the parent objects along the path and
+///         whose indices are the keys or positions of their children within
+///         them.
+/// \pre \p patch `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// patch must be a valid pointer to an AMpatch
+#[no_mangle]
+pub unsafe extern "C" fn AMpatchPath(patch: *const AMpatch) -> *mut AMresult {
+    let patch = to_patch!(patch);
+    AMresult::items(
+        patch
+            .body
+            .path
+            .iter()
+            .map(|(obj_id, prop)| AMitem::located(Some(prop.into()), obj_id.clone()))
+            .collect(),
+    )
+    .into()
+}
+
+/// \memberof AMpatch
+/// \brief Gets the key or position modified by a patch.
+///
+/// \param[in] patch A pointer to an `AMpatch` struct.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_VOID` item
+///         whose object identifier is the modified object's and whose index
+///         is the key or position modified within it. An
+///         `AM_ACTION_TYPE_MARK` patch's item has no index.
+/// \pre \p patch `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// patch must be a valid pointer to an AMpatch
+#[no_mangle]
+pub unsafe extern "C" fn AMpatchIndex(patch: *const AMpatch) -> *mut AMresult {
+    let patch = to_patch!(patch);
+    let obj_id = patch.body.obj.clone();
+    AMresult::item(AMitem::located(patch.index(), obj_id)).into()
+}
+
+/// \memberof AMpatch
+/// \brief Gets the count of items deleted or inserted by a patch.
+///
+/// \param[in] patch A pointer to an `AMpatch` struct.
+/// \return The count of items deleted by an `AM_ACTION_TYPE_DEL_SEQ` patch,
+///         inserted by an `AM_ACTION_TYPE_INSERT` patch or inserted by an
+///         `AM_ACTION_TYPE_SPLICE_TEXT` patch (in the units of the text
+///         indexing encoding) or `1` for any other patch.
+/// \pre \p patch `!= NULL`
+/// \post `(`\p patch `== NULL) -> 0`
+/// \internal
+///
+/// # Safety
+/// patch must be a valid pointer to an AMpatch
+#[no_mangle]
+pub unsafe extern "C" fn AMpatchLength(patch: *const AMpatch) -> usize {
+    match patch.as_ref().map(|patch| &patch.body.action) {
+        Some(PatchAction::DeleteSeq { length, .. }) => *length,
+        Some(PatchAction::Insert { values, .. }) => values.len(),
+        Some(PatchAction::SpliceText { value, .. }) => value.len(),
+        Some(_) => 1,
+        None => 0,
+    }
+}
+
+/// \memberof AMpatch
+/// \brief Tests whether the value put by a patch is in conflict with other
+///        values.
+///
+/// \param[in] patch A pointer to an `AMpatch` struct.
+/// \return `true` if \p patch is an `AM_ACTION_TYPE_PUT_MAP` or
+///         `AM_ACTION_TYPE_PUT_SEQ` patch whose value conflicts with others
+///         or an `AM_ACTION_TYPE_CONFLICT` patch, `false` otherwise.
+/// \pre \p patch `!= NULL`
+/// \post `(`\p patch `== NULL) -> false`
+/// \internal
+///
+/// # Safety
+/// patch must be a valid pointer to an AMpatch
+#[no_mangle]
+pub unsafe extern "C" fn AMpatchConflict(patch: *const AMpatch) -> bool {
+    matches!(
+        patch.as_ref().map(|patch| &patch.body.action),
+        Some(PatchAction::PutMap { conflict: true, .. })
+            | Some(PatchAction::PutSeq { conflict: true, .. })
+            | Some(PatchAction::Conflict { .. })
+    )
+}
+
+/// \memberof AMpatch
+/// \brief Gets the values put, inserted or added by a patch.
+///
+/// \param[in] patch A pointer to an `AMpatch` struct.
+/// \return A pointer to an `AMresult` struct with an item for the value of an
+///         `AM_ACTION_TYPE_PUT_MAP` or `AM_ACTION_TYPE_PUT_SEQ` patch, an
+///         item for each value of an `AM_ACTION_TYPE_INSERT` patch, an
+///         `AM_VAL_TYPE_STR` item for the text of an
+///         `AM_ACTION_TYPE_SPLICE_TEXT` patch, an `AM_VAL_TYPE_INT` item for
+///         the increment of an `AM_ACTION_TYPE_INCREMENT` patch or an
+///         `AM_VAL_TYPE_VOID` item for any other patch. The object
+///         identifier of a put or inserted item identifies the value.
+/// \pre \p patch `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// patch must be a valid pointer to an AMpatch
+#[no_mangle]
+pub unsafe extern "C" fn AMpatchValue(patch: *const AMpatch) -> *mut AMresult {
+    let patch = to_patch!(patch);
+    match &patch.body.action {
+        PatchAction::PutMap {
+            value: (value, obj_id),
+            ..
+        }
+        | PatchAction::PutSeq {
+            value: (value, obj_id),
+            ..
+        } => AMresult::item(AMitem::exact(obj_id.clone(), value.clone().into())).into(),
+        PatchAction::Insert { values, .. } => AMresult::items(
+            values
+                .iter()
+                .map(|(value, obj_id, _)| AMitem::exact(obj_id.clone(), value.clone().into()))
+                .collect(),
+        )
+        .into(),
+        PatchAction::SpliceText { value, .. } => AMresult::item(value.make_string().into()).into(),
+        PatchAction::Increment { value, .. } => to_result(&am::ScalarValue::Int(*value)),
+        _ => AMresult::item(Default::default()).into(),
+    }
+}
+
+/// \memberof AMpatch
+/// \brief Gets the marks created or cleared by a patch.
+///
+/// \param[in] patch A pointer to an `AMpatch` struct.
+/// \return A pointer to an `AMresult` struct with `AM_VAL_TYPE_MARK` items for
+///         the marks of an `AM_ACTION_TYPE_MARK` patch or for the marks
+///         spanning the text of an `AM_ACTION_TYPE_SPLICE_TEXT` patch, or no
+///         items for any other patch. A cleared mark's value is null.
+/// \pre \p patch `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// patch must be a valid pointer to an AMpatch
+#[no_mangle]
+pub unsafe extern "C" fn AMpatchMarks(patch: *const AMpatch) -> *mut AMresult {
+    let patch = to_patch!(patch);
+    let marks: Vec<Mark> = match &patch.body.action {
+        PatchAction::Mark { marks } => marks.clone(),
+        PatchAction::SpliceText {
+            index,
+            value,
+            marks: Some(marks),
+        } => marks
+            .iter()
+            .map(|(name, mark_value)| {
+                Mark::new(
+                    name.to_string(),
+                    mark_value.clone(),
+                    *index,
+                    index + value.len(),
+                )
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    AMresult::items(marks.into_iter().map(AMitem::from).collect()).into()
+}
@@ -402,6 +402,15 @@ impl From<Result<Vec<Mark>, am::AutomergeError>> for AMresult {
     }
 }
 
+impl From<Result<Vec<am::Patch>, am::AutomergeError>> for AMresult {
+    fn from(maybe: Result<Vec<am::Patch>, am::AutomergeError>) -> Self {
+        match maybe {
+            Ok(patches) => Self::from(patches),
+            Err(e) => Self::error(&e.to_string()),
+        }
+    }
+}
+
 impl From<Result<Vec<u8>, am::AutomergeError>> for AMresult {
     fn from(maybe: Result<Vec<u8>, am::AutomergeError>) -> Self {
         match maybe {
@@ -457,6 +466,12 @@ impl From<Vec<am::sync::Have>> for AMresult {
     }
 }
 
+impl From<Vec<am::Patch>> for AMresult {
+    fn from(patches: Vec<am::Patch>) -> Self {
+        Self::items(patches.into_iter().map(|patch| patch.into()).collect())
+    }
+}
+
 impl From<Vec<u8>> for AMresult {
     fn from(bytes: Vec<u8>) -> Self {
         Self::item(am::Value::bytes(bytes).into())
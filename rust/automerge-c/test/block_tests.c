@@ -0,0 +1,369 @@
+#include <setjmp.h>
+#include <stdarg.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+
+/* third-party */
+#include <cmocka.h>
+
+/* local */
+#include <automerge-c/automerge.h>
+#include <automerge-c/config.h>
+#include <automerge-c/utils/result.h>
+#include <automerge-c/utils/stack_callback_data.h>
+#include "base_state.h"
+#include "cmocka_utils.h"
+#include "doc_state.h"
+
+/** \brief The width of a block marker's placeholder character in the text
+ *         encoding's units. */
+#ifdef AUTOMERGE_C_UTF8
+#define BLOCK_WIDTH 3
+#else
+#define BLOCK_WIDTH 1
+#endif
+
+#define assert_str_equal(str, expected)                         \
+    do {                                                        \
+        assert_int_equal((str).count, strlen(expected));        \
+        assert_memory_equal((str).src, expected, (str).count);  \
+    } while (0)
+
+#define assert_block_equal(stack_ptr, span, expected)                                                          \
+    do {                                                                                                       \
+        AMbyteSpan json;                                                                                       \
+        assert_true(AMspanIsBlock(span));                                                                      \
+        assert_true(AMitemToStr(AMstackItem(stack_ptr, AMspanBlock(span), cmocka_cb, AMexpect(AM_VAL_TYPE_STR)), \
+                                &json));                                                                       \
+        assert_str_equal(json, expected);                                                                      \
+    } while (0)
+
+#define assert_text_equal(span, expected)           \
+    do {                                            \
+        assert_false(AMspanIsBlock(span));          \
+        assert_str_equal(AMspanText(span), expected); \
+    } while (0)
+
+static AMobjId const* put_text(DocState* doc_state) {
+    return AMitemObjId(AMstackItem(&doc_state->base_state->stack,
+                                   AMmapPutObject(doc_state->doc, AM_ROOT, AMstr("text"), AM_OBJ_TYPE_TEXT), cmocka_cb,
+                                   AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+}
+
+static AMobjId const* split_block(DocState* doc_state, AMobjId const* text, size_t pos, char const* type) {
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMobjId const* const block = AMitemObjId(
+        AMstackItem(stack_ptr, AMsplitBlock(doc_state->doc, text, pos), cmocka_cb, AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    AMstackItem(NULL, AMmapPutStr(doc_state->doc, block, AMstr("type"), AMstr(type)), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItem(NULL, AMmapPutObject(doc_state->doc, block, AMstr("parents"), AM_OBJ_TYPE_LIST), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_OBJ_TYPE));
+    AMstackItem(NULL, AMmapPutObject(doc_state->doc, block, AMstr("attrs"), AM_OBJ_TYPE_MAP), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_OBJ_TYPE));
+    return block;
+}
+
+static void test_AMsplitBlock_and_AMjoinBlock(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMobjId const* const text = put_text(doc_state);
+    AMobjId const* const block = split_block(doc_state, text, 0, "paragraph");
+    AMstackItem(NULL, AMspliceText(doc_state->doc, text, BLOCK_WIDTH, 0, AMstr("hello")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMitems spans = AMstackItems(stack_ptr, AMspans(doc_state->doc, text, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_SPAN));
+    assert_int_equal(AMitemsSize(&spans), 2);
+    AMspan const* span;
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_block_equal(stack_ptr, span, "{\"attrs\":{},\"parents\":[],\"type\":\"paragraph\"}");
+    assert_true(AMobjIdEqual(AMspanObjId(span), block));
+    assert_int_equal(AMspanStart(span), 0);
+    assert_int_equal(AMspanEnd(span), BLOCK_WIDTH);
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_text_equal(span, "hello");
+    assert_null(AMspanObjId(span));
+    assert_int_equal(AMspanStart(span), BLOCK_WIDTH);
+    assert_int_equal(AMspanEnd(span), BLOCK_WIDTH + 5);
+    /* Joining removes the block marker. */
+    AMstackItem(NULL, AMjoinBlock(doc_state->doc, text, 0), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    spans = AMstackItems(stack_ptr, AMspans(doc_state->doc, text, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_SPAN));
+    assert_int_equal(AMitemsSize(&spans), 1);
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_text_equal(span, "hello");
+    assert_int_equal(AMspanStart(span), 0);
+    assert_int_equal(AMspanEnd(span), 5);
+}
+
+static void test_AMreplaceBlock(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMobjId const* const text = put_text(doc_state);
+    AMobjId const* const block = split_block(doc_state, text, 0, "paragraph");
+    AMobjId const* const replacement = AMitemObjId(
+        AMstackItem(stack_ptr, AMreplaceBlock(doc_state->doc, text, 0), cmocka_cb, AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    assert_false(AMobjIdEqual(replacement, block));
+    AMitems spans = AMstackItems(stack_ptr, AMspans(doc_state->doc, text, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_SPAN));
+    assert_int_equal(AMitemsSize(&spans), 1);
+    AMspan const* span;
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_block_equal(stack_ptr, span, "{}");
+    assert_true(AMobjIdEqual(AMspanObjId(span), replacement));
+}
+
+static void test_AMupdateSpans_change_block_properties(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMobjId const* const text = put_text(doc_state);
+    split_block(doc_state, text, 0, "ordered-list-item");
+    AMstackItem(NULL, AMspliceText(doc_state->doc, text, BLOCK_WIDTH, 0, AMstr("item 1")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    split_block(doc_state, text, BLOCK_WIDTH + 6, "ordered-list-item");
+    AMstackItem(NULL, AMspliceText(doc_state->doc, text, 2 * BLOCK_WIDTH + 6, 0, AMstr("item 2")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMresult* const update = AMstackResult(
+        stack_ptr,
+        AMresultFrom(4, AMitemFromBlock(AMstr("{\"type\": \"paragraph\", \"parents\": [], \"attrs\": {}}")),
+                     AMitemFromStr(AMstr("item 1")),
+                     AMitemFromBlock(AMstr("{\"type\": \"unordered-list-item\", \"parents\": "
+                                           "[\"ordered-list-item\"], \"attrs\": {\"key\": 1}}")),
+                     AMitemFromStr(AMstr("item 2"))),
+        NULL, NULL);
+    AMstackItem(NULL, AMupdateSpans(doc_state->doc, text, AMresultItems(update)), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMitems spans = AMstackItems(stack_ptr, AMspans(doc_state->doc, text, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_SPAN));
+    assert_int_equal(AMitemsSize(&spans), 4);
+    AMspan const* span;
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_block_equal(stack_ptr, span, "{\"attrs\":{},\"parents\":[],\"type\":\"paragraph\"}");
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_text_equal(span, "item 1");
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_block_equal(stack_ptr, span,
+                       "{\"attrs\":{\"key\":1},\"parents\":[\"ordered-list-item\"],\"type\":\"unordered-list-item\"}");
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_text_equal(span, "item 2");
+}
+
+static void test_AMupdateSpans_updates_text(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMobjId const* const text = put_text(doc_state);
+    split_block(doc_state, text, 0, "ordered-list-item");
+    AMstackItem(NULL, AMspliceText(doc_state->doc, text, BLOCK_WIDTH, 0, AMstr("first thing")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    split_block(doc_state, text, BLOCK_WIDTH + 11, "paragraph");
+    AMstackItem(NULL, AMspliceText(doc_state->doc, text, 2 * BLOCK_WIDTH + 11, 0, AMstr("second thing")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    /* The spans of a document can be fed back into it. */
+    AMitems spans = AMstackItems(stack_ptr, AMspans(doc_state->doc, text, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_SPAN));
+    AMitem* const first_block = AMitemsNext(&spans, 1);
+    AMitemsNext(&spans, 1);
+    AMitem* const second_block = AMitemsNext(&spans, 1);
+    AMresult* const update = AMstackResult(
+        stack_ptr,
+        AMresultFrom(4, AMitemResult(first_block), AMitemFromStr(AMstr("the first thing")),
+                     AMitemResult(second_block), AMitemFromStr(AMstr("the things are done"))),
+        NULL, NULL);
+    AMstackItem(NULL, AMupdateSpans(doc_state->doc, text, AMresultItems(update)), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    spans = AMstackItems(stack_ptr, AMspans(doc_state->doc, text, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_SPAN));
+    assert_int_equal(AMitemsSize(&spans), 4);
+    AMspan const* span;
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_block_equal(stack_ptr, span, "{\"attrs\":{},\"parents\":[],\"type\":\"ordered-list-item\"}");
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_text_equal(span, "the first thing");
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_block_equal(stack_ptr, span, "{\"attrs\":{},\"parents\":[],\"type\":\"paragraph\"}");
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_text_equal(span, "the things are done");
+}
+
+static void test_AMupdateSpans_noop(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMobjId const* const text = put_text(doc_state);
+    split_block(doc_state, text, 0, "ordered-list-item");
+    AMstackItem(NULL, AMspliceText(doc_state->doc, text, BLOCK_WIDTH, 0, AMstr("item 1")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItems(stack_ptr, AMdiffIncremental(doc_state->doc), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    AMresult* const update = AMstackResult(
+        stack_ptr,
+        AMresultFrom(2, AMitemFromBlock(AMstr("{\"type\": \"ordered-list-item\", \"parents\": [], \"attrs\": {}}")),
+                     AMitemFromStr(AMstr("item 1"))),
+        NULL, NULL);
+    AMstackItem(NULL, AMupdateSpans(doc_state->doc, text, AMresultItems(update)), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMitems const patches =
+        AMstackItems(stack_ptr, AMdiffIncremental(doc_state->doc), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    assert_int_equal(AMitemsSize(&patches), 0);
+}
+
+static void test_AMupdateSpans_updates_text_and_blocks_at_once(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMobjId const* const text = put_text(doc_state);
+    split_block(doc_state, text, 0, "paragraph");
+    AMstackItem(NULL, AMspliceText(doc_state->doc, text, BLOCK_WIDTH, 0, AMstr("hello world")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMresult* const update = AMstackResult(
+        stack_ptr,
+        AMresultFrom(2, AMitemFromBlock(AMstr("{\"type\": \"unordered-list-item\", \"parents\": [], \"attrs\": {}}")),
+                     AMitemFromStr(AMstr("goodbye world"))),
+        NULL, NULL);
+    AMstackItem(NULL, AMupdateSpans(doc_state->doc, text, AMresultItems(update)), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMitems spans = AMstackItems(stack_ptr, AMspans(doc_state->doc, text, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_SPAN));
+    assert_int_equal(AMitemsSize(&spans), 2);
+    AMspan const* span;
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_block_equal(stack_ptr, span, "{\"attrs\":{},\"parents\":[],\"type\":\"unordered-list-item\"}");
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_text_equal(span, "goodbye world");
+}
+
+/** \brief Inserts a block marker whose type and parents are text objects. */
+static AMobjId const* split_complex_block(DocState* doc_state, AMobjId const* text) {
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMobjId const* const block = AMitemObjId(
+        AMstackItem(stack_ptr, AMsplitBlock(doc_state->doc, text, 0), cmocka_cb, AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    AMobjId const* const type = AMitemObjId(AMstackItem(
+        stack_ptr, AMmapPutObject(doc_state->doc, block, AMstr("type"), AM_OBJ_TYPE_TEXT), cmocka_cb,
+        AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    AMstackItem(NULL, AMspliceText(doc_state->doc, type, 0, 0, AMstr("ordered-list-item")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMobjId const* const parents = AMitemObjId(AMstackItem(
+        stack_ptr, AMmapPutObject(doc_state->doc, block, AMstr("parents"), AM_OBJ_TYPE_LIST), cmocka_cb,
+        AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    AMobjId const* const parent = AMitemObjId(
+        AMstackItem(stack_ptr, AMlistPutObject(doc_state->doc, parents, 0, true, AM_OBJ_TYPE_TEXT), cmocka_cb,
+                    AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    AMstackItem(NULL, AMspliceText(doc_state->doc, parent, 0, 0, AMstr("div")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    return block;
+}
+
+static void test_text_complex_block_properties(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMobjId const* const text = put_text(doc_state);
+    AMobjId const* const block = split_complex_block(doc_state, text);
+    AMobjId const* const type = AMitemObjId(AMstackItem(stack_ptr, AMmapGet(doc_state->doc, block, AMstr("type"), NULL),
+                                                        cmocka_cb, AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    assert_int_equal(AMobjObjType(doc_state->doc, type), AM_OBJ_TYPE_TEXT);
+    AMbyteSpan str;
+    assert_true(AMitemToStr(
+        AMstackItem(stack_ptr, AMtext(doc_state->doc, type, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_STR)), &str));
+    assert_str_equal(str, "ordered-list-item");
+    AMobjId const* const parents = AMitemObjId(AMstackItem(
+        stack_ptr, AMmapGet(doc_state->doc, block, AMstr("parents"), NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    assert_int_equal(AMobjObjType(doc_state->doc, parents), AM_OBJ_TYPE_LIST);
+    assert_int_equal(AMobjSize(doc_state->doc, parents, NULL), 1);
+    AMobjId const* const parent = AMitemObjId(AMstackItem(stack_ptr, AMlistGet(doc_state->doc, parents, 0, NULL),
+                                                          cmocka_cb, AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    assert_int_equal(AMobjObjType(doc_state->doc, parent), AM_OBJ_TYPE_TEXT);
+    assert_true(AMitemToStr(
+        AMstackItem(stack_ptr, AMtext(doc_state->doc, parent, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_STR)), &str));
+    assert_str_equal(str, "div");
+}
+
+static void test_AMupdateSpans_delete_attribute(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMobjId const* const text = put_text(doc_state);
+    split_complex_block(doc_state, text);
+    AMresult* const update =
+        AMstackResult(stack_ptr,
+                      AMresultFrom(1, AMitemFromBlock(AMstr("{\"type\": \"ordered-list-item\", \"parents\": []}"))),
+                      NULL, NULL);
+    AMstackItem(NULL, AMupdateSpans(doc_state->doc, text, AMresultItems(update)), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMitems spans = AMstackItems(stack_ptr, AMspans(doc_state->doc, text, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_SPAN));
+    assert_int_equal(AMitemsSize(&spans), 1);
+    AMspan const* span;
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_block_equal(stack_ptr, span, "{\"parents\":[],\"type\":\"ordered-list-item\"}");
+}
+
+static void test_AMitemFromBlock_rejects_non_objects(void** state) {
+    AMresult* result = AMitemFromBlock(AMstr("[\"paragraph\"]"));
+    assert_int_equal(AMresultStatus(result), AM_STATUS_ERROR);
+    AMresultFree(result);
+    result = AMitemFromBlock(AMstr("{\"type\": "));
+    assert_int_equal(AMresultStatus(result), AM_STATUS_ERROR);
+    AMresultFree(result);
+}
+
+static void test_AMspanMarks_respect_heads(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMobjId const* const text = put_text(doc_state);
+    AMstackItem(NULL, AMspliceText(doc_state->doc, text, 0, 0, AMstr("hello world")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMitem* const value = AMstackItem(stack_ptr, AMitemFromBool(true), cmocka_cb, AMexpect(AM_VAL_TYPE_BOOL));
+    AMstackItem(NULL, AMmarkCreate(doc_state->doc, text, 0, 5, AM_MARK_EXPAND_AFTER, AMstr("bold"), value), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMitems const heads =
+        AMstackItems(stack_ptr, AMgetHeads(doc_state->doc), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    AMstackItem(NULL, AMmarkCreate(doc_state->doc, text, 5, 11, AM_MARK_EXPAND_AFTER, AMstr("italic"), value),
+                cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    AMitems spans =
+        AMstackItems(stack_ptr, AMspans(doc_state->doc, text, &heads), cmocka_cb, AMexpect(AM_VAL_TYPE_SPAN));
+    assert_int_equal(AMitemsSize(&spans), 2);
+    AMspan const* span;
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_text_equal(span, "hello");
+    AMitems marks = AMstackItems(stack_ptr, AMspanMarks(span), cmocka_cb, AMexpect(AM_VAL_TYPE_MARK));
+    assert_int_equal(AMitemsSize(&marks), 1);
+    AMmark const* mark;
+    assert_true(AMitemToMark(AMitemsNext(&marks, 1), &mark));
+    assert_str_equal(AMmarkName(mark), "bold");
+    assert_int_equal(AMmarkStart(mark), 0);
+    assert_int_equal(AMmarkEnd(mark), 5);
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    assert_text_equal(span, " world");
+    marks = AMstackItems(stack_ptr, AMspanMarks(span), cmocka_cb, AMexpect(AM_VAL_TYPE_MARK));
+    assert_int_equal(AMitemsSize(&marks), 0);
+    /* The current spans include the later mark. */
+    spans = AMstackItems(stack_ptr, AMspans(doc_state->doc, text, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_SPAN));
+    assert_int_equal(AMitemsSize(&spans), 2);
+    AMitemsNext(&spans, 1);
+    assert_true(AMitemToSpan(AMitemsNext(&spans, 1), &span));
+    marks = AMstackItems(stack_ptr, AMspanMarks(span), cmocka_cb, AMexpect(AM_VAL_TYPE_MARK));
+    assert_int_equal(AMitemsSize(&marks), 1);
+    assert_true(AMitemToMark(AMitemsNext(&marks, 1), &mark));
+    assert_str_equal(AMmarkName(mark), "italic");
+    assert_int_equal(AMmarkStart(mark), 5);
+    assert_int_equal(AMmarkEnd(mark), 11);
+}
+
+/* These tests mirror `automerge/tests/block_tests.rs` except for
+ * `update_spans_by_word`, which needs a text granularity that
+ * `AMupdateSpans()` has no parameter for. */
+int run_block_tests(void) {
+    struct CMUnitTest const tests[] = {
+        cmocka_unit_test_setup_teardown(test_AMsplitBlock_and_AMjoinBlock, setup_doc, teardown_doc),
+        cmocka_unit_test_setup_teardown(test_AMreplaceBlock, setup_doc, teardown_doc),
+        cmocka_unit_test_setup_teardown(test_AMupdateSpans_change_block_properties, setup_doc, teardown_doc),
+        cmocka_unit_test_setup_teardown(test_AMupdateSpans_updates_text, setup_doc, teardown_doc),
+        cmocka_unit_test_setup_teardown(test_AMupdateSpans_noop, setup_doc, teardown_doc),
+        cmocka_unit_test_setup_teardown(test_AMupdateSpans_updates_text_and_blocks_at_once, setup_doc, teardown_doc),
+        cmocka_unit_test_setup_teardown(test_text_complex_block_properties, setup_doc, teardown_doc),
+        cmocka_unit_test_setup_teardown(test_AMupdateSpans_delete_attribute, setup_doc, teardown_doc),
+        cmocka_unit_test(test_AMitemFromBlock_rejects_non_objects),
+        cmocka_unit_test_setup_teardown(test_AMspanMarks_respect_heads, setup_doc, teardown_doc),
+    };
+
+    return cmocka_run_group_tests(tests, NULL, NULL);
+}
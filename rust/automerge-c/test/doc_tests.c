@@ -320,15 +320,182 @@ static void test_AMspliceText(void** state) {
 #endif
 }
 
+static void test_AMupdateText(void** state) {
+    TestState* test_state = *state;
+    AMstack** stack_ptr = &test_state->doc_state->base_state->stack;
+    AMdoc* const doc = test_state->doc_state->doc;
+    AMobjId const* const text =
+        AMitemObjId(AMstackItem(stack_ptr, AMmapPutObject(doc, AM_ROOT, AMstr("text"), AM_OBJ_TYPE_TEXT), cmocka_cb,
+                                AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    AMstackItem(NULL, AMspliceText(doc, text, 0, 0, AMstr("the quick fox")), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItem(NULL, AMdiffIncremental(doc), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    AMstackItem(NULL, AMupdateText(doc, text, AMstr("the quick brown fox")), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    AMbyteSpan str;
+    assert_true(
+        AMitemToStr(AMstackItem(stack_ptr, AMtext(doc, text, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_STR)), &str));
+    assert_str_equal(str, "the quick brown fox");
+    /* Only the difference was spliced in. */
+    AMitems patches = AMstackItems(stack_ptr, AMdiffIncremental(doc), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    assert_int_equal(AMitemsSize(&patches), 1);
+    AMpatch const* patch;
+    assert_true(AMitemToPatch(AMitemsNext(&patches, 1), &patch));
+    assert_int_equal(AMpatchAction(patch), AM_ACTION_TYPE_SPLICE_TEXT);
+    assert_true(
+        AMitemToStr(AMstackItem(stack_ptr, AMpatchValue(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_STR)), &str));
+    assert_str_equal(str, "brown ");
+}
+
+static void test_AMhydrate(void** state) {
+    TestState* test_state = *state;
+    AMstack** stack_ptr = &test_state->doc_state->base_state->stack;
+    AMdoc* const doc = test_state->doc_state->doc;
+    AMobjId const* const list =
+        AMitemObjId(AMstackItem(stack_ptr, AMmapPutObject(doc, AM_ROOT, AMstr("list"), AM_OBJ_TYPE_LIST), cmocka_cb,
+                                AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    AMstackItem(NULL, AMlistPutInt(doc, list, SIZE_MAX, true, 1), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItem(NULL, AMlistPutStr(doc, list, SIZE_MAX, true, AMstr("two")), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    AMobjId const* const text =
+        AMitemObjId(AMstackItem(stack_ptr, AMmapPutObject(doc, AM_ROOT, AMstr("text"), AM_OBJ_TYPE_TEXT), cmocka_cb,
+                                AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    AMstackItem(NULL, AMspliceText(doc, text, 0, 0, AMstr("hi")), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItem(NULL, AMcommit(doc, AMstr(NULL), NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    AMitems const heads = AMstackItems(stack_ptr, AMgetHeads(doc), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    AMstackItem(NULL, AMmapPutStr(doc, AM_ROOT, AMstr("title"), AMstr("x")), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    /* JSON */
+    AMbyteSpan str;
+    assert_true(AMitemToStr(
+        AMstackItem(stack_ptr, AMhydrate(doc, AM_ROOT, NULL, true), cmocka_cb, AMexpect(AM_VAL_TYPE_STR)), &str));
+    assert_str_equal(str, "{\"list\":[1,\"two\"],\"text\":\"hi\",\"title\":\"x\"}");
+    assert_true(AMitemToStr(
+        AMstackItem(stack_ptr, AMhydrate(doc, AM_ROOT, &heads, true), cmocka_cb, AMexpect(AM_VAL_TYPE_STR)), &str));
+    assert_str_equal(str, "{\"list\":[1,\"two\"],\"text\":\"hi\"}");
+    /* Tree of items */
+    AMitems items = AMstackItems(stack_ptr, AMhydrate(doc, AM_ROOT, &heads, false), cmocka_cb,
+                                 AMexpect(AM_VAL_TYPE_OBJ_TYPE));
+    assert_int_equal(AMitemsSize(&items), 2);
+    AMitem* item = AMitemsNext(&items, 1);
+    AMbyteSpan key;
+    assert_true(AMitemKey(item, &key));
+    assert_str_equal(key, "list");
+    assert_true(AMobjIdEqual(AMitemObjId(item), list));
+    AMitems children = AMitemChildren(item);
+    assert_int_equal(AMitemsSize(&children), 2);
+    size_t pos;
+    int64_t i;
+    item = AMitemsNext(&children, 1);
+    assert_true(AMitemPos(item, &pos));
+    assert_int_equal(pos, 0);
+    assert_true(AMitemToInt(item, &i));
+    assert_int_equal(i, 1);
+    item = AMitemsNext(&children, 1);
+    assert_true(AMitemPos(item, &pos));
+    assert_int_equal(pos, 1);
+    assert_true(AMitemToStr(item, &str));
+    assert_str_equal(str, "two");
+    AMitems const grandchildren = AMitemChildren(item);
+    assert_int_equal(AMitemsSize(&grandchildren), 0);
+    item = AMitemsNext(&items, 1);
+    assert_true(AMitemKey(item, &key));
+    assert_str_equal(key, "text");
+    children = AMitemChildren(item);
+    assert_int_equal(AMitemsSize(&children), 1);
+    assert_true(AMitemToStr(AMitemsNext(&children, 1), &str));
+    assert_str_equal(str, "hi");
+    /* A text object by itself */
+    assert_true(AMitemToStr(
+        AMstackItem(stack_ptr, AMhydrate(doc, text, NULL, false), cmocka_cb, AMexpect(AM_VAL_TYPE_STR)), &str));
+    assert_str_equal(str, "hi");
+}
+
+static void test_AMloadWith_and_AMsaveWith(void** state) {
+    TestState* test_state = *state;
+    AMstack** stack_ptr = &test_state->doc_state->base_state->stack;
+    AMdoc* const doc = test_state->doc_state->doc;
+    AMstackItem(NULL, AMmapPutStr(doc, AM_ROOT, AMstr("title"), AMstr("hello")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    /* The default options are those of `AMsave()`. */
+    AMbyteSpan saved;
+    assert_true(AMitemToBytes(AMstackItem(stack_ptr, AMsave(doc), cmocka_cb, AMexpect(AM_VAL_TYPE_BYTES)), &saved));
+    AMbyteSpan bytes;
+    assert_true(
+        AMitemToBytes(AMstackItem(stack_ptr, AMsaveWith(doc, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_BYTES)), &bytes));
+    assert_int_equal(bytes.count, saved.count);
+    assert_memory_equal(bytes.src, saved.src, bytes.count);
+    /* So are those of a zero-initialized options struct. */
+    assert_true(AMitemToBytes(AMstackItem(stack_ptr, AMsaveWith(doc, &(AMsaveOptions){0}), cmocka_cb,
+                                          AMexpect(AM_VAL_TYPE_BYTES)),
+                              &bytes));
+    assert_int_equal(bytes.count, saved.count);
+    assert_memory_equal(bytes.src, saved.src, bytes.count);
+    AMsaveOptions const save_options = {.compression = AM_COMPRESSION_NONE, .orphans = AM_ORPHANS_DISCARD};
+    assert_true(AMitemToBytes(
+        AMstackItem(stack_ptr, AMsaveWith(doc, &save_options), cmocka_cb, AMexpect(AM_VAL_TYPE_BYTES)), &bytes));
+    /* A zero-initialized options struct selects the default options. */
+    AMdoc* loaded;
+    assert_true(AMitemToDoc(AMstackItem(stack_ptr, AMloadWith(bytes.src, bytes.count, &(AMloadOptions){0}),
+                                        cmocka_cb, AMexpect(AM_VAL_TYPE_DOC)),
+                            &loaded));
+    AMbyteSpan str;
+    assert_true(AMitemToStr(AMstackItem(stack_ptr, AMmapGet(loaded, AM_ROOT, AMstr("title"), NULL), cmocka_cb,
+                                        AMexpect(AM_VAL_TYPE_STR)),
+                            &str));
+    assert_str_equal(str, "hello");
+    /* String migration converts string values into text objects. */
+    AMloadOptions const load_options = {.string_migration = AM_STRING_MIGRATION_CONVERT_TO_TEXT,
+                                        .verification_mode = AM_VERIFICATION_MODE_DONT_CHECK};
+    assert_true(AMitemToDoc(AMstackItem(stack_ptr, AMloadWith(bytes.src, bytes.count, &load_options), cmocka_cb,
+                                        AMexpect(AM_VAL_TYPE_DOC)),
+                            &loaded));
+    AMobjId const* const title = AMitemObjId(AMstackItem(stack_ptr, AMmapGet(loaded, AM_ROOT, AMstr("title"), NULL),
+                                                         cmocka_cb, AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    assert_int_equal(AMobjObjType(loaded, title), AM_OBJ_TYPE_TEXT);
+    assert_true(
+        AMitemToStr(AMstackItem(stack_ptr, AMtext(loaded, title, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_STR)), &str));
+    assert_str_equal(str, "hello");
+    /* Only the text encoding of the build is supported. */
+#if defined(AUTOMERGE_C_UTF8)
+    AMloadOptions const unsupported = {.text_encoding = AM_TEXT_ENCODING_UNICODE_CODE_POINT};
+#else
+    AMloadOptions const unsupported = {.text_encoding = AM_TEXT_ENCODING_UTF8_CODE_UNIT};
+#endif
+    AMresult* result = AMloadWith(bytes.src, bytes.count, &unsupported);
+    assert_int_equal(AMresultStatus(result), AM_STATUS_ERROR);
+    AMresultFree(result);
+    /* Truncated input is an error unless a partial load is allowed. */
+    result = AMloadWith(bytes.src, bytes.count - 1, NULL);
+    assert_int_equal(AMresultStatus(result), AM_STATUS_ERROR);
+    AMresultFree(result);
+    /* zstd compression is only supported by builds which define `AUTOMERGE_C_ZSTD`. */
+    AMsaveOptions const zstd_options = {.compression = AM_COMPRESSION_ZSTD, .compression_threshold = 1};
+#if defined(AUTOMERGE_C_ZSTD)
+    assert_true(AMitemToBytes(
+        AMstackItem(stack_ptr, AMsaveWith(doc, &zstd_options), cmocka_cb, AMexpect(AM_VAL_TYPE_BYTES)), &bytes));
+    assert_true(AMitemToDoc(
+        AMstackItem(stack_ptr, AMloadWith(bytes.src, bytes.count, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_DOC)),
+        &loaded));
+    assert_true(AMitemToStr(AMstackItem(stack_ptr, AMmapGet(loaded, AM_ROOT, AMstr("title"), NULL), cmocka_cb,
+                                        AMexpect(AM_VAL_TYPE_STR)),
+                            &str));
+    assert_str_equal(str, "hello");
+#else
+    result = AMsaveWith(doc, &zstd_options);
+    assert_int_equal(AMresultStatus(result), AM_STATUS_ERROR);
+    AMresultFree(result);
+#endif
+}
+
 int run_doc_tests(void) {
     const struct CMUnitTest tests[] = {
         cmocka_unit_test_setup_teardown(test_AMkeys_empty, setup, teardown),
         cmocka_unit_test_setup_teardown(test_AMkeys_list, setup, teardown),
         cmocka_unit_test_setup_teardown(test_AMkeys_map, setup, teardown),
+        cmocka_unit_test_setup_teardown(test_AMhydrate, setup, teardown),
         cmocka_unit_test_setup_teardown(test_AMload, setup, teardown),
+        cmocka_unit_test_setup_teardown(test_AMloadWith_and_AMsaveWith, setup, teardown),
         cmocka_unit_test_setup_teardown(test_AMputActor_bytes, setup, teardown),
         cmocka_unit_test_setup_teardown(test_AMputActor_str, setup, teardown),
         cmocka_unit_test_setup_teardown(test_AMspliceText, setup, teardown),
+        cmocka_unit_test_setup_teardown(test_AMupdateText, setup, teardown),
     };
 
     return cmocka_run_group_tests(tests, NULL, NULL);
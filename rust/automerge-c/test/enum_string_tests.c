@@ -20,6 +20,40 @@
         assert_int_equal(out, tag);             \
     } while (0)
 
+static void test_AMactionTypeToString(void** state) {
+    assert_to_string(AMactionTypeToString, AM_ACTION_TYPE_DEFAULT);
+    assert_to_string(AMactionTypeToString, AM_ACTION_TYPE_CONFLICT);
+    assert_to_string(AMactionTypeToString, AM_ACTION_TYPE_DEL_MAP);
+    assert_to_string(AMactionTypeToString, AM_ACTION_TYPE_DEL_SEQ);
+    assert_to_string(AMactionTypeToString, AM_ACTION_TYPE_INCREMENT);
+    assert_to_string(AMactionTypeToString, AM_ACTION_TYPE_INSERT);
+    assert_to_string(AMactionTypeToString, AM_ACTION_TYPE_MARK);
+    assert_to_string(AMactionTypeToString, AM_ACTION_TYPE_PUT_MAP);
+    assert_to_string(AMactionTypeToString, AM_ACTION_TYPE_PUT_SEQ);
+    assert_to_string(AMactionTypeToString, AM_ACTION_TYPE_SPLICE_TEXT);
+    /* Zero tag */
+    assert_string_equal(AMactionTypeToString(0), "AM_ACTION_TYPE_DEFAULT");
+    /* Invalid tag */
+    assert_string_equal(AMactionTypeToString(-1), "???");
+}
+
+static void test_AMactionTypeFromString(void** state) {
+    assert_from_string(AMactionTypeFromString, AMactionType, AM_ACTION_TYPE_DEFAULT);
+    assert_from_string(AMactionTypeFromString, AMactionType, AM_ACTION_TYPE_CONFLICT);
+    assert_from_string(AMactionTypeFromString, AMactionType, AM_ACTION_TYPE_DEL_MAP);
+    assert_from_string(AMactionTypeFromString, AMactionType, AM_ACTION_TYPE_DEL_SEQ);
+    assert_from_string(AMactionTypeFromString, AMactionType, AM_ACTION_TYPE_INCREMENT);
+    assert_from_string(AMactionTypeFromString, AMactionType, AM_ACTION_TYPE_INSERT);
+    assert_from_string(AMactionTypeFromString, AMactionType, AM_ACTION_TYPE_MARK);
+    assert_from_string(AMactionTypeFromString, AMactionType, AM_ACTION_TYPE_PUT_MAP);
+    assert_from_string(AMactionTypeFromString, AMactionType, AM_ACTION_TYPE_PUT_SEQ);
+    assert_from_string(AMactionTypeFromString, AMactionType, AM_ACTION_TYPE_SPLICE_TEXT);
+    /* Invalid tag */
+    AMactionType out = -1;
+    assert_false(AMactionTypeFromString(&out, "???"));
+    assert_int_equal(out, (AMactionType)-1);
+}
+
 static void test_AMidxTypeToString(void** state) {
     assert_to_string(AMidxTypeToString, AM_IDX_TYPE_DEFAULT);
     assert_to_string(AMidxTypeToString, AM_IDX_TYPE_KEY);
@@ -95,6 +129,8 @@ static void test_AMvalTypeToString(void** state) {
     assert_to_string(AMvalTypeToString, AM_VAL_TYPE_INT);
     assert_to_string(AMvalTypeToString, AM_VAL_TYPE_NULL);
     assert_to_string(AMvalTypeToString, AM_VAL_TYPE_OBJ_TYPE);
+    assert_to_string(AMvalTypeToString, AM_VAL_TYPE_PATCH);
+    assert_to_string(AMvalTypeToString, AM_VAL_TYPE_SPAN);
     assert_to_string(AMvalTypeToString, AM_VAL_TYPE_STR);
     assert_to_string(AMvalTypeToString, AM_VAL_TYPE_SYNC_HAVE);
     assert_to_string(AMvalTypeToString, AM_VAL_TYPE_SYNC_MESSAGE);
@@ -122,6 +158,8 @@ static void test_AMvalTypeFromString(void** state) {
     assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_INT);
     assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_NULL);
     assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_OBJ_TYPE);
+    assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_PATCH);
+    assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_SPAN);
     assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_STR);
     assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_SYNC_HAVE);
     assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_SYNC_MESSAGE);
@@ -138,6 +176,7 @@ static void test_AMvalTypeFromString(void** state) {
 
 int run_enum_string_tests(void) {
     const struct CMUnitTest tests[] = {
+        cmocka_unit_test(test_AMactionTypeToString), cmocka_unit_test(test_AMactionTypeFromString),
         cmocka_unit_test(test_AMidxTypeToString), cmocka_unit_test(test_AMidxTypeFromString),
         cmocka_unit_test(test_AMobjTypeToString), cmocka_unit_test(test_AMobjTypeFromString),
         cmocka_unit_test(test_AMstatusToString),  cmocka_unit_test(test_AMstatusFromString),
@@ -8,6 +8,8 @@
 
 extern int run_actor_id_tests(void);
 
+extern int run_block_tests(void);
+
 extern int run_byte_span_tests(void);
 
 extern int run_cursor_tests(void);
@@ -24,10 +26,14 @@ extern int run_map_tests(void);
 
 extern int run_mark_tests(void);
 
+extern int run_observer_tests(void);
+
+extern int run_patch_tests(void);
+
 extern int run_ported_wasm_suite(void);
 
 int main(void) {
-    return run_actor_id_tests() + run_byte_span_tests() + run_cursor_tests() + run_doc_tests() +
+    return run_actor_id_tests() + run_block_tests() + run_byte_span_tests() + run_cursor_tests() + run_doc_tests() +
            run_enum_string_tests() + run_item_tests() + run_list_tests() + run_map_tests() + run_mark_tests() +
-           run_ported_wasm_suite();
+           run_observer_tests() + run_patch_tests() + run_ported_wasm_suite();
 }
@@ -0,0 +1,274 @@
+#include <setjmp.h>
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+
+/* third-party */
+#include <cmocka.h>
+
+/* local */
+#include <automerge-c/automerge.h>
+#include <automerge-c/utils/stack_callback_data.h>
+#include "base_state.h"
+#include "cmocka_utils.h"
+#include "doc_state.h"
+
+/** \brief What a change callback has been notified of. */
+typedef struct {
+    /** The count of notifications. */
+    size_t calls;
+    /** The heads of the latest notification. */
+    AMresult* heads;
+    /** The patches of the latest notification. */
+    AMresult* patches;
+    /** A key to put into the document from within the callback or `NULL`. */
+    char const* reentrant_key;
+} Notifications;
+
+typedef struct {
+    DocState* doc_state;
+    Notifications notifications;
+} ObserverState;
+
+static void free_notifications(Notifications* notifications) {
+    AMresultFree(notifications->heads);
+    AMresultFree(notifications->patches);
+    notifications->heads = NULL;
+    notifications->patches = NULL;
+}
+
+static void on_change(AMdoc* doc, AMresult* heads, AMresult* patches, void* user_data) {
+    Notifications* notifications = user_data;
+    ++notifications->calls;
+    /* Keep the latest results; the callback owns them. */
+    free_notifications(notifications);
+    notifications->heads = heads;
+    notifications->patches = patches;
+    if (notifications->reentrant_key) {
+        AMresultFree(AMmapPutStr(doc, AM_ROOT, AMstr(notifications->reentrant_key), AMstr("nested")));
+        AMresultFree(AMcommit(doc, AMstr(NULL), NULL));
+        notifications->reentrant_key = NULL;
+    }
+}
+
+static int setup(void** state) {
+    setup_doc(state);
+    ObserverState* observer_state = test_calloc(1, sizeof(ObserverState));
+    observer_state->doc_state = *state;
+    AMstackItem(NULL,
+                AMsetChangeCallback(observer_state->doc_state->doc, on_change, &observer_state->notifications),
+                cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    *state = observer_state;
+    return 0;
+}
+
+static int teardown(void** state) {
+    ObserverState* observer_state = *state;
+    free_notifications(&observer_state->notifications);
+    *state = observer_state->doc_state;
+    test_free(observer_state);
+    return teardown_doc(state);
+}
+
+static void assert_heads_current(AMstack** stack_ptr, AMdoc* doc, AMresult* heads) {
+    AMitems const expected = AMstackItems(stack_ptr, AMgetHeads(doc), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    AMitems const actual = AMresultItems(heads);
+    assert_true(AMitemsEqual(&actual, &expected));
+}
+
+static void assert_put_map(AMresult* patches, size_t pos, char const* key) {
+    AMitems items = AMresultItems(patches);
+    AMitemsAdvance(&items, (ptrdiff_t)pos);
+    AMpatch const* patch;
+    assert_true(AMitemToPatch(AMitemsNext(&items, 1), &patch));
+    assert_int_equal(AMpatchAction(patch), AM_ACTION_TYPE_PUT_MAP);
+    AMresult* index = AMpatchIndex(patch);
+    AMbyteSpan str;
+    assert_true(AMitemKey(AMresultItem(index), &str));
+    assert_int_equal(str.count, strlen(key));
+    assert_memory_equal(str.src, key, str.count);
+    AMresultFree(index);
+}
+
+static void test_AMcommit_notifies(void** state) {
+    ObserverState* observer_state = *state;
+    DocState* doc_state = observer_state->doc_state;
+    Notifications* notifications = &observer_state->notifications;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMstackItem(NULL, AMmapPutStr(doc_state->doc, AM_ROOT, AMstr("title"), AMstr("hello")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    /* Uncommitted changes aren't notified. */
+    assert_int_equal(notifications->calls, 0);
+    AMstackItem(NULL, AMcommit(doc_state->doc, AMstr(NULL), NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    assert_int_equal(notifications->calls, 1);
+    assert_heads_current(stack_ptr, doc_state->doc, notifications->heads);
+    assert_int_equal(AMresultSize(notifications->patches), 1);
+    assert_put_map(notifications->patches, 0, "title");
+    /* An empty commit doesn't change the heads of the document. */
+    AMstackItem(NULL, AMcommit(doc_state->doc, AMstr(NULL), NULL), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_CHANGE_HASH | AM_VAL_TYPE_VOID));
+    assert_int_equal(notifications->calls, 1);
+    /* An empty change does. */
+    AMstackItem(NULL, AMemptyChange(doc_state->doc, AMstr(NULL), NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    assert_int_equal(notifications->calls, 2);
+    assert_int_equal(AMresultSize(notifications->patches), 0);
+    /* Changes committed by other functions are reported by the next notification. */
+    AMstackItem(NULL, AMmapPutInt(doc_state->doc, AM_ROOT, AMstr("a"), 1), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItems(stack_ptr, AMgetHeads(doc_state->doc), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    AMstackItem(NULL, AMmapPutInt(doc_state->doc, AM_ROOT, AMstr("b"), 2), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItem(NULL, AMcommit(doc_state->doc, AMstr(NULL), NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    assert_int_equal(notifications->calls, 3);
+    assert_int_equal(AMresultSize(notifications->patches), 2);
+    assert_put_map(notifications->patches, 0, "a");
+    assert_put_map(notifications->patches, 1, "b");
+}
+
+static void test_AMmerge_notifies(void** state) {
+    ObserverState* observer_state = *state;
+    DocState* doc_state = observer_state->doc_state;
+    Notifications* notifications = &observer_state->notifications;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMdoc* doc2;
+    assert_true(AMitemToDoc(AMstackItem(stack_ptr, AMcreate(NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_DOC)), &doc2));
+    AMstackItem(NULL, AMmapPutStr(doc2, AM_ROOT, AMstr("title"), AMstr("hello")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItems(stack_ptr, AMmerge(doc_state->doc, doc2), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    assert_int_equal(notifications->calls, 1);
+    assert_heads_current(stack_ptr, doc_state->doc, notifications->heads);
+    assert_put_map(notifications->patches, 0, "title");
+    /* Nothing more to merge */
+    AMstackItems(stack_ptr, AMmergeAndLogPatches(doc_state->doc, doc2), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    assert_int_equal(notifications->calls, 1);
+}
+
+static void test_AMloadIncremental_notifies(void** state) {
+    ObserverState* observer_state = *state;
+    DocState* doc_state = observer_state->doc_state;
+    Notifications* notifications = &observer_state->notifications;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMdoc* doc2;
+    assert_true(AMitemToDoc(AMstackItem(stack_ptr, AMcreate(NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_DOC)), &doc2));
+    AMstackItem(NULL, AMmapPutStr(doc2, AM_ROOT, AMstr("title"), AMstr("hello")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMbyteSpan bytes;
+    assert_true(
+        AMitemToBytes(AMstackItem(stack_ptr, AMsaveIncremental(doc2), cmocka_cb, AMexpect(AM_VAL_TYPE_BYTES)), &bytes));
+    AMstackItem(NULL, AMloadIncremental(doc_state->doc, bytes.src, bytes.count), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_UINT));
+    assert_int_equal(notifications->calls, 1);
+    assert_heads_current(stack_ptr, doc_state->doc, notifications->heads);
+    assert_put_map(notifications->patches, 0, "title");
+}
+
+static void test_AMapplyChanges_notifies(void** state) {
+    ObserverState* observer_state = *state;
+    DocState* doc_state = observer_state->doc_state;
+    Notifications* notifications = &observer_state->notifications;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMdoc* doc2;
+    assert_true(AMitemToDoc(AMstackItem(stack_ptr, AMcreate(NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_DOC)), &doc2));
+    AMstackItem(NULL, AMmapPutStr(doc2, AM_ROOT, AMstr("title"), AMstr("hello")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMitems const changes = AMstackItems(stack_ptr, AMgetChanges(doc2, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE));
+    AMstackItem(NULL, AMapplyChanges(doc_state->doc, &changes), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    assert_int_equal(notifications->calls, 1);
+    assert_heads_current(stack_ptr, doc_state->doc, notifications->heads);
+    assert_int_equal(AMresultSize(notifications->patches), 1);
+    assert_put_map(notifications->patches, 0, "title");
+    /* Applying them again doesn't change the heads of the document. */
+    AMstackItem(NULL, AMapplyChanges(doc_state->doc, &changes), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    assert_int_equal(notifications->calls, 1);
+}
+
+static void test_AMreceiveSyncMessage_notifies(void** state) {
+    ObserverState* observer_state = *state;
+    DocState* doc_state = observer_state->doc_state;
+    Notifications* notifications = &observer_state->notifications;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMdoc* doc2;
+    assert_true(AMitemToDoc(AMstackItem(stack_ptr, AMcreate(NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_DOC)), &doc2));
+    AMstackItem(NULL, AMmapPutStr(doc2, AM_ROOT, AMstr("title"), AMstr("hello")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMsyncState* s1;
+    assert_true(AMitemToSyncState(
+        AMstackItem(stack_ptr, AMsyncStateInit(), cmocka_cb, AMexpect(AM_VAL_TYPE_SYNC_STATE)), &s1));
+    AMsyncState* s2;
+    assert_true(AMitemToSyncState(
+        AMstackItem(stack_ptr, AMsyncStateInit(), cmocka_cb, AMexpect(AM_VAL_TYPE_SYNC_STATE)), &s2));
+    /* Exchange messages until neither document has anything more to send. */
+    for (size_t round = 0; round != 10; ++round) {
+        AMsyncMessage const* msg;
+        AMitem* item = AMstackItem(stack_ptr, AMgenerateSyncMessage(doc2, s2), cmocka_cb,
+                                   AMexpect(AM_VAL_TYPE_SYNC_MESSAGE | AM_VAL_TYPE_VOID));
+        bool const sent = AMitemToSyncMessage(item, &msg);
+        if (sent) {
+            AMstackItem(NULL, AMreceiveSyncMessage(doc_state->doc, s1, msg), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+        }
+        item = AMstackItem(stack_ptr, AMgenerateSyncMessage(doc_state->doc, s1), cmocka_cb,
+                           AMexpect(AM_VAL_TYPE_SYNC_MESSAGE | AM_VAL_TYPE_VOID));
+        if (AMitemToSyncMessage(item, &msg)) {
+            AMstackItem(NULL, AMreceiveSyncMessage(doc2, s2, msg), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+        } else if (!sent) {
+            break;
+        }
+    }
+    assert_int_equal(notifications->calls, 1);
+    assert_heads_current(stack_ptr, doc_state->doc, notifications->heads);
+    assert_put_map(notifications->patches, 0, "title");
+}
+
+static void test_AMsetChangeCallback_reentrancy(void** state) {
+    ObserverState* observer_state = *state;
+    DocState* doc_state = observer_state->doc_state;
+    Notifications* notifications = &observer_state->notifications;
+
+    notifications->reentrant_key = "nested";
+    AMstackItem(NULL, AMmapPutStr(doc_state->doc, AM_ROOT, AMstr("outer"), AMstr("hello")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItem(NULL, AMcommit(doc_state->doc, AMstr(NULL), NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    /* The commit within the callback didn't notify it again... */
+    assert_int_equal(notifications->calls, 1);
+    assert_int_equal(AMresultSize(notifications->patches), 1);
+    assert_put_map(notifications->patches, 0, "outer");
+    /* ...but it's reported by the next notification. */
+    AMstackItem(NULL, AMmapPutStr(doc_state->doc, AM_ROOT, AMstr("zzz"), AMstr("bye")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItem(NULL, AMcommit(doc_state->doc, AMstr(NULL), NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    assert_int_equal(notifications->calls, 2);
+    assert_int_equal(AMresultSize(notifications->patches), 2);
+    assert_put_map(notifications->patches, 0, "nested");
+    assert_put_map(notifications->patches, 1, "zzz");
+}
+
+static void test_AMsetChangeCallback_null(void** state) {
+    ObserverState* observer_state = *state;
+    DocState* doc_state = observer_state->doc_state;
+    Notifications* notifications = &observer_state->notifications;
+
+    AMstackItem(NULL, AMsetChangeCallback(doc_state->doc, NULL, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItem(NULL, AMmapPutStr(doc_state->doc, AM_ROOT, AMstr("title"), AMstr("hello")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItem(NULL, AMcommit(doc_state->doc, AMstr(NULL), NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    assert_int_equal(notifications->calls, 0);
+}
+
+int run_observer_tests(void) {
+    struct CMUnitTest const tests[] = {
+        cmocka_unit_test_setup_teardown(test_AMcommit_notifies, setup, teardown),
+        cmocka_unit_test_setup_teardown(test_AMmerge_notifies, setup, teardown),
+        cmocka_unit_test_setup_teardown(test_AMloadIncremental_notifies, setup, teardown),
+        cmocka_unit_test_setup_teardown(test_AMapplyChanges_notifies, setup, teardown),
+        cmocka_unit_test_setup_teardown(test_AMreceiveSyncMessage_notifies, setup, teardown),
+        cmocka_unit_test_setup_teardown(test_AMsetChangeCallback_reentrancy, setup, teardown),
+        cmocka_unit_test_setup_teardown(test_AMsetChangeCallback_null, setup, teardown),
+    };
+
+    return cmocka_run_group_tests(tests, NULL, NULL);
+}
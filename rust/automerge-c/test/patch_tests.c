@@ -0,0 +1,288 @@
+#include <setjmp.h>
+#include <stdarg.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+
+/* third-party */
+#include <cmocka.h>
+
+/* local */
+#include <automerge-c/automerge.h>
+#include <automerge-c/config.h>
+#include <automerge-c/utils/stack_callback_data.h>
+#include "base_state.h"
+#include "cmocka_utils.h"
+#include "doc_state.h"
+
+#define assert_key_equal(item, expected)                        \
+    do {                                                        \
+        AMbyteSpan key;                                         \
+        assert_true(AMitemKey(item, &key));                     \
+        assert_int_equal(key.count, strlen(expected));          \
+        assert_memory_equal(key.src, expected, key.count);      \
+    } while (0)
+
+#define assert_str_equal(str, expected)                         \
+    do {                                                        \
+        assert_int_equal((str).count, strlen(expected));        \
+        assert_memory_equal((str).src, expected, (str).count);  \
+    } while (0)
+
+static void test_AMdiffIncremental(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMstackItem(NULL, AMmapPutStr(doc_state->doc, AM_ROOT, AMstr("title"), AMstr("hello")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMobjId const* const list =
+        AMitemObjId(AMstackItem(stack_ptr, AMmapPutObject(doc_state->doc, AM_ROOT, AMstr("list"), AM_OBJ_TYPE_LIST),
+                                cmocka_cb, AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    AMstackItem(NULL, AMlistPutInt(doc_state->doc, list, SIZE_MAX, true, 1), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItem(NULL, AMlistPutInt(doc_state->doc, list, SIZE_MAX, true, 2), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    /* The first diff describes the entire document. */
+    AMitems patches =
+        AMstackItems(stack_ptr, AMdiffIncremental(doc_state->doc), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    assert_int_equal(AMitemsSize(&patches), 3);
+    AMpatch const* patch;
+    assert_true(AMitemToPatch(AMitemsNext(&patches, 1), &patch));
+    assert_int_equal(AMpatchAction(patch), AM_ACTION_TYPE_PUT_MAP);
+    assert_key_equal(AMstackItem(stack_ptr, AMpatchIndex(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID)), "list");
+    AMitem* value = AMstackItem(stack_ptr, AMpatchValue(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_OBJ_TYPE));
+    assert_true(AMobjIdEqual(AMitemObjId(value), list));
+    assert_true(AMitemToPatch(AMitemsNext(&patches, 1), &patch));
+    assert_int_equal(AMpatchAction(patch), AM_ACTION_TYPE_PUT_MAP);
+    AMbyteSpan str;
+    assert_true(AMitemToStr(AMstackItem(stack_ptr, AMpatchValue(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_STR)), &str));
+    assert_str_equal(str, "hello");
+    assert_false(AMpatchConflict(patch));
+    assert_true(AMitemToPatch(AMitemsNext(&patches, 1), &patch));
+    assert_int_equal(AMpatchAction(patch), AM_ACTION_TYPE_INSERT);
+    assert_true(AMobjIdEqual(AMpatchObjId(patch), list));
+    assert_int_equal(AMpatchLength(patch), 2);
+    AMitems values = AMstackItems(stack_ptr, AMpatchValue(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_INT));
+    assert_int_equal(AMitemsSize(&values), 2);
+    int64_t i;
+    assert_true(AMitemToInt(AMitemsNext(&values, 1), &i));
+    assert_int_equal(i, 1);
+    assert_true(AMitemToInt(AMitemsNext(&values, 1), &i));
+    assert_int_equal(i, 2);
+    /* Later diffs only describe what has changed since the last one. */
+    AMstackItem(NULL, AMlistDelete(doc_state->doc, list, 0), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    patches = AMstackItems(stack_ptr, AMdiffIncremental(doc_state->doc), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    assert_int_equal(AMitemsSize(&patches), 1);
+    assert_true(AMitemToPatch(AMitemsNext(&patches, 1), &patch));
+    assert_int_equal(AMpatchAction(patch), AM_ACTION_TYPE_DEL_SEQ);
+    size_t pos;
+    assert_true(AMitemPos(AMstackItem(stack_ptr, AMpatchIndex(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID)), &pos));
+    assert_int_equal(pos, 0);
+    assert_int_equal(AMpatchLength(patch), 1);
+    patches = AMstackItems(stack_ptr, AMdiffIncremental(doc_state->doc), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    assert_int_equal(AMitemsSize(&patches), 0);
+}
+
+static void test_AMdiff(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMstackItem(NULL, AMmapPutCounter(doc_state->doc, AM_ROOT, AMstr("count"), 1), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItem(NULL, AMcommit(doc_state->doc, AMstr(NULL), NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    AMitems const before =
+        AMstackItems(stack_ptr, AMgetHeads(doc_state->doc), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    AMstackItem(NULL, AMmapIncrement(doc_state->doc, AM_ROOT, AMstr("count"), 3), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItem(NULL, AMcommit(doc_state->doc, AMstr(NULL), NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    AMitems const after =
+        AMstackItems(stack_ptr, AMgetHeads(doc_state->doc), cmocka_cb, AMexpect(AM_VAL_TYPE_CHANGE_HASH));
+    /* Forward */
+    AMitems patches =
+        AMstackItems(stack_ptr, AMdiff(doc_state->doc, &before, &after), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    assert_int_equal(AMitemsSize(&patches), 1);
+    AMpatch const* patch;
+    assert_true(AMitemToPatch(AMitemsNext(&patches, 1), &patch));
+    assert_int_equal(AMpatchAction(patch), AM_ACTION_TYPE_INCREMENT);
+    assert_key_equal(AMstackItem(stack_ptr, AMpatchIndex(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID)), "count");
+    int64_t i;
+    assert_true(AMitemToInt(AMstackItem(stack_ptr, AMpatchValue(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_INT)), &i));
+    assert_int_equal(i, 3);
+    /* Backward */
+    patches =
+        AMstackItems(stack_ptr, AMdiff(doc_state->doc, &after, &before), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    assert_int_equal(AMitemsSize(&patches), 1);
+    assert_true(AMitemToPatch(AMitemsNext(&patches, 1), &patch));
+    assert_int_equal(AMpatchAction(patch), AM_ACTION_TYPE_INCREMENT);
+    assert_true(AMitemToInt(AMstackItem(stack_ptr, AMpatchValue(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_INT)), &i));
+    assert_int_equal(i, -3);
+    /* From an empty document to the current state */
+    patches = AMstackItems(stack_ptr, AMdiff(doc_state->doc, NULL, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    assert_int_equal(AMitemsSize(&patches), 1);
+    assert_true(AMitemToPatch(AMitemsNext(&patches, 1), &patch));
+    assert_int_equal(AMpatchAction(patch), AM_ACTION_TYPE_PUT_MAP);
+    assert_true(
+        AMitemToCounter(AMstackItem(stack_ptr, AMpatchValue(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_COUNTER)), &i));
+    assert_int_equal(i, 4);
+}
+
+static void test_AMpatchPath(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMobjId const* const config =
+        AMitemObjId(AMstackItem(stack_ptr, AMmapPutObject(doc_state->doc, AM_ROOT, AMstr("config"), AM_OBJ_TYPE_MAP),
+                                cmocka_cb, AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    AMobjId const* const tags =
+        AMitemObjId(AMstackItem(stack_ptr, AMmapPutObject(doc_state->doc, config, AMstr("tags"), AM_OBJ_TYPE_LIST),
+                                cmocka_cb, AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    AMstackItem(NULL, AMlistPutStr(doc_state->doc, tags, SIZE_MAX, true, AMstr("a")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItem(NULL, AMdiffIncremental(doc_state->doc), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    AMstackItem(NULL, AMlistPutStr(doc_state->doc, tags, 0, false, AMstr("b")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMitems patches =
+        AMstackItems(stack_ptr, AMdiffIncremental(doc_state->doc), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    assert_int_equal(AMitemsSize(&patches), 1);
+    AMpatch const* patch;
+    assert_true(AMitemToPatch(AMitemsNext(&patches, 1), &patch));
+    assert_int_equal(AMpatchAction(patch), AM_ACTION_TYPE_PUT_SEQ);
+    assert_true(AMobjIdEqual(AMpatchObjId(patch), tags));
+    /* ["config", "tags"] */
+    AMitems path = AMstackItems(stack_ptr, AMpatchPath(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    assert_int_equal(AMitemsSize(&path), 2);
+    AMitem* item = AMitemsNext(&path, 1);
+    /* The root object's counter is zero. */
+    assert_int_equal(AMobjIdCounter(AMitemObjId(item)), 0);
+    assert_key_equal(item, "config");
+    item = AMitemsNext(&path, 1);
+    assert_true(AMobjIdEqual(AMitemObjId(item), config));
+    assert_key_equal(item, "tags");
+    size_t pos;
+    assert_true(AMitemPos(AMstackItem(stack_ptr, AMpatchIndex(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID)), &pos));
+    assert_int_equal(pos, 0);
+}
+
+static void test_AMmergeAndLogPatches(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMobjId const* const text =
+        AMitemObjId(AMstackItem(stack_ptr, AMmapPutObject(doc_state->doc, AM_ROOT, AMstr("text"), AM_OBJ_TYPE_TEXT),
+                                cmocka_cb, AMexpect(AM_VAL_TYPE_OBJ_TYPE)));
+    AMstackItem(NULL, AMspliceText(doc_state->doc, text, 0, 0, AMstr("hello")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMdoc* doc2;
+    assert_true(AMitemToDoc(AMstackItem(stack_ptr, AMfork(doc_state->doc, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_DOC)),
+                            &doc2));
+    AMitem* bold = AMstackItem(stack_ptr, AMitemFromBool(true), cmocka_cb, AMexpect(AM_VAL_TYPE_BOOL));
+    AMstackItem(NULL, AMmarkCreate(doc2, text, 0, 5, AM_MARK_EXPAND_AFTER, AMstr("bold"), bold), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMstackItem(NULL, AMspliceText(doc2, text, 5, 0, AMstr(" world")), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID));
+    AMitems patches =
+        AMstackItems(stack_ptr, AMmergeAndLogPatches(doc_state->doc, doc2), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    assert_int_equal(AMitemsSize(&patches), 2);
+    /* The mark */
+    AMpatch const* patch;
+    assert_true(AMitemToPatch(AMitemsNext(&patches, 1), &patch));
+    assert_int_equal(AMpatchAction(patch), AM_ACTION_TYPE_MARK);
+    AMitems marks = AMstackItems(stack_ptr, AMpatchMarks(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_MARK));
+    assert_int_equal(AMitemsSize(&marks), 1);
+    AMmark const* mark;
+    assert_true(AMitemToMark(AMitemsNext(&marks, 1), &mark));
+    assert_int_equal(AMmarkStart(mark), 0);
+    assert_int_equal(AMmarkEnd(mark), 5);
+    /* The text inserted within the expanded mark */
+    assert_true(AMitemToPatch(AMitemsNext(&patches, 1), &patch));
+    assert_int_equal(AMpatchAction(patch), AM_ACTION_TYPE_SPLICE_TEXT);
+    AMbyteSpan str;
+    assert_true(AMitemToStr(AMstackItem(stack_ptr, AMpatchValue(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_STR)), &str));
+    assert_str_equal(str, " world");
+    assert_int_equal(AMpatchLength(patch), strlen(" world"));
+    marks = AMstackItems(stack_ptr, AMpatchMarks(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_MARK));
+    assert_int_equal(AMitemsSize(&marks), 1);
+    assert_true(AMitemToMark(AMitemsNext(&marks, 1), &mark));
+    str = AMmarkName(mark);
+    assert_str_equal(str, "bold");
+    assert_int_equal(AMmarkStart(mark), 5);
+    assert_int_equal(AMmarkEnd(mark), 11);
+    /* Nothing more to merge */
+    patches =
+        AMstackItems(stack_ptr, AMmergeAndLogPatches(doc_state->doc, doc2), cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    assert_int_equal(AMitemsSize(&patches), 0);
+}
+
+static void test_AMloadIncrementalLogPatches(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMdoc* doc2;
+    assert_true(AMitemToDoc(AMstackItem(stack_ptr, AMfork(doc_state->doc, NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_DOC)),
+                            &doc2));
+    AMstackItem(NULL, AMmapPutStr(doc2, AM_ROOT, AMstr("title"), AMstr("hello")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMbyteSpan bytes;
+    assert_true(
+        AMitemToBytes(AMstackItem(stack_ptr, AMsaveIncremental(doc2), cmocka_cb, AMexpect(AM_VAL_TYPE_BYTES)), &bytes));
+    AMitems patches = AMstackItems(stack_ptr, AMloadIncrementalLogPatches(doc_state->doc, bytes.src, bytes.count),
+                                   cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+    assert_int_equal(AMitemsSize(&patches), 1);
+    AMpatch const* patch;
+    assert_true(AMitemToPatch(AMitemsNext(&patches, 1), &patch));
+    assert_int_equal(AMpatchAction(patch), AM_ACTION_TYPE_PUT_MAP);
+    assert_key_equal(AMstackItem(stack_ptr, AMpatchIndex(patch), cmocka_cb, AMexpect(AM_VAL_TYPE_VOID)), "title");
+}
+
+static void test_AMreceiveSyncMessageLogPatches(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMdoc* doc2;
+    assert_true(AMitemToDoc(AMstackItem(stack_ptr, AMcreate(NULL), cmocka_cb, AMexpect(AM_VAL_TYPE_DOC)), &doc2));
+    AMstackItem(NULL, AMmapPutStr(doc2, AM_ROOT, AMstr("title"), AMstr("hello")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMsyncState* s1;
+    assert_true(AMitemToSyncState(
+        AMstackItem(stack_ptr, AMsyncStateInit(), cmocka_cb, AMexpect(AM_VAL_TYPE_SYNC_STATE)), &s1));
+    AMsyncState* s2;
+    assert_true(AMitemToSyncState(
+        AMstackItem(stack_ptr, AMsyncStateInit(), cmocka_cb, AMexpect(AM_VAL_TYPE_SYNC_STATE)), &s2));
+    size_t count = 0;
+    /* Exchange messages until neither document has anything more to send. */
+    for (size_t round = 0; round != 10; ++round) {
+        AMsyncMessage const* msg;
+        AMitem* item = AMstackItem(stack_ptr, AMgenerateSyncMessage(doc2, s2), cmocka_cb,
+                                   AMexpect(AM_VAL_TYPE_SYNC_MESSAGE | AM_VAL_TYPE_VOID));
+        bool const sent = AMitemToSyncMessage(item, &msg);
+        if (sent) {
+            AMitems patches = AMstackItems(stack_ptr, AMreceiveSyncMessageLogPatches(doc_state->doc, s1, msg),
+                                           cmocka_cb, AMexpect(AM_VAL_TYPE_PATCH));
+            count += AMitemsSize(&patches);
+        }
+        item = AMstackItem(stack_ptr, AMgenerateSyncMessage(doc_state->doc, s1), cmocka_cb,
+                           AMexpect(AM_VAL_TYPE_SYNC_MESSAGE | AM_VAL_TYPE_VOID));
+        if (AMitemToSyncMessage(item, &msg)) {
+            AMstackItem(NULL, AMreceiveSyncMessageLogPatches(doc2, s2, msg), cmocka_cb,
+                        AMexpect(AM_VAL_TYPE_PATCH));
+        } else if (!sent) {
+            break;
+        }
+    }
+    assert_int_equal(count, 1);
+    AMbyteSpan str;
+    assert_true(AMitemToStr(AMstackItem(stack_ptr, AMmapGet(doc_state->doc, AM_ROOT, AMstr("title"), NULL), cmocka_cb,
+                                        AMexpect(AM_VAL_TYPE_STR)),
+                            &str));
+    assert_str_equal(str, "hello");
+}
+
+int run_patch_tests(void) {
+    struct CMUnitTest const tests[] = {
+        cmocka_unit_test_setup_teardown(test_AMdiffIncremental, setup_doc, teardown_doc),
+        cmocka_unit_test_setup_teardown(test_AMdiff, setup_doc, teardown_doc),
+        cmocka_unit_test_setup_teardown(test_AMpatchPath, setup_doc, teardown_doc),
+        cmocka_unit_test_setup_teardown(test_AMmergeAndLogPatches, setup_doc, teardown_doc),
+        cmocka_unit_test_setup_teardown(test_AMloadIncrementalLogPatches, setup_doc, teardown_doc),
+        cmocka_unit_test_setup_teardown(test_AMreceiveSyncMessageLogPatches, setup_doc, teardown_doc),
+    };
+
+    return cmocka_run_group_tests(tests, NULL, NULL);
+}
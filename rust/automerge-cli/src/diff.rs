@@ -0,0 +1,342 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use automerge::{self as am, patches::TextRepresentation, PatchAction, ReadDoc};
+
+use crate::{
+    log::{load, resolve_hash, LogError},
+    VerifyFlag,
+};
+
+/// What to diff
+pub(crate) enum DiffSource {
+    /// Two sets of (abbreviated) heads of one document. Empty `from` heads
+    /// are the empty document and empty `to` heads are the current heads.
+    Heads {
+        input: Box<dyn std::io::Read>,
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    /// The current heads of two documents
+    Files { from: PathBuf, to: PathBuf },
+}
+
+fn read_file(path: &PathBuf, skip: VerifyFlag) -> Result<am::Automerge, LogError> {
+    let file = std::fs::File::open(path).map_err(|e| LogError::ReadingChanges { source: e })?;
+    load(file, skip)
+}
+
+fn resolve_heads(
+    doc: &am::Automerge,
+    prefixes: &[String],
+) -> Result<Vec<am::ChangeHash>, LogError> {
+    prefixes
+        .iter()
+        .map(|prefix| resolve_hash(doc, prefix))
+        .collect()
+}
+
+pub(crate) fn diff(
+    source: DiffSource,
+    mut output: impl std::io::Write,
+    skip: VerifyFlag,
+    json: bool,
+) -> Result<(), LogError> {
+    let (doc, before, after) = match source {
+        DiffSource::Heads { input, from, to } => {
+            let doc = load(input, skip)?;
+            let before = resolve_heads(&doc, &from)?;
+            let after = if to.is_empty() {
+                doc.get_heads()
+            } else {
+                resolve_heads(&doc, &to)?
+            };
+            (doc, before, after)
+        }
+        DiffSource::Files { from, to } => {
+            let mut doc = read_file(&from, skip)?;
+            let mut other = read_file(&to, skip)?;
+            let before = doc.get_heads();
+            let after = other.get_heads();
+            doc.merge(&mut other)
+                .map_err(|e| LogError::ApplyingInitialChanges { source: e })?;
+            (doc, before, after)
+        }
+    };
+    let text_rep = TextRepresentation::String(am::TextEncoding::UnicodeCodePoint);
+    let patches = doc.diff(&before, &after, text_rep);
+    let rendered = if json {
+        serde_json::to_string_pretty(&patches).unwrap()
+    } else {
+        render(&doc, &before, &patches).join("\n")
+    };
+    if !rendered.is_empty() {
+        writeln!(output, "{}", rendered).map_err(|e| LogError::WritingToOutput { source: e })?;
+    }
+    Ok(())
+}
+
+fn render_value(value: &am::Value<'_>) -> String {
+    match value {
+        am::Value::Object(am::ObjType::Map | am::ObjType::Table) => "{}".to_string(),
+        am::Value::Object(am::ObjType::List) => "[]".to_string(),
+        am::Value::Object(am::ObjType::Text) => "\"\"".to_string(),
+        am::Value::Scalar(scalar) => serde_json::to_string(scalar.as_ref()).unwrap(),
+    }
+}
+
+/// Render one line for each patch, except that all of the splices into and
+/// deletions from a text object are rendered inline on one line
+fn render(doc: &am::Automerge, before: &[am::ChangeHash], patches: &[am::Patch]) -> Vec<String> {
+    let mut lines = Vec::new();
+    // The text objects, the lines that they're rendered on and their paths
+    let mut texts: HashMap<am::ObjId, (usize, String, InlineText)> = HashMap::new();
+    for patch in patches {
+        let path = patch
+            .path
+            .iter()
+            .map(|(_, prop)| format!("/{}", prop))
+            .collect::<String>();
+        let is_text = doc.object_type(&patch.obj) == Ok(am::ObjType::Text);
+        let line = match &patch.action {
+            PatchAction::SpliceText { .. } | PatchAction::DeleteSeq { .. } if is_text => {
+                let (_, _, text) = texts.entry(patch.obj.clone()).or_insert_with(|| {
+                    lines.push(String::new());
+                    let text = doc.text_at(&patch.obj, before).unwrap_or_default();
+                    (lines.len() - 1, path, InlineText::new(&text))
+                });
+                match &patch.action {
+                    PatchAction::SpliceText { index, value, .. } => {
+                        text.splice(*index, &value.make_string())
+                    }
+                    PatchAction::DeleteSeq { index, length } => text.delete(*index, *length),
+                    _ => unreachable!(),
+                }
+                continue;
+            }
+            PatchAction::PutMap {
+                key,
+                value,
+                conflict,
+            } => format!(
+                "put      {}/{} = {}{}",
+                path,
+                key,
+                render_value(&value.0),
+                if *conflict { " (conflict)" } else { "" }
+            ),
+            PatchAction::PutSeq {
+                index,
+                value,
+                conflict,
+            } => format!(
+                "put      {}/{} = {}{}",
+                path,
+                index,
+                render_value(&value.0),
+                if *conflict { " (conflict)" } else { "" }
+            ),
+            PatchAction::Insert { index, values } => format!(
+                "insert   {}/{} {}",
+                path,
+                index,
+                values
+                    .iter()
+                    .map(|(value, _, _)| render_value(value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            PatchAction::SpliceText { index, value, .. } => {
+                format!("splice   {}/{} {:?}", path, index, value.make_string())
+            }
+            PatchAction::Increment { prop, value } => {
+                format!("inc      {}/{} {:+}", path, prop, value)
+            }
+            PatchAction::Conflict { prop } => format!("conflict {}/{}", path, prop),
+            PatchAction::DeleteMap { key } => format!("del      {}/{}", path, key),
+            PatchAction::DeleteSeq { index, length: 1 } => format!("del      {}/{}", path, index),
+            PatchAction::DeleteSeq { index, length } => {
+                format!("del      {}/{} ({} elements)", path, index, length)
+            }
+            PatchAction::Mark { marks } => format!(
+                "mark     {} {}",
+                path,
+                marks
+                    .iter()
+                    .map(|mark| format!(
+                        "{}={} [{}..{})",
+                        mark.name(),
+                        serde_json::to_string(mark.value()).unwrap(),
+                        mark.start,
+                        mark.end
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        lines.push(line);
+    }
+    for (line, path, text) in texts.into_values() {
+        lines[line] = format!("text     {} {}", path, text.render());
+    }
+    lines
+}
+
+enum Segment {
+    Same(char),
+    Added(char),
+    Removed(char),
+}
+
+/// A text with the characters that have been added to and removed from it
+struct InlineText {
+    segments: Vec<Segment>,
+}
+
+impl InlineText {
+    fn new(text: &str) -> Self {
+        Self {
+            segments: text.chars().map(Segment::Same).collect(),
+        }
+    }
+
+    /// The position in `segments` of the character at `index` in the current
+    /// text, which doesn't include the removed characters
+    fn position(&self, index: usize) -> usize {
+        self.segments
+            .iter()
+            .enumerate()
+            .filter(|(_, segment)| !matches!(segment, Segment::Removed(_)))
+            .nth(index)
+            .map(|(pos, _)| pos)
+            .unwrap_or(self.segments.len())
+    }
+
+    fn splice(&mut self, index: usize, text: &str) {
+        let pos = self.position(index);
+        self.segments
+            .splice(pos..pos, text.chars().map(Segment::Added));
+    }
+
+    fn delete(&mut self, index: usize, length: usize) {
+        for _ in 0..length {
+            let pos = self.position(index);
+            match self.segments.get(pos) {
+                Some(Segment::Same(c)) => self.segments[pos] = Segment::Removed(*c),
+                Some(Segment::Added(_)) => {
+                    self.segments.remove(pos);
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Render the text with `[-removed-]` and `{+added+}` characters. The
+    /// removed characters of each change are rendered before the added ones.
+    fn render(&self) -> String {
+        fn flush(rendered: &mut String, removed: &mut String, added: &mut String) {
+            if !removed.is_empty() {
+                rendered.push_str(&format!("[-{}-]", removed));
+                removed.clear();
+            }
+            if !added.is_empty() {
+                rendered.push_str(&format!("{{+{}+}}", added));
+                added.clear();
+            }
+        }
+        let mut rendered = String::new();
+        let (mut removed, mut added) = (String::new(), String::new());
+        for segment in &self.segments {
+            match segment {
+                Segment::Same(c) => {
+                    flush(&mut rendered, &mut removed, &mut added);
+                    rendered.push(*c);
+                }
+                Segment::Added(c) => added.push(*c),
+                Segment::Removed(c) => removed.push(*c),
+            }
+        }
+        flush(&mut rendered, &mut removed, &mut added);
+        rendered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use am::transaction::Transactable;
+
+    fn run(source: DiffSource, json: bool) -> String {
+        let mut out = Vec::new();
+        diff(source, &mut out, VerifyFlag::default(), json).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn diff_between_heads() {
+        let mut doc = am::AutoCommit::new();
+        let text = doc
+            .put_object(am::ROOT, "notes", am::ObjType::Text)
+            .unwrap();
+        doc.splice_text(&text, 0, 0, "hello world").unwrap();
+        doc.put(am::ROOT, "count", am::ScalarValue::counter(1))
+            .unwrap();
+        let list = doc.put_object(am::ROOT, "list", am::ObjType::List).unwrap();
+        doc.insert(&list, 0, 1).unwrap();
+        let before = doc.commit().unwrap();
+        doc.splice_text(&text, 6, 5, "there").unwrap();
+        doc.splice_text(&text, 0, 0, ">").unwrap();
+        doc.increment(am::ROOT, "count", 2).unwrap();
+        doc.insert(&list, 1, "two").unwrap();
+        doc.delete(&list, 0).unwrap();
+        doc.put(am::ROOT, "done", true).unwrap();
+        doc.commit();
+
+        let bytes = doc.save();
+        let source = |from: &am::ChangeHash| DiffSource::Heads {
+            input: Box::new(std::io::Cursor::new(bytes.clone())),
+            from: vec![from.to_string()],
+            to: Vec::new(),
+        };
+        let out = run(source(&before), false);
+        let mut lines = out.lines().collect::<Vec<_>>();
+        lines.sort();
+        assert_eq!(
+            lines,
+            vec![
+                "del      /list/0",
+                "inc      /count +2",
+                "insert   /list/0 \"two\"",
+                "put      /done = true",
+                "text     /notes {+>+}hello [-world-]{+there+}",
+            ]
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&run(source(&before), true)).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 7);
+    }
+
+    #[test]
+    fn diff_between_files() {
+        let mut doc1 = am::AutoCommit::new();
+        doc1.put(am::ROOT, "a", 1).unwrap();
+        let mut doc2 = doc1.fork();
+        doc2.put(am::ROOT, "a", 2).unwrap();
+        doc2.delete(am::ROOT, "a").unwrap();
+        doc2.put(am::ROOT, "b", "x").unwrap();
+        let dir = std::env::temp_dir();
+        let from = dir.join(format!("automerge-diff-from-{}", std::process::id()));
+        let to = dir.join(format!("automerge-diff-to-{}", std::process::id()));
+        std::fs::write(&from, doc1.save()).unwrap();
+        std::fs::write(&to, doc2.save()).unwrap();
+        let out = run(
+            DiffSource::Files {
+                from: from.clone(),
+                to: to.clone(),
+            },
+            false,
+        );
+        std::fs::remove_file(from).unwrap();
+        std::fs::remove_file(to).unwrap();
+        assert_eq!(out, "del      /a\nput      /b = \"x\"\n");
+    }
+}
@@ -0,0 +1,377 @@
+use std::{ffi::OsString, io::Write, path::Path};
+
+use automerge::{
+    self as am, hydrate,
+    transaction::{CommitOptions, Transactable},
+    ReadDoc,
+};
+
+use crate::VerifyFlag;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum EditError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Automerge(#[from] am::AutomergeError),
+    #[error(transparent)]
+    UpdateObject(#[from] am::error::UpdateObjectError),
+    #[error("invalid value: {0}")]
+    InvalidValue(#[from] serde_json::Error),
+    #[error("invalid path {0:?}, paths are JSON pointers such as \"/birds/0\"")]
+    InvalidPath(String),
+    #[error("invalid index {index:?} in {path:?}")]
+    InvalidIndex { path: String, index: String },
+    #[error("nothing at {0:?}")]
+    NotFound(String),
+    #[error("{0:?} is not a text object")]
+    NotText(String),
+}
+
+/// A change to make to a document
+pub(crate) enum Edit {
+    /// Put a JSON value at a path, or insert it there if `insert` is true
+    Set {
+        path: String,
+        value: String,
+        insert: bool,
+    },
+    /// Delete the value at a path
+    Rm { path: String },
+    /// Splice text into the text object at a path
+    SpliceText {
+        path: String,
+        pos: usize,
+        del: isize,
+        text: String,
+    },
+}
+
+pub(crate) struct EditOptions {
+    /// The message of the change
+    pub(crate) message: Option<String>,
+    /// The actor to make the change as. If omitted a random actor is used
+    pub(crate) actor: Option<am::ActorId>,
+    /// Rewrite the file as a compacted document instead of appending the change to it
+    pub(crate) compact: bool,
+    /// Whether to verify the head hashes of the document
+    pub(crate) verify: VerifyFlag,
+}
+
+/// Split a JSON pointer (RFC 6901) into its unescaped segments
+fn parse_path(path: &str) -> Result<Vec<String>, EditError> {
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(segments) = path.strip_prefix('/') else {
+        return Err(EditError::InvalidPath(path.to_string()));
+    };
+    Ok(segments
+        .split('/')
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+/// The property that `segment` names in `obj`. `-` names the end of a sequence.
+fn prop_in<R: ReadDoc>(
+    doc: &R,
+    obj: &am::ObjId,
+    segment: &str,
+    path: &str,
+) -> Result<am::Prop, EditError> {
+    match doc.object_type(obj)? {
+        am::ObjType::Map | am::ObjType::Table => Ok(am::Prop::Map(segment.to_string())),
+        am::ObjType::List | am::ObjType::Text if segment == "-" => {
+            Ok(am::Prop::Seq(doc.length(obj)))
+        }
+        am::ObjType::List | am::ObjType::Text => {
+            segment
+                .parse()
+                .map(am::Prop::Seq)
+                .map_err(|_| EditError::InvalidIndex {
+                    path: path.to_string(),
+                    index: segment.to_string(),
+                })
+        }
+    }
+}
+
+/// Resolve `path` to the object which holds its last segment and the property of that segment.
+/// The empty path has no property.
+fn resolve<R: ReadDoc>(doc: &R, path: &str) -> Result<(am::ObjId, Option<am::Prop>), EditError> {
+    let mut segments = parse_path(path)?;
+    let Some(last) = segments.pop() else {
+        return Ok((am::ROOT, None));
+    };
+    let mut obj = am::ROOT;
+    for segment in segments {
+        let prop = prop_in(doc, &obj, &segment, path)?;
+        match doc.get(&obj, prop)? {
+            Some((am::Value::Object(_), id)) => obj = id,
+            _ => return Err(EditError::NotFound(path.to_string())),
+        }
+    }
+    let prop = prop_in(doc, &obj, &last, path)?;
+    Ok((obj, Some(prop)))
+}
+
+/// Resolve `path` to the object at it
+fn resolve_object<R: ReadDoc>(doc: &R, path: &str) -> Result<am::ObjId, EditError> {
+    match resolve(doc, path)? {
+        (obj, None) => Ok(obj),
+        (obj, Some(prop)) => match doc.get(&obj, prop)? {
+            Some((am::Value::Object(_), id)) => Ok(id),
+            _ => Err(EditError::NotFound(path.to_string())),
+        },
+    }
+}
+
+/// Print the value at `path` in the document in `file` as JSON
+pub(crate) fn get(
+    file: &Path,
+    mut output: impl std::io::Write,
+    path: &str,
+    verify: VerifyFlag,
+) -> Result<(), EditError> {
+    let doc = verify.load(&std::fs::read(file)?)?;
+    let value = match resolve(&doc, path)? {
+        (obj, None) => ReadDoc::hydrate(&doc, &obj, None)?,
+        (obj, Some(prop)) => match doc.get(&obj, prop)? {
+            Some((am::Value::Object(_), id)) => ReadDoc::hydrate(&doc, &id, None)?,
+            Some((am::Value::Scalar(scalar), _)) => hydrate::Value::Scalar(scalar.into_owned()),
+            None => return Err(EditError::NotFound(path.to_string())),
+        },
+    };
+    writeln!(output, "{}", serde_json::to_string_pretty(&value)?)?;
+    Ok(())
+}
+
+/// Make `edit` to the document in `file` as one change and write the change back to `file`
+pub(crate) fn edit(file: &Path, edit: Edit, options: EditOptions) -> Result<(), EditError> {
+    // Resolve symlinks so that we replace the file they point to rather than the link
+    let file = std::fs::canonicalize(file)?;
+    let data = std::fs::read(&file)?;
+    let mut doc = options.verify.load_autocommit(&data)?;
+    if let Some(actor) = options.actor {
+        doc.set_actor(actor);
+    }
+    let before = doc.get_heads();
+    match edit {
+        Edit::Set {
+            path,
+            value,
+            insert,
+        } => {
+            let value: hydrate::Value = serde_json::from_str(&value)?;
+            let (obj, prop) = resolve(&doc, &path)?;
+            let prop = prop.ok_or_else(|| EditError::InvalidPath(path.clone()))?;
+            // Putting at the end of a list appends to it
+            let insert = match prop {
+                am::Prop::Seq(index) => insert || index == doc.length(&obj),
+                am::Prop::Map(_) => false,
+            };
+            set(&mut doc, &obj, prop, &value, insert)?;
+        }
+        Edit::Rm { path } => {
+            let (obj, prop) = resolve(&doc, &path)?;
+            let prop = prop.ok_or_else(|| EditError::InvalidPath(path.clone()))?;
+            if doc.get(&obj, prop.clone())?.is_none() {
+                return Err(EditError::NotFound(path));
+            }
+            doc.delete(&obj, prop)?;
+        }
+        Edit::SpliceText {
+            path,
+            pos,
+            del,
+            text,
+        } => {
+            let obj = resolve_object(&doc, &path)?;
+            if doc.object_type(&obj)? != am::ObjType::Text {
+                return Err(EditError::NotText(path));
+            }
+            doc.splice_text(&obj, pos, del, &text)?;
+        }
+    }
+    let mut commit = CommitOptions::default();
+    if let Some(message) = options.message {
+        commit.set_message(message);
+    }
+    if doc.commit_with(commit).is_none() {
+        return Ok(());
+    }
+    let saved = if options.compact {
+        doc.save()
+    } else {
+        let mut saved = data;
+        saved.extend(doc.save_after(&before));
+        saved
+    };
+    write_atomically(&file, &saved)
+}
+
+/// Replace the contents of `file` with `data` by writing them to a temporary file in the same
+/// directory and renaming it over `file`, so that a failed write leaves `file` as it was
+fn write_atomically(file: &Path, data: &[u8]) -> Result<(), EditError> {
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(file.file_name().unwrap_or_default());
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp = file.with_file_name(tmp_name);
+    let result = (|| {
+        let mut out = std::fs::File::create(&tmp)?;
+        out.write_all(data)?;
+        out.set_permissions(std::fs::metadata(file)?.permissions())?;
+        out.sync_all()?;
+        std::fs::rename(&tmp, file)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    Ok(result?)
+}
+
+fn set(
+    doc: &mut am::AutoCommit,
+    obj: &am::ObjId,
+    prop: am::Prop,
+    value: &hydrate::Value,
+    insert: bool,
+) -> Result<(), EditError> {
+    let obj_type = match value {
+        hydrate::Value::Scalar(scalar) => {
+            match (prop, insert) {
+                (am::Prop::Seq(index), true) => doc.insert(obj, index, scalar.clone())?,
+                (prop, _) => doc.put(obj, prop, scalar.clone())?,
+            }
+            return Ok(());
+        }
+        hydrate::Value::Map(_) => am::ObjType::Map,
+        hydrate::Value::List(_) => am::ObjType::List,
+        hydrate::Value::Text(_) => am::ObjType::Text,
+    };
+    let id = match (prop, insert) {
+        (am::Prop::Seq(index), true) => doc.insert_object(obj, index, obj_type)?,
+        (prop, _) => doc.put_object(obj, prop, obj_type)?,
+    };
+    doc.update_object(&id, value)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> EditOptions {
+        EditOptions {
+            message: None,
+            actor: None,
+            compact: false,
+            verify: VerifyFlag(true),
+        }
+    }
+
+    fn get_json(file: &Path, path: &str) -> serde_json::Value {
+        let mut out = Vec::new();
+        get(file, &mut out, path, VerifyFlag(true)).unwrap();
+        serde_json::from_slice(&out).unwrap()
+    }
+
+    #[test]
+    fn parse_path_unescapes_segments() {
+        assert_eq!(parse_path("").unwrap(), Vec::<String>::new());
+        assert_eq!(parse_path("/a~1b/~0c/0").unwrap(), vec!["a/b", "~c", "0"]);
+        assert!(matches!(
+            parse_path("birds"),
+            Err(EditError::InvalidPath(_))
+        ));
+    }
+
+    #[test]
+    fn edits_are_appended_as_changes() {
+        let file = std::env::temp_dir().join(format!("automerge-edit-{}", std::process::id()));
+        std::fs::write(&file, am::AutoCommit::new().save()).unwrap();
+        let set = |path: &str, value: &str| Edit::Set {
+            path: path.to_string(),
+            value: value.to_string(),
+            insert: false,
+        };
+        edit(&file, set("/birds", r#"{"names": ["wren"]}"#), options()).unwrap();
+        edit(&file, set("/birds/names/-", r#""owl""#), options()).unwrap();
+        edit(
+            &file,
+            Edit::Set {
+                path: "/birds/names/0".to_string(),
+                value: r#""robin""#.to_string(),
+                insert: true,
+            },
+            options(),
+        )
+        .unwrap();
+        edit(&file, set("/notes", "1"), options()).unwrap();
+        edit(
+            &file,
+            Edit::Rm {
+                path: "/notes".to_string(),
+            },
+            options(),
+        )
+        .unwrap();
+        assert_eq!(
+            get_json(&file, ""),
+            serde_json::json!({"birds": {"names": ["robin", "wren", "owl"]}})
+        );
+        assert_eq!(get_json(&file, "/birds/names/2"), serde_json::json!("owl"));
+
+        let doc = am::Automerge::load(&std::fs::read(&file).unwrap()).unwrap();
+        let changes = doc.get_changes(&[]);
+        assert_eq!(changes.len(), 5);
+        // every edit without an actor was made by a new random one
+        let actors = changes
+            .iter()
+            .map(|c| c.actor_id())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(actors.len(), 5);
+        // the temporary files were renamed over the file
+        let dir = std::fs::read_dir(file.parent().unwrap()).unwrap();
+        let tmp_prefix = format!(".{}", file.file_name().unwrap().to_string_lossy());
+        assert!(!dir
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.file_name().to_string_lossy().starts_with(&tmp_prefix)));
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn splice_text_and_compact() {
+        let file = std::env::temp_dir().join(format!("automerge-splice-{}", std::process::id()));
+        let mut doc = am::AutoCommit::new();
+        let text = doc.put_object(am::ROOT, "text", am::ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello world").unwrap();
+        std::fs::write(&file, doc.save()).unwrap();
+        let actor = am::ActorId::from(b"actor");
+        let options = EditOptions {
+            message: Some("greet".to_string()),
+            actor: Some(actor.clone()),
+            compact: true,
+            verify: VerifyFlag(true),
+        };
+        let splice = Edit::SpliceText {
+            path: "/text".to_string(),
+            pos: 6,
+            del: 5,
+            text: "there".to_string(),
+        };
+        edit(&file, splice, options).unwrap();
+        assert_eq!(get_json(&file, "/text"), serde_json::json!("hello there"));
+
+        let doc = am::Automerge::load(&std::fs::read(&file).unwrap()).unwrap();
+        let heads = doc.get_heads();
+        let change = doc.get_change_by_hash(&heads[0]).unwrap();
+        assert_eq!(change.actor_id(), &actor);
+        assert_eq!(change.message().map(String::as_str), Some("greet"));
+        assert!(matches!(
+            get(&file, Vec::new(), "/missing", VerifyFlag(true)),
+            Err(EditError::NotFound(_))
+        ));
+        std::fs::remove_file(file).unwrap();
+    }
+}
@@ -1,23 +1,39 @@
 use anyhow::Result;
-use automerge as am;
+use automerge::{self as am, ReadDoc};
+use ciborium::Value as Cbor;
 
 use crate::{color_json::print_colored_json, VerifyFlag};
 
+/// The CBOR tag of text objects, which are otherwise indistinguishable from strings
+pub(crate) const CBOR_TAG_TEXT: u64 = 0x616d_0001;
+/// The CBOR tag of unsigned integers, which are otherwise indistinguishable from signed ones
+pub(crate) const CBOR_TAG_UINT: u64 = 0x616d_0002;
+/// The CBOR tag of counters
+pub(crate) const CBOR_TAG_COUNTER: u64 = 0x616d_0003;
+/// The CBOR tag of timestamps
+pub(crate) const CBOR_TAG_TIMESTAMP: u64 = 0x616d_0004;
+
 fn get_state_json(input_data: Vec<u8>, skip: VerifyFlag) -> Result<serde_json::Value> {
     let doc = skip.load(&input_data).unwrap(); // FIXME
     serde_json::to_value(am::AutoSerde::from(&doc)).map_err(Into::into)
 }
 
-pub(crate) fn export_json(
+fn read_state_json(
     mut changes_reader: impl std::io::Read,
-    mut writer: impl std::io::Write,
     skip: VerifyFlag,
-    is_tty: bool,
-) -> Result<()> {
+) -> Result<serde_json::Value> {
     let mut input_data = vec![];
     changes_reader.read_to_end(&mut input_data)?;
+    get_state_json(input_data, skip)
+}
 
-    let state_json = get_state_json(input_data, skip)?;
+pub(crate) fn export_json(
+    changes_reader: impl std::io::Read,
+    mut writer: impl std::io::Write,
+    skip: VerifyFlag,
+    is_tty: bool,
+) -> Result<()> {
+    let state_json = read_state_json(changes_reader, skip)?;
     if is_tty {
         print_colored_json(&state_json).unwrap();
         writeln!(writer).unwrap();
@@ -31,6 +47,110 @@ pub(crate) fn export_json(
     Ok(())
 }
 
+pub(crate) fn export_toml(
+    changes_reader: impl std::io::Read,
+    mut writer: impl std::io::Write,
+    skip: VerifyFlag,
+) -> Result<()> {
+    let state_json = read_state_json(changes_reader, skip)?;
+    if let Some(path) = find_null(&state_json, String::new()) {
+        anyhow::bail!(
+            "cannot export the null at {} as TOML has no null, export as json, yaml or cbor instead",
+            path
+        );
+    }
+    write!(writer, "{}", toml::to_string_pretty(&state_json)?)?;
+    Ok(())
+}
+
+/// The JSON pointer of the first null in `value`, which is at `path`
+fn find_null(value: &serde_json::Value, path: String) -> Option<String> {
+    match value {
+        serde_json::Value::Null => Some(path),
+        serde_json::Value::Object(entries) => entries.iter().find_map(|(key, value)| {
+            let key = key.replace('~', "~0").replace('/', "~1");
+            find_null(value, format!("{}/{}", path, key))
+        }),
+        serde_json::Value::Array(elements) => elements
+            .iter()
+            .enumerate()
+            .find_map(|(index, value)| find_null(value, format!("{}/{}", path, index))),
+        _ => None,
+    }
+}
+
+pub(crate) fn export_yaml(
+    changes_reader: impl std::io::Read,
+    mut writer: impl std::io::Write,
+    skip: VerifyFlag,
+) -> Result<()> {
+    let state_json = read_state_json(changes_reader, skip)?;
+    write!(writer, "{}", serde_norway::to_string(&state_json)?)?;
+    Ok(())
+}
+
+/// Export a document as CBOR, which unlike JSON keeps bytes, counters, timestamps, unsigned
+/// integers and text objects apart from the other values
+pub(crate) fn export_cbor(
+    mut changes_reader: impl std::io::Read,
+    writer: impl std::io::Write,
+    skip: VerifyFlag,
+) -> Result<()> {
+    let mut input_data = vec![];
+    changes_reader.read_to_end(&mut input_data)?;
+    let doc = skip.load(&input_data)?;
+    let state = get_state_cbor(&doc, am::Value::Object(am::ObjType::Map), am::ROOT)?;
+    ciborium::into_writer(&state, writer)?;
+    Ok(())
+}
+
+fn get_state_cbor(doc: &am::Automerge, value: am::Value<'_>, id: am::ObjId) -> Result<Cbor> {
+    Ok(match value {
+        am::Value::Object(am::ObjType::Map | am::ObjType::Table) => {
+            let mut entries = Vec::new();
+            for key in doc.keys(&id) {
+                if let Some((value, value_id)) = doc.get(&id, &key)? {
+                    entries.push((Cbor::Text(key), get_state_cbor(doc, value, value_id)?));
+                }
+            }
+            Cbor::Map(entries)
+        }
+        am::Value::Object(am::ObjType::List) => {
+            let mut elements = Vec::new();
+            for index in 0..doc.length(&id) {
+                if let Some((value, value_id)) = doc.get(&id, index)? {
+                    elements.push(get_state_cbor(doc, value, value_id)?);
+                }
+            }
+            Cbor::Array(elements)
+        }
+        am::Value::Object(am::ObjType::Text) => {
+            Cbor::Tag(CBOR_TAG_TEXT, Box::new(Cbor::Text(doc.text(&id)?)))
+        }
+        am::Value::Scalar(scalar) => match scalar.as_ref() {
+            am::ScalarValue::Bytes(b) => Cbor::Bytes(b.clone()),
+            am::ScalarValue::Str(s) => Cbor::Text(s.to_string()),
+            am::ScalarValue::Int(i) => Cbor::Integer((*i).into()),
+            am::ScalarValue::Uint(u) => {
+                Cbor::Tag(CBOR_TAG_UINT, Box::new(Cbor::Integer((*u).into())))
+            }
+            am::ScalarValue::F64(f) => Cbor::Float(*f),
+            am::ScalarValue::Counter(c) => Cbor::Tag(
+                CBOR_TAG_COUNTER,
+                Box::new(Cbor::Integer(i64::from(c).into())),
+            ),
+            am::ScalarValue::Timestamp(t) => {
+                Cbor::Tag(CBOR_TAG_TIMESTAMP, Box::new(Cbor::Integer((*t).into())))
+            }
+            am::ScalarValue::Boolean(b) => Cbor::Bool(*b),
+            am::ScalarValue::Null => Cbor::Null,
+            am::ScalarValue::Unknown { type_code, .. } => {
+                anyhow::bail!("cannot export a value of unknown type {}", type_code)
+            }
+        },
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
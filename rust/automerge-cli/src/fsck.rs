@@ -0,0 +1,258 @@
+use std::collections::{HashMap, HashSet};
+
+use automerge::{self as am, inspect, ReadDoc};
+
+use crate::log::short_hash;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum FsckError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("failed to load the salvaged changes: {0}")]
+    Automerge(#[from] am::AutomergeError),
+}
+
+/// Check every chunk of the document in `input` and write a report of the problems found to
+/// `output`. Returns whether the document is healthy.
+///
+/// If `repair` is given every change which could be salvaged is written to it as a clean document
+/// and what was dropped is added to the report. The changes of a compacted document chunk can't be
+/// salvaged one by one, so the clean document starts from that chunk.
+pub(crate) fn fsck(
+    mut input: impl std::io::Read,
+    mut output: impl std::io::Write,
+    repair: Option<impl std::io::Write>,
+) -> Result<bool, FsckError> {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+    let reports = inspect::inspect(&data);
+    let mut problems = 0;
+    for report in &reports {
+        let kind = match report.kind {
+            Some(inspect::ChunkKind::Document) => "document",
+            Some(inspect::ChunkKind::CompactedDocument) => "compacted document",
+            Some(inspect::ChunkKind::Change) => "change",
+            Some(inspect::ChunkKind::CompressedChange) => "compressed change",
+            None => "unknown",
+        };
+        write!(
+            output,
+            "chunk {}..{} {}: {} changes",
+            report.range.start,
+            report.range.end,
+            kind,
+            report.changes.len()
+        )?;
+        if report.compacted.is_empty() {
+            writeln!(output)?;
+        } else {
+            writeln!(output, ", {} compacted", report.compacted.len())?;
+        }
+        for problem in &report.problems {
+            writeln!(output, "  {}", problem)?;
+        }
+        problems += report.problems.len();
+    }
+
+    // Document chunks can overlap each other and the change chunks after them
+    let mut seen = HashSet::new();
+    let changes = reports
+        .iter()
+        .flat_map(|report| report.changes.iter())
+        .filter(|change| seen.insert(change.hash()))
+        .collect::<Vec<_>>();
+    // Compacted changes have no ops to salvage but they are still present for their dependents
+    let compacted = reports
+        .iter()
+        .flat_map(|report| report.compacted.iter().copied())
+        .collect::<HashSet<_>>();
+
+    // The changes with the same actor and seq as an earlier change
+    let mut duplicates = HashSet::new();
+    let mut seqs = HashMap::new();
+    for change in &changes {
+        let key = (change.actor_id().clone(), change.seq());
+        if let Some(first) = seqs.get(&key) {
+            writeln!(
+                output,
+                "duplicate seq {} of actor {}: {} and {}",
+                change.seq(),
+                change.actor_id(),
+                short_hash(first),
+                short_hash(&change.hash())
+            )?;
+            duplicates.insert(change.hash());
+            problems += 1;
+        } else {
+            seqs.insert(key, change.hash());
+        }
+    }
+    for change in &changes {
+        let missing = change
+            .deps()
+            .iter()
+            .filter(|dep| !seen.contains(dep) && !compacted.contains(dep));
+        for dep in missing {
+            writeln!(
+                output,
+                "orphan {}: missing dependency {}",
+                short_hash(&change.hash()),
+                short_hash(dep)
+            )?;
+            problems += 1;
+        }
+    }
+    writeln!(
+        output,
+        "{} chunks, {} changes, {} problems",
+        reports.len(),
+        changes.len(),
+        problems
+    )?;
+
+    if let Some(mut repaired) = repair {
+        let salvaged = changes
+            .iter()
+            .filter(|change| !duplicates.contains(&change.hash()))
+            .flat_map(|change| change.raw_bytes().iter().copied())
+            .collect::<Vec<_>>();
+        // Start from the compacted document which covers the most changes, if there is one
+        let base = reports
+            .iter()
+            .filter(|r| !r.compacted.is_empty() && r.problems.is_empty())
+            .max_by_key(|r| r.compacted.len() + r.changes.len());
+        let mut doc = match base {
+            Some(report) => {
+                writeln!(
+                    output,
+                    "starting from compacted document {}..{}",
+                    report.range.start, report.range.end
+                )?;
+                am::Automerge::load(&data[report.range.clone()])?
+            }
+            None => am::Automerge::new(),
+        };
+        doc.load_incremental(&salvaged)?;
+        repaired.write_all(&doc.save_with_options(am::SaveOptions::new().retain_orphans(false)))?;
+
+        let unloadable = reports
+            .iter()
+            .filter(|r| r.changes.is_empty() && !r.problems.is_empty());
+        for report in unloadable {
+            writeln!(
+                output,
+                "dropped chunk {}..{}",
+                report.range.start, report.range.end
+            )?;
+        }
+        for change in &changes {
+            let hash = change.hash();
+            let reason = if duplicates.contains(&hash) {
+                "duplicate seq"
+            } else if doc.get_change_by_hash(&hash).is_none() {
+                "missing dependencies"
+            } else {
+                continue;
+            };
+            writeln!(output, "dropped change {} ({})", hash, reason)?;
+        }
+        writeln!(output, "wrote {} changes", doc.get_changes_meta(&[]).len())?;
+    }
+    Ok(problems == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use am::transaction::Transactable;
+
+    fn run(data: &[u8], repair: bool) -> (bool, String, Vec<u8>) {
+        let mut out = Vec::new();
+        let mut repaired = Vec::new();
+        let healthy = fsck(data, &mut out, repair.then_some(&mut repaired)).unwrap();
+        (healthy, String::from_utf8(out).unwrap(), repaired)
+    }
+
+    #[test]
+    fn healthy_documents_have_no_problems() {
+        let mut doc = am::AutoCommit::new();
+        doc.put(am::ROOT, "a", 1).unwrap();
+        let mut data = doc.save();
+        let heads = doc.get_heads();
+        doc.put(am::ROOT, "b", 2).unwrap();
+        data.extend(doc.save_after(&heads));
+        let (healthy, out, _) = run(&data, false);
+        assert!(healthy);
+        assert!(out.ends_with("2 chunks, 2 changes, 0 problems\n"));
+    }
+
+    #[test]
+    fn repair_salvages_loadable_changes() {
+        let mut doc = am::AutoCommit::new().with_actor(am::ActorId::from(b"aaaa"));
+        doc.put(am::ROOT, "a", 1).unwrap();
+        let mut data = doc.save();
+        let good = data.len();
+        // Junk, then a change whose dependency is missing
+        data.extend_from_slice(b"junk");
+        let mut orphaned = doc.fork();
+        orphaned.put(am::ROOT, "b", 2).unwrap();
+        orphaned.commit();
+        let heads = orphaned.get_heads();
+        orphaned.put(am::ROOT, "c", 3).unwrap();
+        data.extend(orphaned.save_after(&heads));
+        // And a second change with the same actor and seq as the first
+        let mut duplicate = am::AutoCommit::new().with_actor(am::ActorId::from(b"aaaa"));
+        duplicate.put(am::ROOT, "d", 4).unwrap();
+        data.extend(duplicate.save());
+
+        assert!(am::Automerge::load(&data).is_err());
+        let (healthy, out, repaired) = run(&data, true);
+        assert!(!healthy);
+        assert!(out.contains(&format!(
+            "chunk {}..{} unknown: 0 changes\n",
+            good,
+            good + 4
+        )));
+        assert!(out.contains("duplicate seq 1 of actor 61616161"));
+        assert!(out.contains("orphan "));
+        assert!(out.contains(&format!("dropped chunk {}..{}\n", good, good + 4)));
+        assert!(out.contains("(duplicate seq)"));
+        assert!(out.contains("(missing dependencies)"));
+        assert!(out.ends_with("wrote 1 changes\n"));
+
+        let doc = am::Automerge::load(&repaired).unwrap();
+        assert_eq!(
+            doc.get(am::ROOT, "a").unwrap().unwrap().0,
+            am::Value::int(1)
+        );
+        assert_eq!(doc.get_changes_meta(&[]).len(), 1);
+    }
+
+    #[test]
+    fn compacted_changes_count_as_present() {
+        let mut doc = am::AutoCommit::new();
+        doc.put(am::ROOT, "a", 1).unwrap();
+        doc.commit();
+        let checkpoint = doc.get_heads();
+        doc.put(am::ROOT, "a", 2).unwrap();
+        doc.commit();
+        doc.compact(&checkpoint).unwrap();
+        let mut data = doc.save();
+        let heads = doc.get_heads();
+        doc.put(am::ROOT, "b", 3).unwrap();
+        data.extend(doc.save_after(&heads));
+
+        let (healthy, out, repaired) = run(&data, true);
+        assert!(healthy, "{}", out);
+        assert!(out.contains(" compacted document: 1 changes, 1 compacted\n"));
+        assert!(out.contains("starting from compacted document 0.."));
+        assert!(out.ends_with("wrote 3 changes\n"));
+
+        let repaired = am::Automerge::load(&repaired).unwrap();
+        assert_eq!(repaired.get_heads(), doc.get_heads());
+        assert_eq!(
+            repaired.get(am::ROOT, "b").unwrap().unwrap().0,
+            am::Value::int(3)
+        );
+    }
+}
@@ -1,5 +1,8 @@
 use automerge as am;
 use automerge::transaction::Transactable;
+use ciborium::Value as Cbor;
+
+use crate::export::{CBOR_TAG_COUNTER, CBOR_TAG_TEXT, CBOR_TAG_TIMESTAMP, CBOR_TAG_UINT};
 
 pub(crate) fn initialize_from_json(
     json_value: &serde_json::Value,
@@ -106,3 +109,125 @@ pub fn import_json(
     writer.write_all(&doc.save())?;
     Ok(())
 }
+
+pub fn import_toml(
+    mut reader: impl std::io::Read,
+    mut writer: impl std::io::Write,
+) -> anyhow::Result<()> {
+    let mut buffer = String::new();
+    reader.read_to_string(&mut buffer)?;
+
+    let json_value: serde_json::Value = toml::from_str(&buffer)?;
+    let mut doc = initialize_from_json(&json_value)?;
+    writer.write_all(&doc.save())?;
+    Ok(())
+}
+
+pub fn import_yaml(
+    mut reader: impl std::io::Read,
+    mut writer: impl std::io::Write,
+) -> anyhow::Result<()> {
+    let mut buffer = String::new();
+    reader.read_to_string(&mut buffer)?;
+
+    let json_value: serde_json::Value = serde_norway::from_str(&buffer)?;
+    let mut doc = initialize_from_json(&json_value)?;
+    writer.write_all(&doc.save())?;
+    Ok(())
+}
+
+/// Import a document exported by [`crate::export::export_cbor`]
+pub fn import_cbor(
+    reader: impl std::io::Read,
+    mut writer: impl std::io::Write,
+) -> anyhow::Result<()> {
+    let mut doc = am::AutoCommit::new();
+    match ciborium::from_reader(reader)? {
+        Cbor::Map(entries) => {
+            for (key, value) in entries {
+                let Cbor::Text(key) = key else {
+                    anyhow::bail!("expected a string key");
+                };
+                import_cbor_value(&mut doc, &am::ObjId::Root, key.into(), value, false)?;
+            }
+        }
+        _ => anyhow::bail!("expected a map"),
+    }
+    writer.write_all(&doc.save())?;
+    Ok(())
+}
+
+/// Put `value` at `prop` in `obj`, or insert it there if `insert` is true
+fn import_cbor_value(
+    doc: &mut am::AutoCommit,
+    obj: &am::ObjId,
+    prop: am::Prop,
+    value: Cbor,
+    insert: bool,
+) -> anyhow::Result<()> {
+    let scalar = match value {
+        Cbor::Map(entries) => {
+            let id = put_object(doc, obj, prop, am::ObjType::Map, insert)?;
+            for (key, value) in entries {
+                let Cbor::Text(key) = key else {
+                    anyhow::bail!("expected a string key");
+                };
+                import_cbor_value(doc, &id, key.into(), value, false)?;
+            }
+            return Ok(());
+        }
+        Cbor::Array(elements) => {
+            let id = put_object(doc, obj, prop, am::ObjType::List, insert)?;
+            for (index, value) in elements.into_iter().enumerate() {
+                import_cbor_value(doc, &id, index.into(), value, true)?;
+            }
+            return Ok(());
+        }
+        Cbor::Tag(CBOR_TAG_TEXT, value) => {
+            let Cbor::Text(text) = *value else {
+                anyhow::bail!("expected a tagged string");
+            };
+            let id = put_object(doc, obj, prop, am::ObjType::Text, insert)?;
+            doc.splice_text(&id, 0, 0, &text)?;
+            return Ok(());
+        }
+        Cbor::Tag(CBOR_TAG_UINT, value) => am::ScalarValue::Uint(cbor_integer(*value)?),
+        Cbor::Tag(CBOR_TAG_COUNTER, value) => am::ScalarValue::counter(cbor_integer(*value)?),
+        Cbor::Tag(CBOR_TAG_TIMESTAMP, value) => am::ScalarValue::Timestamp(cbor_integer(*value)?),
+        Cbor::Integer(i) => match i64::try_from(i) {
+            Ok(i) => am::ScalarValue::Int(i),
+            Err(_) => am::ScalarValue::Uint(u64::try_from(i)?),
+        },
+        Cbor::Bytes(b) => am::ScalarValue::Bytes(b),
+        Cbor::Float(f) => am::ScalarValue::F64(f),
+        Cbor::Text(s) => am::ScalarValue::Str(s.into()),
+        Cbor::Bool(b) => am::ScalarValue::Boolean(b),
+        Cbor::Null => am::ScalarValue::Null,
+        other => anyhow::bail!("unsupported CBOR value: {:?}", other),
+    };
+    match (prop, insert) {
+        (am::Prop::Seq(index), true) => doc.insert(obj, index, scalar)?,
+        (prop, _) => doc.put(obj, prop, scalar)?,
+    }
+    Ok(())
+}
+
+fn put_object(
+    doc: &mut am::AutoCommit,
+    obj: &am::ObjId,
+    prop: am::Prop,
+    obj_type: am::ObjType,
+    insert: bool,
+) -> anyhow::Result<am::ObjId> {
+    Ok(match (prop, insert) {
+        (am::Prop::Seq(index), true) => doc.insert_object(obj, index, obj_type)?,
+        (prop, _) => doc.put_object(obj, prop, obj_type)?,
+    })
+}
+
+fn cbor_integer<T: TryFrom<ciborium::value::Integer>>(value: Cbor) -> anyhow::Result<T> {
+    match value {
+        Cbor::Integer(i) => T::try_from(i).map_err(|_| anyhow::anyhow!("integer out of range")),
+        _ => anyhow::bail!("expected a tagged integer"),
+    }
+}
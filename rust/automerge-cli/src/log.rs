@@ -0,0 +1,281 @@
+use std::collections::HashSet;
+
+use automerge as am;
+use thiserror::Error;
+
+use crate::VerifyFlag;
+
+/// The number of hex digits that abbreviated change hashes are shown with
+const SHORT_HASH_LEN: usize = 8;
+
+#[derive(Error, Debug)]
+pub enum LogError {
+    #[error("Error reading change file: {:?}", source)]
+    ReadingChanges {
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Error loading changes: {:?}", source)]
+    ApplyingInitialChanges {
+        #[source]
+        source: am::AutomergeError,
+    },
+    #[error("Error writing to output: {:?}", source)]
+    WritingToOutput {
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("No change matches {0}")]
+    UnknownChange(String),
+    #[error("{0} matches more than one change")]
+    AmbiguousChange(String),
+}
+
+pub(crate) struct LogOptions {
+    /// Draw the dependency graph alongside the changes
+    pub(crate) graph: bool,
+    /// Only show the changes made by actors whose IDs start with this prefix
+    pub(crate) actor: Option<String>,
+    /// Only show the changes that aren't ancestors of the change whose hash
+    /// starts with this prefix
+    pub(crate) since: Option<String>,
+}
+
+pub(crate) fn short_hash(hash: &am::ChangeHash) -> String {
+    let mut hash = hash.to_string();
+    hash.truncate(SHORT_HASH_LEN);
+    hash
+}
+
+/// Find the one change in `doc` whose hash starts with `prefix`
+pub(crate) fn resolve_hash(doc: &am::Automerge, prefix: &str) -> Result<am::ChangeHash, LogError> {
+    let prefix = prefix.to_lowercase();
+    let mut matches = doc
+        .get_changes_meta(&[])
+        .into_iter()
+        .map(|meta| meta.hash)
+        .filter(|hash| hash.to_string().starts_with(&prefix));
+    match (matches.next(), matches.next()) {
+        (Some(hash), None) => Ok(hash),
+        (None, _) => Err(LogError::UnknownChange(prefix)),
+        (Some(_), Some(_)) => Err(LogError::AmbiguousChange(prefix)),
+    }
+}
+
+pub(crate) fn load(
+    mut input: impl std::io::Read,
+    skip: VerifyFlag,
+) -> Result<am::Automerge, LogError> {
+    let mut buf: Vec<u8> = Vec::new();
+    input
+        .read_to_end(&mut buf)
+        .map_err(|e| LogError::ReadingChanges { source: e })?;
+    skip.load(&buf)
+        .map_err(|e| LogError::ApplyingInitialChanges { source: e })
+}
+
+pub(crate) fn log(
+    input: impl std::io::Read,
+    mut output: impl std::io::Write,
+    skip: VerifyFlag,
+    options: LogOptions,
+) -> Result<(), LogError> {
+    let doc = load(input, skip)?;
+    let since = match &options.since {
+        Some(prefix) => vec![resolve_hash(&doc, prefix)?],
+        None => Vec::new(),
+    };
+    // The changes are in causal order but are shown newest first
+    let mut changes = doc.get_changes_meta(&since);
+    changes.reverse();
+    let in_range: HashSet<_> = changes.iter().map(|meta| meta.hash).collect();
+    let mut graph = Graph::default();
+    for meta in &changes {
+        let lanes = graph.next(
+            &meta.hash,
+            meta.deps.iter().filter(|d| in_range.contains(d)),
+        );
+        let actor = meta.actor.to_hex_string();
+        if let Some(prefix) = &options.actor {
+            if !actor.starts_with(&prefix.to_lowercase()) {
+                continue;
+            }
+        }
+        let deps = meta.deps.iter().map(short_hash).collect::<Vec<_>>();
+        let message = meta.message.as_deref().unwrap_or_default();
+        let line = format!(
+            "{} {} seq={} time={} deps=[{}] {}",
+            short_hash(&meta.hash),
+            actor,
+            meta.seq,
+            meta.timestamp,
+            deps.join(","),
+            message
+        );
+        let line = if options.graph {
+            format!("{} {}", lanes, line)
+        } else {
+            line
+        };
+        writeln!(output, "{}", line.trim_end())
+            .map_err(|e| LogError::WritingToOutput { source: e })?;
+    }
+    Ok(())
+}
+
+/// The lanes of a dependency graph drawn from the newest change to the oldest
+///
+/// Each lane waits for the change that the change above it depends on.
+#[derive(Default)]
+struct Graph {
+    lanes: Vec<Option<am::ChangeHash>>,
+}
+
+impl Graph {
+    /// Place the change `hash` in a lane and return the row that draws it
+    fn next<'a, I: Iterator<Item = &'a am::ChangeHash>>(
+        &mut self,
+        hash: &am::ChangeHash,
+        deps: I,
+    ) -> String {
+        let waiting = |lane: &Option<am::ChangeHash>| lane.as_ref() == Some(hash);
+        let col = match self.lanes.iter().position(waiting) {
+            Some(col) => col,
+            None => match self.lanes.iter().position(Option::is_none) {
+                Some(col) => col,
+                None => {
+                    self.lanes.push(None);
+                    self.lanes.len() - 1
+                }
+            },
+        };
+        let row = self
+            .lanes
+            .iter()
+            .enumerate()
+            .map(|(i, lane)| match lane {
+                _ if i == col => "*",
+                Some(_) => "|",
+                None => " ",
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        // Branches which forked from this change end here
+        for lane in self.lanes.iter_mut() {
+            if waiting(lane) {
+                *lane = None;
+            }
+        }
+        let deps = deps
+            .filter(|dep| !self.lanes.contains(&Some(**dep)))
+            .copied()
+            .collect::<Vec<_>>();
+        self.lanes[col] = deps.first().copied();
+        for dep in deps.into_iter().skip(1) {
+            match self.lanes.iter().position(Option::is_none) {
+                Some(free) => self.lanes[free] = Some(dep),
+                None => self.lanes.push(Some(dep)),
+            }
+        }
+        while let Some(None) = self.lanes.last() {
+            self.lanes.pop();
+        }
+        row
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use am::transaction::Transactable;
+
+    fn forked_doc() -> (Vec<u8>, am::AutoCommit) {
+        let mut doc1 = am::AutoCommit::new().with_actor(am::ActorId::from(b"aaaa"));
+        doc1.put(am::ROOT, "a", 1).unwrap();
+        doc1.commit_with(am::transaction::CommitOptions::default().with_message("first"));
+        let mut doc2 = doc1.fork().with_actor(am::ActorId::from(b"bbbb"));
+        doc1.put(am::ROOT, "b", 2).unwrap();
+        doc1.commit();
+        doc2.put(am::ROOT, "c", 3).unwrap();
+        doc2.commit();
+        doc1.merge(&mut doc2).unwrap();
+        (doc1.save(), doc1)
+    }
+
+    fn run(bytes: &[u8], options: LogOptions) -> Vec<String> {
+        let mut out = Vec::new();
+        log(bytes, &mut out, VerifyFlag::default(), options).unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn log_lists_changes_newest_first() {
+        let (bytes, mut doc) = forked_doc();
+        let options = LogOptions {
+            graph: false,
+            actor: None,
+            since: None,
+        };
+        let lines = run(&bytes, options);
+        assert_eq!(lines.len(), 3);
+        let first = doc.get_changes(&[])[0].hash();
+        assert_eq!(
+            lines[2],
+            format!("{} 61616161 seq=1 time=0 deps=[] first", short_hash(&first))
+        );
+    }
+
+    #[test]
+    fn log_filters_by_actor_and_since() {
+        let (bytes, mut doc) = forked_doc();
+        let first = doc.get_changes(&[])[0].hash();
+        let options = LogOptions {
+            graph: false,
+            actor: Some("6262".to_string()),
+            since: None,
+        };
+        let lines = run(&bytes, options);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains(" 62626262 seq=1 "));
+
+        let options = LogOptions {
+            graph: false,
+            actor: None,
+            since: Some(short_hash(&first)),
+        };
+        assert_eq!(run(&bytes, options).len(), 2);
+    }
+
+    #[test]
+    fn log_draws_graph() {
+        let (bytes, _) = forked_doc();
+        let options = LogOptions {
+            graph: true,
+            actor: None,
+            since: None,
+        };
+        let graph = run(&bytes, options);
+        // Two concurrent heads which both depend on the first change
+        assert!(graph[0].starts_with("* "));
+        assert!(graph[1].starts_with("| * "));
+        assert!(graph[2].starts_with("* "));
+    }
+
+    #[test]
+    fn resolve_hash_rejects_unknown_prefixes() {
+        let (bytes, _) = forked_doc();
+        let doc = am::Automerge::load(&bytes).unwrap();
+        assert!(matches!(
+            resolve_hash(&doc, "zz"),
+            Err(LogError::UnknownChange(_))
+        ));
+        assert!(matches!(
+            resolve_hash(&doc, ""),
+            Err(LogError::AmbiguousChange(_))
+        ));
+    }
+}
@@ -7,11 +7,17 @@ use clap::{
 };
 
 mod color_json;
+mod diff;
+mod edit;
 mod examine;
 mod examine_sync;
 mod export;
+mod fsck;
 mod import;
+mod log;
 mod merge;
+mod show;
+mod stats;
 
 #[derive(Parser, Debug)]
 #[clap(about = "Automerge CLI")]
@@ -24,6 +30,8 @@ struct Opts {
 enum ExportFormat {
     Json,
     Toml,
+    Yaml,
+    Cbor,
 }
 
 #[derive(Copy, Clone, Default, Debug)]
@@ -37,6 +45,17 @@ impl VerifyFlag {
             automerge::Automerge::load_unverified_heads(buf)
         }
     }
+
+    fn load_autocommit(
+        &self,
+        buf: &[u8],
+    ) -> Result<automerge::AutoCommit, automerge::AutomergeError> {
+        if self.0 {
+            automerge::AutoCommit::load(buf)
+        } else {
+            automerge::AutoCommit::load_unverified_heads(buf)
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -71,6 +90,8 @@ impl FromStr for ExportFormat {
         match input {
             "json" => Ok(ExportFormat::Json),
             "toml" => Ok(ExportFormat::Toml),
+            "yaml" => Ok(ExportFormat::Yaml),
+            "cbor" => Ok(ExportFormat::Cbor),
             _ => Err(anyhow!("Invalid export format: {}", input)),
         }
     }
@@ -80,7 +101,8 @@ impl FromStr for ExportFormat {
 enum Command {
     /// Output current state of an Automerge document in a specified format
     Export {
-        /// Format for output: json, toml
+        /// Format for output: json, toml, yaml, cbor. TOML has no null, so documents holding
+        /// a null can't be exported as toml
         #[clap(long, short, default_value = "json")]
         format: ExportFormat,
 
@@ -97,7 +119,7 @@ enum Command {
     },
 
     Import {
-        /// Format for input: json, toml
+        /// Format for input: json, toml, yaml, cbor
         #[clap(long, short, default_value = "json")]
         format: ExportFormat,
 
@@ -120,6 +142,151 @@ enum Command {
     /// Read an automerge sync messaage and print a JSON representation of it
     ExamineSync { input_file: Option<PathBuf> },
 
+    /// Print the differences between two sets of heads of one automerge document or between two
+    /// automerge documents
+    Diff {
+        /// The document, or the document to diff from if `other_file` is given
+        input_file: Option<PathBuf>,
+
+        /// The document to diff to
+        other_file: Option<PathBuf>,
+
+        /// The (abbreviated) heads to diff from. If omitted assumes the empty document
+        #[clap(long, value_delimiter = ',', conflicts_with = "other_file")]
+        from: Vec<String>,
+
+        /// The (abbreviated) heads to diff to. If omitted assumes the current heads
+        #[clap(long, value_delimiter = ',', conflicts_with = "other_file")]
+        to: Vec<String>,
+
+        /// Print the patches as JSON
+        #[clap(long)]
+        json: bool,
+
+        /// Whether to verify the head hashes of a compressed document
+        #[clap(long, action = clap::ArgAction::SetFalse)]
+        skip_verifying_heads: VerifyFlag,
+    },
+
+    /// Check every chunk of an automerge document for corruption, orphaned changes, duplicate
+    /// seqs, mismatched heads and unknown columns
+    Fsck {
+        input_file: Option<PathBuf>,
+
+        /// Salvage every change which can be loaded and write them to this file as a clean document,
+        /// starting from the compacted document chunk if there is one
+        #[clap(long, value_name = "OUT")]
+        repair: Option<PathBuf>,
+    },
+
+    /// Print the value at a path in an automerge document as JSON
+    Get {
+        file: PathBuf,
+
+        /// A JSON pointer such as "/birds/0". The empty path is the whole document
+        path: String,
+
+        /// Whether to verify the head hashes of a compressed document
+        #[clap(long, action = clap::ArgAction::SetFalse)]
+        skip_verifying_heads: VerifyFlag,
+    },
+
+    /// Put a JSON value at a path in an automerge document
+    Set {
+        file: PathBuf,
+
+        /// A JSON pointer such as "/birds/0". "-" is the end of a list
+        path: String,
+
+        /// The JSON value to put
+        value: String,
+
+        /// Insert the value into a list instead of replacing the value at the index
+        #[clap(long)]
+        insert: bool,
+
+        #[clap(flatten)]
+        options: EditArgs,
+    },
+
+    /// Delete the value at a path in an automerge document
+    Rm {
+        file: PathBuf,
+
+        /// A JSON pointer such as "/birds/0"
+        path: String,
+
+        #[clap(flatten)]
+        options: EditArgs,
+    },
+
+    /// Splice text into the text object at a path in an automerge document
+    SpliceText {
+        file: PathBuf,
+
+        /// A JSON pointer to the text object
+        path: String,
+
+        /// The index to splice at
+        pos: usize,
+
+        /// The number of characters to delete
+        del: isize,
+
+        /// The text to insert
+        #[clap(default_value = "")]
+        text: String,
+
+        #[clap(flatten)]
+        options: EditArgs,
+    },
+
+    /// Print one line for each change in an automerge document, newest first
+    Log {
+        input_file: Option<PathBuf>,
+
+        /// Draw the dependency graph of the changes
+        #[clap(long)]
+        graph: bool,
+
+        /// Only show the changes made by actors whose hex IDs start with this prefix
+        #[clap(long)]
+        actor: Option<String>,
+
+        /// Only show the changes that were made since the change with this (abbreviated) hash
+        #[clap(long)]
+        since: Option<String>,
+
+        /// Whether to verify the head hashes of a compressed document
+        #[clap(long, action = clap::ArgAction::SetFalse)]
+        skip_verifying_heads: VerifyFlag,
+    },
+
+    /// Print the ops of one change in an automerge document
+    Show {
+        /// The (abbreviated) hash of the change
+        hash: String,
+
+        input_file: Option<PathBuf>,
+
+        /// Whether to verify the head hashes of a compressed document
+        #[clap(long, action = clap::ArgAction::SetFalse)]
+        skip_verifying_heads: VerifyFlag,
+    },
+
+    /// Print statistics about an automerge document and a breakdown of where its bytes go
+    Stats {
+        input_file: Option<PathBuf>,
+
+        /// The number of the largest objects to list
+        #[clap(long, default_value_t = 10)]
+        top: usize,
+
+        /// Whether to verify the head hashes of a compressed document
+        #[clap(long, action = clap::ArgAction::SetFalse)]
+        skip_verifying_heads: VerifyFlag,
+    },
+
     /// Read one or more automerge documents and output a merged, compacted version of them
     Merge {
         /// The file to write to. If omitted assumes stdout
@@ -131,6 +298,37 @@ enum Command {
     },
 }
 
+/// The options of the commands which change a document
+#[derive(Debug, clap::Args)]
+struct EditArgs {
+    /// The message of the change
+    #[clap(long, short)]
+    message: Option<String>,
+
+    /// The hex ID of the actor to make the change as. If omitted a random actor is used
+    #[clap(long)]
+    actor: Option<automerge::ActorId>,
+
+    /// Rewrite the file as a compacted document instead of appending the change to it
+    #[clap(long)]
+    compact: bool,
+
+    /// Whether to verify the head hashes of a compressed document
+    #[clap(long, action = clap::ArgAction::SetFalse)]
+    skip_verifying_heads: VerifyFlag,
+}
+
+impl From<EditArgs> for edit::EditOptions {
+    fn from(args: EditArgs) -> Self {
+        Self {
+            message: args.message,
+            actor: args.actor,
+            compact: args.compact,
+            verify: args.skip_verifying_heads,
+        }
+    }
+}
+
 fn open_file_or_stdin(maybe_path: Option<PathBuf>) -> Result<Box<dyn std::io::Read>> {
     if std::io::stdin().is_terminal() {
         if let Some(path) = maybe_path {
@@ -172,31 +370,39 @@ fn main() -> Result<()> {
             } else {
                 Box::new(std::io::stdout())
             };
+            let mut in_buffer = open_file_or_stdin(changes_file)?;
             match format {
-                ExportFormat::Json => {
-                    let mut in_buffer = open_file_or_stdin(changes_file)?;
-                    export::export_json(
-                        &mut in_buffer,
-                        output,
-                        skip_verifying_heads,
-                        std::io::stdout().is_terminal(),
-                    )
+                ExportFormat::Json => export::export_json(
+                    &mut in_buffer,
+                    output,
+                    skip_verifying_heads,
+                    std::io::stdout().is_terminal(),
+                ),
+                ExportFormat::Toml => {
+                    export::export_toml(&mut in_buffer, output, skip_verifying_heads)
+                }
+                ExportFormat::Yaml => {
+                    export::export_yaml(&mut in_buffer, output, skip_verifying_heads)
+                }
+                ExportFormat::Cbor => {
+                    export::export_cbor(&mut in_buffer, output, skip_verifying_heads)
                 }
-                ExportFormat::Toml => unimplemented!(),
             }
         }
         Command::Import {
             format,
             input_file,
             changes_file,
-        } => match format {
-            ExportFormat::Json => {
-                let mut out_buffer = create_file_or_stdout(changes_file)?;
-                let mut in_buffer = open_file_or_stdin(input_file)?;
-                import::import_json(&mut in_buffer, &mut out_buffer)
+        } => {
+            let mut out_buffer = create_file_or_stdout(changes_file)?;
+            let mut in_buffer = open_file_or_stdin(input_file)?;
+            match format {
+                ExportFormat::Json => import::import_json(&mut in_buffer, &mut out_buffer),
+                ExportFormat::Toml => import::import_toml(&mut in_buffer, &mut out_buffer),
+                ExportFormat::Yaml => import::import_yaml(&mut in_buffer, &mut out_buffer),
+                ExportFormat::Cbor => import::import_cbor(&mut in_buffer, &mut out_buffer),
             }
-            ExportFormat::Toml => unimplemented!(),
-        },
+        }
         Command::Examine {
             input_file,
             skip_verifying_heads,
@@ -228,6 +434,137 @@ fn main() -> Result<()> {
             }
             Ok(())
         }
+        Command::Diff {
+            input_file,
+            other_file,
+            from,
+            to,
+            json,
+            skip_verifying_heads,
+        } => {
+            let source = match (input_file, other_file) {
+                (Some(from), Some(to)) => diff::DiffSource::Files { from, to },
+                (input_file, _) => diff::DiffSource::Heads {
+                    input: open_file_or_stdin(input_file)?,
+                    from,
+                    to,
+                },
+            };
+            match diff::diff(source, std::io::stdout(), skip_verifying_heads, json) {
+                Ok(()) => {}
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                }
+            }
+            Ok(())
+        }
+        Command::Fsck { input_file, repair } => {
+            let in_buffer = open_file_or_stdin(input_file)?;
+            let repaired = repair.map(File::create).transpose()?;
+            let repairing = repaired.is_some();
+            let healthy = fsck::fsck(in_buffer, std::io::stdout(), repaired)?;
+            if !healthy && !repairing {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Command::Get {
+            file,
+            path,
+            skip_verifying_heads,
+        } => {
+            edit::get(&file, std::io::stdout(), &path, skip_verifying_heads)?;
+            Ok(())
+        }
+        Command::Set {
+            file,
+            path,
+            value,
+            insert,
+            options,
+        } => {
+            let set = edit::Edit::Set {
+                path,
+                value,
+                insert,
+            };
+            edit::edit(&file, set, options.into())?;
+            Ok(())
+        }
+        Command::Rm {
+            file,
+            path,
+            options,
+        } => {
+            edit::edit(&file, edit::Edit::Rm { path }, options.into())?;
+            Ok(())
+        }
+        Command::SpliceText {
+            file,
+            path,
+            pos,
+            del,
+            text,
+            options,
+        } => {
+            let splice = edit::Edit::SpliceText {
+                path,
+                pos,
+                del,
+                text,
+            };
+            edit::edit(&file, splice, options.into())?;
+            Ok(())
+        }
+        Command::Log {
+            input_file,
+            graph,
+            actor,
+            since,
+            skip_verifying_heads,
+        } => {
+            let in_buffer = open_file_or_stdin(input_file)?;
+            let options = log::LogOptions {
+                graph,
+                actor,
+                since,
+            };
+            match log::log(in_buffer, std::io::stdout(), skip_verifying_heads, options) {
+                Ok(()) => {}
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                }
+            }
+            Ok(())
+        }
+        Command::Show {
+            hash,
+            input_file,
+            skip_verifying_heads,
+        } => {
+            let in_buffer = open_file_or_stdin(input_file)?;
+            match show::show(in_buffer, std::io::stdout(), skip_verifying_heads, &hash) {
+                Ok(()) => {}
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                }
+            }
+            Ok(())
+        }
+        Command::Stats {
+            input_file,
+            top,
+            skip_verifying_heads,
+        } => {
+            let in_buffer = open_file_or_stdin(input_file)?;
+            match stats::stats(in_buffer, std::io::stdout(), skip_verifying_heads, top) {
+                Ok(()) => {}
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                }
+            }
+            Ok(())
+        }
         Command::Merge { input, output_file } => {
             let out_buffer = create_file_or_stdout(output_file)?;
             match merge::merge(input.into(), out_buffer) {
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use automerge::{self as am, ReadDoc};
+
+use crate::{
+    log::{load, resolve_hash, short_hash, LogError},
+    VerifyFlag,
+};
+
+/// Render the path to the object with ID `obj` as of the change `hash`
+///
+/// Objects which can't be found are rendered as their IDs.
+fn object_path(doc: &am::Automerge, obj: &str, hash: am::ChangeHash) -> String {
+    let Ok(id) = doc.import_obj(obj) else {
+        return obj.to_string();
+    };
+    if id == am::ROOT {
+        return String::new();
+    }
+    match doc.parents_at(&id, &[hash]) {
+        Ok(parents) => parents
+            .path()
+            .into_iter()
+            .map(|(_, prop)| format!("/{}", prop))
+            .collect(),
+        Err(_) => obj.to_string(),
+    }
+}
+
+/// Render the value of an op and its datatype, if it has either
+fn op_value(op: &serde_json::Value) -> String {
+    let mut value = match (op.get("name"), op.get("value")) {
+        (Some(name), Some(value)) => format!(" {} = {}", name.as_str().unwrap_or_default(), value),
+        (None, Some(value)) => format!(" = {}", value),
+        _ => String::new(),
+    };
+    if let Some(datatype) = op.get("datatype").and_then(|d| d.as_str()) {
+        value.push_str(&format!(" ({})", datatype));
+    }
+    if op.get("expand").and_then(|e| e.as_bool()) == Some(true) {
+        value.push_str(" (expand)");
+    }
+    value
+}
+
+pub(crate) fn show(
+    input: impl std::io::Read,
+    mut output: impl std::io::Write,
+    skip: VerifyFlag,
+    hash: &str,
+) -> Result<(), LogError> {
+    let doc = load(input, skip)?;
+    let hash = resolve_hash(&doc, hash)?;
+    let change = doc
+        .get_change_by_hash(&hash)
+        .ok_or_else(|| LogError::UnknownChange(hash.to_string()))?
+        .decode();
+    let deps = change.deps.iter().map(short_hash).collect::<Vec<_>>();
+    let mut lines = vec![
+        format!("change {}", hash),
+        format!("actor   {}", change.actor_id),
+        format!("seq     {}", change.seq),
+        format!("time    {}", change.time),
+        format!("deps    [{}]", deps.join(",")),
+    ];
+    if let Some(message) = &change.message {
+        lines.push(format!("message {}", message));
+    }
+    lines.push(String::new());
+    let mut paths = HashMap::new();
+    for (offset, op) in change.operations.iter().enumerate() {
+        let json = serde_json::to_value(op).unwrap();
+        let obj = op.obj.to_string();
+        let path = paths
+            .entry(obj.clone())
+            .or_insert_with(|| object_path(&doc, &obj, hash));
+        let target = match (json.get("key"), json.get("elemId")) {
+            (Some(key), _) => format!("{}/{}", path, key.as_str().unwrap_or_default()),
+            (None, Some(elem)) if op.insert => {
+                format!("{}[after {}]", path, elem.as_str().unwrap_or_default())
+            }
+            (None, Some(elem)) => format!("{}[{}]", path, elem.as_str().unwrap_or_default()),
+            (None, None) => path.clone(),
+        };
+        let action = json
+            .get("action")
+            .and_then(|a| a.as_str())
+            .unwrap_or_default();
+        lines.push(format!(
+            "{:>6}@{} {:<9} {}{}",
+            change.start_op.get() + offset as u64,
+            change.actor_id,
+            action,
+            target,
+            op_value(&json)
+        ));
+    }
+    for line in lines {
+        writeln!(output, "{}", line).map_err(|e| LogError::WritingToOutput { source: e })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use am::transaction::Transactable;
+
+    #[test]
+    fn show_renders_ops_against_paths() {
+        let mut doc = am::AutoCommit::new().with_actor(am::ActorId::from(b"aaaa"));
+        let birds = doc.put_object(am::ROOT, "birds", am::ObjType::Map).unwrap();
+        doc.commit();
+        doc.put(&birds, "wrens", 3).unwrap();
+        let list = doc
+            .put_object(&birds, "sightings", am::ObjType::List)
+            .unwrap();
+        doc.insert(&list, 0, "owl").unwrap();
+        let hash = doc.commit().unwrap();
+        let mut out = Vec::new();
+        show(
+            doc.save().as_slice(),
+            &mut out,
+            VerifyFlag::default(),
+            &short_hash(&hash),
+        )
+        .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let ops = out
+            .lines()
+            .skip_while(|l| !l.is_empty())
+            .skip(1)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ops,
+            vec![
+                "     2@61616161 set       /birds/wrens = 3 (int)",
+                "     3@61616161 makeList  /birds/sightings",
+                "     4@61616161 set       /birds/sightings[after _head] = \"owl\"",
+            ]
+        );
+        assert!(out.starts_with(&format!("change {}\nactor   61616161\nseq     2\n", hash)));
+    }
+
+    #[test]
+    fn show_rejects_unknown_hashes() {
+        let mut doc = am::AutoCommit::new();
+        doc.put(am::ROOT, "a", 1).unwrap();
+        let result = show(
+            doc.save().as_slice(),
+            Vec::new(),
+            VerifyFlag::default(),
+            "zz",
+        );
+        assert!(matches!(result, Err(LogError::UnknownChange(_))));
+    }
+}
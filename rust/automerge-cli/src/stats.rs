@@ -0,0 +1,146 @@
+use std::collections::{BTreeMap, HashMap};
+
+use automerge::{self as am, inspect, ReadDoc};
+
+use crate::{
+    log::{load, LogError},
+    VerifyFlag,
+};
+
+/// The path to `obj`, or its ID if it has been deleted
+fn object_path(doc: &am::Automerge, obj: &am::ObjId) -> String {
+    if *obj == am::ROOT {
+        return "/".to_string();
+    }
+    match doc.parents(obj).ok().and_then(|p| p.visible_path()) {
+        Some(path) => path
+            .into_iter()
+            .map(|(_, prop)| format!("/{}", prop))
+            .collect(),
+        None => format!("{} (deleted)", obj),
+    }
+}
+
+/// Print statistics about the document in `input` and a breakdown of where its bytes go
+pub(crate) fn stats(
+    mut input: impl std::io::Read,
+    mut output: impl std::io::Write,
+    skip: VerifyFlag,
+    top: usize,
+) -> Result<(), LogError> {
+    let mut data = Vec::new();
+    input
+        .read_to_end(&mut data)
+        .map_err(|e| LogError::ReadingChanges { source: e })?;
+    let doc = load(data.as_slice(), skip)?;
+    let stats = doc.stats();
+    let op_stats = doc.op_stats();
+
+    let mut lines = vec![
+        format!("bytes          {}", data.len()),
+        format!("changes        {}", stats.num_changes),
+        format!("actors         {}", stats.num_actors),
+        format!("ops            {}", stats.num_ops),
+        format!("  visible      {}", op_stats.num_visible_ops),
+        format!("  tombstones   {}", op_stats.num_tombstones),
+        format!("  increments   {}", op_stats.num_increments),
+        format!("text bytes     {}", op_stats.text_bytes),
+        String::new(),
+        "bytes per column".to_string(),
+    ];
+
+    // The columns of every chunk, largest first within the changes and the ops
+    let mut columns: BTreeMap<(inspect::Section, String), (usize, bool, bool)> = BTreeMap::new();
+    for report in inspect::inspect(&data) {
+        for column in report.columns {
+            let entry = columns.entry((column.section, column.name)).or_default();
+            entry.0 += column.bytes;
+            entry.1 |= column.deflated;
+            entry.2 |= column.zstd;
+        }
+    }
+    let mut columns = columns.into_iter().collect::<Vec<_>>();
+    columns.sort_by_key(|((section, _), (bytes, _, _))| (*section, std::cmp::Reverse(*bytes)));
+    for ((section, name), (bytes, deflated, zstd)) in columns {
+        let section = match section {
+            inspect::Section::Changes => "changes",
+            inspect::Section::Ops => "ops",
+        };
+        lines.push(format!(
+            "  {:<8}{:<16}{:>10}{}",
+            section,
+            name,
+            bytes,
+            match (deflated, zstd) {
+                (_, true) => " (zstd)",
+                (true, false) => " (deflated)",
+                (false, false) => "",
+            }
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push("changes per actor".to_string());
+    let mut actors: HashMap<String, u64> = HashMap::new();
+    for meta in doc.get_changes_meta(&[]) {
+        *actors.entry(meta.actor.to_hex_string()).or_default() += 1;
+    }
+    let mut actors = actors.into_iter().collect::<Vec<_>>();
+    actors.sort_by(|(a1, c1), (a2, c2)| c2.cmp(c1).then(a1.cmp(a2)));
+    for (actor, count) in actors {
+        lines.push(format!("  {} {}", actor, count));
+    }
+
+    lines.push(String::new());
+    lines.push("largest objects by op count".to_string());
+    let mut objects = op_stats.ops_per_object;
+    objects.sort_by(|(_, c1), (_, c2)| c2.cmp(c1));
+    for (obj, count) in objects.into_iter().take(top) {
+        lines.push(format!("  {:>8} {}", count, object_path(&doc, &obj)));
+    }
+
+    for line in lines {
+        writeln!(output, "{}", line).map_err(|e| LogError::WritingToOutput { source: e })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use am::transaction::Transactable;
+
+    #[test]
+    fn stats_break_down_the_document() {
+        let mut doc = am::AutoCommit::new().with_actor(am::ActorId::from(b"aaaa"));
+        let text = doc.put_object(am::ROOT, "text", am::ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello").unwrap();
+        doc.put(am::ROOT, "count", am::ScalarValue::counter(1))
+            .unwrap();
+        doc.commit();
+        doc.splice_text(&text, 0, 1, "").unwrap();
+        doc.increment(am::ROOT, "count", 1).unwrap();
+        doc.put(am::ROOT, "count", 5).unwrap();
+        doc.commit();
+
+        let mut out = Vec::new();
+        stats(doc.save().as_slice(), &mut out, VerifyFlag::default(), 2).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines[1], "changes        2");
+        assert_eq!(lines[3], "ops            9");
+        assert_eq!(lines[4], "  visible      6");
+        assert_eq!(lines[5], "  tombstones   2");
+        assert_eq!(lines[6], "  increments   1");
+        assert_eq!(lines[7], "text bytes     5");
+        assert!(lines.iter().any(|l| l.starts_with("  ops     key string")));
+        assert!(lines.contains(&"  61616161 2"));
+        let largest = lines
+            .iter()
+            .skip_while(|l| **l != "largest objects by op count")
+            .skip(1)
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(largest, vec!["         5 /text", "         4 /"]);
+    }
+}
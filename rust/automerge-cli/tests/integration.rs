@@ -61,6 +61,156 @@ fn import_export_isomorphic() {
     assert_eq!(stdout, json_bytes);
 }
 
+#[test]
+fn fsck_repairs_trailing_junk() {
+    let bin = env!("CARGO_BIN_EXE_automerge");
+    let json_bytes = serde_json::to_string_pretty(&serde_json::json!({"wrens": 3.0})).unwrap();
+    let mut doc = cmd!(bin, "import")
+        .stdin_bytes(json_bytes.clone())
+        .stdout_capture()
+        .run()
+        .unwrap()
+        .stdout;
+    doc.extend_from_slice(b"junk");
+
+    let check = cmd!(bin, "fsck").stdin_bytes(doc.clone()).unchecked().run();
+    assert!(!check.unwrap().status.success());
+
+    let repaired = env::temp_dir().join(format!("automerge-fsck-{}", std::process::id()));
+    cmd!(bin, "fsck", "--repair", &repaired)
+        .stdin_bytes(doc)
+        .stdout_null()
+        .run()
+        .unwrap();
+    let stdout = cmd!(bin, "export").stdin_path(&repaired).read().unwrap();
+    std::fs::remove_file(repaired).unwrap();
+    assert_eq!(stdout, json_bytes);
+}
+
+#[test]
+fn fsck_repairs_compacted_documents_from_their_checkpoint() {
+    use automerge::transaction::Transactable;
+    let bin = env!("CARGO_BIN_EXE_automerge");
+    let mut doc = automerge::AutoCommit::new();
+    doc.put(automerge::ROOT, "a", 1).unwrap();
+    doc.put(automerge::ROOT, "b", 3).unwrap();
+    doc.commit();
+    let checkpoint = doc.get_heads();
+    doc.put(automerge::ROOT, "a", 2).unwrap();
+    doc.commit();
+    doc.compact(&checkpoint).unwrap();
+    let doc = doc.save();
+
+    let report = cmd!(bin, "fsck").stdin_bytes(doc.clone()).read().unwrap();
+    assert!(report.ends_with("1 chunks, 1 changes, 0 problems"));
+
+    let repaired = env::temp_dir().join(format!("automerge-fsck-compacted-{}", std::process::id()));
+    cmd!(bin, "fsck", "--repair", &repaired)
+        .stdin_bytes(doc)
+        .stdout_null()
+        .run()
+        .unwrap();
+    let stdout = cmd!(bin, "export").stdin_path(&repaired).read().unwrap();
+    std::fs::remove_file(repaired).unwrap();
+    let result: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(result, serde_json::json!({"a": 2, "b": 3}));
+}
+
+/// Convert `initial_state_json` into `format` and back through automerge documents
+fn round_trip_through(format: &str) {
+    let bin = env!("CARGO_BIN_EXE_automerge");
+    let initial_state_json = serde_json::json!({
+        "birds": {
+            "wrens": 3,
+            "sparrows": 15.5,
+            "names": ["wren", "sparrow"],
+            "seen": true
+        }
+    });
+    let json_bytes = serde_json::to_string_pretty(&initial_state_json).unwrap();
+
+    let stdout = cmd!(bin, "import")
+        .stdin_bytes(json_bytes)
+        .pipe(cmd!(bin, "export", "--format", format))
+        .pipe(cmd!(bin, "import", "--format", format))
+        .pipe(cmd!(bin, "export"))
+        .read()
+        .unwrap();
+    let result: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(result, initial_state_json);
+}
+
+#[test]
+fn import_export_toml() {
+    round_trip_through("toml");
+}
+
+#[test]
+fn toml_export_names_the_null_it_cannot_represent() {
+    let bin = env!("CARGO_BIN_EXE_automerge");
+    let json_bytes =
+        serde_json::to_string_pretty(&serde_json::json!({"birds": {"names": ["wren", null]}}))
+            .unwrap();
+    let output = cmd!(bin, "import")
+        .stdin_bytes(json_bytes)
+        .pipe(cmd!(bin, "export", "--format", "toml"))
+        .stdout_null()
+        .stderr_capture()
+        .unchecked()
+        .run()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("/birds/names/1"), "{}", stderr);
+}
+
+#[test]
+fn import_export_yaml() {
+    round_trip_through("yaml");
+}
+
+#[test]
+fn import_export_cbor() {
+    round_trip_through("cbor");
+}
+
+#[test]
+fn cbor_keeps_types_json_cannot_express() {
+    use automerge::{transaction::Transactable, ReadDoc, ScalarValue};
+
+    let bin = env!("CARGO_BIN_EXE_automerge");
+    let mut doc = automerge::AutoCommit::new();
+    doc.put(automerge::ROOT, "bytes", vec![1_u8, 2, 3]).unwrap();
+    doc.put(automerge::ROOT, "counter", ScalarValue::counter(5))
+        .unwrap();
+    doc.put(automerge::ROOT, "timestamp", ScalarValue::Timestamp(1000))
+        .unwrap();
+    doc.put(automerge::ROOT, "int", 7_i64).unwrap();
+    doc.put(automerge::ROOT, "uint", 7_u64).unwrap();
+    doc.put(automerge::ROOT, "str", "a string").unwrap();
+    let text = doc
+        .put_object(automerge::ROOT, "text", automerge::ObjType::Text)
+        .unwrap();
+    doc.splice_text(&text, 0, 0, "some text").unwrap();
+
+    let stdout = cmd!(bin, "export", "--format", "cbor")
+        .stdin_bytes(doc.save())
+        .pipe(cmd!(bin, "import", "--format", "cbor"))
+        .stdout_capture()
+        .run()
+        .unwrap()
+        .stdout;
+    let result = automerge::Automerge::load(&stdout).unwrap();
+    for key in ["bytes", "counter", "timestamp", "int", "uint", "str"] {
+        let (expected, _) = doc.get(automerge::ROOT, key).unwrap().unwrap();
+        let (actual, _) = result.get(automerge::ROOT, key).unwrap().unwrap();
+        assert_eq!(actual, expected, "{}", key);
+    }
+    let (_, text) = result.get(automerge::ROOT, "text").unwrap().unwrap();
+    assert_eq!(result.object_type(&text), Ok(automerge::ObjType::Text));
+    assert_eq!(result.text(&text).unwrap(), "some text");
+}
+
 /*
 #[test]
 fn import_change_export() {
@@ -596,6 +596,7 @@ impl From<&[am::sync::Capability]> for AR {
             .filter_map(|c| match c {
                 am::sync::Capability::MessageV1 => Some(JsValue::from_str("message-v1")),
                 am::sync::Capability::MessageV2 => Some(JsValue::from_str("message-v2")),
+                am::sync::Capability::Zstd => Some(JsValue::from_str("zstd")),
                 am::sync::Capability::Unknown(_) => None,
             })
             .collect())
@@ -654,6 +655,7 @@ impl TryFrom<JS> for Vec<Capability> {
                 match as_str.as_str() {
                     "message-v1" => Ok(Capability::MessageV1),
                     "message-v2" => Ok(Capability::MessageV2),
+                    "zstd" => Ok(Capability::Zstd),
                     other => Err(error::BadCapabilities::ElemNotValid(i, other.to_string())),
                 }
             })
@@ -1,4 +1,6 @@
-use automerge::{transaction::Transactable, Automerge, ObjType, ROOT};
+use automerge::{
+    transaction::Transactable, Automerge, LoadMode, LoadOptions, ObjType, ReadDoc, ROOT,
+};
 use criterion::{criterion_group, criterion_main, Criterion};
 use std::hint::black_box;
 
@@ -68,6 +70,28 @@ fn deep_history_doc(n: u64) -> Automerge {
     doc
 }
 
+fn many_texts_doc(n: u64) -> Automerge {
+    let mut doc = Automerge::new();
+    let mut tx = doc.transaction();
+    tx.put(ROOT, "title", "notes").unwrap();
+    let notes = tx.put_object(ROOT, "notes", ObjType::List).unwrap();
+    for i in 0..n {
+        let note = tx.insert_object(&notes, i as usize, ObjType::Text).unwrap();
+        tx.splice_text(&note, 0, 0, &random_string(100)).unwrap();
+        // delete every other character so the indexes aren't one long run
+        for j in 0..50 {
+            tx.delete(&note, j).unwrap();
+        }
+    }
+    tx.commit();
+
+    doc
+}
+
+fn load_with_mode(data: &[u8], mode: LoadMode) -> Automerge {
+    Automerge::load_with_options(data, LoadOptions::new().load_mode(mode)).unwrap()
+}
+
 fn save_load(doc: &Automerge) {
     let save_data = doc.save();
     let new_doc = Automerge::load(save_data.as_slice()).unwrap();
@@ -98,5 +122,42 @@ fn criterion_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, criterion_benchmark);
+fn lazy_load_benchmark(c: &mut Criterion) {
+    let n = 1_000;
+    let data = many_texts_doc(n).save();
+
+    // criterion only measures time, so report how much memory each mode
+    // holds on to alongside it
+    for mode in [LoadMode::Eager, LoadMode::Lazy] {
+        let doc = load_with_mode(&data, mode);
+        let loaded = doc.memory_usage();
+        doc.get(ROOT, "title").unwrap();
+        let read = doc.memory_usage();
+        println!(
+            "load_many_texts_doc {} {:?}: indexes {} of {} bytes, {} of {} after reading root",
+            n,
+            mode,
+            loaded.indexes,
+            loaded.total(),
+            read.indexes,
+            read.total()
+        );
+    }
+
+    let mut group = c.benchmark_group(format!("load_many_texts_doc {}", n));
+    for mode in [LoadMode::Eager, LoadMode::Lazy] {
+        group.bench_function(format!("{:?}", mode), |b| {
+            b.iter(|| load_with_mode(black_box(&data), mode))
+        });
+        group.bench_function(format!("{:?} then read root", mode), |b| {
+            b.iter(|| {
+                let doc = load_with_mode(black_box(&data), mode);
+                doc.get(ROOT, "title").unwrap().is_some()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark, lazy_load_benchmark);
 criterion_main!(benches);
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::ops::RangeBounds;
 
 use crate::automerge::SaveOptions;
@@ -7,7 +8,7 @@ use crate::exid::ExId;
 use crate::iter::{DocIter, Keys, ListRange, MapRange, Spans, Values};
 use crate::marks::{ExpandMark, Mark, MarkSet};
 use crate::op_set2::{ChangeMetadata, Parents};
-use crate::patches::{PatchLog, TextRepresentation};
+use crate::patches::{PatchLog, PatchSubscriptions, SubscriptionId, TextRepresentation};
 use crate::sync::SyncDoc;
 use crate::transaction::{CommitOptions, Transactable};
 use crate::types::Clock;
@@ -17,7 +18,7 @@ use crate::{
     transaction::TransactionInner, ActorId, Automerge, AutomergeError, Change, ChangeHash, Cursor,
     Prop, Value,
 };
-use crate::{LoadOptions, VerificationMode};
+use crate::{LoadOptions, UpdateTextOptions, VerificationMode};
 
 /// An automerge document that automatically manages transactions.
 ///
@@ -59,6 +60,7 @@ pub struct AutoCommit {
     pub(crate) doc: Automerge,
     transaction: Option<(PatchLog, TransactionInner)>,
     patch_log: PatchLog,
+    observer_log: Option<PatchLog>,
     diff_cursor: Vec<ChangeHash>,
     diff_cache: Option<(OpRange, Vec<Patch>)>,
     save_cursor: Vec<ChangeHash>,
@@ -76,6 +78,7 @@ impl Default for AutoCommit {
             doc: Automerge::new(),
             transaction: None,
             patch_log: PatchLog::inactive(text_rep),
+            observer_log: None,
             diff_cursor: Vec::new(),
             diff_cache: None,
             save_cursor: Vec::new(),
@@ -100,6 +103,7 @@ impl AutoCommit {
             doc,
             transaction: None,
             patch_log: PatchLog::inactive(text_rep),
+            observer_log: None,
             diff_cursor: Vec::new(),
             diff_cache: None,
             save_cursor: Vec::new(),
@@ -114,6 +118,7 @@ impl AutoCommit {
             doc,
             transaction: None,
             patch_log: PatchLog::inactive(text_encoding.into()),
+            observer_log: None,
             diff_cursor: Vec::new(),
             diff_cache: None,
             save_cursor: Vec::new(),
@@ -128,6 +133,7 @@ impl AutoCommit {
             doc,
             transaction: None,
             patch_log: PatchLog::inactive(text_encoding.into()),
+            observer_log: None,
             diff_cursor: Vec::new(),
             diff_cache: None,
             save_cursor: Vec::new(),
@@ -159,6 +165,7 @@ impl AutoCommit {
             doc,
             transaction: None,
             patch_log: PatchLog::inactive(text_encoding.into()),
+            observer_log: None,
             diff_cursor: Vec::new(),
             diff_cache: None,
             save_cursor: Vec::new(),
@@ -202,6 +209,16 @@ impl AutoCommit {
         self.doc.make_patches(patch_log)
     }
 
+    /// Generate the patches recorded in `patch_log` along with the patches which undo them
+    ///
+    /// See [`Automerge::make_patches_with_inverse()`]
+    pub fn make_patches_with_inverse(
+        &self,
+        patch_log: &mut PatchLog,
+    ) -> Result<Vec<(Patch, Vec<Patch>)>, AutomergeError> {
+        self.doc.make_patches_with_inverse(patch_log)
+    }
+
     /// Generates a diff from `before` to `after`
     ///
     /// By default the diff requires a sequental scan of all the ops in the doc.
@@ -291,12 +308,49 @@ impl AutoCommit {
         patches
     }
 
+    /// Like [`Self::diff_incremental()`] but pairs each patch with the patches which undo it
+    ///
+    /// The inverse patches are worked out from the document at [`Self::diff_cursor()`]. To undo
+    /// all of the changes apply the inverse patches in the reverse order of the forward patches.
+    /// Returns [`AutomergeError::InverseOfCompactedHistory`] if the diff cursor is before the
+    /// checkpoint of a call to [`Self::compact()`], as the values the inverses would restore have
+    /// been removed.
+    pub fn diff_incremental_with_inverse(
+        &mut self,
+    ) -> Result<Vec<(Patch, Vec<Patch>)>, AutomergeError> {
+        self.ensure_transaction_closed();
+        let before = self.diff_cursor();
+        if !self.doc.history_intact_at(&before) {
+            return Err(AutomergeError::InverseOfCompactedHistory);
+        }
+        let patches = self.diff_incremental();
+        Ok(crate::patches::invert(
+            &self.doc,
+            &before,
+            self.patch_log.text_rep(),
+            patches,
+        ))
+    }
+
+    /// Like [`Self::diff_incremental()`] but divides the patches between the subscribers in
+    /// `subscriptions`
+    ///
+    /// See [`PatchSubscriptions::route()`]
+    pub fn diff_incremental_subscribed(
+        &mut self,
+        subscriptions: &PatchSubscriptions,
+    ) -> HashMap<SubscriptionId, Vec<Patch>> {
+        let patches = self.diff_incremental();
+        subscriptions.route(self, &patches)
+    }
+
     pub fn fork(&mut self) -> Self {
         self.ensure_transaction_closed();
         Self {
             doc: self.doc.fork(),
             transaction: self.transaction.clone(),
             patch_log: PatchLog::inactive(self.patch_log.text_rep()),
+            observer_log: None,
             diff_cursor: vec![],
             diff_cache: None,
             save_cursor: vec![],
@@ -310,6 +364,7 @@ impl AutoCommit {
             doc: self.doc.fork_at(heads)?,
             transaction: self.transaction.clone(),
             patch_log: PatchLog::inactive(self.patch_log.text_rep()),
+            observer_log: None,
             diff_cursor: vec![],
             diff_cache: None,
             save_cursor: vec![],
@@ -358,15 +413,21 @@ impl AutoCommit {
             self.patch_log
                 .migrate_actors(&self.doc.ops().actors)
                 .unwrap();
+            if let Some(observer_log) = self.observer_log.as_mut() {
+                observer_log.migrate_actors(&self.doc.ops().actors).unwrap();
+            }
             let inner = TransactionInner::new(args);
-            let branch = self.patch_log.branch();
+            let mut branch = self.patch_log.branch();
+            if self.is_observed() {
+                branch.set_active(true);
+            }
             self.transaction = Some((branch, inner));
         }
     }
 
     pub(crate) fn ensure_transaction_closed(&mut self) {
         if let Some((patch_log, tx)) = self.transaction.take() {
-            self.patch_log.merge(patch_log);
+            self.merge_logged(patch_log);
             let hash = tx.commit(&mut self.doc, None, None);
             if self.isolation.is_some() && hash.is_some() {
                 self.isolation = hash.map(|h| vec![h])
@@ -382,57 +443,137 @@ impl AutoCommit {
     /// The return value is the number of ops which were applied, this is not useful and will
     /// change in future.
     pub fn load_incremental(&mut self, data: &[u8]) -> Result<usize, AutomergeError> {
-        self.ensure_transaction_closed();
-        if self.isolation.is_some() {
-            self.doc
-                .load_incremental_log_patches(data, &mut PatchLog::null())
-        } else {
-            self.doc
-                .load_incremental_log_patches(data, &mut self.patch_log)
-        }
+        self.log_external(None, |doc, log| doc.load_incremental_log_patches(data, log))
+    }
+
+    /// Like [`Self::load_incremental()`] but log the changes to the current state of the document
+    /// to `patch_log` instead of to this document's own log
+    pub fn load_incremental_log_patches(
+        &mut self,
+        data: &[u8],
+        patch_log: &mut PatchLog,
+    ) -> Result<usize, AutomergeError> {
+        self.log_external(Some(patch_log), |doc, log| {
+            doc.load_incremental_log_patches(data, log)
+        })
     }
 
     pub fn apply_changes(
         &mut self,
         changes: impl IntoIterator<Item = Change> + Clone,
     ) -> Result<(), AutomergeError> {
-        self.ensure_transaction_closed();
-        if self.isolation.is_some() {
-            self.doc
-                .apply_changes_log_patches(changes, &mut PatchLog::null())
-        } else {
-            self.doc
-                .apply_changes_log_patches(changes, &mut self.patch_log)
-        }
+        self.log_external(None, |doc, log| doc.apply_changes_log_patches(changes, log))
+    }
+
+    /// Like [`Self::apply_changes()`] but log the changes to the current state of the document
+    /// to `patch_log` instead of to this document's own log
+    pub fn apply_changes_log_patches(
+        &mut self,
+        changes: impl IntoIterator<Item = Change> + Clone,
+        patch_log: &mut PatchLog,
+    ) -> Result<(), AutomergeError> {
+        self.log_external(Some(patch_log), |doc, log| {
+            doc.apply_changes_log_patches(changes, log)
+        })
     }
 
     pub fn apply_changes_batch(
         &mut self,
         changes: impl IntoIterator<Item = Change> + Clone,
     ) -> Result<(), AutomergeError> {
-        self.ensure_transaction_closed();
-        if self.isolation.is_some() {
-            self.doc
-                .apply_changes_batch_log_patches(changes, &mut PatchLog::null())
-        } else {
-            self.doc
-                .apply_changes_batch_log_patches(changes, &mut self.patch_log)
-        }
+        self.log_external(None, |doc, log| {
+            doc.apply_changes_batch_log_patches(changes, log)
+        })
     }
 
     /// Takes all the changes in `other` which are not in `self` and applies them
     pub fn merge(&mut self, other: &mut AutoCommit) -> Result<Vec<ChangeHash>, AutomergeError> {
-        self.ensure_transaction_closed();
         other.ensure_transaction_closed();
-        if self.isolation.is_some() {
-            self.doc
-                .merge_and_log_patches(&mut other.doc, &mut PatchLog::null())
-        } else {
-            self.doc
-                .merge_and_log_patches(&mut other.doc, &mut self.patch_log)
+        self.log_external(None, |doc, log| {
+            doc.merge_and_log_patches(&mut other.doc, log)
+        })
+    }
+
+    /// Like [`Self::merge()`] but log the changes to the current state of the document to
+    /// `patch_log` instead of to this document's own log
+    pub fn merge_and_log_patches(
+        &mut self,
+        other: &mut AutoCommit,
+        patch_log: &mut PatchLog,
+    ) -> Result<Vec<ChangeHash>, AutomergeError> {
+        other.ensure_transaction_closed();
+        self.log_external(Some(patch_log), |doc, log| {
+            doc.merge_and_log_patches(&mut other.doc, log)
+        })
+    }
+
+    /// Record the patches for every change made to the document from now on in `patch_log`
+    ///
+    /// This is independent of the diff cursor: local edits and loaded, merged and synced changes
+    /// are all logged, and [`Self::diff_incremental()`] does not reset the log. Take the patches
+    /// recorded so far with [`Self::take_observed_patches()`]. Pass `None` to stop recording.
+    pub fn set_observer_log(&mut self, patch_log: Option<PatchLog>) {
+        self.ensure_transaction_closed();
+        self.observer_log = patch_log;
+    }
+
+    /// Make the patches recorded in the log set by [`Self::set_observer_log()`] since it was set or
+    /// since the last call to this method, and clear the log
+    pub fn take_observed_patches(&mut self) -> Vec<Patch> {
+        self.ensure_transaction_closed();
+        match self.observer_log.as_mut() {
+            Some(log) if log.is_active() => {
+                let patches = log.make_patches(&self.doc);
+                log.truncate();
+                patches
+            }
+            _ => Vec::new(),
         }
     }
 
+    fn is_observed(&self) -> bool {
+        self.isolation.is_none() && self.observer_log.as_ref().is_some_and(|l| l.is_active())
+    }
+
+    /// Add the events logged by a transaction to the diff cursor's log and to the observer log
+    fn merge_logged(&mut self, patch_log: PatchLog) {
+        if self.is_observed() {
+            if let Some(observer_log) = self.observer_log.as_mut() {
+                observer_log.merge(patch_log.clone());
+            }
+        }
+        if self.patch_log.is_active() {
+            self.patch_log.merge(patch_log);
+        }
+    }
+
+    /// Apply changes from elsewhere to the document with `f`
+    ///
+    /// The changes are logged to `patch_log` if one is given and otherwise to our own log, or to
+    /// nowhere while isolated. The observer log is given the changes as a diff of the heads.
+    fn log_external<T, F>(
+        &mut self,
+        patch_log: Option<&mut PatchLog>,
+        f: F,
+    ) -> Result<T, AutomergeError>
+    where
+        F: FnOnce(&mut Automerge, &mut PatchLog) -> Result<T, AutomergeError>,
+    {
+        self.ensure_transaction_closed();
+        let before = self.is_observed().then(|| self.doc.get_heads());
+        let result = match patch_log {
+            Some(patch_log) => f(&mut self.doc, patch_log),
+            None if self.isolation.is_some() => f(&mut self.doc, &mut PatchLog::null()),
+            None => f(&mut self.doc, &mut self.patch_log),
+        };
+        if let (Some(before), Some(observer_log)) = (before, self.observer_log.as_mut()) {
+            let before_clock = self.doc.clock_at(&before);
+            let after_clock = self.doc.clock_at(&self.doc.get_heads());
+            diff::log_diff(&self.doc, &before_clock, &after_clock, observer_log);
+        }
+        result
+    }
+
     /// Save the entirety of this document in a compact form.
     pub fn save(&mut self) -> Vec<u8> {
         self.save_with_options(SaveOptions::default())
@@ -462,10 +603,7 @@ impl AutoCommit {
 
     /// Save this document, but don't run it through DEFLATE afterwards
     pub fn save_nocompress(&mut self) -> Vec<u8> {
-        self.save_with_options(SaveOptions {
-            deflate: false,
-            ..Default::default()
-        })
+        self.save_with_options(SaveOptions::new().deflate(false))
     }
 
     /// Save the changes since the last call to [`Self::save()`]
@@ -541,6 +679,43 @@ impl AutoCommit {
         self.doc.import_obj(s)
     }
 
+    /// Count the ops in this document by visibility and by object
+    ///
+    /// This closes the transaction first, if one is in progress.
+    pub fn op_stats(&mut self) -> crate::OpStats {
+        self.ensure_transaction_closed();
+        self.doc.op_stats()
+    }
+
+    /// Remove deleted ops which every peer has seen, see [`Automerge::compact()`]
+    ///
+    /// This closes the transaction first, if one is in progress.
+    pub fn compact(&mut self, heads: &[ChangeHash]) -> Result<usize, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.compact(heads)
+    }
+
+    /// Estimate the heap memory held by this document, see [`Automerge::memory_usage()`]
+    ///
+    /// In addition to the document this counts the patches which have been logged but not yet
+    /// returned by [`Self::diff_incremental()`], those cached from the last call to it and those
+    /// recorded in the log set by [`Self::set_observer_log()`].
+    pub fn memory_usage(&self) -> crate::MemoryUsage {
+        let cached = self
+            .diff_cache
+            .as_ref()
+            .map_or(0, |(_, patches)| crate::heap_size::vec(patches));
+        let in_transaction = self
+            .transaction
+            .as_ref()
+            .map_or(0, |(log, _)| log.heap_size());
+        let observed = self.observer_log.as_ref().map_or(0, |log| log.heap_size());
+        crate::MemoryUsage {
+            patches: self.patch_log.heap_size() + observed + in_transaction + cached,
+            ..self.doc.memory_usage()
+        }
+    }
+
     #[doc(hidden)]
     pub fn dump(&mut self) {
         self.ensure_transaction_closed();
@@ -600,7 +775,7 @@ impl AutoCommit {
         // ensure that even no changes triggers a change
         self.ensure_transaction_open();
         let (patch_log, tx) = self.transaction.take().unwrap();
-        self.patch_log.merge(patch_log);
+        self.merge_logged(patch_log);
         let hash = tx.commit(&mut self.doc, options.message, options.time);
         if self.isolation.is_some() && hash.is_some() {
             self.isolation = hash.map(|h| vec![h])
@@ -671,6 +846,9 @@ impl AutoCommit {
             let before_clock = self.doc.clock_at(&before);
             let after_clock = self.doc.clock_at(after);
             diff::log_diff(&self.doc, &before_clock, &after_clock, &mut self.patch_log);
+            if let Some(observer_log) = self.observer_log.as_mut() {
+                diff::log_diff(&self.doc, &before_clock, &after_clock, observer_log);
+            }
         }
     }
 
@@ -1092,20 +1270,49 @@ impl Transactable for AutoCommit {
         &mut self,
         obj: &ExId,
         new_text: S,
+    ) -> Result<(), AutomergeError> {
+        self.update_text_with_options(obj, new_text, UpdateTextOptions::default())
+    }
+
+    fn update_text_with_options<S: AsRef<str>>(
+        &mut self,
+        obj: &ExId,
+        new_text: S,
+        options: UpdateTextOptions,
     ) -> Result<(), AutomergeError> {
         self.ensure_transaction_open();
         let (patch_log, tx) = self.transaction.as_mut().unwrap();
-        crate::text_diff::myers_diff(&mut self.doc, tx, patch_log, obj, new_text)
+        crate::text_diff::myers_diff(&mut self.doc, tx, patch_log, obj, new_text, options)
     }
 
     fn update_spans<'a, O: AsRef<ExId>, I: IntoIterator<Item = crate::BlockOrText<'a>>>(
         &mut self,
         text: O,
         new_text: I,
+    ) -> Result<(), AutomergeError> {
+        self.update_spans_with_options(text, new_text, UpdateTextOptions::default())
+    }
+
+    fn update_spans_with_options<
+        'a,
+        O: AsRef<ExId>,
+        I: IntoIterator<Item = crate::BlockOrText<'a>>,
+    >(
+        &mut self,
+        text: O,
+        new_text: I,
+        options: UpdateTextOptions,
     ) -> Result<(), AutomergeError> {
         self.ensure_transaction_open();
         let (patch_log, tx) = self.transaction.as_mut().unwrap();
-        crate::text_diff::myers_block_diff(&mut self.doc, tx, patch_log, text.as_ref(), new_text)
+        crate::text_diff::myers_block_diff(
+            &mut self.doc,
+            tx,
+            patch_log,
+            text.as_ref(),
+            new_text,
+            options,
+        )
     }
 
     fn update_object<O: AsRef<ExId>>(
@@ -1135,33 +1342,20 @@ impl SyncDoc for SyncWrapper<'_> {
         sync_state: &mut sync::State,
         message: sync::Message,
     ) -> Result<(), AutomergeError> {
-        self.inner.ensure_transaction_closed();
-        if self.inner.isolation.is_some() {
-            self.inner.doc.receive_sync_message_log_patches(
-                sync_state,
-                message,
-                &mut PatchLog::null(),
-            )
-        } else {
-            self.inner.doc.receive_sync_message_log_patches(
-                sync_state,
-                message,
-                &mut self.inner.patch_log,
-            )
-        }
+        self.inner.log_external(None, |doc, log| {
+            doc.receive_sync_message_log_patches(sync_state, message, log)
+        })
     }
 
-    // I dont like this function - it makes sense on automerge but not autocommit
-    // FIXME
     fn receive_sync_message_log_patches(
         &mut self,
         sync_state: &mut sync::State,
         message: sync::Message,
         patch_log: &mut PatchLog,
     ) -> Result<(), AutomergeError> {
-        self.inner
-            .doc
-            .receive_sync_message_log_patches(sync_state, message, patch_log)
+        self.inner.log_external(Some(patch_log), |doc, log| {
+            doc.receive_sync_message_log_patches(sync_state, message, log)
+        })
     }
 }
 
@@ -11,7 +11,7 @@ pub(crate) use crate::op_set2::types::ScalarValue;
 pub(crate) use crate::op_set2::{
     ChangeMetadata, KeyRef, OpQuery, OpQueryTerm, OpSet, OpType, Parents,
 };
-pub(crate) use crate::read::{ReadDoc, ReadDocInternal};
+pub(crate) use crate::read::{MemoryUsage, OpStats, ReadDoc, ReadDocInternal};
 
 use crate::change_graph::ChangeGraph;
 use crate::cursor::{CursorPosition, MoveCursor, OpCursor};
@@ -19,12 +19,14 @@ use crate::exid::ExId;
 use crate::iter::{DocIter, Keys, ListRange, MapRange, Spans, Values};
 use crate::marks::{Mark, MarkAccumulator, MarkSet};
 use crate::patches::{Patch, PatchLog, TextRepresentation};
-use crate::storage::{self, change, load, CompressConfig, Document, VerificationMode};
+use crate::storage::{self, change, load, Codec, CompressConfig, Document, VerificationMode};
 use crate::transaction::{
     self, CommitOptions, Failure, Success, Transactable, Transaction, TransactionArgs,
 };
 
 use crate::hydrate;
+use crate::op_set2::types::Action;
+use crate::sync::Capability;
 use crate::types::{
     ActorId, ChangeHash, Clock, ListEncoding, ObjId, ObjMeta, OpId, TextEncoding, Value,
 };
@@ -71,6 +73,20 @@ pub enum StringMigration {
     ConvertToText,
 }
 
+/// When to build the indexes used to look up ops in each object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Index every object while loading
+    Eager,
+    /// Defer indexing an object until it is first accessed
+    ///
+    /// Loading only indexes the counters, each object is indexed the first time it is read or
+    /// modified. This makes loading a large document faster and cheaper when only a few objects
+    /// are used, at the cost of a slower first access to each object. Reads which look at the
+    /// document as of some earlier heads scan the ops of an object rather than indexing it.
+    Lazy,
+}
+
 #[derive(Debug)]
 pub struct LoadOptions<'a> {
     on_partial_load: OnPartialLoad,
@@ -78,6 +94,7 @@ pub struct LoadOptions<'a> {
     string_migration: StringMigration,
     patch_log: Option<&'a mut PatchLog>,
     text_encoding: TextEncoding,
+    load_mode: LoadMode,
 }
 
 impl<'a> LoadOptions<'a> {
@@ -145,6 +162,13 @@ impl<'a> LoadOptions<'a> {
             ..self
         }
     }
+
+    /// When to build the indexes of each object
+    ///
+    /// The default is [`LoadMode::Eager`]
+    pub fn load_mode(self, load_mode: LoadMode) -> Self {
+        Self { load_mode, ..self }
+    }
 }
 
 impl std::default::Default for LoadOptions<'static> {
@@ -155,6 +179,7 @@ impl std::default::Default for LoadOptions<'static> {
             patch_log: None,
             string_migration: StringMigration::NoMigration,
             text_encoding: TextEncoding::default(),
+            load_mode: LoadMode::Eager,
         }
     }
 }
@@ -340,7 +365,12 @@ impl Automerge {
     }
 
     /// Start a transaction isolated at a given heads
-    pub fn transaction_at(&mut self, patch_log: PatchLog, heads: &[ChangeHash]) -> Transaction<'_> {
+    pub fn transaction_at(
+        &mut self,
+        mut patch_log: PatchLog,
+        heads: &[ChangeHash],
+    ) -> Transaction<'_> {
+        patch_log.record_before(|| heads.to_vec());
         let args = self.transaction_args(Some(heads));
         Transaction::new(self, args, patch_log)
     }
@@ -687,9 +717,9 @@ impl Automerge {
     /// * `data` - The data to load
     /// * `options` - The options to use when loading
     #[tracing::instrument(skip(data), err)]
-    pub fn load_with_options<'a, 'b>(
-        data: &'a [u8],
-        options: LoadOptions<'b>,
+    pub fn load_with_options(
+        data: &[u8],
+        options: LoadOptions<'_>,
     ) -> Result<Self, AutomergeError> {
         if data.is_empty() {
             tracing::trace!("no data, initializing empty document");
@@ -708,7 +738,12 @@ impl Automerge {
             storage::Chunk::Document(d) => {
                 tracing::trace!("first chunk is document chunk, inflating");
                 first_chunk_was_doc = true;
-                reconstruct_document(&d, options.verification_mode, options.text_encoding)?
+                reconstruct_document(
+                    &d,
+                    options.verification_mode,
+                    options.text_encoding,
+                    options.load_mode,
+                )?
             }
             storage::Chunk::Change(stored_change) => {
                 tracing::trace!("first chunk is change chunk");
@@ -767,6 +802,18 @@ impl Automerge {
         patch_log.make_patches(self)
     }
 
+    /// Create the patches from a [`PatchLog`] along with the patches which undo each of them
+    ///
+    /// Each element is a forward patch and the patches which reverse it. To undo all of the
+    /// changes apply the inverse patches in the reverse order of the forward patches. The log
+    /// must have been created with [`PatchLog::with_inverse()`].
+    pub fn make_patches_with_inverse(
+        &self,
+        patch_log: &mut PatchLog,
+    ) -> Result<Vec<(Patch, Vec<Patch>)>, AutomergeError> {
+        patch_log.make_patches_with_inverse(self)
+    }
+
     /// Get a set of [`Patch`]es which materialize the current state of the document
     ///
     /// This is a convienence method for [`doc.diff(&[], current_heads)`][diff]
@@ -921,10 +968,7 @@ impl Automerge {
 
     /// Save this document, but don't run it through `DEFLATE` afterwards
     pub fn save_nocompress(&self) -> Vec<u8> {
-        self.save_with_options(SaveOptions {
-            deflate: false,
-            ..Default::default()
-        })
+        self.save_with_options(SaveOptions::new().deflate(false))
     }
 
     /// Save the changes since the given heads
@@ -973,6 +1017,15 @@ impl Automerge {
         self.change_graph.clock_for_heads(heads)
     }
 
+    /// Whether the ops visible at `heads` are all still in the document, i.e. `heads` is empty or
+    /// not before the checkpoint of a call to [`Self::compact()`]
+    pub(crate) fn history_intact_at(&self, heads: &[ChangeHash]) -> bool {
+        heads.is_empty()
+            || self
+                .change_graph
+                .covers_compacted(&self.change_graph.clock_for_heads(heads))
+    }
+
     fn get_isolated_actor_index(&mut self, level: usize) -> usize {
         if level == 0 {
             self.get_or_create_actor_index()
@@ -1087,6 +1140,90 @@ impl Automerge {
         }
     }
 
+    /// Estimate the heap memory held by this document, broken down by what it is used for
+    ///
+    /// This is cheap enough to call periodically: it reads the capacity of each collection
+    /// rather than walking the ops, visiting at most each column slab, queued change and cached
+    /// clock. An `Automerge` caches no patches, see [`crate::AutoCommit::memory_usage()`].
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            change_graph: self.change_graph.heap_size(),
+            queue: crate::heap_size::vec(&self.queue)
+                + self.queue.iter().map(|c| c.heap_size()).sum::<usize>(),
+            ..self.ops.memory_usage()
+        }
+    }
+
+    /// Remove deleted ops which every peer has seen, to save memory and to speed up editing long
+    /// lived lists and text
+    ///
+    /// `heads` must have been acknowledged by every peer, that is every change which will ever be
+    /// applied to this document, or to any document it syncs with, either has `heads` in its
+    /// history or is already in this document. Each put which was overwritten or deleted by a
+    /// change in the history of `heads` is removed from memory and from the output of
+    /// [`Self::save()`]. So is each element of a list or text object once all of its ops are
+    /// removed, unless a change above `heads` inserts after it or updates it.
+    ///
+    /// This gives up the history before `heads`:
+    ///
+    /// * The changes in the history of `heads` can no longer be rebuilt from the ops, so
+    ///   [`Self::get_changes()`] and [`Self::save_after()`] leave them out and
+    ///   [`ReadDoc::get_change_by_hash()`] returns `None` for them. Their metadata and hashes are
+    ///   kept, so the heads of the document don't change and syncing with peers whose heads
+    ///   include `heads` carries on working.
+    /// * A peer with no changes at all is sent the whole document when syncing. Any other peer
+    ///   which is missing changes from before `heads` can't be caught up, and receiving its sync
+    ///   message fails with [`AutomergeError::CompactedChange`].
+    /// * The hashes of the compacted changes are saved as they are, rather than computed from the
+    ///   changes, so loading a compacted document can't verify them. It only checks that they
+    ///   are in the history of the document's heads and that whole histories were compacted.
+    /// * Reading, diffing or forking the document at heads before `heads` no longer gives the
+    ///   right result, and cursors pointing at a removed element can't be resolved.
+    /// * Saved documents which have been compacted are marked as such, so older versions of
+    ///   automerge refuse to load them.
+    ///
+    /// Compacting again with later heads removes the ops which have died since. Returns the number
+    /// of ops removed.
+    pub fn compact(&mut self, heads: &[ChangeHash]) -> Result<usize, AutomergeError> {
+        if let Some(head) = heads.iter().find(|h| !self.has_change(h)) {
+            return Err(AutomergeError::InvalidHash(*head));
+        }
+        let clock = self.change_graph.compact(heads);
+        Ok(self.ops.compact(&clock))
+    }
+
+    /// Count the ops in this document by visibility and by object
+    ///
+    /// Unlike [`ReadDoc::stats()`] this visits every op in the document.
+    pub fn op_stats(&self) -> OpStats {
+        let mut stats = OpStats::default();
+        let mut per_object: Vec<(ObjId, u64)> = Vec::new();
+        for op in self.ops.iter() {
+            if op.is_inc() {
+                stats.num_increments += 1;
+            } else if op.visible() {
+                stats.num_visible_ops += 1;
+            } else {
+                stats.num_tombstones += 1;
+            }
+            if let (true, Action::Set, ScalarValue::Str(s)) = (op.insert, op.action, &op.value) {
+                if self.ops.object_type(&op.obj) == Some(ObjType::Text) {
+                    stats.text_bytes += s.len() as u64;
+                }
+            }
+            // ops are sorted by object
+            match per_object.last_mut() {
+                Some((obj, count)) if *obj == op.obj => *count += 1,
+                _ => per_object.push((op.obj, 1)),
+            }
+        }
+        stats.ops_per_object = per_object
+            .into_iter()
+            .map(|(obj, count)| (self.id_to_exid(obj.0), count))
+            .collect();
+        stats
+    }
+
     pub fn dump(&self) {
         /*
                 log!(
@@ -1680,10 +1817,16 @@ impl Automerge {
         other.shared_heads == self.get_heads()
     }
 
-    fn has_change(&self, head: &ChangeHash) -> bool {
+    pub(crate) fn has_change(&self, head: &ChangeHash) -> bool {
         self.change_graph.has_change(head)
     }
 
+    pub(crate) fn is_compacted(&self, hash: &ChangeHash) -> bool {
+        self.change_graph
+            .hash_to_index(hash)
+            .is_some_and(|index| self.change_graph.is_compacted(index))
+    }
+
     pub fn text_encoding(&self) -> TextEncoding {
         self.ops.text_encoding
     }
@@ -1977,18 +2120,79 @@ impl Default for Automerge {
 }
 
 /// Options to pass to [`Automerge::save_with_options()`] and [`crate::AutoCommit::save_with_options()`]
-#[derive(Debug)]
+///
+/// ```
+/// # use automerge::SaveOptions;
+/// let options = SaveOptions::new()
+///     .retain_orphans(false)
+///     .compression_threshold(1024);
+/// ```
+#[derive(Debug, Clone)]
 pub struct SaveOptions {
-    /// Whether to apply DEFLATE compression to the RLE encoded columns in the document
+    /// Whether to compress the RLE encoded columns in the document at all. Columns are
+    /// compressed with [`Self::compression`] if they are at least [`Self::compression_threshold`]
+    /// bytes long.
+    ///
+    /// The name predates [`Compression`], this is `true` for zstd compressed documents too.
     pub deflate: bool,
     /// Whether to save changes which we do not have the dependencies for
     pub retain_orphans: bool,
+    /// The algorithm used to compress columns
+    pub compression: Compression,
+    /// The size in bytes below which columns are left uncompressed
+    pub compression_threshold: usize,
 }
 
 impl SaveOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to compress the columns of the document
+    ///
+    /// The default is `true`
+    pub fn deflate(self, deflate: bool) -> Self {
+        Self { deflate, ..self }
+    }
+
+    /// Whether to save changes which we do not have the dependencies for
+    ///
+    /// The default is `true`
+    pub fn retain_orphans(self, retain_orphans: bool) -> Self {
+        Self {
+            retain_orphans,
+            ..self
+        }
+    }
+
+    /// The algorithm used to compress columns
+    ///
+    /// The default is [`Compression::Deflate`]
+    pub fn compression(self, compression: Compression) -> Self {
+        Self {
+            compression,
+            ..self
+        }
+    }
+
+    /// The size in bytes below which columns are left uncompressed
+    ///
+    /// The default is 256
+    pub fn compression_threshold(self, compression_threshold: usize) -> Self {
+        Self {
+            compression_threshold,
+            ..self
+        }
+    }
+
     fn compress(&self) -> CompressConfig {
         if self.deflate {
-            CompressConfig::Threshold(change::DEFLATE_MIN_SIZE)
+            let codec = match self.compression {
+                Compression::Deflate => Codec::Deflate,
+                #[cfg(feature = "zstd")]
+                Compression::Zstd { level } => Codec::Zstd(level),
+            };
+            CompressConfig::Threshold(self.compression_threshold, codec)
         } else {
             CompressConfig::None
         }
@@ -2000,7 +2204,65 @@ impl std::default::Default for SaveOptions {
         Self {
             deflate: true,
             retain_orphans: true,
+            compression: Compression::Deflate,
+            compression_threshold: change::DEFLATE_MIN_SIZE,
+        }
+    }
+}
+
+/// The algorithm used to compress the columns of a saved document
+///
+/// Columns compressed with anything other than [`Compression::Deflate`] are flagged as such in
+/// the column specification. Versions of automerge which cannot read them reject the document
+/// with an error rather than loading it incorrectly.
+///
+/// Readers say which algorithms they can load through their sync [`Capability`]s, and
+/// [`Compression::negotiate`] picks the best one they all support. [`crate::sync`] does this
+/// itself when it sends a peer the whole document.
+///
+/// ```
+/// # use automerge::{sync::Capability, AutoCommit, Compression, SaveOptions};
+/// let mut doc = AutoCommit::new();
+/// // an older peer which only speaks the V2 sync message
+/// let theirs = [Capability::MessageV1, Capability::MessageV2];
+/// let options = SaveOptions::new().compression(Compression::negotiate(&theirs));
+/// assert_eq!(options.compression, Compression::Deflate);
+/// let saved = doc.save_with_options(options);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Compression {
+    /// DEFLATE, which every version of automerge can read
+    #[default]
+    Deflate,
+    /// Zstandard at the given level (1 to 22, or negative for faster levels). This usually
+    /// compresses text heavy documents much better than DEFLATE but the resulting documents can
+    /// only be loaded by versions of automerge built with the `zstd` feature.
+    #[cfg(feature = "zstd")]
+    Zstd {
+        /// The zstd compression level
+        level: i32,
+    },
+}
+
+impl Compression {
+    /// The level [`Compression::negotiate`] picks for zstd
+    pub const NEGOTIATED_ZSTD_LEVEL: i32 = 3;
+
+    /// The best compression which a reader advertising `capabilities` can load
+    ///
+    /// This is [`Compression::Zstd`] at [`Self::NEGOTIATED_ZSTD_LEVEL`] if this build has the
+    /// `zstd` feature and the reader advertises [`Capability::Zstd`], otherwise it is
+    /// [`Compression::Deflate`].
+    #[cfg_attr(not(feature = "zstd"), allow(unused_variables))]
+    pub fn negotiate(capabilities: &[Capability]) -> Self {
+        #[cfg(feature = "zstd")]
+        if capabilities.contains(&Capability::Zstd) {
+            return Self::Zstd {
+                level: Self::NEGOTIATED_ZSTD_LEVEL,
+            };
         }
+        Self::Deflate
     }
 }
 
@@ -2015,6 +2277,7 @@ pub(crate) fn reconstruct_document<'a>(
     doc: &'a storage::Document<'a>,
     mode: VerificationMode,
     text_encoding: TextEncoding,
+    load_mode: LoadMode,
 ) -> Result<Automerge, AutomergeError> {
     let storage::load::ReconOpSet {
         op_set,
@@ -2022,7 +2285,7 @@ pub(crate) fn reconstruct_document<'a>(
         max_op,
         change_graph,
         ..
-    } = storage::load::reconstruct_opset(doc, mode, text_encoding)
+    } = storage::load::reconstruct_opset(doc, mode, text_encoding, load_mode)
         .map_err(|e| load::Error::InflateDocument(Box::new(e)))?;
 
     let mut doc = Automerge {
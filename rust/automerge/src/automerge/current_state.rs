@@ -16,6 +16,8 @@ use std::ops::Range;
 /// Due to only notifying of visible operations the [`PatchLog`] will only be called with `put`,
 /// `insert`, and `splice`, operations.
 pub(crate) fn log_current_state_patches(doc: &Automerge, patch_log: &mut PatchLog) {
+    // these patches create the document from nothing
+    patch_log.record_before(Vec::new);
     // The OpSet already exposes operations in the order they appear in the document.
     // `OpSet::iter_objs` iterates over the objects in causal order, this means that parent objects
     // will always appear before their children. Furthermore, the operations within each object are
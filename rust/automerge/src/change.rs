@@ -114,6 +114,21 @@ impl Change {
         self.stored.bytes()
     }
 
+    /// An estimate of the heap memory held by this change
+    pub(crate) fn heap_size(&self) -> usize {
+        let compressed = match &self.compression {
+            CompressionState::Compressed(c) => c.len(),
+            _ => 0,
+        };
+        let other_actors = self.other_actor_ids();
+        self.raw_bytes().len()
+            + compressed
+            + self.actor_id().heap_size()
+            + std::mem::size_of_val(other_actors)
+            + other_actors.iter().map(|a| a.heap_size()).sum::<usize>()
+            + self.message().map_or(0, |m| m.capacity())
+    }
+
     pub(crate) fn num_ops(&self) -> usize {
         debug_assert_eq!(self.stored.num_ops, self.iter_ops().count());
         self.stored.num_ops
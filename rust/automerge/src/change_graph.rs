@@ -12,7 +12,7 @@ use crate::{
     error::AutomergeError,
     op_set2::{change::BuildChangeMetadata, ActorCursor, ActorIdx, MetaCursor, ValueMeta},
     storage::{Columns, DocChangeColumns},
-    types::OpId,
+    types::{OpId, ScalarValue},
     Change, ChangeHash,
 };
 
@@ -39,6 +39,59 @@ pub(crate) struct ChangeGraph {
     nodes_by_hash: HashMap<ChangeHash, NodeIdx>,
     clock_cache: HashMap<NodeIdx, Clock>,
     seq_index: Vec<Vec<NodeIdx>>,
+    // the changes covered by this clock have had their ops compacted away so only their
+    // metadata and hash remain
+    compacted: Clock,
+}
+
+/// A change as the graph sees it. Changes whose ops have been compacted away can't be rebuilt into
+/// a [`Change`] so they are added to the graph from their metadata and stored hash instead.
+pub(crate) trait ChangeNode {
+    fn hash(&self) -> ChangeHash;
+    fn deps(&self) -> &[ChangeHash];
+    fn seq(&self) -> u64;
+    fn max_op(&self) -> u64;
+    fn num_ops(&self) -> usize;
+    fn timestamp(&self) -> i64;
+    fn message(&self) -> Option<&str>;
+    fn extra_bytes(&self) -> &[u8];
+    fn is_compacted(&self) -> bool {
+        false
+    }
+}
+
+impl ChangeNode for Change {
+    fn hash(&self) -> ChangeHash {
+        Change::hash(self)
+    }
+
+    fn deps(&self) -> &[ChangeHash] {
+        Change::deps(self)
+    }
+
+    fn seq(&self) -> u64 {
+        Change::seq(self)
+    }
+
+    fn max_op(&self) -> u64 {
+        Change::max_op(self)
+    }
+
+    fn num_ops(&self) -> usize {
+        self.len()
+    }
+
+    fn timestamp(&self) -> i64 {
+        Change::timestamp(self)
+    }
+
+    fn message(&self) -> Option<&str> {
+        Change::message(self).map(String::as_str)
+    }
+
+    fn extra_bytes(&self) -> &[u8] {
+        Change::extra_bytes(self)
+    }
 }
 
 const CACHE_STEP: u32 = 16;
@@ -75,6 +128,31 @@ struct Edge {
 }
 
 impl ChangeGraph {
+    /// An estimate of the heap memory held by the graph
+    pub(crate) fn heap_size(&self) -> usize {
+        use crate::heap_size::{btree_set, hash_map, vec};
+        let clocks = self.clock_cache.values().map(|c| vec(&c.0)).sum::<usize>();
+        let seq_index = self.seq_index.iter().map(vec).sum::<usize>();
+        vec(&self.edges)
+            + vec(&self.hashes)
+            + vec(&self.actors)
+            + vec(&self.parents)
+            + vec(&self.seq)
+            + vec(&self.max_ops)
+            + self.num_ops.heap_size()
+            + self.timestamps.heap_size()
+            + self.messages.heap_size()
+            + self.extra_bytes_meta.heap_size()
+            + vec(&self.extra_bytes_raw)
+            + btree_set(&self.heads)
+            + hash_map(&self.nodes_by_hash)
+            + hash_map(&self.clock_cache)
+            + clocks
+            + vec(&self.seq_index)
+            + seq_index
+            + vec(&self.compacted.0)
+    }
+
     pub(crate) fn new(num_actors: usize) -> Self {
         Self {
             edges: Vec::new(),
@@ -92,6 +170,7 @@ impl ChangeGraph {
             heads: BTreeSet::new(),
             clock_cache: HashMap::new(),
             seq_index: vec![vec![]; num_actors],
+            compacted: Clock::new(num_actors),
         }
     }
 
@@ -112,6 +191,7 @@ impl ChangeGraph {
             heads: BTreeSet::new(),
             clock_cache: HashMap::new(),
             seq_index: vec![vec![]; num_actors],
+            compacted: Clock::new(num_actors),
         }
     }
 
@@ -158,6 +238,7 @@ impl ChangeGraph {
         for clock in self.clock_cache.values_mut() {
             clock.rewrite_with_new_actor(idx)
         }
+        self.compacted.rewrite_with_new_actor(idx);
         self.seq_index.insert(idx, vec![]);
     }
 
@@ -174,6 +255,9 @@ impl ChangeGraph {
         for clock in &mut self.clock_cache.values_mut() {
             clock.remove_actor(idx)
         }
+        if idx < self.compacted.0.len() {
+            self.compacted.remove_actor(idx);
+        }
     }
 
     pub(crate) fn len(&self) -> usize {
@@ -192,6 +276,66 @@ impl ChangeGraph {
         self.hashes.get(index)
     }
 
+    /// Mark every change which is an ancestor of `heads` (inclusive) as compacted and return the
+    /// clock covering all changes compacted so far
+    pub(crate) fn compact(&mut self, heads: &[ChangeHash]) -> Clock {
+        let clock = self.clock_for_heads(heads);
+        Clock::merge(&mut self.compacted, &clock);
+        self.compacted.clone()
+    }
+
+    /// Whether the ops of the change at `index` have been compacted away
+    pub(crate) fn is_compacted(&self, index: usize) -> bool {
+        let actor = self.actors[index].into();
+        self.compacted
+            .get_for_actor(&actor)
+            .map(|c| self.seq[index] <= c.seq)
+            .unwrap_or(false)
+    }
+
+    /// Whether `clock` includes every compacted change, in which case the state of the document at
+    /// `clock` can still be read
+    pub(crate) fn covers_compacted(&self, clock: &Clock) -> bool {
+        self.compacted.0.iter().enumerate().all(|(actor, c)| {
+            c.seq == 0
+                || clock
+                    .get_for_actor(&actor)
+                    .map(|d| d.seq >= c.seq)
+                    .unwrap_or(false)
+        })
+    }
+
+    pub(crate) fn has_compacted(&self) -> bool {
+        self.compacted.0.iter().any(|c| c.seq > 0)
+    }
+
+    /// Find a change whose compaction is inconsistent with the rest of the graph, where
+    /// `compacted` says which changes were loaded without their ops. Each compacted change must
+    /// be in the history of `heads` and depend only on compacted changes, and every change older
+    /// than a compacted change by the same actor must be compacted too.
+    pub(crate) fn find_invalid_compacted(
+        &self,
+        compacted: &[bool],
+        heads: &[ChangeHash],
+    ) -> Option<usize> {
+        if !self.has_compacted() {
+            return None;
+        }
+        let mut reachable = vec![false; self.hashes.len()];
+        self.traverse_ancestors(self.heads_to_nodes(heads), |idx| {
+            reachable[idx.0 as usize] = true;
+            true
+        });
+        self.node_ids().map(|n| n.0 as usize).find(|&index| {
+            compacted[index] != self.is_compacted(index)
+                || (compacted[index]
+                    && (!reachable[index]
+                        || self
+                            .parents(NodeIdx(index as u32))
+                            .any(|p| !compacted[p.0 as usize])))
+        })
+    }
+
     pub(crate) fn max_op_for_actor(&mut self, actor_index: usize) -> u64 {
         self.seq_index
             .get(actor_index)
@@ -245,6 +389,26 @@ impl ChangeGraph {
         let raw = (out.len()..out.len() + self.extra_bytes_raw.len()).into();
         out.extend(&self.extra_bytes_raw);
 
+        // documents which were never compacted are written exactly as before
+        let hash = if self.has_compacted() {
+            let mut hash_meta = ColumnData::<MetaCursor>::new();
+            hash_meta.extend(self.node_ids().map(|n| {
+                if self.is_compacted(n.0 as usize) {
+                    ValueMeta::from(self.hashes[n.0 as usize].as_bytes())
+                } else {
+                    ValueMeta::from(&ScalarValue::Null)
+                }
+            }));
+            let meta = hash_meta.save_to_unless_empty(out).into();
+            let start = out.len();
+            for n in self.node_ids().filter(|n| self.is_compacted(n.0 as usize)) {
+                out.extend(self.hashes[n.0 as usize].as_bytes());
+            }
+            Some(ValueRange::new(meta, (start..out.len()).into()))
+        } else {
+            None
+        };
+
         DocChangeColumns {
             actor,
             seq,
@@ -253,6 +417,7 @@ impl ChangeGraph {
             message,
             deps: DepsRange::new(num_deps, deps),
             extra: ValueRange::new(meta, raw),
+            hash,
             other: Columns::empty(),
         }
     }
@@ -396,7 +561,7 @@ impl ChangeGraph {
             .copied()
     }
 
-    fn update_heads(&mut self, change: &Change) {
+    fn update_heads<N: ChangeNode>(&mut self, change: &N) {
         for d in change.deps() {
             self.heads.remove(d);
         }
@@ -405,7 +570,8 @@ impl ChangeGraph {
 
     pub(crate) fn from_iter<
         'a,
-        I: Iterator<Item = (&'a Change, usize)> + ExactSizeIterator + Clone,
+        N: ChangeNode + 'a,
+        I: Iterator<Item = (&'a N, usize)> + ExactSizeIterator + Clone,
     >(
         iter: I,
         deps: usize,
@@ -428,7 +594,8 @@ impl ChangeGraph {
 
     pub(crate) fn add_nodes<
         'a,
-        I: Iterator<Item = (&'a Change, usize)> + ExactSizeIterator + Clone,
+        N: ChangeNode + 'a,
+        I: Iterator<Item = (&'a N, usize)> + ExactSizeIterator + Clone,
     >(
         &mut self,
         iter: I,
@@ -439,11 +606,10 @@ impl ChangeGraph {
         self.max_ops
             .extend(iter.clone().map(|(c, _)| c.max_op() as u32));
         self.num_ops
-            .extend(iter.clone().map(|(c, _)| c.len() as u64));
+            .extend(iter.clone().map(|(c, _)| c.num_ops() as u64));
         self.timestamps
             .extend(iter.clone().map(|(c, _)| c.timestamp()));
-        self.messages
-            .extend(iter.clone().map(|(c, _)| c.message().cloned()));
+        self.messages.extend(iter.clone().map(|(c, _)| c.message()));
         self.extra_bytes_meta
             .extend(iter.clone().map(|(c, _)| ValueMeta::from(c.extra_bytes())));
         self.parents
@@ -453,7 +619,11 @@ impl ChangeGraph {
         }
     }
 
-    fn add_changes<'a, I: Iterator<Item = (&'a Change, usize)> + ExactSizeIterator + Clone>(
+    fn add_changes<
+        'a,
+        N: ChangeNode + 'a,
+        I: Iterator<Item = (&'a N, usize)> + ExactSizeIterator + Clone,
+    >(
         &mut self,
         iter: I,
     ) -> Result<(), MissingDep> {
@@ -477,6 +647,11 @@ impl ChangeGraph {
                 self.add_parent(node_idx, parent_hash);
             }
 
+            if change.is_compacted() {
+                let data = ClockData::new(change.max_op() as u32, change.seq() as u32);
+                self.compacted.include(actor, data);
+            }
+
             if (node_idx + 1).0 % CACHE_STEP == 0 {
                 self.cache_clock(node_idx);
             }
@@ -719,6 +894,43 @@ mod tests {
         assert_eq!(changes, expected_changes);
     }
 
+    #[test]
+    fn find_invalid_compacted() {
+        let mut builder = TestGraphBuilder::new();
+        let actor1 = builder.actor();
+        let actor2 = builder.actor();
+        let change1 = builder.change(&actor1, 10, &[]);
+        let change2 = builder.change(&actor2, 20, &[change1]);
+        let change3 = builder.change(&actor1, 10, &[change2]);
+
+        let mut graph = builder.build();
+        graph.compact(&[change2]);
+        assert_eq!(
+            graph.find_invalid_compacted(&[true, true, false], &[change3]),
+            None
+        );
+        // change1 is older than a compacted change by the same actor but still has its ops
+        assert_eq!(
+            graph.find_invalid_compacted(&[false, true, false], &[change3]),
+            Some(0)
+        );
+        // change2 isn't in the history of the heads
+        assert_eq!(
+            graph.find_invalid_compacted(&[true, true, false], &[change1]),
+            Some(1)
+        );
+
+        // change2 is compacted but the change it depends on isn't
+        let mut graph = builder.build();
+        graph
+            .compacted
+            .include(builder.index(&actor2), ClockData { max_op: 30, seq: 1 });
+        assert_eq!(
+            graph.find_invalid_compacted(&[false, true, false], &[change3]),
+            Some(1)
+        );
+    }
+
     struct TestGraphBuilder {
         actors: Vec<ActorId>,
         changes: Vec<Change>,
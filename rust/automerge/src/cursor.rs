@@ -4,6 +4,7 @@ use crate::types::OpId;
 #[cfg(doc)]
 use crate::ReadDoc;
 use crate::{ActorId, AutomergeError};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 
 /// An identifier of a position in a Sequence (either Self::List or Self::Text).
@@ -201,6 +202,26 @@ impl fmt::Display for OpCursor {
     }
 }
 
+impl Serialize for Cursor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Cursor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Cursor::try_from(s.as_str())
+            .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&s), &"a valid cursor"))
+    }
+}
+
 impl TryFrom<&str> for Cursor {
     type Error = AutomergeError;
 
@@ -9,6 +9,8 @@ use thiserror::Error;
 pub enum AutomergeError {
     #[error(transparent)]
     ChangeGraph(#[from] crate::change_graph::MissingDep),
+    #[error("change {0} has been compacted and can no longer be rebuilt")]
+    CompactedChange(ChangeHash),
     #[error("failed to load compressed data: {0}")]
     Deflate(#[source] std::io::Error),
     #[error("duplicate seq {0} found for actor {1}")]
@@ -66,6 +68,10 @@ pub enum AutomergeError {
     HydrateError(#[from] HydrateError),
     #[error("patch logs cannot be shared between documents")]
     PatchLogMismatch,
+    #[error("patch log was not created with PatchLog::with_inverse")]
+    PatchLogWithoutInverse,
+    #[error("the history needed to invert these patches has been compacted")]
+    InverseOfCompactedHistory,
     #[error(transparent)]
     EncodingError(#[from] PackError),
 }
@@ -151,3 +157,22 @@ pub enum UpdateObjectError {
     #[error(transparent)]
     Automerge(#[from] AutomergeError),
 }
+
+#[cfg(feature = "json-patch")]
+#[derive(Error, Debug)]
+pub enum JsonPatchError {
+    #[error("invalid JSON pointer: {0}")]
+    InvalidPointer(String),
+    #[error("no value at {0}")]
+    NotFound(String),
+    #[error("cannot add, remove or replace the root object")]
+    Root,
+    #[error("test failed at {0}")]
+    TestFailed(String),
+    #[error("cannot move {from} into one of its children {path}")]
+    MoveIntoChild { from: String, path: String },
+    #[error(transparent)]
+    Automerge(#[from] AutomergeError),
+    #[error(transparent)]
+    UpdateObject(#[from] UpdateObjectError),
+}
@@ -1,8 +1,7 @@
 use crate::storage::parse;
 use crate::types::{ObjId, OpId};
 use crate::ActorId;
-use serde::Serialize;
-use serde::Serializer;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::cmp::{Ord, Ordering};
 use std::fmt;
 use std::hash::{Hash, Hasher};
@@ -185,6 +184,24 @@ impl Serialize for ExId {
     }
 }
 
+/// Object IDs deserialize from the `"<counter>@<actor>"` strings they serialize to, or `"_root"`.
+/// The actor index is not part of the string, so it is looked up again when the ID is used.
+impl<'de> Deserialize<'de> for ExId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        if s == "_root" {
+            return Ok(ExId::Root);
+        }
+        s.split_once('@')
+            .and_then(|(ctr, actor)| Some((ctr.parse().ok()?, hex::decode(actor).ok()?)))
+            .map(|(ctr, actor)| ExId::Id(ctr, ActorId::from(actor), 0))
+            .ok_or_else(|| de::Error::invalid_value(de::Unexpected::Str(&s), &"a valid ObjID"))
+    }
+}
+
 impl AsRef<ExId> for ExId {
     fn as_ref(&self) -> &ExId {
         self
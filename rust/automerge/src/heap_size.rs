@@ -0,0 +1,24 @@
+//! Estimates of the heap memory held by the standard collections, used by
+//! [`crate::Automerge::memory_usage()`]. These count the memory a collection allocates for its
+//! own elements but not anything those elements point to.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::mem::size_of;
+
+pub(crate) fn vec<T>(v: &Vec<T>) -> usize {
+    v.capacity() * size_of::<T>()
+}
+
+// hashbrown allocates a control byte alongside every bucket
+pub(crate) fn hash_map<K, V, S>(m: &HashMap<K, V, S>) -> usize {
+    m.capacity() * (size_of::<(K, V)>() + 1)
+}
+
+pub(crate) fn hash_set<T, S>(s: &HashSet<T, S>) -> usize {
+    s.capacity() * (size_of::<T>() + 1)
+}
+
+// btree nodes are at least half full so this is within a factor of two
+pub(crate) fn btree_set<T>(s: &BTreeSet<T>) -> usize {
+    s.len() * size_of::<T>()
+}
@@ -3,6 +3,7 @@ use crate::patches::TextRepresentation;
 use crate::types::{Clock, ListEncoding, ObjId, ScalarValue};
 use crate::TextEncoding;
 use crate::{error::HydrateError, value, ObjType, Patch, PatchAction, Prop};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::borrow::Cow;
 use std::collections::HashMap;
 
@@ -166,6 +167,87 @@ impl Value {
     }
 }
 
+/// Hydrated values serialize as plain JSON-like data, as `automerge-wasm` exports them: maps
+/// are objects, lists are arrays, text is a string and scalars serialize as [`ScalarValue`] does.
+/// Deserializing cannot tell text from a string, so strings become [`ScalarValue::Str`].
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Value::Scalar(s) => s.serialize(serializer),
+            Value::Map(map) => serializer.collect_map(map.iter().map(|(k, v)| (k, &v.value))),
+            Value::List(list) => serializer.collect_seq(list.iter().map(|v| &v.value)),
+            Value::Text(text) => serializer.serialize_str(&String::from(text)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ValueVisitor;
+        impl<'de> de::Visitor<'de> for ValueVisitor {
+            type Value = Value;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                formatter.write_str("a scalar, list or map")
+            }
+
+            fn visit_bool<E: de::Error>(self, value: bool) -> Result<Value, E> {
+                Ok(Value::Scalar(ScalarValue::Boolean(value)))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Value, E> {
+                Ok(Value::Scalar(ScalarValue::Int(value)))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Value, E> {
+                match i64::try_from(value) {
+                    Ok(i) => Ok(Value::Scalar(ScalarValue::Int(i))),
+                    Err(_) => Ok(Value::Scalar(ScalarValue::Uint(value))),
+                }
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Value, E> {
+                Ok(Value::Scalar(ScalarValue::F64(value)))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Value, E> {
+                Ok(Value::Scalar(ScalarValue::Str(value.into())))
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+                Ok(Value::Scalar(ScalarValue::Null))
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+                Ok(Value::Scalar(ScalarValue::Null))
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+                let mut values = Vec::new();
+                while let Some(value) = seq.next_element()? {
+                    values.push(value);
+                }
+                Ok(Value::List(values.into()))
+            }
+
+            fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+                let mut values = HashMap::new();
+                while let Some((key, value)) = map.next_entry::<String, Value>()? {
+                    values.insert(key, value);
+                }
+                Ok(Value::Map(values.into()))
+            }
+        }
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
 impl From<Value> for value::Value<'_> {
     fn from(value: Value) -> Self {
         match value {
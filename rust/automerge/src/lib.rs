@@ -88,6 +88,30 @@
 //! Text is encoded in UTF-8 by default but uses UTF-16 when using the wasm target,
 //! you can configure it with the feature `utf16-indexing`.
 //!
+//! ### Parallelism
+//!
+//! With the `rayon` feature enabled loading and saving decode, verify and compress the columns
+//! of a document in parallel and the indexes are packed into columns in parallel. The values
+//! which go in the indexes are still collected in a single pass over the ops, as that pass also
+//! reconstructs the change history and the value of a counter depends on the ops which
+//! increment it. The saved bytes and the loaded document are identical to those produced
+//! without it.
+//!
+//! ### Compression
+//!
+//! Saved documents compress their columns with DEFLATE by default. The `zstd` feature adds
+//! [`Compression::Zstd`], selected with [`SaveOptions::compression`], which usually compresses
+//! text heavy documents much better. Documents compressed with zstd can only be loaded by builds
+//! with the `zstd` feature, the others reject them with an error. Builds with the feature
+//! advertise it to their sync peers, and [`Compression::negotiate`] picks zstd only for readers
+//! which advertised it. The sync protocol uses this when it sends a peer the whole document.
+//!
+//! ### JSON Patch
+//!
+//! The `json-patch` feature adds [`patches::to_json_patch`] and [`patches::apply_json_patch`] to
+//! convert between patches and [RFC 6902](https://www.rfc-editor.org/rfc/rfc6902) JSON Patch
+//! documents.
+//!
 //! ## Sync Protocol
 //!
 //! See the [`sync`] module.
@@ -270,12 +294,14 @@ mod convert;
 mod cursor;
 pub mod error;
 mod exid;
+mod heap_size;
 pub mod hydrate;
 mod indexed_cache;
 pub mod iter;
 mod legacy;
 pub mod marks;
 pub mod op_set2;
+mod parallel;
 pub mod patches;
 mod read;
 mod sequence_tree;
@@ -287,7 +313,9 @@ pub mod transaction;
 mod types;
 mod value;
 
-pub use crate::automerge::{Automerge, LoadOptions, OnPartialLoad, SaveOptions, StringMigration};
+pub use crate::automerge::{
+    Automerge, Compression, LoadMode, LoadOptions, OnPartialLoad, SaveOptions, StringMigration,
+};
 pub use autocommit::AutoCommit;
 pub use autoserde::AutoSerde;
 pub use change::{Change, LoadError as LoadChangeError};
@@ -299,9 +327,11 @@ pub use exid::{ExId as ObjId, ObjIdFromBytesError};
 pub use legacy::Change as ExpandedChange;
 pub use op_set2::{ChangeMetadata, Parent, Parents, ScalarValue as ScalarValueRef, ValueRef};
 pub use patches::{Patch, PatchAction, PatchLog};
-pub use read::ReadDoc;
+pub use read::{MemoryUsage, OpStats, ReadDoc, Stats};
 pub use sequence_tree::SequenceTree;
+pub use storage::inspect;
 pub use storage::VerificationMode;
+pub use text_diff::{TextGranularity, UpdateTextOptions};
 pub use text_value::ConcreteTextValue;
 pub use transaction::BlockOrText;
 pub use types::{ActorId, ChangeHash, ObjType, OpType, ParseChangeHashError, Prop, TextEncoding};
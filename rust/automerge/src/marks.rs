@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 
 use std::borrow::Cow;
@@ -17,7 +18,7 @@ use crate::value::ScalarValue;
 /// If multiple collaborators have set marks with the same name but different values
 /// in overlapping ranges, automerge will chose a consistent (but arbitrary) value
 /// when reading marks from the doc.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Mark {
     pub start: usize,
     pub end: usize,
@@ -22,9 +22,9 @@ pub(crate) trait GetHash {
     fn get_hash(&self, index: usize) -> Option<ChangeHash>;
 }
 
-impl GetHash for Vec<crate::Change> {
+impl GetHash for Vec<ChangeHash> {
     fn get_hash(&self, index: usize) -> Option<ChangeHash> {
-        Some(self.get(index)?.hash())
+        self.get(index).copied()
     }
 }
 
@@ -708,6 +708,7 @@ impl BatchApply {
         self.insert_new_actors(doc);
 
         log.migrate_actors(&doc.ops().actors).unwrap();
+        log.record_before(|| doc.get_heads());
 
         self.import_ops(doc);
 
@@ -2,7 +2,7 @@ use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::{BTreeSet, HashMap};
 
-use crate::change_graph::ChangeGraph;
+use crate::change_graph::{ChangeGraph, ChangeNode};
 use crate::error::AutomergeError;
 use crate::storage::document::ReadChangeError;
 use crate::{
@@ -21,6 +21,8 @@ pub(crate) struct ChangeCollector<'a> {
     preds: HashMap<OpId, Vec<OpId>>,
     max_op: u64,
     num_deps: usize,
+    // the stored hashes of changes loaded from a compacted document, by change index
+    compacted: Vec<Option<ChangeHash>>,
 }
 
 #[derive(Clone, Debug)]
@@ -85,18 +87,22 @@ impl<'a> ChangeCollector<'a> {
         I: Iterator<Item = Result<DocChangeMetadata<'a>, ReadChangeError>>,
     {
         let mut num_deps = 0;
+        let mut compacted = Vec::new();
         let mut changes: Vec<_> = changes
             .map(|m| {
-                m.map(|meta| BuildChangeMetadata {
-                    actor: meta.actor,
-                    seq: meta.seq,
-                    max_op: meta.max_op,
-                    timestamp: meta.timestamp,
-                    message: meta.message,
-                    deps: meta.deps,
-                    extra: meta.extra,
-                    start_op: 0,
-                    builder: 0,
+                m.map(|meta| {
+                    compacted.push(meta.hash);
+                    BuildChangeMetadata {
+                        actor: meta.actor,
+                        seq: meta.seq,
+                        max_op: meta.max_op,
+                        timestamp: meta.timestamp,
+                        message: meta.message,
+                        deps: meta.deps,
+                        extra: meta.extra,
+                        start_op: 0,
+                        builder: 0,
+                    }
                 })
             })
             .collect::<Result<_, _>>()?;
@@ -114,7 +120,9 @@ impl<'a> ChangeCollector<'a> {
             }
             num_deps += changes[i].deps.len();
         }
-        Ok(Self::from_change_meta(changes, num_deps))
+        let mut collector = Self::from_change_meta(changes, num_deps);
+        collector.compacted = compacted;
+        Ok(collector)
     }
 
     fn from_change_meta(
@@ -147,6 +155,7 @@ impl<'a> ChangeCollector<'a> {
             preds: HashMap::default(),
             max_op: 0,
             num_deps,
+            compacted: Vec::new(),
         }
     }
 
@@ -155,7 +164,9 @@ impl<'a> ChangeCollector<'a> {
         change_graph: &'a ChangeGraph,
         have_deps: &[ChangeHash],
     ) -> Vec<Change> {
-        let (changes, num_deps) = change_graph.get_build_metadata_clock(have_deps);
+        let (mut changes, num_deps) = change_graph.get_build_metadata_clock(have_deps);
+        // the ops of compacted changes are gone, peers above the compaction point never need them
+        changes.retain(|c| !change_graph.is_compacted(c.builder));
         Self::from_build_meta(op_set, change_graph, changes, num_deps)
     }
 
@@ -223,6 +234,13 @@ impl<'a> ChangeCollector<'a> {
         I: IntoIterator<Item = ChangeHash>,
     {
         let (changes, num_deps) = change_graph.get_build_metadata(hashes)?;
+        if let Some(c) = changes
+            .iter()
+            .find(|c| change_graph.is_compacted(c.builder))
+        {
+            let hash = change_graph.index_to_hash(c.builder).copied().unwrap();
+            return Err(AutomergeError::CompactedChange(hash));
+        }
         Ok(Self::from_build_meta(
             op_set,
             change_graph,
@@ -388,12 +406,14 @@ impl<'a> ChangeCollector<'a> {
         let mut max_ops = vec![0; num_actors];
         let mut seq = vec![0; num_actors];
         let mut changes = Vec::with_capacity(self.changes.len());
+        let mut hashes = Vec::with_capacity(self.changes.len());
         let mut heads = BTreeSet::new();
+        let mut doc_max_op = self.max_op;
 
         let mut actors = Vec::with_capacity(self.changes.len());
         let mut mapper = super::ActorMapper::new(&op_set.actors);
 
-        for change in self.changes.into_iter() {
+        for (index, change) in self.changes.into_iter().enumerate() {
             let actor = change.actor;
 
             if actor >= num_actors {
@@ -415,45 +435,147 @@ impl<'a> ChangeCollector<'a> {
 
             max_ops[actor] = max_op;
 
-            let ops = self.builders[builder].get_ops()?;
-
-            if let Some(Some(last)) = ops.last() {
-                assert_eq!(last.id.counter(), max_op);
-            }
+            // a compacted change may have deleted ops which are no longer in the op set
+            doc_max_op = std::cmp::max(doc_max_op, max_op);
 
-            let finished = super::build_change_inner(ops, &change, &changes, &mut mapper);
+            let collected = if let Some(hash) = self.compacted.get(index).copied().flatten() {
+                let deps = change
+                    .deps
+                    .iter()
+                    .map(|i| hashes.get(*i as usize).copied())
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(Error::ChangesOutOfOrder)?;
+                CollectedChange::Compacted(CompactedChange { change, hash, deps })
+            } else {
+                let ops = self.builders[builder].get_ops()?;
+
+                if let Some(Some(last)) = ops.last() {
+                    assert_eq!(last.id.counter(), max_op);
+                }
 
-            let hash = finished.hash();
+                let finished = super::build_change_inner(ops, &change, &hashes, &mut mapper);
+                CollectedChange::Built(Change::new(finished))
+            };
 
-            for dep in finished.dependencies() {
+            for dep in collected.deps() {
                 heads.remove(dep);
             }
 
-            heads.insert(hash);
+            heads.insert(collected.hash());
+            hashes.push(collected.hash());
 
-            let change = Change::new(finished);
-
-            changes.push(change);
+            changes.push(collected);
             actors.push(actor);
         }
 
-        let max_op = self.max_op;
-
         let change_graph = ChangeGraph::from_iter(
             changes.iter().zip(actors.into_iter()),
             self.num_deps,
             num_actors,
         )?;
 
+        // the hashes of compacted changes are stored rather than computed from their ops, so
+        // they can't be verified, the most we can do is check they fit with the rest of the graph
+        let compacted = changes.iter().map(|c| c.is_compacted()).collect::<Vec<_>>();
+        let head_hashes = heads.iter().copied().collect::<Vec<_>>();
+        if let Some(index) = change_graph.find_invalid_compacted(&compacted, &head_hashes) {
+            return Err(Error::InvalidCompactedChange(changes[index].hash()));
+        }
+
+        let changes = changes
+            .into_iter()
+            .filter_map(|c| match c {
+                CollectedChange::Built(change) => Some(change),
+                CollectedChange::Compacted(_) => None,
+            })
+            .collect();
+
         Ok(CollectedChanges {
             changes,
             heads,
-            max_op,
+            max_op: doc_max_op,
             change_graph,
         })
     }
 }
 
+/// A change loaded from a compacted document, its ops are gone so it can't be rebuilt and its
+/// hash is the one stored in the document
+struct CompactedChange<'a> {
+    change: BuildChangeMetadata<'a>,
+    hash: ChangeHash,
+    deps: Vec<ChangeHash>,
+}
+
+// nearly all loaded changes are built, boxing them would cost an allocation each
+#[allow(clippy::large_enum_variant)]
+enum CollectedChange<'a> {
+    Built(Change),
+    Compacted(CompactedChange<'a>),
+}
+
+impl ChangeNode for CollectedChange<'_> {
+    fn hash(&self) -> ChangeHash {
+        match self {
+            Self::Built(c) => c.hash(),
+            Self::Compacted(c) => c.hash,
+        }
+    }
+
+    fn deps(&self) -> &[ChangeHash] {
+        match self {
+            Self::Built(c) => c.deps(),
+            Self::Compacted(c) => &c.deps,
+        }
+    }
+
+    fn seq(&self) -> u64 {
+        match self {
+            Self::Built(c) => c.seq(),
+            Self::Compacted(c) => c.change.seq,
+        }
+    }
+
+    fn max_op(&self) -> u64 {
+        match self {
+            Self::Built(c) => c.max_op(),
+            Self::Compacted(c) => c.change.max_op,
+        }
+    }
+
+    fn num_ops(&self) -> usize {
+        match self {
+            Self::Built(c) => c.len(),
+            Self::Compacted(c) => c.change.num_ops(),
+        }
+    }
+
+    fn timestamp(&self) -> i64 {
+        match self {
+            Self::Built(c) => c.timestamp(),
+            Self::Compacted(c) => c.change.timestamp,
+        }
+    }
+
+    fn message(&self) -> Option<&str> {
+        match self {
+            Self::Built(c) => c.message().map(String::as_str),
+            Self::Compacted(c) => c.change.message.as_deref(),
+        }
+    }
+
+    fn extra_bytes(&self) -> &[u8] {
+        match self {
+            Self::Built(c) => c.extra_bytes(),
+            Self::Compacted(c) => &c.change.extra,
+        }
+    }
+
+    fn is_compacted(&self) -> bool {
+        matches!(self, Self::Compacted(_))
+    }
+}
+
 pub(crate) struct CollectedChanges {
     pub(crate) changes: Vec<Change>,
     pub(crate) heads: BTreeSet<ChangeHash>,
@@ -46,6 +46,15 @@ pub(super) struct Indexes {
     pub(super) mark: MarkIndexColumn,
 }
 
+impl Indexes {
+    pub(super) fn heap_size(&self) -> usize {
+        self.text.heap_size()
+            + self.visible.heap_size()
+            + self.inc.heap_size()
+            + self.mark.heap_size()
+    }
+}
+
 impl Default for Indexes {
     fn default() -> Self {
         Self {
@@ -190,18 +199,11 @@ impl Columns {
         cols: &BTreeMap<ColumnSpec, Range<usize>>,
         data: &[u8],
         m: &ScanMeta,
-        len: usize,
-    ) -> Result<ColumnData<C>, PackError> {
+    ) -> Result<Option<ColumnData<C>>, PackError> {
         if let Some(range) = cols.get(&spec) {
-            //let c1 : ColumnData<C> = ColumnData::external(data.clone(), range.clone(), m)?;
-            let c2: ColumnData<C> = ColumnData::load_with(&data[range.clone()], m)?;
-            //assert_eq!(c1.to_vec(), c2.to_vec());
-            //assert_eq!(c1.export(), c2.export());
-            //assert_eq!(c1.len(), c2.len());
-            Ok(c2)
-            //Ok(c1)
+            Ok(Some(ColumnData::load_with(&data[range.clone()], m)?))
         } else {
-            Ok(ColumnData::init_empty(len))
+            Ok(None)
         }
     }
 
@@ -215,28 +217,67 @@ impl Columns {
         };
         let cols = iter.map(|c| (c.spec(), c.data())).collect();
 
-        let id_actor = Self::load_column(ID_ACTOR_COL_SPEC, &cols, data, &m, 0)?;
+        // Every column present in the data is decoded (and verified) independently of the
+        // others, so they can all be loaded at once. Missing columns are filled in afterwards
+        // once we know how long they ought to be and errors are reported in column order so the
+        // result is the same whether or not the loads ran in parallel.
+        let (mut id_actor, mut id_ctr, mut obj_actor, mut obj_ctr) =
+            (Ok(None), Ok(None), Ok(None), Ok(None));
+        let (mut key_actor, mut key_ctr, mut key_str) = (Ok(None), Ok(None), Ok(None));
+        let (mut insert, mut action, mut mark_name, mut expand) =
+            (Ok(None), Ok(None), Ok(None), Ok(None));
+        let (mut succ_count, mut succ_actor, mut succ_ctr) = (Ok(None), Ok(None), Ok(None));
+        let (mut value_meta, mut value) = (Ok(None), Ok(None));
+        {
+            let (cols, m) = (&cols, &m);
+            crate::parallel::scope(|s| {
+                s.spawn(|| id_actor = Self::load_column(ID_ACTOR_COL_SPEC, cols, data, m));
+                s.spawn(|| id_ctr = Self::load_column(ID_COUNTER_COL_SPEC, cols, data, m));
+                s.spawn(|| obj_actor = Self::load_column(OBJ_ID_ACTOR_COL_SPEC, cols, data, m));
+                s.spawn(|| obj_ctr = Self::load_column(OBJ_ID_COUNTER_COL_SPEC, cols, data, m));
+                s.spawn(|| key_actor = Self::load_column(KEY_ACTOR_COL_SPEC, cols, data, m));
+                s.spawn(|| key_ctr = Self::load_column(KEY_COUNTER_COL_SPEC, cols, data, m));
+                s.spawn(|| key_str = Self::load_column(KEY_STR_COL_SPEC, cols, data, m));
+                s.spawn(|| insert = Self::load_column(INSERT_COL_SPEC, cols, data, m));
+                s.spawn(|| action = Self::load_column(ACTION_COL_SPEC, cols, data, m));
+                s.spawn(|| mark_name = Self::load_column(MARK_NAME_COL_SPEC, cols, data, m));
+                s.spawn(|| expand = Self::load_column(EXPAND_COL_SPEC, cols, data, m));
+                s.spawn(|| succ_count = Self::load_column(SUCC_COUNT_COL_SPEC, cols, data, m));
+                s.spawn(|| succ_actor = Self::load_column(SUCC_ACTOR_COL_SPEC, cols, data, m));
+                s.spawn(|| succ_ctr = Self::load_column(SUCC_COUNTER_COL_SPEC, cols, data, m));
+                s.spawn(|| value_meta = Self::load_column(VALUE_META_COL_SPEC, cols, data, m));
+                s.spawn(|| value = Self::load_column(VALUE_COL_SPEC, cols, data, m));
+            });
+        }
+
+        let (id_actor, id_ctr, obj_actor, obj_ctr) = (id_actor?, id_ctr?, obj_actor?, obj_ctr?);
+        let (key_actor, key_ctr, key_str) = (key_actor?, key_ctr?, key_str?);
+        let (insert, action, mark_name, expand) = (insert?, action?, mark_name?, expand?);
+        let (succ_count, succ_actor, succ_ctr) = (succ_count?, succ_actor?, succ_ctr?);
+        let (value_meta, value) = (value_meta?, value?);
+
+        let id_actor = id_actor.unwrap_or_else(|| ColumnData::init_empty(0));
         let len = id_actor.len();
 
-        let id_ctr = Self::load_column(ID_COUNTER_COL_SPEC, &cols, data, &m, len)?;
-        let obj_actor = Self::load_column(OBJ_ID_ACTOR_COL_SPEC, &cols, data, &m, len)?;
-        let obj_ctr = Self::load_column(OBJ_ID_COUNTER_COL_SPEC, &cols, data, &m, len)?;
-        let key_actor = Self::load_column(KEY_ACTOR_COL_SPEC, &cols, data, &m, len)?;
-        let key_ctr = Self::load_column(KEY_COUNTER_COL_SPEC, &cols, data, &m, len)?;
-        let key_str = Self::load_column(KEY_STR_COL_SPEC, &cols, data, &m, len)?;
-        let insert = Self::load_column(INSERT_COL_SPEC, &cols, data, &m, len)?;
-        let action = Self::load_column(ACTION_COL_SPEC, &cols, data, &m, len)?;
-        let mark_name = Self::load_column(MARK_NAME_COL_SPEC, &cols, data, &m, len)?;
-        let expand = Self::load_column(EXPAND_COL_SPEC, &cols, data, &m, len)?;
-
-        let succ_count = Self::load_column(SUCC_COUNT_COL_SPEC, &cols, data, &m, len)?;
+        let id_ctr = id_ctr.unwrap_or_else(|| ColumnData::init_empty(len));
+        let obj_actor = obj_actor.unwrap_or_else(|| ColumnData::init_empty(len));
+        let obj_ctr = obj_ctr.unwrap_or_else(|| ColumnData::init_empty(len));
+        let key_actor = key_actor.unwrap_or_else(|| ColumnData::init_empty(len));
+        let key_ctr = key_ctr.unwrap_or_else(|| ColumnData::init_empty(len));
+        let key_str = key_str.unwrap_or_else(|| ColumnData::init_empty(len));
+        let insert = insert.unwrap_or_else(|| ColumnData::init_empty(len));
+        let action = action.unwrap_or_else(|| ColumnData::init_empty(len));
+        let mark_name = mark_name.unwrap_or_else(|| ColumnData::init_empty(len));
+        let expand = expand.unwrap_or_else(|| ColumnData::init_empty(len));
+
+        let succ_count = succ_count.unwrap_or_else(|| ColumnData::init_empty(len));
         let succ_len = succ_count.acc().as_usize();
-        let succ_actor = Self::load_column(SUCC_ACTOR_COL_SPEC, &cols, data, &m, succ_len)?;
-        let succ_ctr = Self::load_column(SUCC_COUNTER_COL_SPEC, &cols, data, &m, succ_len)?;
+        let succ_actor = succ_actor.unwrap_or_else(|| ColumnData::init_empty(succ_len));
+        let succ_ctr = succ_ctr.unwrap_or_else(|| ColumnData::init_empty(succ_len));
 
-        let value_meta = Self::load_column(VALUE_META_COL_SPEC, &cols, data, &m, len)?;
+        let value_meta = value_meta.unwrap_or_else(|| ColumnData::init_empty(len));
         let value_len = value_meta.acc().as_usize();
-        let value = Self::load_column(VALUE_COL_SPEC, &cols, data, &m, value_len)?;
+        let value = value.unwrap_or_else(|| ColumnData::init_empty(value_len));
 
         let index = Indexes::default();
 
@@ -261,6 +302,26 @@ impl Columns {
         })
     }
 
+    /// The heap memory held by the op columns, not counting the indexes
+    pub(super) fn heap_size(&self) -> usize {
+        self.id_actor.heap_size()
+            + self.id_ctr.heap_size()
+            + self.obj_actor.heap_size()
+            + self.obj_ctr.heap_size()
+            + self.key_actor.heap_size()
+            + self.key_ctr.heap_size()
+            + self.key_str.heap_size()
+            + self.succ_count.heap_size()
+            + self.succ_actor.heap_size()
+            + self.succ_ctr.heap_size()
+            + self.insert.heap_size()
+            + self.action.heap_size()
+            + self.value_meta.heap_size()
+            + self.value.heap_size()
+            + self.mark_name.heap_size()
+            + self.expand.heap_size()
+    }
+
     fn remap_actors<F>(&mut self, f: F)
     where
         F: Fn(Option<Cow<'_, ActorIdx>>) -> Option<Cow<'_, ActorIdx>>,
@@ -20,6 +20,7 @@ use super::types::{Action, ActorCursor, ActorIdx, KeyRef, MarkData, OpType, Scal
 
 use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::ops::{Range, RangeBounds};
 use std::sync::Arc;
 
@@ -30,10 +31,11 @@ mod mark_index;
 mod marks;
 mod op_iter;
 mod op_query;
+mod read_index;
 mod top_op;
 mod visible;
 
-pub(crate) use index::{IndexBuilder, ObjIndex, ObjInfo};
+pub(crate) use index::{IndexBuilder, ObjIndex, ObjIndexes, ObjInfo};
 
 pub(crate) use crate::iter::{Keys, ListRange, MapRange};
 
@@ -46,6 +48,7 @@ pub(crate) use op_iter::{
     OpIter, ReadOpError, SuccIterIter, SuccWalker, ValueIter,
 };
 pub(crate) use op_query::{OpQuery, OpQueryTerm};
+use read_index::{IndexRef, ReadIndexes};
 pub(crate) use top_op::TopOpIter;
 pub(crate) use visible::{DiffOp, DiffOpIter, VisIter, VisibleOpIter};
 
@@ -57,6 +60,12 @@ pub(crate) struct OpSet {
     pub(crate) obj_info: ObjIndex,
     cols: Columns,
     pub(crate) text_encoding: TextEncoding,
+    // when loaded with `LoadMode::Lazy` the objects whose text, visible and
+    // mark indexes have been built, all other objects are unindexed
+    indexed: Option<HashSet<ObjId>>,
+    // the indexes of unindexed objects which have been read since the last
+    // modification, built the first time each object is read
+    read_indexes: ReadIndexes,
 }
 
 #[derive(Debug, Clone)]
@@ -88,6 +97,8 @@ impl OpSet {
             //mark_index: MarkIndexColumn::new(),
             obj_info: ObjIndex::default(),
             text_encoding: TextEncoding::default(),
+            indexed: None,
+            read_indexes: ReadIndexes::default(),
         }
     }
 
@@ -129,6 +140,120 @@ impl OpSet {
         self.cols.index.inc = indexes.inc;
         self.cols.index.mark = indexes.mark;
         self.obj_info = indexes.obj_info;
+        self.indexed = indexes.indexed;
+        self.read_indexes = match &self.indexed {
+            Some(_) => ReadIndexes::new(
+                std::iter::once(ObjId::root()).chain(self.obj_info.0.keys().map(|id| ObjId(*id))),
+            ),
+            None => ReadIndexes::default(),
+        };
+    }
+
+    /// The text, visible and mark indexes to read the ops in `range` with, if they all belong to
+    /// one object
+    ///
+    /// When loaded with `LoadMode::Lazy` this builds the indexes of an unindexed object the first
+    /// time it is read.
+    pub(crate) fn indexes(&self, range: &Range<usize>) -> Option<IndexRef<'_>> {
+        let main = IndexRef {
+            text: &self.cols.index.text,
+            visible: &self.cols.index.visible,
+            mark: &self.cols.index.mark,
+        };
+        let Some(indexed) = &self.indexed else {
+            return Some(main);
+        };
+        if range.is_empty() {
+            return Some(main);
+        }
+        let first = self.obj_at(range.start)?;
+        let last = self.obj_at(range.end - 1)?;
+        if first != last {
+            None
+        } else if indexed.contains(&first) {
+            Some(main)
+        } else {
+            self.read_indexes
+                .get(&first, range, || self.build_obj_indexes(range))
+        }
+    }
+
+    fn obj_at(&self, pos: usize) -> Option<ObjId> {
+        let range = pos..pos + 1;
+        ObjIdIter::new(
+            self.cols.obj_actor.iter_range(range.clone()),
+            self.cols.obj_ctr.iter_range(range),
+        )
+        .next()
+    }
+
+    fn build_obj_indexes(&self, range: &Range<usize>) -> ObjIndexes {
+        let mut builder = IndexBuilder::with_capacity(range.len(), 0, self.text_encoding);
+        for op in self.iter_range(range) {
+            let op_is_counter = op.is_counter();
+            let op_succ = op.succ();
+            builder.process_op(&op);
+            for id in op_succ {
+                builder.process_succ(op_is_counter, id);
+            }
+        }
+        builder.finish_object()
+    }
+
+    /// Build the text, visible and mark indexes of an object loaded with `LoadMode::Lazy` into
+    /// the op set's index columns
+    pub(crate) fn index_object(&mut self, obj: &ObjId) {
+        let Some(indexed) = &mut self.indexed else {
+            return;
+        };
+        if !indexed.insert(*obj) {
+            return;
+        }
+        self.read_indexes.remove(obj);
+        let range = self.scope_to_obj(obj);
+        let ObjIndexes {
+            text,
+            visible,
+            mark,
+        } = self.build_obj_indexes(&range);
+        // the unindexed values are those of an op which isn't visible so
+        // only visible ops need updating
+        for (pos, width) in range.clone().zip(text) {
+            if width > 0 {
+                self.cols.index.text.splice(pos, 1, [width]);
+            }
+        }
+        for (pos, vis) in range.clone().zip(visible) {
+            if vis {
+                self.cols.index.visible.splice(pos, 1, [true]);
+            }
+        }
+        for (pos, mark) in range.zip(mark) {
+            if let Some(mark) = mark {
+                self.cols.index.mark.set(pos, mark);
+            }
+        }
+    }
+
+    // index the objects which have been read before the op set changes under
+    // their read indexes
+    fn settle_read_indexes(&mut self) {
+        for obj in self.read_indexes.take_built() {
+            self.index_object(&obj);
+        }
+    }
+
+    fn index_objects_at<I: Iterator<Item = usize>>(&mut self, positions: I) {
+        if self.indexed.is_none() {
+            return;
+        }
+        self.settle_read_indexes();
+        let objs = positions
+            .filter_map(|pos| self.obj_at(pos))
+            .collect::<HashSet<_>>();
+        for obj in objs {
+            self.index_object(&obj);
+        }
     }
 
     pub(crate) fn splice_objects<O: OpLike>(&mut self, ops: &[O]) {
@@ -140,12 +265,19 @@ impl OpSet {
     }
 
     pub(crate) fn splice<O: OpLike>(&mut self, pos: usize, ops: &[O]) {
+        if self.indexed.is_some() {
+            self.settle_read_indexes();
+            for op in ops {
+                self.index_object(&op.obj());
+            }
+        }
         self.cols.splice(pos, ops, self.text_encoding);
         self.splice_objects(ops);
         //self.len += ops.len();
     }
 
     pub(crate) fn add_succ(&mut self, op_pos: &[SuccInsert]) {
+        self.index_objects_at(op_pos.iter().map(|i| i.pos));
         let mut succ_inc = 0;
         let mut last_pos = None;
         for i in op_pos.iter().rev() {
@@ -166,6 +298,82 @@ impl OpSet {
         }
     }
 
+    /// Remove the ops which are dead as of `clock` and which no change with `clock` in its history
+    /// can reference, then rebuild the columns and indexes from the ops which remain. Returns the
+    /// number of ops removed.
+    ///
+    /// An op is dead if it is a put (but not of a counter) which was made and deleted or
+    /// overwritten by changes covered by `clock`. Object creation and mark ops are always kept. An
+    /// element of a sequence is only removed along with all of its ops, and only if no op above
+    /// `clock` inserts after it or updates it.
+    pub(crate) fn compact(&mut self, clock: &Clock) -> usize {
+        let referenced = self
+            .iter()
+            .filter(|op| !clock.covers(&op.id))
+            .filter_map(|op| op.key.elemid())
+            .collect::<HashSet<_>>();
+
+        let is_dead = |op: &Op<'_>| {
+            op.action == Action::Set
+                && !op.is_counter()
+                && clock.covers(&op.id)
+                && !op.visible()
+                && op.succ().all(|id| clock.covers(&id))
+        };
+
+        // the element whose ops we are walking, where its insert op is and whether all of its
+        // ops so far are dead
+        let mut element: Option<(ElemId, usize, bool)> = None;
+        let mut keep = Vec::with_capacity(self.len());
+        let finish_element = |element: Option<(ElemId, usize, bool)>, keep: &mut Vec<bool>| {
+            if let Some((id, pos, true)) = element {
+                if !referenced.contains(&id) {
+                    keep[pos] = false;
+                }
+            }
+        };
+
+        for op in self.iter() {
+            let dead = is_dead(&op);
+            if op.insert {
+                finish_element(element.take(), &mut keep);
+                element = Some((ElemId(op.id), op.pos, dead));
+                // the insert op is removed by `finish_element` once we've seen the whole element
+                keep.push(true);
+                continue;
+            }
+            match &mut element {
+                Some((id, _, all_dead)) if op.key.elemid() == Some(*id) => *all_dead &= dead,
+                _ => finish_element(element.take(), &mut keep),
+            }
+            keep.push(!dead);
+        }
+        finish_element(element, &mut keep);
+
+        let removed = keep.iter().filter(|k| !**k).count();
+        if removed == 0 {
+            return 0;
+        }
+
+        let (cols, builder) = {
+            let ops = self.iter().filter(|op| keep[op.pos]).collect::<Vec<_>>();
+            let mut cols = Columns::default();
+            cols.splice(0, &ops, self.text_encoding);
+            let mut builder =
+                IndexBuilder::with_capacity(cols.len(), cols.sub_len(), self.text_encoding);
+            for op in &ops {
+                builder.process_op(op);
+                for id in op.succ() {
+                    builder.process_succ(op.is_counter(), id);
+                }
+            }
+            (cols, builder)
+        };
+        self.cols = cols;
+        self.set_indexes(builder);
+        removed
+    }
+
     pub(crate) fn parent_object(
         &self,
         child: &ObjId,
@@ -254,6 +462,20 @@ impl OpSet {
         self.cols.len()
     }
 
+    /// The heap memory held by the op columns, their indexes and the actor table
+    pub(crate) fn memory_usage(&self) -> crate::MemoryUsage {
+        use crate::heap_size::{hash_map, hash_set, vec};
+        crate::MemoryUsage {
+            op_set: self.cols.heap_size(),
+            indexes: self.cols.index.heap_size()
+                + hash_map(&self.obj_info.0)
+                + self.indexed.as_ref().map_or(0, hash_set)
+                + self.read_indexes.heap_size(),
+            actors: vec(&self.actors) + self.actors.iter().map(|a| a.heap_size()).sum::<usize>(),
+            ..Default::default()
+        }
+    }
+
     pub(crate) fn sub_len(&self) -> usize {
         self.cols.sub_len()
     }
@@ -268,8 +490,8 @@ impl OpSet {
         let vis = VisIter::new(self, clock.as_ref(), range.clone());
         let typ = self.object_type(obj).unwrap_or(ObjType::Map);
         if typ == ObjType::Text && encoding != ListEncoding::List {
-            if clock.is_none() {
-                let text = self.cols.index.text.iter_range(range.clone());
+            if let Some(index) = clock.is_none().then(|| self.indexes(&range)).flatten() {
+                let text = index.text.iter_range(range.clone());
                 let iter = SkipIter::new(text.clone(), vis.clone());
                 iter.filter_map(|n| n.as_deref().copied()).sum::<u64>() as usize
             } else {
@@ -316,10 +538,11 @@ impl OpSet {
         if index == 0 {
             return None;
         }
-        let mut iter = self.cols.index.text.iter_range(range.clone()).with_acc();
+        let indexes = self.indexes(&range)?;
+        let mut iter = indexes.text.iter_range(range.clone()).with_acc();
         let tx = iter.nth(index - 1)?;
         let iter = self.iter_range(&(tx.pos..range.end));
-        let marks = self.cols.index.mark.rich_text_at(tx.pos, clock);
+        let marks = indexes.mark.rich_text_at(tx.pos, clock);
         let mut query = InsertQuery::new(iter, index, encoding, clock.cloned(), marks);
         query.resolve(index - 1).ok()
     }
@@ -444,7 +667,9 @@ impl OpSet {
 
         let range = self.scope_to_obj(obj);
 
-        let mut iter = self.cols.index.text.iter_range(range.clone()).with_acc();
+        let indexes = self.indexes(&range)?;
+
+        let mut iter = indexes.text.iter_range(range.clone()).with_acc();
 
         let mut ops = vec![];
         let mut end_pos = range.end;
@@ -648,6 +873,8 @@ impl OpSet {
             cols: Columns::default(),
             obj_info: ObjIndex::default(),
             text_encoding,
+            indexed: None,
+            read_indexes: ReadIndexes::default(),
         }
     }
 
@@ -675,6 +902,8 @@ impl OpSet {
             cols,
             obj_info: ObjIndex::default(),
             text_encoding: TextEncoding::default(),
+            indexed: None,
+            read_indexes: ReadIndexes::default(),
         }
     }
 
@@ -691,6 +920,8 @@ impl OpSet {
             cols,
             obj_info: ObjIndex::default(),
             text_encoding,
+            indexed: None,
+            read_indexes: ReadIndexes::default(),
         };
 
         Ok(op_set)
@@ -863,8 +1094,14 @@ impl OpSet {
     }
 
     pub(crate) fn rewrite_with_new_actor(&mut self, idx: usize) {
+        self.settle_read_indexes();
+        self.read_indexes
+            .rewrite(|obj| Some(obj.with_new_actor(idx)));
         self.cols.rewrite_with_new_actor(idx);
         self.cols.index.mark.rewrite_with_new_actor(idx);
+        if let Some(indexed) = &mut self.indexed {
+            *indexed = indexed.iter().map(|obj| obj.with_new_actor(idx)).collect();
+        }
         self.obj_info = ObjIndex(
             self.obj_info
                 .0
@@ -876,7 +1113,15 @@ impl OpSet {
 
     pub(crate) fn remove_actor(&mut self, idx: usize) {
         self.actors.remove(idx);
+        self.settle_read_indexes();
+        self.read_indexes.rewrite(|obj| obj.without_actor(idx));
         self.cols.rewrite_without_actor(idx);
+        if let Some(indexed) = &mut self.indexed {
+            *indexed = indexed
+                .iter()
+                .filter_map(|obj| obj.without_actor(idx))
+                .collect();
+        }
         self.obj_info = ObjIndex(
             self.obj_info
                 .0
@@ -1017,6 +1262,101 @@ mod tests {
         assert_eq!(ops, actual_ops);
     }
 
+    #[test]
+    fn dictionary_encoded_key_column() {
+        let mut doc = AutoCommit::new();
+        let todos = doc.put_object(crate::ROOT, "todos", ObjType::List).unwrap();
+        for i in 0..200 {
+            let todo = doc.insert_object(&todos, i, ObjType::Map).unwrap();
+            doc.put(&todo, "id", i as i64).unwrap();
+            doc.put(&todo, "title", format!("todo {}", i)).unwrap();
+            doc.put(&todo, "done", false).unwrap();
+        }
+        let key_str = &doc.doc.ops().cols.key_str;
+        let dict: ColumnData<hexane::DictCursor> = key_str.iter().collect();
+        assert_eq!(dict.to_vec(), key_str.to_vec());
+        let (inline, dict) = (key_str.save().len(), dict.save().len());
+        assert!(dict * 2 < inline, "{} vs {} bytes", dict, inline);
+    }
+
+    #[test]
+    fn lazy_load_indexes_objects_on_first_read() {
+        use crate::marks::{ExpandMark, Mark};
+        use crate::{LoadMode, LoadOptions, ReadDoc, ScalarValue as Value};
+
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(crate::ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello world").unwrap();
+        doc.splice_text(&text, 5, 1, "").unwrap();
+        let bold = Mark::new("bold".to_string(), true, 0, 5);
+        doc.mark(&text, bold, ExpandMark::Both).unwrap();
+        let list = doc.put_object(crate::ROOT, "list", ObjType::List).unwrap();
+        for i in 0..10 {
+            doc.insert(&list, i, i as i64).unwrap();
+        }
+        doc.delete(&list, 3).unwrap();
+        doc.put(crate::ROOT, "counter", Value::counter(1)).unwrap();
+        doc.increment(crate::ROOT, "counter", 5).unwrap();
+        doc.put(crate::ROOT, "key", "a").unwrap();
+        doc.put(crate::ROOT, "key", "b").unwrap();
+        let saved = doc.save();
+
+        let mut eager = AutoCommit::load(&saved).unwrap();
+        let options = LoadOptions::new().load_mode(LoadMode::Lazy);
+        let mut lazy = AutoCommit::load_with_options(&saved, options).unwrap();
+        // the object ids change when a new actor is inserted before theirs
+        let objs = |doc: &AutoCommit| {
+            [crate::ROOT, text.clone(), list.clone()]
+                .map(|obj| doc.doc.exid_to_obj(&obj).unwrap().id)
+        };
+        let indexed = |doc: &AutoCommit| {
+            let ops = doc.doc.ops();
+            objs(doc)
+                .iter()
+                .filter(|obj| ops.indexed.as_ref().map_or(true, |i| i.contains(obj)))
+                .count()
+        };
+        let read = |doc: &AutoCommit| {
+            let ops = doc.doc.ops();
+            objs(doc)
+                .iter()
+                .filter(|obj| ops.read_indexes.is_built(obj))
+                .count()
+        };
+        assert_eq!((indexed(&lazy), read(&lazy)), (0, 0));
+        assert_eq!((indexed(&eager), read(&eager)), (3, 0));
+
+        let check = |lazy: &AutoCommit, eager: &AutoCommit| {
+            let rep = TextRepresentation::String(TextEncoding::default());
+            assert_eq!(lazy.doc.current_state(rep), eager.doc.current_state(rep));
+            assert_eq!(lazy.text(&text).unwrap(), eager.text(&text).unwrap());
+            assert_eq!(lazy.length(&text), eager.length(&text));
+            assert_eq!(lazy.length(&list), eager.length(&list));
+            assert_eq!(lazy.get(&list, 4).unwrap(), eager.get(&list, 4).unwrap());
+            assert_eq!(lazy.marks(&text).unwrap(), eager.marks(&text).unwrap());
+            assert_eq!(
+                lazy.get(crate::ROOT, "counter").unwrap(),
+                eager.get(crate::ROOT, "counter").unwrap()
+            );
+        };
+        check(&lazy, &eager);
+        assert_eq!((indexed(&lazy), read(&lazy)), (0, 3));
+
+        for doc in [&mut lazy, &mut eager] {
+            doc.set_actor(ActorId::from(b"actor"));
+            doc.splice_text(&text, 2, 0, "!").unwrap();
+            doc.insert(&list, 4, "x").unwrap();
+            doc.increment(crate::ROOT, "counter", 2).unwrap();
+        }
+        assert_eq!((indexed(&lazy), read(&lazy)), (3, 0));
+        check(&lazy, &eager);
+
+        let (lazy, eager) = (&lazy.doc.ops().cols.index, &eager.doc.ops().cols.index);
+        assert_eq!(lazy.text.to_vec(), eager.text.to_vec());
+        assert_eq!(lazy.visible.to_vec(), eager.visible.to_vec());
+        assert_eq!(lazy.inc.to_vec(), eager.inc.to_vec());
+    }
+
     fn load_document_chunk(data: &[u8]) -> Document<'_> {
         let input = crate::storage::parse::Input::new(data);
         let (_i, chunk) = crate::storage::Chunk::parse(input).unwrap();
@@ -2,7 +2,7 @@ use crate::op_set2::op_set::{MarkIndexBuilder, MarkIndexColumn};
 use crate::op_set2::{ChangeOp, Op, OpBuilder, OpSet};
 use crate::types::{ObjId, ObjType, OpId, TextEncoding};
 use hexane::{BooleanCursor, ColumnData, IntCursor, UIntCursor};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 // TODO : this could be faster and use less memory if
 // hexane::Encoder was used here instead of Vec<>
@@ -15,6 +15,7 @@ pub(crate) struct IndexBuilder {
     marks: Vec<Option<MarkIndexBuilder>>,
     obj_info: ObjIndex,
     encoding: TextEncoding,
+    lazy: bool,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -84,30 +85,55 @@ pub(crate) struct Indexes {
     pub(crate) inc: ColumnData<IntCursor>,
     pub(crate) mark: MarkIndexColumn,
     pub(crate) obj_info: ObjIndex,
+    pub(crate) indexed: Option<HashSet<ObjId>>,
+}
+
+// The text, visible and mark entries of an object, computed from its ops
+pub(crate) struct ObjIndexes {
+    pub(crate) text: Vec<u64>,
+    pub(crate) visible: Vec<bool>,
+    pub(crate) mark: Vec<Option<MarkIndexBuilder>>,
 }
 
 impl IndexBuilder {
     pub(crate) fn new(op_set: &OpSet, encoding: TextEncoding) -> Self {
+        Self::with_capacity(op_set.len(), op_set.sub_len(), encoding)
+    }
+
+    pub(crate) fn with_capacity(len: usize, sub_len: usize, encoding: TextEncoding) -> Self {
         Self {
             counters: HashMap::new(),
-            succ: Vec::with_capacity(op_set.len()),
-            widths: Vec::with_capacity(op_set.len()),
-            incs: Vec::with_capacity(op_set.sub_len()),
-            marks: Vec::with_capacity(op_set.len()),
+            succ: Vec::with_capacity(len),
+            widths: Vec::with_capacity(len),
+            incs: Vec::with_capacity(sub_len),
+            marks: Vec::with_capacity(len),
             obj_info: ObjIndex::default(),
             encoding,
+            lazy: false,
         }
     }
 
-    pub(crate) fn process_op(&mut self, op: &Op<'_>) {
-        self.marks.push(op.mark_index());
+    /// Only build the `inc` index and leave the text, visible and mark indexes of every object to
+    /// be built by [`OpSet::indexes`] or [`OpSet::index_object`] when the object is first read
+    /// or modified
+    pub(crate) fn lazy(mut self) -> Self {
+        self.widths = Vec::new();
+        self.marks = Vec::new();
+        self.lazy = true;
+        self
+    }
 
+    pub(crate) fn process_op(&mut self, op: &Op<'_>) {
         self.succ.push(vis_num(op));
 
-        if op.succ().len() == 0 {
-            self.widths.push(op.width(self.encoding.into()) as u64);
-        } else {
-            self.widths.push(0);
+        if !self.lazy {
+            self.marks.push(op.mark_index());
+
+            if op.succ().len() == 0 {
+                self.widths.push(op.width(self.encoding.into()) as u64);
+            } else {
+                self.widths.push(0);
+            }
         }
 
         let count = self.counters.remove(&op.id);
@@ -135,18 +161,40 @@ impl IndexBuilder {
     }
 
     pub(crate) fn finish(self) -> Indexes {
-        let mut text = ColumnData::new();
-        text.splice(0, 0, self.widths);
-
-        let visible = self.succ.iter().map(|&n| n == 0).collect();
+        let obj_info = self.obj_info;
+        let (incs, widths, succ, marks) = (self.incs, self.widths, self.succ, self.marks);
+
+        if self.lazy {
+            let len = succ.len();
+            let mut inc = ColumnData::new();
+            inc.splice(0, 0, incs);
+            return Indexes {
+                // the values an op which isn't visible has in each index
+                text: std::iter::repeat(0).take(len).collect(),
+                visible: ColumnData::init_empty(len),
+                inc,
+                mark: MarkIndexColumn::init_empty(len),
+                obj_info,
+                indexed: Some(HashSet::new()),
+            };
+        }
 
+        // each index is packed from its own buffer so they can be built at the same time, the
+        // buffers themselves are filled in op order by `process_op`
         let mut inc = ColumnData::new();
-        inc.splice(0, 0, self.incs);
-
+        let mut text = ColumnData::new();
+        let mut visible = ColumnData::new();
         let mut mark = MarkIndexColumn::new();
-        mark.splice(0, 0, self.marks);
-
-        let obj_info = self.obj_info;
+        crate::parallel::scope(|s| {
+            s.spawn(|| {
+                inc.splice(0, 0, incs);
+            });
+            s.spawn(|| {
+                text.splice(0, 0, widths);
+            });
+            s.spawn(|| visible = succ.iter().map(|&n| n == 0).collect());
+            s.spawn(|| mark.splice(0, 0, marks));
+        });
 
         Indexes {
             text,
@@ -154,6 +202,15 @@ impl IndexBuilder {
             inc,
             mark,
             obj_info,
+            indexed: None,
+        }
+    }
+
+    pub(crate) fn finish_object(self) -> ObjIndexes {
+        ObjIndexes {
+            text: self.widths,
+            visible: self.succ.iter().map(|&n| n == 0).collect(),
+            mark: self.marks,
         }
     }
 }
@@ -164,6 +164,18 @@ impl MarkIndexColumn {
         self.data.len()
     }
 
+    pub(crate) fn heap_size(&self) -> usize {
+        let names = self
+            .cache
+            .values()
+            .map(|m| match &m.name {
+                Cow::Owned(name) => name.capacity(),
+                Cow::Borrowed(_) => 0,
+            })
+            .sum::<usize>();
+        self.data.heap_size() + crate::heap_size::hash_map(&self.cache) + names
+    }
+
     pub(crate) fn rewrite_with_new_actor(&mut self, idx: usize) {
         // FIXME - would be much better to do this by run instead of by value
         let new_data = self
@@ -187,6 +199,26 @@ impl MarkIndexColumn {
         }
     }
 
+    pub(crate) fn init_empty(len: usize) -> Self {
+        Self {
+            data: ColumnData::init_empty(len),
+            cache: HashMap::new(),
+        }
+    }
+
+    // replace a `None` left by `init_empty`
+    pub(crate) fn set(&mut self, index: usize, value: MarkIndexBuilder) {
+        debug_assert!(self.data.get(index).flatten().is_none());
+        let value = match value {
+            MarkIndexBuilder::Start(id, mark) => {
+                self.cache.insert(id, mark);
+                MarkIndexValue::Start(id)
+            }
+            MarkIndexBuilder::End(id) => MarkIndexValue::End(id),
+        };
+        self.data.splice(index, 1, [value]);
+    }
+
     pub(crate) fn splice(
         &mut self,
         index: usize,
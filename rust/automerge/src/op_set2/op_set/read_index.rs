@@ -0,0 +1,140 @@
+use crate::op_set2::op_set::{MarkIndexColumn, ObjIndexes};
+use crate::types::ObjId;
+use hexane::{BooleanCursor, ColumnData, UIntCursor};
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Mutex, OnceLock};
+
+/// The text, visible and mark indexes to read the ops of one object with
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct IndexRef<'a> {
+    pub(crate) text: &'a ColumnData<UIntCursor>,
+    pub(crate) visible: &'a ColumnData<BooleanCursor>,
+    pub(crate) mark: &'a MarkIndexColumn,
+}
+
+// The indexes of one object, laid out at the positions its ops have in the
+// op set so they are read exactly like the op set's own indexes
+#[derive(Debug, Clone)]
+struct ReadIndex {
+    text: ColumnData<UIntCursor>,
+    visible: ColumnData<BooleanCursor>,
+    mark: MarkIndexColumn,
+}
+
+impl ReadIndex {
+    fn new(range: &Range<usize>, indexes: ObjIndexes) -> Self {
+        let mut text = ColumnData::init_empty(range.start);
+        text.splice(range.start, 0, indexes.text);
+        let mut visible = ColumnData::init_empty(range.start);
+        visible.splice(range.start, 0, indexes.visible);
+        let mut mark = MarkIndexColumn::init_empty(range.start);
+        mark.splice(range.start, 0, indexes.mark);
+        Self {
+            text,
+            visible,
+            mark,
+        }
+    }
+
+    fn heap_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.text.heap_size()
+            + self.visible.heap_size()
+            + self.mark.heap_size()
+    }
+}
+
+/// The indexes of the objects of a document loaded with `LoadMode::Lazy`
+/// which have been read but not yet modified
+///
+/// Reads only borrow the op set so each object's indexes are built in a
+/// [`OnceLock`] the first time the object is read. The op set moves them into
+/// its own index columns (see [`ReadIndexes::take_built`]) before it is next
+/// modified.
+#[derive(Debug, Default)]
+pub(crate) struct ReadIndexes {
+    objs: HashMap<ObjId, OnceLock<Box<ReadIndex>>>,
+    built: Mutex<Vec<ObjId>>,
+}
+
+impl Clone for ReadIndexes {
+    fn clone(&self) -> Self {
+        Self {
+            objs: self.objs.clone(),
+            built: Mutex::new(self.built.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl ReadIndexes {
+    pub(crate) fn new<I: Iterator<Item = ObjId>>(objs: I) -> Self {
+        Self {
+            objs: objs.map(|obj| (obj, OnceLock::new())).collect(),
+            built: Mutex::default(),
+        }
+    }
+
+    /// The indexes of `obj`, whose ops are at `range`, built by `build` if
+    /// this is the first time they are read
+    pub(crate) fn get<F>(&self, obj: &ObjId, range: &Range<usize>, build: F) -> Option<IndexRef<'_>>
+    where
+        F: FnOnce() -> ObjIndexes,
+    {
+        let index = self.objs.get(obj)?.get_or_init(|| {
+            self.built.lock().unwrap().push(*obj);
+            Box::new(ReadIndex::new(range, build()))
+        });
+        Some(IndexRef {
+            text: &index.text,
+            visible: &index.visible,
+            mark: &index.mark,
+        })
+    }
+
+    /// Drop the indexes which have been built, returning the objects they
+    /// belonged to so they can be indexed in the op set instead
+    pub(crate) fn take_built(&mut self) -> Vec<ObjId> {
+        let built = std::mem::take(self.built.get_mut().unwrap());
+        for obj in &built {
+            self.objs.remove(obj);
+        }
+        built
+    }
+
+    pub(crate) fn remove(&mut self, obj: &ObjId) {
+        self.objs.remove(obj);
+    }
+
+    /// Rename (or with `None` drop) the object of each unbuilt index,
+    /// [`ReadIndexes::take_built`] must have been called first
+    pub(crate) fn rewrite<F>(&mut self, f: F)
+    where
+        F: Fn(ObjId) -> Option<ObjId>,
+    {
+        debug_assert!(self.built.get_mut().unwrap().is_empty());
+        self.objs = std::mem::take(&mut self.objs)
+            .into_iter()
+            .filter_map(|(obj, index)| Some((f(obj)?, index)))
+            .collect();
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_built(&self, obj: &ObjId) -> bool {
+        self.objs
+            .get(obj)
+            .is_some_and(|index| index.get().is_some())
+    }
+
+    pub(crate) fn heap_size(&self) -> usize {
+        crate::heap_size::hash_map(&self.objs)
+            + crate::heap_size::vec(&self.built.lock().unwrap())
+            + self
+                .objs
+                .values()
+                .filter_map(|index| index.get())
+                .map(|index| index.heap_size())
+                .sum::<usize>()
+    }
+}
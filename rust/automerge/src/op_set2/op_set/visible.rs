@@ -5,7 +5,7 @@ use crate::op_set2::op::SuccCursors;
 use crate::op_set2::types::{Action, ScalarValue};
 use crate::op_set2::OpSet;
 
-use hexane::{BooleanCursor, ColumnDataIter};
+use hexane::{BooleanCursor, ColumnData, ColumnDataIter};
 
 use std::fmt::Debug;
 use std::ops::Range;
@@ -27,7 +27,7 @@ impl Shiftable for ScanVisIter<'_> {
         let id = self.id.shift_next(range.clone());
         let action = self.action.shift_next(range.clone());
         let succ = self.succ.shift_next(range);
-        let vis = Self::is_visible(id?, action?, succ?, &self.clock);
+        let vis = Self::is_visible(id?, action?, succ?, self.clock.as_ref());
         if vis {
             Some(0)
         } else {
@@ -65,12 +65,12 @@ impl Skipper for VisIter<'_> {}
 
 impl<'a> VisIter<'a> {
     pub(crate) fn new(op_set: &'a OpSet, clock: Option<&Clock>, range: Range<usize>) -> Self {
-        if let Some(clock) = clock {
-            let scan = ScanVisIter::new(op_set, range, clock.clone());
-            Self::Scan(Box::new(scan))
-        } else {
-            let indexed = IndexedVisIter::new(op_set, range);
-            Self::Indexed(indexed)
+        match clock.is_none().then(|| op_set.indexes(&range)).flatten() {
+            Some(indexes) => Self::Indexed(IndexedVisIter::new(indexes.visible, range)),
+            None => {
+                let scan = ScanVisIter::new(op_set, range, clock.cloned());
+                Self::Scan(Box::new(scan))
+            }
         }
     }
 }
@@ -93,8 +93,8 @@ pub(crate) struct IndexedVisIter<'a> {
 }
 
 impl<'a> IndexedVisIter<'a> {
-    fn new(op_set: &'a OpSet, range: Range<usize>) -> Self {
-        let iter = op_set.cols.index.visible.iter_range(range);
+    fn new(visible: &'a ColumnData<BooleanCursor>, range: Range<usize>) -> Self {
+        let iter = visible.iter_range(range);
         Self { iter, vis: 0 }
     }
 }
@@ -128,11 +128,11 @@ pub(crate) struct ScanVisIter<'a> {
     id: OpIdIter<'a>,
     action: ActionIter<'a>,
     succ: SuccIterIter<'a>,
-    clock: Clock,
+    clock: Option<Clock>,
 }
 
 impl<'a> ScanVisIter<'a> {
-    fn new(op_set: &'a OpSet, range: Range<usize>, clock: Clock) -> Self {
+    fn new(op_set: &'a OpSet, range: Range<usize>, clock: Option<Clock>) -> Self {
         let id = op_set.id_iter_range(&range);
         let action = op_set.action_iter_range(&range);
         let succ = op_set.succ_iter_range(&range);
@@ -144,22 +144,22 @@ impl<'a> ScanVisIter<'a> {
         }
     }
 
-    fn is_visible(id: OpId, action: Action, succ: SuccCursors<'_>, clock: &Clock) -> bool {
+    fn is_visible(id: OpId, action: Action, succ: SuccCursors<'_>, clock: Option<&Clock>) -> bool {
         let is_inc = action == Action::Increment;
         let mut deleted = false;
         for (id, inc) in succ.with_inc() {
-            if inc.is_none() && clock.covers(&id) {
+            if inc.is_none() && vis(clock, &id) {
                 deleted = true;
             }
         }
-        !(deleted || !clock.covers(&id) || is_inc)
+        !(deleted || !vis(clock, &id) || is_inc)
     }
 
     fn next_visible(&mut self) -> Option<bool> {
         let id = self.id.next()?;
         let action = self.action.next()?;
         let succ = self.succ.next()?;
-        Some(Self::is_visible(id, action, succ, &self.clock))
+        Some(Self::is_visible(id, action, succ, self.clock.as_ref()))
     }
 }
 
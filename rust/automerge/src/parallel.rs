@@ -0,0 +1,148 @@
+//! Run independent pieces of work on the rayon thread pool when the `rayon` feature is enabled
+//! and one after the other when it isn't. Results are always returned in the order the work was
+//! given so the output doesn't depend on the feature.
+
+/// Run `f` on each item, the bytes each one writes are appended to `out` in the order of the
+/// items. Without the `rayon` feature each item writes straight to `out`.
+#[cfg(feature = "rayon")]
+pub(crate) fn map_into<T, R, F>(items: &[T], out: &mut Vec<u8>, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T, &mut Vec<u8>) -> R + Sync + Send,
+{
+    use rayon::prelude::*;
+    if serial() {
+        return items.iter().map(|item| f(item, out)).collect();
+    }
+    let written = items
+        .par_iter()
+        .map(|item| {
+            let mut buf = Vec::new();
+            let result = f(item, &mut buf);
+            (result, buf)
+        })
+        .collect::<Vec<_>>();
+    written
+        .into_iter()
+        .map(|(result, buf)| {
+            out.extend(buf);
+            result
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "rayon"))]
+pub(crate) fn map_into<T, R, F>(items: &[T], out: &mut Vec<u8>, f: F) -> Vec<R>
+where
+    F: Fn(&T, &mut Vec<u8>) -> R,
+{
+    items.iter().map(|item| f(item, out)).collect()
+}
+
+/// Run every task spawned on the scope and wait for them to finish
+#[cfg(feature = "rayon")]
+pub(crate) fn scope<'s, F>(f: F)
+where
+    F: for<'a> FnOnce(&'a Scope<'a, 's>) + Send,
+{
+    if serial() {
+        return f(&Scope(None));
+    }
+    rayon::scope(|s| f(&Scope(Some(s))))
+}
+
+#[cfg(not(feature = "rayon"))]
+pub(crate) fn scope<'s, F>(f: F)
+where
+    F: for<'a> FnOnce(&'a Scope<'a, 's>),
+{
+    f(&Scope(std::marker::PhantomData))
+}
+
+#[cfg(feature = "rayon")]
+pub(crate) struct Scope<'a, 's>(Option<&'a rayon::Scope<'s>>);
+
+#[cfg(feature = "rayon")]
+impl<'s> Scope<'_, 's> {
+    pub(crate) fn spawn<F: FnOnce() + Send + 's>(&self, f: F) {
+        match self.0 {
+            Some(scope) => scope.spawn(|_| f()),
+            None => f(),
+        }
+    }
+}
+
+#[cfg(not(feature = "rayon"))]
+pub(crate) struct Scope<'a, 's>(std::marker::PhantomData<&'a &'s ()>);
+
+#[cfg(not(feature = "rayon"))]
+impl<'s> Scope<'_, 's> {
+    pub(crate) fn spawn<F: FnOnce() + 's>(&self, f: F) {
+        f()
+    }
+}
+
+// tests compare the output of the thread pool with running the same work one piece at a time on
+// the calling thread
+#[cfg(all(feature = "rayon", test))]
+thread_local! {
+    static SERIAL: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+#[cfg(all(feature = "rayon", test))]
+fn serial() -> bool {
+    SERIAL.with(|serial| serial.get())
+}
+
+#[cfg(all(feature = "rayon", not(test)))]
+fn serial() -> bool {
+    false
+}
+
+#[cfg(all(feature = "rayon", test))]
+mod tests {
+    use super::SERIAL;
+    use crate::{transaction::Transactable, AutoCommit, Automerge, ObjType, ScalarValue, ROOT};
+
+    fn serially<R>(f: impl FnOnce() -> R) -> R {
+        SERIAL.with(|serial| serial.set(true));
+        let result = f();
+        SERIAL.with(|serial| serial.set(false));
+        result
+    }
+
+    #[test]
+    fn parallel_load_and_save_match_the_serial_path() {
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(&ROOT, "list", ObjType::List).unwrap();
+        for i in 0..40 {
+            let text = doc.insert_object(&list, i, ObjType::Text).unwrap();
+            doc.splice_text(&text, 0, 0, &"some text ".repeat(i % 7 + 1))
+                .unwrap();
+            doc.delete(&text, 0).unwrap();
+            let map = doc.insert_object(&list, i, ObjType::Map).unwrap();
+            doc.put(&map, "n", i as i64).unwrap();
+            doc.put(&map, "n", i as i64 + 1).unwrap();
+            doc.put(&map, "counter", ScalarValue::counter(0)).unwrap();
+            doc.increment(&map, "counter", 5).unwrap();
+            doc.commit();
+        }
+        let doc = doc.document().clone();
+
+        let parallel = doc.save();
+        let serial = serially(|| doc.save());
+        assert_eq!(parallel, serial);
+        // make sure some columns were big enough to compress
+        assert_ne!(parallel, doc.save_nocompress());
+        assert_eq!(doc.save_nocompress(), serially(|| doc.save_nocompress()));
+
+        let loaded = Automerge::load(&parallel).unwrap();
+        let loaded_serially = serially(|| Automerge::load(&parallel)).unwrap();
+        assert_eq!(loaded.get_heads(), loaded_serially.get_heads());
+        assert_eq!(loaded.hydrate(None), loaded_serially.hydrate(None));
+        assert_eq!(loaded.hydrate(None), doc.hydrate(None));
+        assert_eq!(loaded.save(), loaded_serially.save());
+        assert_eq!(loaded.save(), parallel);
+    }
+}
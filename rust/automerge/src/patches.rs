@@ -1,9 +1,20 @@
+mod compact;
+mod inverse;
+#[cfg(feature = "json-patch")]
+mod json_patch;
 mod patch;
 mod patch_builder;
 mod patch_log;
+mod serde_impls;
+mod subscriptions;
+pub use compact::compact;
+pub(crate) use inverse::invert;
+#[cfg(feature = "json-patch")]
+pub use json_patch::{apply_json_patch, to_json_patch, JsonPatchOp};
 pub use patch::{Patch, PatchAction};
 pub(crate) use patch_builder::PatchBuilder;
 pub use patch_log::PatchLog;
+pub use subscriptions::{PatchSubscriptions, SubscriptionId};
 
 use crate::types::{ListEncoding, ObjType, TextEncoding};
 
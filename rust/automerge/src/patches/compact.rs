@@ -0,0 +1,511 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use crate::{ObjId, Prop};
+
+use super::{Patch, PatchAction};
+
+/// Reduce a list of patches to an equivalent, usually shorter, list of patches
+///
+/// Patches obtained by concatenating the output of several calls to
+/// [`crate::AutoCommit::diff_incremental`] or [`crate::Automerge::make_patches`] often contain
+/// many small edits to the same sequence and values which are overwritten by later patches. This
+/// function
+///
+/// * Drops patches which modify a map key or sequence element which a later patch overwrites or
+///   deletes, including patches to objects which live underneath that key or element
+/// * Merges adjacent [`PatchAction::SpliceText`], [`PatchAction::Insert`] and
+///   [`PatchAction::DeleteSeq`] actions on the same object into as few patches as possible
+///
+/// Applying the compacted patches to a [`crate::hydrate::Value`] with
+/// [`crate::hydrate::Value::apply_patches`] produces the same value as applying the original
+/// patches.
+pub fn compact(patches: Vec<Patch>) -> Vec<Patch> {
+    let live = drop_overwritten(patches);
+    let mut result: Vec<Patch> = Vec::with_capacity(live.len());
+    for patch in live {
+        push_merged(&mut result, patch);
+    }
+    result
+}
+
+/// Walk the patches backwards keeping track of which map keys and sequence elements are
+/// overwritten by a later patch, dropping any patch which only touches overwritten state
+fn drop_overwritten(patches: Vec<Patch>) -> Vec<Patch> {
+    let mut killed = Killed::default();
+    let mut live = Vec::with_capacity(patches.len());
+    for patch in patches.into_iter().rev() {
+        if killed.is_dead(&patch) {
+            continue;
+        }
+        killed.record(&patch);
+        live.push(patch);
+    }
+    live.reverse();
+    live
+}
+
+/// The map keys and sequence elements (in the index space of the current point in the walk)
+/// which are overwritten later on
+#[derive(Default)]
+struct Killed {
+    keys: HashSet<(ObjId, String)>,
+    elems: HashMap<ObjId, Vec<Range<usize>>>,
+}
+
+impl Killed {
+    fn is_dead(&self, patch: &Patch) -> bool {
+        let on_path = patch
+            .path
+            .iter()
+            .any(|(obj, prop)| self.is_killed(obj, prop));
+        on_path
+            || match &patch.action {
+                PatchAction::PutMap { key, .. } | PatchAction::DeleteMap { key } => {
+                    self.is_killed_key(&patch.obj, key)
+                }
+                PatchAction::PutSeq { index, .. } => self.is_killed_elem(&patch.obj, *index),
+                PatchAction::Increment { prop, .. } | PatchAction::Conflict { prop } => {
+                    self.is_killed(&patch.obj, prop)
+                }
+                _ => false,
+            }
+    }
+
+    fn is_killed(&self, obj: &ObjId, prop: &Prop) -> bool {
+        match prop {
+            Prop::Map(key) => self.is_killed_key(obj, key),
+            Prop::Seq(index) => self.is_killed_elem(obj, *index),
+        }
+    }
+
+    fn is_killed_key(&self, obj: &ObjId, key: &str) -> bool {
+        // avoid allocating a key for the common case of no overwritten keys
+        !self.keys.is_empty() && self.keys.contains(&(obj.clone(), key.to_string()))
+    }
+
+    fn is_killed_elem(&self, obj: &ObjId, index: usize) -> bool {
+        self.elems
+            .get(obj)
+            .map(|ranges| ranges.iter().any(|r| r.contains(&index)))
+            .unwrap_or(false)
+    }
+
+    fn record(&mut self, patch: &Patch) {
+        match &patch.action {
+            PatchAction::PutMap { key, .. } | PatchAction::DeleteMap { key } => {
+                self.keys.insert((patch.obj.clone(), key.clone()));
+            }
+            PatchAction::PutSeq { index, .. } => {
+                self.elems
+                    .entry(patch.obj.clone())
+                    .or_default()
+                    .push(*index..*index + 1);
+            }
+            PatchAction::Insert { index, values } => {
+                self.before_insert(&patch.obj, *index, values.len());
+            }
+            PatchAction::SpliceText { index, value, .. } => {
+                self.before_insert(&patch.obj, *index, value.len());
+            }
+            PatchAction::DeleteSeq { index, length } => {
+                self.before_delete(&patch.obj, *index, *length);
+                self.elems
+                    .entry(patch.obj.clone())
+                    .or_default()
+                    .push(*index..*index + *length);
+            }
+            _ => {}
+        }
+    }
+
+    /// Map the killed elements of `obj` to the index space before `len` elements were inserted
+    /// at `index`. Elements created by the insertion no longer exist.
+    fn before_insert(&mut self, obj: &ObjId, index: usize, len: usize) {
+        let Some(ranges) = self.elems.get_mut(obj) else {
+            return;
+        };
+        for r in ranges.iter_mut() {
+            let shift = |i: usize| {
+                if i <= index {
+                    i
+                } else if i >= index + len {
+                    i - len
+                } else {
+                    index
+                }
+            };
+            *r = shift(r.start)..shift(r.end);
+        }
+        ranges.retain(|r| !r.is_empty());
+    }
+
+    /// Map the killed elements of `obj` to the index space before `len` elements were deleted
+    /// at `index`
+    fn before_delete(&mut self, obj: &ObjId, index: usize, len: usize) {
+        let Some(ranges) = self.elems.get_mut(obj) else {
+            return;
+        };
+        let mut mapped = Vec::with_capacity(ranges.len());
+        for r in ranges.drain(..) {
+            if r.end <= index {
+                mapped.push(r);
+            } else if r.start >= index {
+                mapped.push(r.start + len..r.end + len);
+            } else {
+                mapped.push(r.start..index);
+                mapped.push(index + len..r.end + len);
+            }
+        }
+        *ranges = mapped;
+    }
+}
+
+/// Push `patch` onto `patches`, merging it with the last patch if they are sequence edits to the
+/// same object
+fn push_merged(patches: &mut Vec<Patch>, patch: Patch) {
+    let Some(last) = patches.last_mut() else {
+        patches.push(patch);
+        return;
+    };
+    if last.obj != patch.obj || last.path != patch.path {
+        patches.push(patch);
+        return;
+    }
+    match (&mut last.action, &patch.action) {
+        (
+            PatchAction::SpliceText {
+                index: tail_index,
+                value: tail_value,
+                marks: tail_marks,
+            },
+            PatchAction::SpliceText {
+                index,
+                value,
+                marks,
+            },
+        ) if tail_marks == marks
+            && (*tail_index..=*tail_index + tail_value.len()).contains(index) =>
+        {
+            if tail_value
+                .splice_text_value(index - *tail_index, value)
+                .is_err()
+            {
+                patches.push(patch);
+            }
+        }
+        (
+            PatchAction::Insert {
+                index: tail_index,
+                values: tail_values,
+            },
+            PatchAction::Insert { index, values },
+        ) if (*tail_index..=*tail_index + tail_values.len()).contains(index) => {
+            for (n, value) in values.iter().enumerate() {
+                tail_values.insert(index - *tail_index + n, value.clone());
+            }
+        }
+        (
+            PatchAction::DeleteSeq {
+                index: tail_index,
+                length: tail_length,
+            },
+            PatchAction::DeleteSeq { index, length },
+        ) if index == tail_index || index + length == *tail_index => {
+            *tail_index = *index;
+            *tail_length += length;
+        }
+        (
+            tail @ (PatchAction::SpliceText { .. } | PatchAction::Insert { .. }),
+            &PatchAction::DeleteSeq { index, length },
+        ) => {
+            let removed = remove_inserted(tail, index, length);
+            if removed == 0 {
+                patches.push(patch);
+                return;
+            }
+            if inserted_len(tail) == 0 {
+                patches.pop();
+            }
+            if length > removed {
+                push_merged(
+                    patches,
+                    Patch {
+                        action: PatchAction::DeleteSeq {
+                            index,
+                            length: length - removed,
+                        },
+                        ..patch
+                    },
+                );
+            }
+        }
+        _ => patches.push(patch),
+    }
+}
+
+fn inserted_len(action: &PatchAction) -> usize {
+    match action {
+        PatchAction::SpliceText { value, .. } => value.len(),
+        PatchAction::Insert { values, .. } => values.len(),
+        _ => 0,
+    }
+}
+
+/// Remove the part of an insertion which is deleted again by deleting `length` elements at
+/// `index`, returning the number of elements removed from the insertion.
+///
+/// Deleting `length - removed` elements at `index` after applying the trimmed insertion is
+/// equivalent to applying the original insertion followed by the original deletion.
+fn remove_inserted(action: &mut PatchAction, index: usize, length: usize) -> usize {
+    let (tail_index, tail_len) = match action {
+        PatchAction::SpliceText { index, value, .. } => (*index, value.len()),
+        PatchAction::Insert { index, values } => (*index, values.len()),
+        _ => return 0,
+    };
+    let start = index.max(tail_index);
+    let end = (index + length).min(tail_index + tail_len);
+    if start >= end {
+        return 0;
+    }
+    for _ in start..end {
+        match action {
+            PatchAction::SpliceText { value, .. } => value.remove(start - tail_index),
+            PatchAction::Insert { values, .. } => {
+                values.remove(start - tail_index);
+            }
+            _ => unreachable!(),
+        }
+    }
+    end - start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compact;
+    use crate::hydrate;
+    use crate::patches::TextRepresentation;
+    use crate::transaction::Transactable;
+    use crate::{
+        AutoCommit, ObjType, Patch, PatchAction, ReadDoc, ScalarValue, TextEncoding, ROOT,
+    };
+
+    fn actions(patches: &[Patch]) -> Vec<&PatchAction> {
+        patches.iter().map(|p| &p.action).collect()
+    }
+
+    fn text_rep() -> TextRepresentation {
+        TextRepresentation::String(TextEncoding::default())
+    }
+
+    fn assert_equivalent(doc: &mut AutoCommit, initial: &hydrate::Value, patches: &[Patch]) {
+        let compacted = compact(patches.to_vec());
+        assert!(compacted.len() <= patches.len());
+        let mut expected = initial.clone();
+        expected
+            .apply_patches(text_rep(), patches.to_vec())
+            .unwrap();
+        let mut actual = initial.clone();
+        actual.apply_patches(text_rep(), compacted).unwrap();
+        assert_eq!(actual, expected);
+        assert_eq!(actual, doc.hydrate(&ROOT, None).unwrap());
+    }
+
+    #[test]
+    fn merges_adjacent_text_edits() {
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.update_diff_cursor();
+        let initial = doc.hydrate(&ROOT, None).unwrap();
+        let mut patches = Vec::new();
+        for (pos, del, ins) in [(0, 0, "hello"), (5, 0, " world"), (5, 6, ""), (0, 1, "J")] {
+            doc.splice_text(&text, pos, del, ins).unwrap();
+            patches.extend(doc.diff_incremental());
+        }
+        assert_eq!(patches.len(), 5);
+        let compacted = compact(patches.clone());
+        assert_eq!(compacted.len(), 1);
+        let PatchAction::SpliceText { index, value, .. } = &compacted[0].action else {
+            panic!("expected a splice");
+        };
+        assert_eq!(*index, 0);
+        assert_eq!(value.make_string(), "Jello");
+        assert_equivalent(&mut doc, &initial, &patches);
+    }
+
+    #[test]
+    fn merges_backspaces() {
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        for i in 0..5 {
+            doc.insert(&list, i, i as i64).unwrap();
+        }
+        doc.update_diff_cursor();
+        let initial = doc.hydrate(&ROOT, None).unwrap();
+        let mut patches = Vec::new();
+        for i in (1..4).rev() {
+            doc.delete(&list, i).unwrap();
+            patches.extend(doc.diff_incremental());
+        }
+        let compacted = compact(patches.clone());
+        assert_eq!(
+            actions(&compacted),
+            vec![&PatchAction::DeleteSeq {
+                index: 1,
+                length: 3
+            }]
+        );
+        assert_equivalent(&mut doc, &initial, &patches);
+    }
+
+    #[test]
+    fn drops_overwritten_puts() {
+        let mut doc = AutoCommit::new();
+        doc.update_diff_cursor();
+        let initial = doc.hydrate(&ROOT, None).unwrap();
+        let mut patches = Vec::new();
+        for i in 0..3 {
+            doc.put(ROOT, "key", i).unwrap();
+            patches.extend(doc.diff_incremental());
+        }
+        doc.put(ROOT, "other", "value").unwrap();
+        patches.extend(doc.diff_incremental());
+        let compacted = compact(patches.clone());
+        assert_eq!(compacted.len(), 2);
+        assert!(matches!(
+            &compacted[0].action,
+            PatchAction::PutMap { key, value: (crate::Value::Scalar(v), _), .. }
+                if key == "key" && v.as_ref() == &ScalarValue::Int(2)
+        ));
+        assert_equivalent(&mut doc, &initial, &patches);
+    }
+
+    #[test]
+    fn drops_patches_to_deleted_objects() {
+        let mut doc = AutoCommit::new();
+        doc.update_diff_cursor();
+        let initial = doc.hydrate(&ROOT, None).unwrap();
+        let mut patches = Vec::new();
+        let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+        let list = doc.put_object(&map, "list", ObjType::List).unwrap();
+        patches.extend(doc.diff_incremental());
+        doc.insert(&list, 0, "a").unwrap();
+        doc.insert(&list, 1, "b").unwrap();
+        let inner = doc.insert_object(&list, 2, ObjType::Map).unwrap();
+        patches.extend(doc.diff_incremental());
+        doc.put(&inner, "x", 1).unwrap();
+        patches.extend(doc.diff_incremental());
+        doc.delete(&list, 2).unwrap();
+        patches.extend(doc.diff_incremental());
+        doc.put(ROOT, "other", 1).unwrap();
+        patches.extend(doc.diff_incremental());
+
+        let compacted = compact(patches.clone());
+        assert!(compacted.iter().all(|p| p.obj != inner));
+        assert_equivalent(&mut doc, &initial, &patches);
+
+        doc.delete(ROOT, "map").unwrap();
+        patches.extend(doc.diff_incremental());
+        let compacted = compact(patches.clone());
+        assert_eq!(compacted.len(), 2);
+        assert_equivalent(&mut doc, &initial, &patches);
+    }
+
+    #[test]
+    fn keeps_patches_to_elements_which_moved() {
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        doc.insert(&list, 0, "a").unwrap();
+        doc.insert(&list, 1, "b").unwrap();
+        doc.update_diff_cursor();
+        let initial = doc.hydrate(&ROOT, None).unwrap();
+        let mut patches = Vec::new();
+        doc.put(&list, 1, "c").unwrap();
+        patches.extend(doc.diff_incremental());
+        doc.insert(&list, 0, "z").unwrap();
+        patches.extend(doc.diff_incremental());
+        doc.delete(&list, 1).unwrap();
+        patches.extend(doc.diff_incremental());
+        assert_eq!(compact(patches.clone()).len(), 3);
+        assert_equivalent(&mut doc, &initial, &patches);
+    }
+
+    #[derive(Debug, Clone)]
+    enum Action {
+        PutMap(usize, i64),
+        DeleteMap(usize),
+        PutObject(usize),
+        Insert(usize, i64),
+        Put(usize, i64),
+        Delete(usize),
+        Splice(usize, usize, String),
+    }
+
+    fn arb_action() -> impl proptest::strategy::Strategy<Value = Action> {
+        use proptest::prelude::*;
+        prop_oneof![
+            (0..4_usize, any::<i64>()).prop_map(|(k, v)| Action::PutMap(k, v)),
+            (0..4_usize).prop_map(Action::DeleteMap),
+            (0..4_usize).prop_map(Action::PutObject),
+            (any::<usize>(), any::<i64>()).prop_map(|(i, v)| Action::Insert(i, v)),
+            (any::<usize>(), any::<i64>()).prop_map(|(i, v)| Action::Put(i, v)),
+            any::<usize>().prop_map(Action::Delete),
+            (any::<usize>(), 0..4_usize, "[a-z]{0,4}")
+                .prop_map(|(i, d, s)| Action::Splice(i, d, s)),
+        ]
+    }
+
+    fn apply_action(doc: &mut AutoCommit, action: Action) {
+        let keys = ["a", "b", "c", "d"];
+        let container = match doc.get(ROOT, "container").unwrap() {
+            Some((_, id)) => id,
+            None => doc.put_object(ROOT, "container", ObjType::Map).unwrap(),
+        };
+        let list = match doc.get(ROOT, "list").unwrap() {
+            Some((_, id)) => id,
+            None => doc.put_object(ROOT, "list", ObjType::List).unwrap(),
+        };
+        let text = match doc.get(ROOT, "text").unwrap() {
+            Some((_, id)) => id,
+            None => doc.put_object(ROOT, "text", ObjType::Text).unwrap(),
+        };
+        let list_len = doc.length(&list);
+        let text_len = doc.length(&text);
+        match action {
+            Action::PutMap(k, v) => doc.put(&container, keys[k], v).unwrap(),
+            Action::DeleteMap(k) => doc.delete(&container, keys[k]).unwrap(),
+            Action::PutObject(k) => {
+                let obj = doc.put_object(&container, keys[k], ObjType::List).unwrap();
+                doc.insert(&obj, 0, 1).unwrap();
+            }
+            Action::Insert(i, v) => doc.insert(&list, i % (list_len + 1), v).unwrap(),
+            Action::Put(i, v) if list_len > 0 => doc.put(&list, i % list_len, v).unwrap(),
+            Action::Delete(i) if list_len > 0 => doc.delete(&list, i % list_len).unwrap(),
+            Action::Splice(i, d, s) => {
+                let pos = i % (text_len + 1);
+                let del = d.min(text_len - pos);
+                doc.splice_text(&text, pos, del as isize, &s).unwrap()
+            }
+            _ => {}
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn compacted_patches_are_equivalent(
+            batches in proptest::collection::vec(proptest::collection::vec(arb_action(), 1..5), 1..10)
+        ) {
+            let mut doc = AutoCommit::new();
+            doc.update_diff_cursor();
+            let initial = doc.hydrate(&ROOT, None).unwrap();
+            let mut patches = Vec::new();
+            for batch in batches {
+                for action in batch {
+                    apply_action(&mut doc, action);
+                }
+                patches.extend(doc.diff_incremental());
+            }
+            assert_equivalent(&mut doc, &initial, &patches);
+        }
+    }
+}
@@ -0,0 +1,849 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::automerge::Automerge;
+use crate::clock::Clock;
+use crate::iter::{SpanInternal, SpansInternal};
+use crate::marks::{Mark, MarkSet};
+use crate::sequence_tree::SequenceTree;
+use crate::text_value::ConcreteTextValue;
+use crate::types::{ObjType, TextEncoding};
+use crate::{ChangeHash, ObjId, Prop, ReadDoc, ScalarValue, Value};
+
+use super::{Patch, PatchAction, TextRepresentation};
+
+/// Pair each of `patches` with the patches which undo it
+///
+/// `before` are the heads of the document before the first patch. Each object starts out in its
+/// state at `before` and the forward patches are applied to it one at a time, so the value a patch
+/// overwrites is whatever the previous patches for that object left there. Patches are grouped by
+/// object and an object's patches only depend on its own earlier state, so no other ordering is
+/// needed.
+pub(crate) fn invert(
+    doc: &Automerge,
+    before: &[ChangeHash],
+    text_rep: TextRepresentation,
+    patches: Vec<Patch>,
+) -> Vec<(Patch, Vec<Patch>)> {
+    let mut inverter = Inverter {
+        doc,
+        clock: doc.clock_at(before),
+        text_rep,
+        objs: HashMap::new(),
+    };
+    patches
+        .into_iter()
+        .map(|patch| {
+            let inverse = inverter.invert(&patch);
+            (patch, inverse)
+        })
+        .collect()
+}
+
+struct Inverter<'a> {
+    doc: &'a Automerge,
+    clock: Clock,
+    text_rep: TextRepresentation,
+    objs: HashMap<ObjId, State>,
+}
+
+/// The value of an object as seen by someone applying the forward patches
+enum State {
+    /// Keys which have been read so far, `None` if the key is not present
+    Map(HashMap<String, Option<(Value<'static>, ObjId)>>),
+    Seq(Vec<Elem>),
+}
+
+#[derive(Clone, Debug)]
+enum Elem {
+    Value(Value<'static>, ObjId),
+    /// One unit of text in the patch encoding. A character (or grapheme) which is several units
+    /// wide is stored in its first unit and the rest have no `chunk`.
+    Text {
+        chunk: Option<String>,
+        marks: Option<Arc<MarkSet>>,
+    },
+}
+
+impl Elem {
+    fn marks(&self) -> Option<&Arc<MarkSet>> {
+        match self {
+            Elem::Text { marks, .. } => marks.as_ref(),
+            Elem::Value(..) => None,
+        }
+    }
+}
+
+impl Inverter<'_> {
+    fn invert(&mut self, patch: &Patch) -> Vec<Patch> {
+        let obj = &patch.obj;
+        let inverse = |action| Patch {
+            obj: obj.clone(),
+            path: patch.path.clone(),
+            action,
+        };
+        match &patch.action {
+            PatchAction::PutMap { key, value, .. } => {
+                match self.put_map(obj, key, Some(value.clone())) {
+                    Some(old) => self.restore(patch, Prop::Map(key.clone()), old),
+                    None => vec![inverse(PatchAction::DeleteMap { key: key.clone() })],
+                }
+            }
+            PatchAction::DeleteMap { key } => match self.put_map(obj, key, None) {
+                Some(old) => self.restore(patch, Prop::Map(key.clone()), old),
+                None => Vec::new(),
+            },
+            PatchAction::PutSeq { index, value, .. } => {
+                let seq = self.seq(obj);
+                match seq.get_mut(*index) {
+                    Some(elem) => {
+                        let old =
+                            std::mem::replace(elem, Elem::Value(value.0.clone(), value.1.clone()));
+                        match old {
+                            Elem::Value(value, id) => {
+                                self.restore(patch, Prop::Seq(*index), (value, id))
+                            }
+                            Elem::Text { .. } => Vec::new(),
+                        }
+                    }
+                    None => Vec::new(),
+                }
+            }
+            PatchAction::Insert { index, values } => {
+                let seq = self.seq(obj);
+                let index = (*index).min(seq.len());
+                seq.splice(
+                    index..index,
+                    values
+                        .iter()
+                        .map(|(value, id, _)| Elem::Value(value.clone(), id.clone())),
+                );
+                vec![inverse(PatchAction::DeleteSeq {
+                    index,
+                    length: values.len(),
+                })]
+            }
+            PatchAction::SpliceText {
+                index,
+                value,
+                marks,
+            } => {
+                let elems = self.text_elems(&value.make_string(), marks.clone().map(Arc::new));
+                let length = elems.len();
+                let seq = self.seq(obj);
+                let index = (*index).min(seq.len());
+                seq.splice(index..index, elems);
+                vec![inverse(PatchAction::DeleteSeq { index, length })]
+            }
+            PatchAction::DeleteSeq { index, length } => {
+                let seq = self.seq(obj);
+                let start = (*index).min(seq.len());
+                let end = (index + length).min(seq.len());
+                let removed = seq.drain(start..end).collect::<Vec<_>>();
+                self.restore_elems(patch, start, removed)
+            }
+            PatchAction::Increment { prop, value } => {
+                self.increment(obj, prop, *value);
+                vec![inverse(PatchAction::Increment {
+                    prop: prop.clone(),
+                    value: -value,
+                })]
+            }
+            PatchAction::Conflict { .. } => Vec::new(),
+            PatchAction::Mark { marks } => {
+                let seq = self.seq(obj);
+                let mut undo = Vec::new();
+                for mark in marks {
+                    let end = mark.end.min(seq.len());
+                    let start = mark.start.min(end);
+                    undo.push(previous_marks(&seq[start..end], start, &mark.name));
+                    for elem in &mut seq[start..end] {
+                        if let Elem::Text { marks, .. } = elem {
+                            *marks = with_mark(marks.as_deref(), &mark.name, &mark.value);
+                        }
+                    }
+                }
+                let undo = undo.into_iter().rev().flatten().collect::<Vec<_>>();
+                if undo.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![inverse(PatchAction::Mark { marks: undo })]
+                }
+            }
+        }
+    }
+
+    /// Set `key` in the map `obj` and return the previous value
+    fn put_map(
+        &mut self,
+        obj: &ObjId,
+        key: &str,
+        value: Option<(Value<'static>, ObjId)>,
+    ) -> Option<(Value<'static>, ObjId)> {
+        self.map_value(obj, key);
+        let Some(State::Map(keys)) = self.objs.get_mut(obj) else {
+            return None;
+        };
+        keys.insert(key.to_string(), value).flatten()
+    }
+
+    fn increment(&mut self, obj: &ObjId, prop: &Prop, by: i64) {
+        let value = match prop {
+            Prop::Map(key) => self.map_value(obj, key),
+            Prop::Seq(index) => match self.seq(obj).get_mut(*index) {
+                Some(Elem::Value(value, _)) => Some(value),
+                _ => None,
+            },
+        };
+        if let Some(Value::Scalar(s)) = value {
+            if let ScalarValue::Counter(c) = s.to_mut() {
+                c.increment(by);
+            }
+        }
+    }
+
+    /// The patches which put `old` back at `prop` in the object `patch` modified, followed by
+    /// the patches which recreate its contents if it is an object
+    fn restore(&self, patch: &Patch, prop: Prop, old: (Value<'static>, ObjId)) -> Vec<Patch> {
+        let (value, id) = old;
+        let action = match &prop {
+            Prop::Map(key) => PatchAction::PutMap {
+                key: key.clone(),
+                value: (value.clone(), id.clone()),
+                conflict: false,
+            },
+            Prop::Seq(index) => PatchAction::PutSeq {
+                index: *index,
+                value: (value.clone(), id.clone()),
+                conflict: false,
+            },
+        };
+        let mut result = vec![Patch {
+            obj: patch.obj.clone(),
+            path: patch.path.clone(),
+            action,
+        }];
+        if value.is_object() {
+            let mut path = patch.path.clone();
+            path.push((patch.obj.clone(), prop));
+            self.populate(&id, path, &mut result);
+        }
+        result
+    }
+
+    /// The patches which reinsert `removed` at `index` in the sequence `patch` modified
+    fn restore_elems(&self, patch: &Patch, index: usize, removed: Vec<Elem>) -> Vec<Patch> {
+        let mut result = Vec::new();
+        let mut pos = index;
+        let mut elems = removed.into_iter().peekable();
+        while let Some(first) = elems.next() {
+            match first {
+                Elem::Value(value, id) => {
+                    let mut run = vec![(value, id)];
+                    while let Some(Elem::Value(..)) = elems.peek() {
+                        if let Some(Elem::Value(value, id)) = elems.next() {
+                            run.push((value, id));
+                        }
+                    }
+                    let mut values = SequenceTree::new();
+                    for (value, id) in &run {
+                        values.push((value.clone(), id.clone(), false));
+                    }
+                    result.push(Patch {
+                        obj: patch.obj.clone(),
+                        path: patch.path.clone(),
+                        action: PatchAction::Insert { index: pos, values },
+                    });
+                    for (n, (value, id)) in run.iter().enumerate() {
+                        if value.is_object() {
+                            let mut path = patch.path.clone();
+                            path.push((patch.obj.clone(), Prop::Seq(pos + n)));
+                            self.populate(id, path, &mut result);
+                        }
+                    }
+                    pos += run.len();
+                }
+                Elem::Text { chunk, marks } => {
+                    let mut text = chunk.unwrap_or_default();
+                    let mut width = 1;
+                    while let Some(Elem::Text {
+                        chunk: next_chunk,
+                        marks: next_marks,
+                    }) = elems.peek()
+                    {
+                        if next_chunk.is_some() && *next_marks != marks {
+                            break;
+                        }
+                        text.push_str(next_chunk.as_deref().unwrap_or_default());
+                        width += 1;
+                        elems.next();
+                    }
+                    result.push(self.splice(patch, pos, &text, marks));
+                    pos += width;
+                }
+            }
+        }
+        result
+    }
+
+    fn splice(
+        &self,
+        patch: &Patch,
+        index: usize,
+        text: &str,
+        marks: Option<Arc<MarkSet>>,
+    ) -> Patch {
+        Patch {
+            obj: patch.obj.clone(),
+            path: patch.path.clone(),
+            action: PatchAction::SpliceText {
+                index,
+                value: ConcreteTextValue::new(text, self.text_rep),
+                marks: marks.map(|m| m.as_ref().clone()),
+            },
+        }
+    }
+
+    /// Append the patches which create the contents of `obj` as it was before the forward
+    /// patches. Objects which are overwritten or deleted receive no further patches, so this is
+    /// also the last state of `obj` anyone applying the forward patches saw.
+    fn populate(&self, obj: &ObjId, path: Vec<(ObjId, Prop)>, result: &mut Vec<Patch>) {
+        let clock = Some(self.clock.clone());
+        let patch = |action| Patch {
+            obj: obj.clone(),
+            path: path.clone(),
+            action,
+        };
+        let mut children = Vec::new();
+        match (self.doc.object_type(obj), self.text_rep) {
+            (Ok(ObjType::Map | ObjType::Table), _) => {
+                for item in self.doc.map_range_for(obj, .., clock) {
+                    let value = item.value.to_value();
+                    let id = item.id();
+                    if value.is_object() {
+                        children.push((id.clone(), Prop::Map(item.key.to_string())));
+                    }
+                    result.push(patch(PatchAction::PutMap {
+                        key: item.key.to_string(),
+                        value: (value, id),
+                        conflict: item.conflict,
+                    }));
+                }
+            }
+            (Ok(ObjType::Text), TextRepresentation::String(encoding)) => {
+                for span in self.spans(obj, encoding) {
+                    match span {
+                        SpanInternal::Text(text, index, marks) => {
+                            result.push(patch(PatchAction::SpliceText {
+                                index,
+                                value: ConcreteTextValue::new(&text, self.text_rep),
+                                marks: marks.map(|m| m.as_ref().clone()),
+                            }))
+                        }
+                        SpanInternal::Obj(id, index) => {
+                            let id = self.doc.id_to_exid(id);
+                            let mut values = SequenceTree::new();
+                            values.push((Value::Object(ObjType::Map), id.clone(), false));
+                            result.push(patch(PatchAction::Insert { index, values }));
+                            children.push((id, Prop::Seq(index)));
+                        }
+                    }
+                }
+            }
+            (Ok(ObjType::List | ObjType::Text), _) => {
+                let mut values = SequenceTree::new();
+                for item in self.doc.list_range_for(obj, .., clock) {
+                    let value = item.value.to_value();
+                    let id = item.id();
+                    if value.is_object() {
+                        children.push((id.clone(), Prop::Seq(item.index)));
+                    }
+                    values.push((value, id, item.conflict));
+                }
+                if values.len() > 0 {
+                    result.push(patch(PatchAction::Insert { index: 0, values }));
+                }
+            }
+            (Err(_), _) => {}
+        }
+        for (child, prop) in children {
+            let mut child_path = path.clone();
+            child_path.push((obj.clone(), prop));
+            self.populate(&child, child_path, result);
+        }
+    }
+
+    fn spans(&self, obj: &ObjId, encoding: TextEncoding) -> Vec<SpanInternal> {
+        let Ok(meta) = self.doc.exid_to_obj(obj) else {
+            return Vec::new();
+        };
+        let range = self.doc.ops().scope_to_obj(&meta.id);
+        SpansInternal::new(self.doc.ops(), range, Some(self.clock.clone()), encoding).collect()
+    }
+
+    /// The value of `key` in `obj`, reading it from the document the first time it is needed
+    fn map_value(&mut self, obj: &ObjId, key: &str) -> Option<&mut Value<'static>> {
+        let State::Map(keys) = self
+            .objs
+            .entry(obj.clone())
+            .or_insert_with(|| State::Map(HashMap::new()))
+        else {
+            return None;
+        };
+        keys.entry(key.to_string())
+            .or_insert_with(|| {
+                self.doc
+                    .get_for(obj, key.into(), Some(self.clock.clone()))
+                    .ok()
+                    .flatten()
+                    .map(|(value, id)| (value.into_owned(), id))
+            })
+            .as_mut()
+            .map(|(value, _)| value)
+    }
+
+    /// The elements of the sequence `obj`, reading them from the document the first time they are
+    /// needed
+    fn seq(&mut self, obj: &ObjId) -> &mut Vec<Elem> {
+        if !self.objs.contains_key(obj) {
+            let elems = self.initial_elems(obj);
+            self.objs.insert(obj.clone(), State::Seq(elems));
+        }
+        match self.objs.get_mut(obj) {
+            Some(State::Seq(elems)) => elems,
+            _ => unreachable!("sequence patch for a map"),
+        }
+    }
+
+    fn initial_elems(&self, obj: &ObjId) -> Vec<Elem> {
+        match (self.doc.object_type(obj), self.text_rep) {
+            (Ok(ObjType::Text), TextRepresentation::String(encoding)) => {
+                let mut elems = Vec::new();
+                for span in self.spans(obj, encoding) {
+                    match span {
+                        SpanInternal::Text(text, _, marks) => {
+                            elems.extend(self.text_elems(&text, marks))
+                        }
+                        SpanInternal::Obj(id, _) => elems.push(Elem::Value(
+                            Value::Object(ObjType::Map),
+                            self.doc.id_to_exid(id),
+                        )),
+                    }
+                }
+                elems
+            }
+            _ => self
+                .doc
+                .list_range_for(obj, .., Some(self.clock.clone()))
+                .map(|item| Elem::Value(item.value.to_value(), item.id()))
+                .collect(),
+        }
+    }
+
+    /// Split `text` into one element per unit of the patch encoding
+    fn text_elems(&self, text: &str, marks: Option<Arc<MarkSet>>) -> Vec<Elem> {
+        let TextRepresentation::String(encoding) = self.text_rep else {
+            return Vec::new();
+        };
+        let chunks: Box<dyn Iterator<Item = &str>> = match encoding {
+            TextEncoding::GraphemeCluster => Box::new(text.graphemes(true)),
+            _ => Box::new(text.split_inclusive(|_| true)),
+        };
+        let mut elems = Vec::new();
+        for chunk in chunks {
+            elems.push(Elem::Text {
+                chunk: Some(chunk.to_string()),
+                marks: marks.clone(),
+            });
+            for _ in 1..encoding.width(chunk) {
+                elems.push(Elem::Text {
+                    chunk: None,
+                    marks: marks.clone(),
+                });
+            }
+        }
+        elems
+    }
+}
+
+/// The marks which restore the previous value of the mark `name` over `elems`, which start at
+/// `offset`
+fn previous_marks(elems: &[Elem], offset: usize, name: &str) -> Vec<Mark> {
+    let mut result: Vec<Mark> = Vec::new();
+    for (n, elem) in elems.iter().enumerate() {
+        let value = elem
+            .marks()
+            .and_then(|marks| marks.iter().find(|(mark, _)| *mark == name))
+            .map(|(_, value)| value.clone())
+            .unwrap_or(ScalarValue::Null);
+        let index = offset + n;
+        match result.last_mut() {
+            Some(last) if last.end == index && last.value == value => last.end += 1,
+            _ => result.push(Mark::new(name.to_string(), value, index, index + 1)),
+        }
+    }
+    result
+}
+
+fn with_mark(marks: Option<&MarkSet>, name: &str, value: &ScalarValue) -> Option<Arc<MarkSet>> {
+    let mut marks = marks
+        .into_iter()
+        .flat_map(|m| m.iter())
+        .filter(|(mark, _)| *mark != name)
+        .map(|(mark, value)| (mark.to_string(), value.clone()))
+        .collect::<MarkSet>();
+    if !value.is_null() {
+        marks.insert(name.into(), value.clone());
+    }
+    if marks.is_empty() {
+        None
+    } else {
+        Some(Arc::new(marks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::marks::{ExpandMark, Mark};
+    use crate::patches::{PatchAction, TextRepresentation};
+    use crate::transaction::Transactable;
+    use crate::{
+        hydrate, AutoCommit, Automerge, AutomergeError, ObjType, Patch, PatchLog, ReadDoc,
+        ScalarValue, ROOT,
+    };
+
+    /// Make `edit` on a fork of `doc`, merge it back and check that the forward patches take the
+    /// before state to the after state and the inverse patches take it back again
+    fn check<F: FnOnce(&mut Automerge)>(doc: &mut Automerge, edit: F) -> Vec<(Patch, Vec<Patch>)> {
+        let text_rep = TextRepresentation::String(doc.text_encoding());
+        let before = doc.hydrate(None);
+        let mut other = doc.fork();
+        edit(&mut other);
+        let mut log = PatchLog::active(text_rep).with_inverse();
+        doc.merge_and_log_patches(&mut other, &mut log).unwrap();
+        let pairs = doc.make_patches_with_inverse(&mut log).unwrap();
+        let after = doc.hydrate(None);
+
+        let without_marks = |patches: Vec<Patch>| {
+            patches
+                .into_iter()
+                .filter(|p| !matches!(p.action, PatchAction::Mark { .. }))
+                .collect::<Vec<_>>()
+        };
+        let mut state = before.clone();
+        state
+            .apply_patches(
+                text_rep,
+                without_marks(pairs.iter().map(|(p, _)| p.clone()).collect()),
+            )
+            .unwrap();
+        assert_eq!(state, after);
+        let inverse = pairs
+            .iter()
+            .rev()
+            .flat_map(|(_, inverse)| inverse.clone())
+            .collect();
+        state
+            .apply_patches(text_rep, without_marks(inverse))
+            .unwrap();
+        assert_eq!(state, before);
+        pairs
+    }
+
+    #[test]
+    fn invert_map_and_list_patches() {
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        tx.put(ROOT, "name", "before").unwrap();
+        tx.put(ROOT, "count", ScalarValue::counter(1)).unwrap();
+        let config = tx.put_object(ROOT, "config", ObjType::Map).unwrap();
+        tx.put(&config, "debug", true).unwrap();
+        let nested = tx.put_object(&config, "nested", ObjType::List).unwrap();
+        tx.insert(&nested, 0, 1).unwrap();
+        let list = tx.put_object(ROOT, "list", ObjType::List).unwrap();
+        for i in 0..5 {
+            let item = tx.insert_object(&list, i, ObjType::Map).unwrap();
+            tx.put(&item, "i", i as i64).unwrap();
+        }
+        tx.commit();
+
+        check(&mut doc, |doc| {
+            let mut tx = doc.transaction();
+            tx.put(ROOT, "name", "after").unwrap();
+            tx.put(ROOT, "name", "after again").unwrap();
+            tx.increment(ROOT, "count", 5).unwrap();
+            tx.put(ROOT, "new", 1).unwrap();
+            tx.put_object(ROOT, "config", ObjType::Map).unwrap();
+            tx.delete(&list, 1).unwrap();
+            tx.delete(&list, 1).unwrap();
+            tx.insert(&list, 0, "first").unwrap();
+            tx.put(&list, 2, "replaced").unwrap();
+            tx.commit();
+        });
+    }
+
+    #[test]
+    fn invert_text_patches() {
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        let text = tx.put_object(ROOT, "text", ObjType::Text).unwrap();
+        tx.splice_text(&text, 0, 0, "hello world 🌍").unwrap();
+        tx.mark(
+            &text,
+            Mark::new("bold".to_string(), true, 0, 5),
+            ExpandMark::None,
+        )
+        .unwrap();
+        tx.commit();
+
+        let pairs = check(&mut doc, |doc| {
+            let mut tx = doc.transaction();
+            tx.splice_text(&text, 3, 6, "").unwrap();
+            tx.splice_text(&text, 0, 0, "oh ").unwrap();
+            tx.commit();
+        });
+        // the deleted text is restored with the marks it had
+        let restore = pairs
+            .iter()
+            .flat_map(|(_, inverse)| inverse)
+            .filter_map(|p| match &p.action {
+                PatchAction::SpliceText { value, marks, .. } => {
+                    Some((value.make_string(), marks.as_ref().map(|m| m.len())))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            restore,
+            vec![("lo".to_string(), Some(1)), (" wor".to_string(), None)]
+        );
+    }
+
+    #[test]
+    fn invert_marks() {
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        let text = tx.put_object(ROOT, "text", ObjType::Text).unwrap();
+        tx.splice_text(&text, 0, 0, "abcdefgh").unwrap();
+        tx.mark(
+            &text,
+            Mark::new("bold".to_string(), true, 2, 4),
+            ExpandMark::None,
+        )
+        .unwrap();
+        tx.commit();
+
+        let pairs = check(&mut doc, |doc| {
+            let mut tx = doc.transaction();
+            tx.mark(
+                &text,
+                Mark::new("bold".to_string(), false, 0, 6),
+                ExpandMark::None,
+            )
+            .unwrap();
+            tx.commit();
+        });
+        let [(_, inverse)] = pairs.as_slice() else {
+            panic!("expected one patch, got {:?}", pairs);
+        };
+        let PatchAction::Mark { marks } = &inverse[0].action else {
+            panic!("expected a mark patch, got {:?}", inverse);
+        };
+        assert_eq!(
+            marks,
+            &vec![
+                Mark::new("bold".to_string(), ScalarValue::Null, 0, 2),
+                Mark::new("bold".to_string(), true, 2, 4),
+                Mark::new("bold".to_string(), ScalarValue::Null, 4, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn invert_loaded_changes() {
+        let mut source = Automerge::new();
+        let mut tx = source.transaction();
+        let list = tx.put_object(ROOT, "list", ObjType::List).unwrap();
+        tx.insert(&list, 0, "a").unwrap();
+        tx.commit();
+
+        let text_rep = TextRepresentation::String(source.text_encoding());
+        let mut doc = Automerge::new();
+        let mut log = PatchLog::active(text_rep).with_inverse();
+        doc.load_incremental_log_patches(&source.save(), &mut log)
+            .unwrap();
+        let pairs = doc.make_patches_with_inverse(&mut log).unwrap();
+
+        let mut state = doc.hydrate(None);
+        let inverse = pairs.into_iter().rev().flat_map(|(_, inverse)| inverse);
+        state.apply_patches(text_rep, inverse).unwrap();
+        assert_eq!(state, hydrate::Value::Map(hydrate::Map::new()));
+    }
+
+    #[derive(Debug, Clone)]
+    enum Edit {
+        Put(usize, i64),
+        PutObj(usize),
+        DeleteKey(usize),
+        Increment(i64),
+        Insert(usize, i64),
+        InsertObj(usize),
+        Delete(usize, usize),
+        Set(usize, i64),
+        Splice(usize, usize, String),
+    }
+
+    fn arb_edit() -> impl Strategy<Value = Edit> {
+        prop_oneof![
+            (0..3_usize, any::<i64>()).prop_map(|(k, v)| Edit::Put(k, v)),
+            (0..3_usize).prop_map(Edit::PutObj),
+            (0..3_usize).prop_map(Edit::DeleteKey),
+            (-5..5_i64).prop_map(Edit::Increment),
+            (0..10_usize, any::<i64>()).prop_map(|(i, v)| Edit::Insert(i, v)),
+            (0..10_usize).prop_map(Edit::InsertObj),
+            (0..10_usize, 1..4_usize).prop_map(|(i, n)| Edit::Delete(i, n)),
+            (0..10_usize, any::<i64>()).prop_map(|(i, v)| Edit::Set(i, v)),
+            (0..10_usize, 0..4_usize, "[aé🌍]{0,3}").prop_map(|(i, n, s)| Edit::Splice(i, n, s)),
+        ]
+    }
+
+    fn apply_edits(doc: &mut Automerge, edits: &[Edit]) {
+        let mut tx = doc.transaction();
+        let (_, list) = tx.get(ROOT, "list").unwrap().unwrap();
+        let (_, text) = tx.get(ROOT, "text").unwrap().unwrap();
+        let keys = ["a", "b", "c"];
+        for edit in edits {
+            let len = tx.length(&list);
+            let text_len = tx.length(&text);
+            match edit {
+                Edit::Put(k, v) => tx.put(ROOT, keys[*k], *v).unwrap(),
+                Edit::PutObj(k) => {
+                    let obj = tx.put_object(ROOT, keys[*k], ObjType::Map).unwrap();
+                    tx.put(&obj, "x", 1).unwrap();
+                }
+                Edit::DeleteKey(k) => tx.delete(ROOT, keys[*k]).unwrap(),
+                Edit::Increment(n) => tx.increment(ROOT, "counter", *n).unwrap(),
+                Edit::Insert(i, v) => tx.insert(&list, i % (len + 1), *v).unwrap(),
+                Edit::InsertObj(i) => {
+                    let obj = tx
+                        .insert_object(&list, i % (len + 1), ObjType::List)
+                        .unwrap();
+                    tx.insert(&obj, 0, "nested").unwrap();
+                }
+                Edit::Delete(i, n) if len > 0 => {
+                    let i = i % len;
+                    for _ in 0..(*n).min(len - i) {
+                        tx.delete(&list, i).unwrap();
+                    }
+                }
+                Edit::Set(i, v) if len > 0 => tx.put(&list, i % len, *v).unwrap(),
+                Edit::Splice(i, n, s) => {
+                    let i = i % (text_len + 1);
+                    let n = (*n).min(text_len - i) as isize;
+                    tx.splice_text(&text, i, n, s).unwrap();
+                }
+                _ => {}
+            }
+        }
+        tx.commit();
+    }
+
+    proptest! {
+        #[test]
+        fn inverse_patches_undo_forward_patches(
+            setup in proptest::collection::vec(arb_edit(), 0..10),
+            edits in proptest::collection::vec(arb_edit(), 0..10),
+        ) {
+            let mut doc = Automerge::new();
+            let mut tx = doc.transaction();
+            tx.put_object(ROOT, "list", ObjType::List).unwrap();
+            tx.put_object(ROOT, "text", ObjType::Text).unwrap();
+            tx.put(ROOT, "counter", ScalarValue::counter(0)).unwrap();
+            tx.commit();
+            apply_edits(&mut doc, &setup);
+            check(&mut doc, |doc| apply_edits(doc, &edits));
+        }
+    }
+
+    #[test]
+    fn invert_autocommit_local_edits() {
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        doc.insert(&list, 0, "a").unwrap();
+        doc.insert(&list, 1, "b").unwrap();
+        doc.put(ROOT, "key", "before").unwrap();
+        doc.update_diff_cursor();
+        let text_rep = TextRepresentation::String(doc.text_encoding());
+        let before = doc.hydrate(ROOT, None).unwrap();
+
+        doc.put(ROOT, "key", "after").unwrap();
+        doc.delete(&list, 0).unwrap();
+        doc.insert(&list, 1, "c").unwrap();
+        let pairs = doc.diff_incremental_with_inverse().unwrap();
+        assert!(!pairs.is_empty());
+
+        let mut state = before.clone();
+        state
+            .apply_patches(
+                text_rep,
+                pairs.iter().map(|(p, _)| p.clone()).collect::<Vec<_>>(),
+            )
+            .unwrap();
+        assert_eq!(state, doc.hydrate(ROOT, None).unwrap());
+        let inverse: Vec<_> = pairs
+            .iter()
+            .rev()
+            .flat_map(|(_, inverse)| inverse.clone())
+            .collect();
+        state.apply_patches(text_rep, inverse).unwrap();
+        assert_eq!(state, before);
+    }
+
+    #[test]
+    fn inverse_errors_after_compacting_the_history_it_needs() {
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, "key", "first").unwrap();
+        doc.update_diff_cursor();
+        doc.put(ROOT, "key", "second").unwrap();
+        let heads = doc.get_heads();
+        doc.compact(&heads).unwrap();
+        assert!(matches!(
+            doc.diff_incremental_with_inverse(),
+            Err(AutomergeError::InverseOfCompactedHistory)
+        ));
+
+        // a cursor at or after the checkpoint is fine
+        doc.update_diff_cursor();
+        doc.put(ROOT, "key", "third").unwrap();
+        let pairs = doc.diff_incremental_with_inverse().unwrap();
+        assert_eq!(pairs.len(), 1);
+
+        let mut other = Automerge::new();
+        let mut tx = other.transaction();
+        tx.put(ROOT, "key", "first").unwrap();
+        tx.commit();
+        let text_rep = TextRepresentation::String(other.text_encoding());
+        let mut log = PatchLog::active(text_rep).with_inverse();
+        let mut tx = other.transaction_log_patches(log.clone());
+        tx.put(ROOT, "key", "second").unwrap();
+        let (_, returned) = tx.commit();
+        log = returned;
+        let heads = other.get_heads();
+        other.compact(&heads).unwrap();
+        assert!(matches!(
+            other.make_patches_with_inverse(&mut log),
+            Err(AutomergeError::InverseOfCompactedHistory)
+        ));
+    }
+
+    #[test]
+    fn make_patches_with_inverse_requires_option() {
+        let doc = Automerge::new();
+        let mut log = PatchLog::active(TextRepresentation::String(doc.text_encoding()));
+        assert!(doc.make_patches_with_inverse(&mut log).is_err());
+    }
+}
@@ -0,0 +1,615 @@
+//! Conversion between [`Patch`]es and [RFC 6902](https://www.rfc-editor.org/rfc/rfc6902) JSON
+//! Patch documents
+//!
+//! JSON Patch describes changes to a JSON document, so these conversions use the same mapping
+//! between automerge documents and JSON as [`crate::AutoSerde`]: maps and tables are JSON objects,
+//! lists are arrays, text objects are strings and scalars are serialized as by
+//! [`crate::ScalarValue`]'s [`serde::Serialize`] implementation.
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::JsonPatchError;
+use crate::hydrate;
+use crate::transaction::Transactable;
+use crate::{ObjId, ObjType, Prop, ReadDoc, ScalarValue, Value, ROOT};
+
+use super::{Patch, PatchAction};
+
+/// A single operation in a JSON Patch document
+///
+/// This serializes to and from the JSON representation given in RFC 6902, e.g.
+/// `{"op": "add", "path": "/a/b", "value": 1}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum JsonPatchOp {
+    Add {
+        path: String,
+        value: serde_json::Value,
+    },
+    Remove {
+        path: String,
+    },
+    Replace {
+        path: String,
+        value: serde_json::Value,
+    },
+    Move {
+        from: String,
+        path: String,
+    },
+    Copy {
+        from: String,
+        path: String,
+    },
+    Test {
+        path: String,
+        value: serde_json::Value,
+    },
+}
+
+/// Convert `patches` into JSON Patch operations
+///
+/// `doc` should be the document the patches were generated from, in the state the patches lead
+/// to. JSON Patch cannot edit a string in place or increment a number, so all the edits to a text
+/// object become a single `replace` of the whole string, and increments of a counter become a
+/// `replace` of the counter, using the values in `doc`. Marks, conflicts and changes to block
+/// markers within text have no JSON representation and are skipped.
+pub fn to_json_patch<R: ReadDoc>(doc: &R, patches: &[Patch]) -> Vec<JsonPatchOp> {
+    let mut ops: Vec<JsonPatchOp> = Vec::with_capacity(patches.len());
+    for patch in patches {
+        if patch
+            .path
+            .iter()
+            .any(|(parent, _)| doc.object_type(parent) == Ok(ObjType::Text))
+        {
+            continue;
+        }
+        if doc.object_type(&patch.obj) == Ok(ObjType::Text) {
+            if let Ok(text) = doc.text(&patch.obj) {
+                push(
+                    &mut ops,
+                    JsonPatchOp::Replace {
+                        path: pointer(&patch.path, None),
+                        value: serde_json::Value::String(text),
+                    },
+                );
+            }
+            continue;
+        }
+        match &patch.action {
+            PatchAction::PutMap { key, value, .. } => push(
+                &mut ops,
+                JsonPatchOp::Add {
+                    path: pointer(&patch.path, Some(&Prop::Map(key.clone()))),
+                    value: value_to_json(&value.0),
+                },
+            ),
+            PatchAction::PutSeq { index, value, .. } => push(
+                &mut ops,
+                JsonPatchOp::Replace {
+                    path: pointer(&patch.path, Some(&Prop::Seq(*index))),
+                    value: value_to_json(&value.0),
+                },
+            ),
+            PatchAction::Insert { index, values } => {
+                for (n, (value, _, _)) in values.iter().enumerate() {
+                    push(
+                        &mut ops,
+                        JsonPatchOp::Add {
+                            path: pointer(&patch.path, Some(&Prop::Seq(index + n))),
+                            value: value_to_json(value),
+                        },
+                    );
+                }
+            }
+            PatchAction::DeleteMap { key } => push(
+                &mut ops,
+                JsonPatchOp::Remove {
+                    path: pointer(&patch.path, Some(&Prop::Map(key.clone()))),
+                },
+            ),
+            PatchAction::DeleteSeq { index, length } => {
+                let path = pointer(&patch.path, Some(&Prop::Seq(*index)));
+                for _ in 0..*length {
+                    push(&mut ops, JsonPatchOp::Remove { path: path.clone() });
+                }
+            }
+            PatchAction::Increment { prop, .. } => {
+                if let Ok(Some((value, _))) = doc.get(&patch.obj, prop.clone()) {
+                    push(
+                        &mut ops,
+                        JsonPatchOp::Replace {
+                            path: pointer(&patch.path, Some(prop)),
+                            value: value_to_json(&value),
+                        },
+                    );
+                }
+            }
+            PatchAction::SpliceText { .. }
+            | PatchAction::Conflict { .. }
+            | PatchAction::Mark { .. } => {}
+        }
+    }
+    ops
+}
+
+/// Apply a JSON Patch document to `doc`
+///
+/// The JSON Patch `path` and `from` pointers are relative to the root of `doc`. Operations map
+/// onto [`Transactable`] methods as follows
+///
+/// * `add` and `replace` put or insert a value, creating maps and lists for JSON objects and
+///   arrays. Adding or replacing a string where there is currently a text object updates the
+///   text with [`Transactable::update_text`].
+/// * `remove` deletes a key or list element
+/// * `move` and `copy` read the value at `from` and add it at `path`, `move` then deletes the
+///   original. Automerge has no move operation so the moved value is a new object.
+/// * `test` compares the current value at `path` with the given value
+///
+/// Operations are applied in order and application stops at the first error. This function is
+/// not atomic: the operations before the one which failed remain in `doc`. RFC 6902 requires
+/// that a patch is applied all or nothing, so the caller must roll back on error, e.g. by
+/// applying the patch in [`crate::Automerge::transact`], which rolls back when the closure
+/// returns an error, or by calling [`crate::AutoCommit::rollback`], which also discards any
+/// other uncommitted changes.
+pub fn apply_json_patch<T: Transactable>(
+    doc: &mut T,
+    ops: &[JsonPatchOp],
+) -> Result<(), JsonPatchError> {
+    for op in ops {
+        match op {
+            JsonPatchOp::Add { path, value } => add(doc, path, value)?,
+            JsonPatchOp::Remove { path } => remove(doc, path)?,
+            JsonPatchOp::Replace { path, value } => replace(doc, path, value)?,
+            JsonPatchOp::Move { from, path } => {
+                if path.starts_with(from.as_str()) && path[from.len()..].starts_with('/') {
+                    return Err(JsonPatchError::MoveIntoChild {
+                        from: from.clone(),
+                        path: path.clone(),
+                    });
+                }
+                if from != path {
+                    let value = get(doc, from)?;
+                    remove(doc, from)?;
+                    add(doc, path, &value)?;
+                }
+            }
+            JsonPatchOp::Copy { from, path } => {
+                let value = get(doc, from)?;
+                add(doc, path, &value)?;
+            }
+            JsonPatchOp::Test { path, value } => {
+                if !json_eq(&get(doc, path)?, value) {
+                    return Err(JsonPatchError::TestFailed(path.clone()));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Push `op` unless it is a `replace` repeating the previous operation, as consecutive edits to a
+/// text object produce
+fn push(ops: &mut Vec<JsonPatchOp>, op: JsonPatchOp) {
+    if !matches!(op, JsonPatchOp::Replace { .. }) || ops.last() != Some(&op) {
+        ops.push(op);
+    }
+}
+
+fn pointer(path: &[(ObjId, Prop)], last: Option<&Prop>) -> String {
+    let mut result = String::new();
+    for prop in path.iter().map(|(_, prop)| prop).chain(last) {
+        result.push('/');
+        match prop {
+            Prop::Map(key) => result.push_str(&key.replace('~', "~0").replace('/', "~1")),
+            Prop::Seq(index) => result.push_str(&index.to_string()),
+        }
+    }
+    result
+}
+
+/// Split a JSON pointer into its unescaped reference tokens
+fn tokens(pointer: &str) -> Result<Vec<String>, JsonPatchError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(JsonPatchError::InvalidPointer(pointer.to_string()));
+    };
+    rest.split('/')
+        .map(|token| {
+            if token.replace("~0", "").replace("~1", "").contains('~') {
+                Err(JsonPatchError::InvalidPointer(pointer.to_string()))
+            } else {
+                Ok(token.replace("~1", "/").replace("~0", "~"))
+            }
+        })
+        .collect()
+}
+
+/// Where a JSON pointer refers to within the document
+enum Location {
+    Root,
+    /// A key in a map or an index in a list, `None` is the "-" token past the end of a list
+    Prop(ObjId, Option<Prop>),
+}
+
+fn resolve<R: ReadDoc>(doc: &R, pointer: &str) -> Result<Location, JsonPatchError> {
+    let mut tokens = tokens(pointer)?;
+    let Some(last) = tokens.pop() else {
+        return Ok(Location::Root);
+    };
+    let mut obj = ROOT;
+    for token in tokens {
+        let Some(prop) = prop(doc, &obj, &token, pointer)? else {
+            return Err(JsonPatchError::NotFound(pointer.to_string()));
+        };
+        match doc.get(&obj, prop)? {
+            Some((Value::Object(ObjType::Map | ObjType::Table | ObjType::List), id)) => obj = id,
+            _ => return Err(JsonPatchError::NotFound(pointer.to_string())),
+        }
+    }
+    let prop = prop(doc, &obj, &last, pointer)?;
+    Ok(Location::Prop(obj, prop))
+}
+
+fn prop<R: ReadDoc>(
+    doc: &R,
+    obj: &ObjId,
+    token: &str,
+    pointer: &str,
+) -> Result<Option<Prop>, JsonPatchError> {
+    match doc.object_type(obj)? {
+        ObjType::Map | ObjType::Table => Ok(Some(Prop::Map(token.to_string()))),
+        ObjType::List if token == "-" => Ok(None),
+        ObjType::List => {
+            let is_index = !token.is_empty()
+                && token.bytes().all(|b| b.is_ascii_digit())
+                && (token == "0" || !token.starts_with('0'));
+            match token.parse::<usize>() {
+                Ok(index) if is_index => Ok(Some(Prop::Seq(index))),
+                _ => Err(JsonPatchError::InvalidPointer(pointer.to_string())),
+            }
+        }
+        ObjType::Text => Err(JsonPatchError::NotFound(pointer.to_string())),
+    }
+}
+
+fn get<R: ReadDoc>(doc: &R, pointer: &str) -> Result<serde_json::Value, JsonPatchError> {
+    match resolve(doc, pointer)? {
+        Location::Root => Ok(hydrate_to_json(&doc.hydrate(ROOT, None)?)),
+        Location::Prop(obj, Some(prop)) => match doc.get(&obj, prop)? {
+            Some((Value::Scalar(s), _)) => Ok(scalar_to_json(&s)),
+            Some((Value::Object(_), id)) => Ok(hydrate_to_json(&doc.hydrate(id, None)?)),
+            None => Err(JsonPatchError::NotFound(pointer.to_string())),
+        },
+        Location::Prop(_, None) => Err(JsonPatchError::NotFound(pointer.to_string())),
+    }
+}
+
+fn add<T: Transactable>(
+    doc: &mut T,
+    pointer: &str,
+    value: &serde_json::Value,
+) -> Result<(), JsonPatchError> {
+    match resolve(doc, pointer)? {
+        Location::Root => Err(JsonPatchError::Root),
+        Location::Prop(obj, None) => {
+            let index = doc.length(&obj);
+            insert(doc, &obj, index, value)
+        }
+        Location::Prop(obj, Some(Prop::Seq(index))) => {
+            if index > doc.length(&obj) {
+                return Err(JsonPatchError::NotFound(pointer.to_string()));
+            }
+            insert(doc, &obj, index, value)
+        }
+        Location::Prop(obj, Some(prop)) => put(doc, &obj, prop, value),
+    }
+}
+
+fn replace<T: Transactable>(
+    doc: &mut T,
+    pointer: &str,
+    value: &serde_json::Value,
+) -> Result<(), JsonPatchError> {
+    match resolve(doc, pointer)? {
+        Location::Root => Err(JsonPatchError::Root),
+        Location::Prop(obj, Some(prop)) => {
+            if doc.get(&obj, prop.clone())?.is_none() {
+                return Err(JsonPatchError::NotFound(pointer.to_string()));
+            }
+            put(doc, &obj, prop, value)
+        }
+        Location::Prop(_, None) => Err(JsonPatchError::NotFound(pointer.to_string())),
+    }
+}
+
+fn remove<T: Transactable>(doc: &mut T, pointer: &str) -> Result<(), JsonPatchError> {
+    match resolve(doc, pointer)? {
+        Location::Root => Err(JsonPatchError::Root),
+        Location::Prop(obj, Some(prop)) => {
+            if doc.get(&obj, prop.clone())?.is_none() {
+                return Err(JsonPatchError::NotFound(pointer.to_string()));
+            }
+            Ok(doc.delete(&obj, prop)?)
+        }
+        Location::Prop(_, None) => Err(JsonPatchError::NotFound(pointer.to_string())),
+    }
+}
+
+fn put<T: Transactable>(
+    doc: &mut T,
+    obj: &ObjId,
+    prop: Prop,
+    value: &serde_json::Value,
+) -> Result<(), JsonPatchError> {
+    if let serde_json::Value::String(s) = value {
+        if let Some((Value::Object(ObjType::Text), text)) = doc.get(obj, prop.clone())? {
+            return Ok(doc.update_text(&text, s)?);
+        }
+    }
+    match json_to_hydrate(value) {
+        hydrate::Value::Scalar(s) => doc.put(obj, prop, s)?,
+        value => {
+            let typ = object_type(&value);
+            let id = doc.put_object(obj, prop, typ)?;
+            doc.update_object(&id, &value)?;
+        }
+    }
+    Ok(())
+}
+
+fn insert<T: Transactable>(
+    doc: &mut T,
+    obj: &ObjId,
+    index: usize,
+    value: &serde_json::Value,
+) -> Result<(), JsonPatchError> {
+    match json_to_hydrate(value) {
+        hydrate::Value::Scalar(s) => doc.insert(obj, index, s)?,
+        value => {
+            let typ = object_type(&value);
+            let id = doc.insert_object(obj, index, typ)?;
+            doc.update_object(&id, &value)?;
+        }
+    }
+    Ok(())
+}
+
+fn object_type(value: &hydrate::Value) -> ObjType {
+    match value {
+        hydrate::Value::List(_) => ObjType::List,
+        hydrate::Value::Text(_) => ObjType::Text,
+        _ => ObjType::Map,
+    }
+}
+
+/// Compare two JSON values, treating numbers as equal if they have the same numeric value as
+/// required by RFC 6902
+fn json_eq(a: &serde_json::Value, b: &serde_json::Value) -> bool {
+    use serde_json::Value as J;
+    match (a, b) {
+        (J::Number(a), J::Number(b)) => match (a.as_i64(), b.as_i64()) {
+            (Some(a), Some(b)) => a == b,
+            _ => match (a.as_u64(), b.as_u64()) {
+                (Some(a), Some(b)) => a == b,
+                _ => a.as_f64() == b.as_f64(),
+            },
+        },
+        (J::Array(a), J::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| json_eq(a, b))
+        }
+        (J::Object(a), J::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(k, v)| b.get(k).map(|w| json_eq(v, w)).unwrap_or(false))
+        }
+        (a, b) => a == b,
+    }
+}
+
+fn value_to_json(value: &Value<'_>) -> serde_json::Value {
+    match value {
+        Value::Object(ObjType::Map | ObjType::Table) => {
+            serde_json::Value::Object(Default::default())
+        }
+        Value::Object(ObjType::List) => serde_json::Value::Array(Vec::new()),
+        Value::Object(ObjType::Text) => serde_json::Value::String(String::new()),
+        Value::Scalar(s) => scalar_to_json(s),
+    }
+}
+
+fn scalar_to_json(value: &ScalarValue) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or(serde_json::Value::Null)
+}
+
+fn hydrate_to_json(value: &hydrate::Value) -> serde_json::Value {
+    match value {
+        hydrate::Value::Scalar(s) => scalar_to_json(s),
+        hydrate::Value::Map(map) => serde_json::Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), hydrate_to_json(&v.value)))
+                .collect(),
+        ),
+        hydrate::Value::List(list) => {
+            serde_json::Value::Array(list.iter().map(|v| hydrate_to_json(&v.value)).collect())
+        }
+        hydrate::Value::Text(text) => serde_json::Value::String(String::from(text)),
+    }
+}
+
+fn json_to_hydrate(value: &serde_json::Value) -> hydrate::Value {
+    match value {
+        serde_json::Value::Null => hydrate::Value::Scalar(ScalarValue::Null),
+        serde_json::Value::Bool(b) => hydrate::Value::Scalar(ScalarValue::Boolean(*b)),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                hydrate::Value::Scalar(ScalarValue::Int(i))
+            } else if let Some(u) = n.as_u64() {
+                hydrate::Value::Scalar(ScalarValue::Uint(u))
+            } else {
+                hydrate::Value::Scalar(ScalarValue::F64(n.as_f64().unwrap_or(f64::NAN)))
+            }
+        }
+        serde_json::Value::String(s) => hydrate::Value::Scalar(ScalarValue::Str(s.into())),
+        serde_json::Value::Array(values) => hydrate::Value::List(
+            values
+                .iter()
+                .map(json_to_hydrate)
+                .collect::<Vec<_>>()
+                .into(),
+        ),
+        serde_json::Value::Object(map) => hydrate::Value::Map(
+            map.iter()
+                .map(|(k, v)| (k.clone(), json_to_hydrate(v)))
+                .collect::<HashMap<_, _>>()
+                .into(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{apply_json_patch, to_json_patch, JsonPatchOp};
+    use crate::error::JsonPatchError;
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, AutoSerde, Automerge, ObjType, ReadDoc, ScalarValue, ROOT};
+
+    fn ops(value: serde_json::Value) -> Vec<JsonPatchOp> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn to_json<R: ReadDoc>(doc: &R) -> serde_json::Value {
+        serde_json::to_value(AutoSerde::from(doc)).unwrap()
+    }
+
+    #[test]
+    fn ops_serialize_as_rfc_6902() {
+        let op = JsonPatchOp::Move {
+            from: "/a".to_string(),
+            path: "/b".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&op).unwrap(),
+            json!({"op": "move", "from": "/a", "path": "/b"})
+        );
+        assert_eq!(
+            ops(json!([{"op": "add", "path": "/a", "value": [1]}])),
+            vec![JsonPatchOp::Add {
+                path: "/a".to_string(),
+                value: json!([1])
+            }]
+        );
+    }
+
+    #[test]
+    fn apply_rfc_examples() {
+        let mut doc = AutoCommit::new();
+        apply_json_patch(
+            &mut doc,
+            &ops(json!([
+                {"op": "add", "path": "/foo", "value": ["bar", "baz"]},
+                {"op": "add", "path": "/foo/1", "value": "qux"},
+                {"op": "add", "path": "/foo/-", "value": {"a~b/c": 1}},
+                {"op": "test", "path": "/foo/3/a~0b~1c", "value": 1.0},
+                {"op": "remove", "path": "/foo/2"},
+                {"op": "replace", "path": "/foo/0", "value": "quux"},
+                {"op": "copy", "from": "/foo/2", "path": "/copied"},
+                {"op": "move", "from": "/foo/1", "path": "/moved"},
+            ])),
+        )
+        .unwrap();
+        assert_eq!(
+            to_json(&doc),
+            json!({
+                "foo": ["quux", {"a~b/c": 1}],
+                "copied": {"a~b/c": 1},
+                "moved": "qux",
+            })
+        );
+    }
+
+    #[test]
+    fn apply_errors() {
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, "a", 1).unwrap();
+        let cases = [
+            (json!({"op": "remove", "path": "/missing"}), "not found"),
+            (
+                json!({"op": "replace", "path": "/missing", "value": 1}),
+                "not found",
+            ),
+            (
+                json!({"op": "add", "path": "/a/b", "value": 1}),
+                "not found",
+            ),
+            (json!({"op": "add", "path": "a", "value": 1}), "pointer"),
+            (json!({"op": "test", "path": "/a", "value": 2}), "test"),
+            (json!({"op": "move", "from": "/a", "path": "/a/b"}), "move"),
+            (json!({"op": "add", "path": "", "value": {}}), "root"),
+        ];
+        for (op, expected) in cases {
+            let err = apply_json_patch(&mut doc, &ops(json!([op]))).unwrap_err();
+            let matches = match err {
+                JsonPatchError::NotFound(_) => expected == "not found",
+                JsonPatchError::InvalidPointer(_) => expected == "pointer",
+                JsonPatchError::TestFailed(_) => expected == "test",
+                JsonPatchError::MoveIntoChild { .. } => expected == "move",
+                JsonPatchError::Root => expected == "root",
+                _ => false,
+            };
+            assert!(matches, "unexpected error for {}", expected);
+        }
+    }
+
+    #[test]
+    fn apply_in_rolled_back_transaction() {
+        let mut doc = Automerge::new();
+        let result = doc.transact(|tx| {
+            apply_json_patch(
+                tx,
+                &ops(json!([
+                    {"op": "add", "path": "/a", "value": 1},
+                    {"op": "test", "path": "/a", "value": 2},
+                ])),
+            )
+        });
+        assert!(result.is_err());
+        assert_eq!(to_json(&doc), json!({}));
+    }
+
+    #[test]
+    fn export_and_import_round_trip() {
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        doc.insert(&list, 0, "a").unwrap();
+        doc.insert(&list, 1, "b").unwrap();
+        doc.put(ROOT, "counter", ScalarValue::counter(1)).unwrap();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello").unwrap();
+        let mut other = doc.fork();
+        doc.update_diff_cursor();
+
+        let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+        doc.put(&map, "key/with~chars", true).unwrap();
+        let nested = doc.insert_object(&list, 1, ObjType::List).unwrap();
+        doc.insert(&nested, 0, 1.5).unwrap();
+        doc.delete(&list, 0).unwrap();
+        doc.increment(ROOT, "counter", 2).unwrap();
+        doc.splice_text(&text, 5, 0, " world").unwrap();
+        doc.splice_text(&text, 0, 1, "H").unwrap();
+        let block = doc.split_block(&text, 0).unwrap();
+        doc.put(&block, "type", "paragraph").unwrap();
+
+        let patches = doc.diff_incremental();
+        let json_patch = to_json_patch(&doc, &patches);
+        apply_json_patch(&mut other, &json_patch).unwrap();
+        // text values are updated in place rather than replaced
+        assert_eq!(other.get(ROOT, "text").unwrap().unwrap().1, text);
+        assert_eq!(to_json(&other), to_json(&doc));
+    }
+}
@@ -138,7 +138,7 @@ impl<R: ReadDoc> PatchBuilder<'_, R> {
                     for _ in 0..length {
                         value.remove(index - *tail_index);
                     }
-                    if value.len() == 0 {
+                    if value.is_empty() {
                         self.patches.pop();
                     }
                     return;
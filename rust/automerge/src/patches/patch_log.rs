@@ -50,6 +50,8 @@ pub struct PatchLog {
     text_rep: TextRepresentation,
     pub(crate) heads: Option<Vec<ChangeHash>>,
     pub(crate) actors: Vec<ActorId>,
+    inverse: bool,
+    before: Option<Vec<ChangeHash>>,
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -176,6 +178,8 @@ impl PatchLog {
             heads: None,
             text_rep,
             actors: vec![],
+            inverse: false,
+            before: None,
         }
     }
 
@@ -201,6 +205,37 @@ impl PatchLog {
         Self::new(true, text_rep)
     }
 
+    /// Also record enough to generate the inverse of each patch
+    ///
+    /// The log remembers the heads of the document when it starts recording changes. The values
+    /// the changes overwrite or delete are read from the document's history at those heads when
+    /// the patches are made with [`crate::Automerge::make_patches_with_inverse()`] or
+    /// [`crate::AutoCommit::make_patches_with_inverse()`].
+    pub fn with_inverse(self) -> Self {
+        Self {
+            inverse: true,
+            ..self
+        }
+    }
+
+    /// The memory held by the logged events, not counting anything the events point to
+    pub(crate) fn heap_size(&self) -> usize {
+        use crate::heap_size::{hash_set, vec};
+        vec(&self.events)
+            + hash_set(&self.expose)
+            + self.heads.as_ref().map_or(0, vec)
+            + vec(&self.actors)
+            + self.before.as_ref().map_or(0, vec)
+    }
+
+    /// Record `heads` as the state of the document before any changes in this log, unless the log
+    /// has already started recording
+    pub(crate) fn record_before<F: FnOnce() -> Vec<ChangeHash>>(&mut self, heads: F) {
+        if self.inverse && self.active && self.before.is_none() {
+            self.before = Some(heads());
+        }
+    }
+
     pub(crate) fn set_active(&mut self, setting: bool) {
         self.active = setting
     }
@@ -388,6 +423,22 @@ impl PatchLog {
         }
     }
 
+    pub(crate) fn make_patches_with_inverse(
+        &mut self,
+        doc: &Automerge,
+    ) -> Result<Vec<(Patch, Vec<Patch>)>, AutomergeError> {
+        if !self.inverse {
+            return Err(AutomergeError::PatchLogWithoutInverse);
+        }
+        // if nothing recorded the heads then nothing was logged and there are no patches
+        let before = self.before.clone().unwrap_or_default();
+        if !doc.history_intact_at(&before) {
+            return Err(AutomergeError::InverseOfCompactedHistory);
+        }
+        let patches = self.make_patches(doc);
+        Ok(super::inverse::invert(doc, &before, self.text_rep, patches))
+    }
+
     fn make_patches_inner<R: ReadDocInternal>(
         events: &[(ObjId, Event)],
         mut expose_queue: ExposeQueue,
@@ -478,6 +529,7 @@ impl PatchLog {
         self.active = true;
         self.events.truncate(0);
         self.expose.clear();
+        self.before = None;
     }
 
     pub(crate) fn branch(&mut self) -> Self {
@@ -488,6 +540,8 @@ impl PatchLog {
             text_rep: self.text_rep,
             heads: None,
             actors: self.actors.clone(),
+            inverse: self.inverse,
+            before: None,
         }
     }
 
@@ -0,0 +1,681 @@
+//! Serialization of [`Patch`] and [`PatchAction`]
+//!
+//! Patches serialize to the same shape as the JavaScript `Patch` objects produced by
+//! `automerge-wasm`, e.g.
+//!
+//! ```json
+//! { "action": "put", "path": ["todos", 0, "done"], "value": true }
+//! { "action": "insert", "path": ["todos", 1], "values": [{}] }
+//! { "action": "splice", "path": ["todos", 1, "title", 0], "value": "hi", "marks": { "bold": true } }
+//! { "action": "inc", "path": ["count"], "value": 2 }
+//! { "action": "del", "path": ["todos", 0], "length": 2 }
+//! { "action": "mark", "path": ["text"], "marks": [{ "name": "bold", "value": true, "start": 0, "end": 2 }] }
+//! { "action": "conflict", "path": ["title"] }
+//! ```
+//!
+//! `path` is the path to the object the patch modifies followed by the key or index it modifies
+//! (except for `mark`). New objects are written as `{}`, `[]` or `""` and `conflict`, `conflicts`,
+//! `length` and `marks` are only present when they are not the default, as in JavaScript.
+//!
+//! A JavaScript patch does not carry enough information to recreate the Rust patch, so some
+//! extra fields are added, which JavaScript consumers can ignore:
+//!
+//! * `obj` - the ID of the object the patch modifies
+//! * `pathIds` - the ID of each object on the path to `obj`, starting with `"_root"`
+//! * `id` / `ids` - the ID of the value or values put or inserted
+//! * `datatype` / `datatypes` - the type of the value or values put or inserted, using the names
+//!   `automerge-wasm` uses: `map`, `table`, `list`, `text`, `str`, `int`, `uint`, `f64`,
+//!   `counter`, `timestamp`, `boolean`, `null`, `bytes` and `unknown<type code>`
+//!
+//! When deserializing, missing IDs default to the root object and missing datatypes are inferred
+//! from the JSON value, so patches exported by `automerge-wasm` can be read too. Spliced text is
+//! read with the default [`TextEncoding`].
+use std::collections::BTreeMap;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::marks::{Mark, MarkSet};
+use crate::sequence_tree::SequenceTree;
+use crate::text_value::ConcreteTextValue;
+use crate::types::TextEncoding;
+use crate::{ObjId, ObjType, Prop, ScalarValue, Value};
+
+use super::{Patch, PatchAction, TextRepresentation};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Action {
+    Put,
+    Insert,
+    Splice,
+    Inc,
+    Del,
+    Mark,
+    Conflict,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Marks {
+    Set(BTreeMap<String, ScalarValue>),
+    List(Vec<Mark>),
+}
+
+/// The JSON values which appear in the `value` and `values` fields of a patch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Uint(u64),
+    F64(f64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PatchRepr {
+    action: Action,
+    path: Vec<Prop>,
+    // `value: null` is a put of null, not a missing value
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "present"
+    )]
+    value: Option<Json>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    values: Option<Vec<Json>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    conflict: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    conflicts: Option<Vec<bool>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    length: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    marks: Option<Marks>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    obj: Option<ObjId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path_ids: Option<Vec<ObjId>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<ObjId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ids: Option<Vec<ObjId>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    datatype: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    datatypes: Option<Vec<String>>,
+}
+
+fn present<'de, D>(deserializer: D) -> Result<Option<Json>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Json::deserialize(deserializer).map(Some)
+}
+
+impl PatchRepr {
+    fn new(action: Action, path: Vec<Prop>) -> Self {
+        PatchRepr {
+            action,
+            path,
+            value: None,
+            values: None,
+            conflict: false,
+            conflicts: None,
+            length: None,
+            marks: None,
+            obj: None,
+            path_ids: None,
+            id: None,
+            ids: None,
+            datatype: None,
+            datatypes: None,
+        }
+    }
+}
+
+impl From<&PatchAction> for PatchRepr {
+    fn from(action: &PatchAction) -> Self {
+        match action {
+            PatchAction::PutMap {
+                key,
+                value,
+                conflict,
+            } => PatchRepr {
+                conflict: *conflict,
+                ..PatchRepr::put(Prop::Map(key.clone()), value)
+            },
+            PatchAction::PutSeq {
+                index,
+                value,
+                conflict,
+            } => PatchRepr {
+                conflict: *conflict,
+                ..PatchRepr::put(Prop::Seq(*index), value)
+            },
+            PatchAction::Insert { index, values } => {
+                let (json, datatypes) = values
+                    .iter()
+                    .map(|(value, _, _)| export_value(value))
+                    .unzip();
+                let conflicts = values.iter().map(|(_, _, c)| *c).collect::<Vec<_>>();
+                PatchRepr {
+                    values: Some(json),
+                    datatypes: Some(datatypes),
+                    ids: Some(values.iter().map(|(_, id, _)| id.clone()).collect()),
+                    conflicts: conflicts.iter().any(|c| *c).then_some(conflicts),
+                    ..PatchRepr::new(Action::Insert, vec![Prop::Seq(*index)])
+                }
+            }
+            PatchAction::SpliceText {
+                index,
+                value,
+                marks,
+            } => PatchRepr {
+                value: Some(Json::Str(value.make_string())),
+                marks: marks.as_ref().filter(|m| m.num_marks() > 0).map(|m| {
+                    Marks::Set(
+                        m.iter()
+                            .map(|(name, value)| (name.to_string(), value.clone()))
+                            .collect(),
+                    )
+                }),
+                ..PatchRepr::new(Action::Splice, vec![Prop::Seq(*index)])
+            },
+            PatchAction::Increment { prop, value } => PatchRepr {
+                value: Some(Json::Int(*value)),
+                ..PatchRepr::new(Action::Inc, vec![prop.clone()])
+            },
+            PatchAction::Conflict { prop } => PatchRepr::new(Action::Conflict, vec![prop.clone()]),
+            PatchAction::DeleteMap { key } => {
+                PatchRepr::new(Action::Del, vec![Prop::Map(key.clone())])
+            }
+            PatchAction::DeleteSeq { index, length } => PatchRepr {
+                length: (*length > 1).then_some(*length),
+                ..PatchRepr::new(Action::Del, vec![Prop::Seq(*index)])
+            },
+            PatchAction::Mark { marks } => PatchRepr {
+                marks: Some(Marks::List(marks.clone())),
+                ..PatchRepr::new(Action::Mark, vec![])
+            },
+        }
+    }
+}
+
+impl PatchRepr {
+    fn put(prop: Prop, value: &(Value<'static>, ObjId)) -> Self {
+        let (json, datatype) = export_value(&value.0);
+        PatchRepr {
+            value: Some(json),
+            datatype: Some(datatype),
+            id: Some(value.1.clone()),
+            ..PatchRepr::new(Action::Put, vec![prop])
+        }
+    }
+
+    /// Remove the key or index this patch modifies from the end of `path` and build the action
+    fn take_action(&mut self) -> Result<PatchAction, String> {
+        if let Action::Mark = self.action {
+            let Some(Marks::List(marks)) = self.marks.take() else {
+                return Err("mark patch without a list of marks".to_string());
+            };
+            return Ok(PatchAction::Mark { marks });
+        }
+        let prop = self.path.pop().ok_or("empty patch path")?;
+        let id = self.id.take().unwrap_or(ObjId::Root);
+        match (&self.action, prop) {
+            (Action::Put, prop) => {
+                let value = self.value.take().ok_or("put patch without a value")?;
+                let value = (import_value(value, self.datatype.as_deref())?, id);
+                Ok(match prop {
+                    Prop::Map(key) => PatchAction::PutMap {
+                        key,
+                        value,
+                        conflict: self.conflict,
+                    },
+                    Prop::Seq(index) => PatchAction::PutSeq {
+                        index,
+                        value,
+                        conflict: self.conflict,
+                    },
+                })
+            }
+            (Action::Insert, Prop::Seq(index)) => {
+                let json = self.values.take().ok_or("insert patch without values")?;
+                let datatypes = self.datatypes.take().unwrap_or_default();
+                let ids = self.ids.take().unwrap_or_default();
+                let conflicts = self.conflicts.take().unwrap_or_default();
+                let mut values = SequenceTree::new();
+                for (n, value) in json.into_iter().enumerate() {
+                    let datatype = datatypes.get(n).map(String::as_str);
+                    values.push((
+                        import_value(value, datatype)?,
+                        ids.get(n).cloned().unwrap_or(ObjId::Root),
+                        conflicts.get(n).copied().unwrap_or(false),
+                    ));
+                }
+                Ok(PatchAction::Insert { index, values })
+            }
+            (Action::Splice, Prop::Seq(index)) => {
+                let Some(Json::Str(text)) = self.value.take() else {
+                    return Err("splice patch without a string value".to_string());
+                };
+                let marks = match self.marks.take() {
+                    Some(Marks::Set(marks)) => Some(marks.into_iter().collect::<MarkSet>()),
+                    Some(Marks::List(_)) => return Err("splice patch with a list of marks".into()),
+                    None => None,
+                };
+                Ok(PatchAction::SpliceText {
+                    index,
+                    value: ConcreteTextValue::new(
+                        &text,
+                        TextRepresentation::String(TextEncoding::default()),
+                    ),
+                    marks,
+                })
+            }
+            (Action::Inc, prop) => {
+                let value = self
+                    .value
+                    .take()
+                    .and_then(|v| match v {
+                        Json::Int(i) => Some(i),
+                        Json::F64(f) => Some(f as i64),
+                        _ => None,
+                    })
+                    .ok_or("inc patch without a numeric value")?;
+                Ok(PatchAction::Increment { prop, value })
+            }
+            (Action::Conflict, prop) => Ok(PatchAction::Conflict { prop }),
+            (Action::Del, Prop::Map(key)) => Ok(PatchAction::DeleteMap { key }),
+            (Action::Del, Prop::Seq(index)) => Ok(PatchAction::DeleteSeq {
+                index,
+                length: self.length.unwrap_or(1),
+            }),
+            (Action::Insert | Action::Splice, Prop::Map(_)) => {
+                Err("insert or splice patch with a map key".to_string())
+            }
+            (Action::Mark, _) => unreachable!(),
+        }
+    }
+}
+
+/// The JSON for `value`, as `automerge-wasm` would export it, and its datatype
+fn export_value(value: &Value<'_>) -> (Json, String) {
+    match value {
+        Value::Object(typ) => {
+            let (json, datatype) = match typ {
+                ObjType::Map => (Json::Object(BTreeMap::new()), "map"),
+                ObjType::Table => (Json::Object(BTreeMap::new()), "table"),
+                ObjType::List => (Json::Array(Vec::new()), "list"),
+                ObjType::Text => (Json::Str(String::new()), "text"),
+            };
+            (json, datatype.to_string())
+        }
+        Value::Scalar(s) => {
+            let datatype = match s.as_ref() {
+                ScalarValue::Bytes(_) => "bytes".to_string(),
+                ScalarValue::Str(_) => "str".to_string(),
+                ScalarValue::Int(_) => "int".to_string(),
+                ScalarValue::Uint(_) => "uint".to_string(),
+                ScalarValue::F64(_) => "f64".to_string(),
+                ScalarValue::Counter(_) => "counter".to_string(),
+                ScalarValue::Timestamp(_) => "timestamp".to_string(),
+                ScalarValue::Boolean(_) => "boolean".to_string(),
+                ScalarValue::Null => "null".to_string(),
+                ScalarValue::Unknown { type_code, .. } => format!("unknown{}", type_code),
+            };
+            let bytes =
+                |bytes: &[u8]| Json::Array(bytes.iter().map(|b| Json::Uint(*b as u64)).collect());
+            let json = match s.as_ref() {
+                ScalarValue::Bytes(b) => bytes(b),
+                ScalarValue::Str(s) => Json::Str(s.to_string()),
+                ScalarValue::Int(i) => Json::Int(*i),
+                ScalarValue::Uint(u) => Json::Uint(*u),
+                ScalarValue::F64(f) => Json::F64(*f),
+                ScalarValue::Counter(c) => Json::Int(i64::from(c)),
+                ScalarValue::Timestamp(t) => Json::Int(*t),
+                ScalarValue::Boolean(b) => Json::Bool(*b),
+                ScalarValue::Unknown { bytes: b, .. } => bytes(b),
+                ScalarValue::Null => Json::Null,
+            };
+            (json, datatype)
+        }
+    }
+}
+
+impl Json {
+    fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Int(i) => Some(*i),
+            Json::Uint(u) => i64::try_from(*u).ok(),
+            _ => None,
+        }
+    }
+
+    fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::Int(i) => u64::try_from(*i).ok(),
+            Json::Uint(u) => Some(*u),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Int(i) => Some(*i as f64),
+            Json::Uint(u) => Some(*u as f64),
+            Json::F64(f) => Some(*f),
+            _ => None,
+        }
+    }
+}
+
+fn import_value(json: Json, datatype: Option<&str>) -> Result<Value<'static>, String> {
+    use Json as J;
+    let invalid = || format!("invalid value {:?} for datatype {:?}", json, datatype);
+    let bytes = |json: &J| -> Option<Vec<u8>> {
+        let J::Array(values) = json else {
+            return None;
+        };
+        values
+            .iter()
+            .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+            .collect()
+    };
+    let scalar = match datatype {
+        Some("map") => return Ok(Value::Object(ObjType::Map)),
+        Some("table") => return Ok(Value::Object(ObjType::Table)),
+        Some("list") => return Ok(Value::Object(ObjType::List)),
+        Some("text") => return Ok(Value::Object(ObjType::Text)),
+        Some("str") => match &json {
+            J::Str(s) => Some(ScalarValue::Str(s.into())),
+            _ => None,
+        },
+        Some("int") => json.as_i64().map(ScalarValue::Int),
+        Some("uint") => json.as_u64().map(ScalarValue::Uint),
+        Some("f64") => json.as_f64().map(ScalarValue::F64),
+        Some("counter") => json.as_i64().map(ScalarValue::counter),
+        Some("timestamp") => json.as_i64().map(ScalarValue::Timestamp),
+        Some("boolean") => match &json {
+            J::Bool(b) => Some(ScalarValue::Boolean(*b)),
+            _ => None,
+        },
+        Some("null") => Some(ScalarValue::Null),
+        Some("bytes") => bytes(&json).map(ScalarValue::Bytes),
+        Some(other) => match other.strip_prefix("unknown").map(str::parse::<u8>) {
+            Some(Ok(type_code)) => {
+                bytes(&json).map(|bytes| ScalarValue::Unknown { type_code, bytes })
+            }
+            _ => return Err(format!("unknown datatype {}", other)),
+        },
+        None => match &json {
+            J::Null => Some(ScalarValue::Null),
+            J::Bool(b) => Some(ScalarValue::Boolean(*b)),
+            J::Int(i) => Some(ScalarValue::Int(*i)),
+            J::Uint(u) => Some(ScalarValue::Uint(*u)),
+            J::F64(f) => Some(ScalarValue::F64(*f)),
+            J::Str(s) => Some(ScalarValue::Str(s.as_str().into())),
+            J::Array(a) if a.is_empty() => return Ok(Value::Object(ObjType::List)),
+            J::Object(o) if o.is_empty() => return Ok(Value::Object(ObjType::Map)),
+            _ => None,
+        },
+    };
+    scalar.map(Value::from).ok_or_else(invalid)
+}
+
+impl Serialize for PatchAction {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        PatchRepr::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PatchAction {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut repr = PatchRepr::deserialize(deserializer)?;
+        repr.take_action().map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Patch {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut repr = PatchRepr::from(&self.action);
+        let mut path = self
+            .path
+            .iter()
+            .map(|(_, prop)| prop.clone())
+            .collect::<Vec<_>>();
+        path.append(&mut repr.path);
+        repr.path = path;
+        repr.obj = Some(self.obj.clone());
+        repr.path_ids = Some(self.path.iter().map(|(obj, _)| obj.clone()).collect());
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Patch {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut repr = PatchRepr::deserialize(deserializer)?;
+        let action = repr.take_action().map_err(de::Error::custom)?;
+        let ids = repr.path_ids.take().unwrap_or_default();
+        let path = repr
+            .path
+            .into_iter()
+            .enumerate()
+            .map(|(n, prop)| (ids.get(n).cloned().unwrap_or(ObjId::Root), prop))
+            .collect();
+        Ok(Patch {
+            obj: repr.obj.unwrap_or(ObjId::Root),
+            path,
+            action,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::marks::{ExpandMark, Mark};
+    use crate::transaction::Transactable;
+    use crate::{hydrate, AutoCommit, Cursor, ObjId, ObjType, Patch, Prop, ReadDoc, ScalarValue};
+    use crate::{PatchAction, ROOT};
+
+    fn round_trip<T>(value: &T) -> T
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        serde_json::from_value(serde_json::to_value(value).unwrap()).unwrap()
+    }
+
+    fn all_patches() -> Vec<Patch> {
+        let mut doc = AutoCommit::new();
+        doc.put(&ROOT, "title", "hello").unwrap();
+        doc.put(&ROOT, "count", ScalarValue::counter(1)).unwrap();
+        doc.put(&ROOT, "bytes", vec![1u8, 2, 3]).unwrap();
+        doc.put(&ROOT, "big", u64::MAX).unwrap();
+        doc.put(&ROOT, "when", ScalarValue::Timestamp(12)).unwrap();
+        doc.put(&ROOT, "nothing", ()).unwrap();
+        doc.put(&ROOT, "gone", 1.5).unwrap();
+        let list = doc.put_object(&ROOT, "list", ObjType::List).unwrap();
+        doc.insert(&list, 0, 1).unwrap();
+        doc.insert(&list, 1, "two").unwrap();
+        doc.insert_object(&list, 2, ObjType::Map).unwrap();
+        doc.insert(&list, 3, false).unwrap();
+        let text = doc.put_object(&ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello world").unwrap();
+        doc.update_diff_cursor();
+
+        let mut other = doc.fork().with_actor("bbbb".try_into().unwrap());
+        doc.put(&ROOT, "title", "mine").unwrap();
+        other.put(&ROOT, "title", "theirs").unwrap();
+        doc.merge(&mut other).unwrap();
+        doc.increment(&ROOT, "count", 2).unwrap();
+        doc.delete(&ROOT, "gone").unwrap();
+        doc.delete(&list, 1).unwrap();
+        doc.delete(&list, 1).unwrap();
+        doc.put(&list, 0, "one").unwrap();
+        doc.mark(
+            &text,
+            Mark::new("bold".to_string(), true, 0, 5),
+            ExpandMark::After,
+        )
+        .unwrap();
+        doc.splice_text(&text, 5, 0, "!").unwrap();
+        let heads = doc.get_heads();
+        let mut patches = doc.diff(&[], &heads);
+        patches.extend(doc.diff_incremental());
+        patches.push(Patch {
+            obj: ROOT,
+            path: Vec::new(),
+            action: PatchAction::Conflict {
+                prop: "title".into(),
+            },
+        });
+        patches
+    }
+
+    #[test]
+    fn patches_round_trip() {
+        let patches = all_patches();
+        for action in ["put", "insert", "splice", "inc", "del", "mark", "conflict"] {
+            assert!(
+                patches
+                    .iter()
+                    .any(|p| serde_json::to_value(p).unwrap()["action"] == action),
+                "no {} patch",
+                action
+            );
+        }
+        for patch in &patches {
+            assert_eq!(&round_trip(patch), patch);
+            assert_eq!(round_trip(&patch.action), patch.action);
+        }
+    }
+
+    #[test]
+    fn patches_match_the_js_shape() {
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(&ROOT, "list", ObjType::List).unwrap();
+        doc.update_diff_cursor();
+        doc.insert(&list, 0, "a").unwrap();
+        doc.insert_object(&list, 1, ObjType::Text).unwrap();
+        let patch = doc.diff_incremental().pop().unwrap();
+        assert_eq!(
+            serde_json::to_value(&patch).unwrap(),
+            json!({
+                "action": "insert",
+                "path": ["list", 0],
+                "values": ["a", ""],
+                "obj": list.to_string(),
+                "pathIds": ["_root"],
+                "ids": patch_ids(&patch)
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>(),
+                "datatypes": ["str", "text"],
+            })
+        );
+    }
+
+    fn patch_ids(patch: &Patch) -> Vec<ObjId> {
+        match &patch.action {
+            PatchAction::Insert { values, .. } => {
+                values.iter().map(|(_, id, _)| id.clone()).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn js_patches_can_be_read() {
+        let patch: Patch = serde_json::from_value(json!({
+            "action": "del", "path": ["list", 3], "length": 2
+        }))
+        .unwrap();
+        assert_eq!(patch.obj, ObjId::Root);
+        assert_eq!(patch.path, vec![(ObjId::Root, Prop::from("list"))]);
+        assert_eq!(
+            patch.action,
+            PatchAction::DeleteSeq {
+                index: 3,
+                length: 2
+            }
+        );
+
+        let patch: Patch = serde_json::from_value(json!({
+            "action": "put", "path": ["config"], "value": {}
+        }))
+        .unwrap();
+        assert_eq!(
+            patch.action,
+            PatchAction::PutMap {
+                key: "config".into(),
+                value: (ObjType::Map.into(), ObjId::Root),
+                conflict: false,
+            }
+        );
+
+        let err = serde_json::from_value::<Patch>(json!({
+            "action": "insert", "path": ["key"], "values": [1]
+        }));
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn ids_props_cursors_and_marks_round_trip() {
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(&ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello").unwrap();
+        let cursor = doc.get_cursor(&text, 2, None).unwrap();
+
+        assert_eq!(serde_json::to_value(&ROOT).unwrap(), json!("_root"));
+        assert_eq!(round_trip(&ROOT), ROOT);
+        assert_eq!(round_trip(&text), text);
+        assert_eq!(round_trip::<Cursor>(&cursor), cursor);
+        assert_eq!(
+            serde_json::to_value(vec![Prop::from("a"), Prop::from(1)]).unwrap(),
+            json!(["a", 1])
+        );
+        assert_eq!(round_trip(&Prop::from("a")), Prop::from("a"));
+        assert_eq!(round_trip(&Prop::from(7)), Prop::from(7));
+        let mark = Mark::new("link".to_string(), "https://automerge.org", 1, 3);
+        assert_eq!(round_trip(&mark), mark);
+    }
+
+    #[test]
+    fn hydrated_values_round_trip() {
+        let mut doc = AutoCommit::new();
+        doc.put(&ROOT, "name", "alice").unwrap();
+        doc.put(&ROOT, "age", 32).unwrap();
+        doc.put(&ROOT, "score", 1.5).unwrap();
+        doc.put(&ROOT, "nothing", ()).unwrap();
+        let list = doc.put_object(&ROOT, "tags", ObjType::List).unwrap();
+        doc.insert(&list, 0, true).unwrap();
+        let value = doc.hydrate(&ROOT, None).unwrap();
+
+        let json = serde_json::to_value(&value).unwrap();
+        assert_eq!(
+            json,
+            json!({ "name": "alice", "age": 32, "score": 1.5, "nothing": null, "tags": [true] })
+        );
+        assert_eq!(round_trip::<hydrate::Value>(&value), value);
+    }
+}
@@ -0,0 +1,487 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{ObjId, Prop, ReadDoc};
+
+use super::{Patch, PatchAction};
+
+/// Identifies a subscription registered with [`PatchSubscriptions`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubscriptionId(u64);
+
+/// A set of subscribers, each interested in the patches for one part of a document
+///
+/// Subscribers register either a path prefix with [`Self::subscribe_path()`] or an object with
+/// [`Self::subscribe_obj()`], then [`Self::route()`] divides a list of patches between them. Each
+/// patch carries the path to the object it modifies, so routing a patch costs a lookup per
+/// component of its path rather than a check per subscriber.
+///
+/// ```
+/// # use automerge::{AutoCommit, ObjType, transaction::Transactable, ROOT};
+/// # use automerge::patches::PatchSubscriptions;
+/// let mut doc = AutoCommit::new();
+/// let todos = doc.put_object(ROOT, "todos", ObjType::List).unwrap();
+/// doc.update_diff_cursor();
+///
+/// let mut subscriptions = PatchSubscriptions::new();
+/// let all_todos = subscriptions.subscribe_obj(todos.clone());
+/// let title = subscriptions.subscribe_path(vec!["title".into()]);
+///
+/// doc.insert(&todos, 0, "write docs").unwrap();
+/// let routed = doc.diff_incremental_subscribed(&subscriptions);
+/// assert_eq!(routed[&all_todos].len(), 1);
+/// assert!(!routed.contains_key(&title));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PatchSubscriptions {
+    next_id: u64,
+    subscriptions: HashMap<SubscriptionId, Subscription>,
+    paths: PathNode,
+    objs: HashMap<ObjId, Vec<SubscriptionId>>,
+}
+
+#[derive(Debug, Clone)]
+enum Subscription {
+    Path(Vec<Prop>),
+    Obj(ObjId),
+}
+
+/// A node in a trie of subscribed paths
+#[derive(Debug, Clone, Default)]
+struct PathNode {
+    subscribers: Vec<SubscriptionId>,
+    keys: HashMap<String, PathNode>,
+    indices: BTreeMap<usize, PathNode>,
+}
+
+/// The part of an object a patch modifies
+enum Target {
+    /// A single key or index
+    Prop(Prop),
+    /// A sequence from `index` onwards, because elements were inserted or removed
+    From { index: usize, removed: usize },
+    /// Nothing which can contain another object
+    None,
+}
+
+impl PatchSubscriptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to the patches for the value at `path` and everything beneath it
+    ///
+    /// The subscriber also receives patches which replace or delete any object on `path`, or
+    /// which insert or remove elements before it in a sequence, as these change what `path`
+    /// refers to. An empty path subscribes to every patch.
+    pub fn subscribe_path(&mut self, path: Vec<Prop>) -> SubscriptionId {
+        let id = self.next_id();
+        let mut node = &mut self.paths;
+        for prop in &path {
+            node = node.child_mut(prop);
+        }
+        node.subscribers.push(id);
+        self.subscriptions.insert(id, Subscription::Path(path));
+        id
+    }
+
+    /// Subscribe to the patches for `obj` and all of its descendants
+    ///
+    /// Unlike a path subscription this follows the object wherever it is in the document. The
+    /// subscriber also receives patches which overwrite or delete `obj` in its parent.
+    pub fn subscribe_obj(&mut self, obj: ObjId) -> SubscriptionId {
+        let id = self.next_id();
+        self.objs.entry(obj.clone()).or_default().push(id);
+        self.subscriptions.insert(id, Subscription::Obj(obj));
+        id
+    }
+
+    /// Remove a subscription, returning `false` if it did not exist
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        match self.subscriptions.remove(&id) {
+            Some(Subscription::Path(path)) => {
+                self.paths.remove(&path, id);
+                true
+            }
+            Some(Subscription::Obj(obj)) => {
+                if let Some(ids) = self.objs.get_mut(&obj) {
+                    ids.retain(|i| *i != id);
+                    if ids.is_empty() {
+                        self.objs.remove(&obj);
+                    }
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
+
+    /// Divide `patches` between the subscribers they are relevant to
+    ///
+    /// `doc` is used to find where each subscribed object currently lives, so it should be in the
+    /// state the patches lead to. Subscribers with no relevant patches are omitted from the result
+    /// and each subscriber's patches are in the same order as in `patches`.
+    pub fn route<R: ReadDoc>(
+        &self,
+        doc: &R,
+        patches: &[Patch],
+    ) -> HashMap<SubscriptionId, Vec<Patch>> {
+        let mut routed: HashMap<SubscriptionId, Vec<Patch>> = HashMap::new();
+        if self.subscriptions.is_empty() {
+            return routed;
+        }
+        let locations = self.obj_locations(doc);
+        let mut matched = Vec::new();
+        for patch in patches {
+            let target = Target::of(&patch.action);
+            self.paths.matching(patch, &target, &mut matched);
+            for obj in patch.path.iter().map(|(obj, _)| obj).chain([&patch.obj]) {
+                if let Some(ids) = self.objs.get(obj) {
+                    matched.extend(ids);
+                }
+            }
+            if let Some(children) = locations.get(&patch.obj) {
+                for (prop, ids) in children {
+                    if target.removes(prop) {
+                        matched.extend(ids.iter());
+                    }
+                }
+            }
+            for id in matched.drain(..) {
+                routed.entry(id).or_default().push(patch.clone());
+            }
+        }
+        routed
+    }
+
+    /// For each subscribed object which is no longer visible, the parent object and property of
+    /// every hidden link on its path to the root, which is where the patch removing it will be
+    ///
+    /// The hidden links of each object on the way are cached, so objects which share ancestors
+    /// only look those ancestors up once per batch.
+    fn obj_locations<R: ReadDoc>(
+        &self,
+        doc: &R,
+    ) -> HashMap<ObjId, Vec<(Prop, &Vec<SubscriptionId>)>> {
+        let mut hidden: HashMap<ObjId, Vec<(ObjId, Prop)>> = HashMap::new();
+        let mut locations: HashMap<ObjId, Vec<_>> = HashMap::new();
+        for (obj, ids) in &self.objs {
+            hidden_links(doc, obj, &mut hidden);
+            for (parent, prop) in &hidden[obj] {
+                locations
+                    .entry(parent.clone())
+                    .or_default()
+                    .push((prop.clone(), ids));
+            }
+        }
+        locations
+    }
+
+    fn next_id(&mut self) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+}
+
+/// Fill in `cache` with the hidden links on the path from `obj` to the root, for `obj` and each
+/// of its ancestors which are not already cached
+fn hidden_links<R: ReadDoc>(doc: &R, obj: &ObjId, cache: &mut HashMap<ObjId, Vec<(ObjId, Prop)>>) {
+    let mut uncached = Vec::new();
+    let mut current = obj.clone();
+    while !cache.contains_key(&current) {
+        let parent = doc.parents(&current).ok().and_then(|mut p| p.next());
+        match parent {
+            Some(parent) => {
+                let next = parent.obj.clone();
+                uncached.push((current, parent));
+                current = next;
+            }
+            None => {
+                cache.insert(current.clone(), Vec::new());
+            }
+        }
+    }
+    for (obj, parent) in uncached.into_iter().rev() {
+        let mut links = cache[&parent.obj].clone();
+        if !parent.visible {
+            links.push((parent.obj, parent.prop));
+        }
+        cache.insert(obj, links);
+    }
+}
+
+impl PathNode {
+    fn child(&self, prop: &Prop) -> Option<&PathNode> {
+        match prop {
+            Prop::Map(key) => self.keys.get(key),
+            Prop::Seq(index) => self.indices.get(index),
+        }
+    }
+
+    fn child_mut(&mut self, prop: &Prop) -> &mut PathNode {
+        match prop {
+            Prop::Map(key) => self.keys.entry(key.clone()).or_default(),
+            Prop::Seq(index) => self.indices.entry(*index).or_default(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.subscribers.is_empty() && self.keys.is_empty() && self.indices.is_empty()
+    }
+
+    /// Remove `id` from the node at `path`, pruning nodes which become empty
+    fn remove(&mut self, path: &[Prop], id: SubscriptionId) {
+        let Some((prop, rest)) = path.split_first() else {
+            self.subscribers.retain(|i| *i != id);
+            return;
+        };
+        let child_is_empty = match prop {
+            Prop::Map(key) => self.keys.get_mut(key),
+            Prop::Seq(index) => self.indices.get_mut(index),
+        }
+        .map(|child| {
+            child.remove(rest, id);
+            child.is_empty()
+        });
+        if child_is_empty == Some(true) {
+            match prop {
+                Prop::Map(key) => self.keys.remove(key),
+                Prop::Seq(index) => self.indices.remove(index),
+            };
+        }
+    }
+
+    /// Collect the subscribers to paths which are a prefix of the patch's path, or which are
+    /// beneath the part of the object the patch modifies
+    fn matching(&self, patch: &Patch, target: &Target, matched: &mut Vec<SubscriptionId>) {
+        let mut node = self;
+        matched.extend(&node.subscribers);
+        for (_, prop) in &patch.path {
+            let Some(child) = node.child(prop) else {
+                return;
+            };
+            node = child;
+            matched.extend(&node.subscribers);
+        }
+        match target {
+            Target::Prop(prop) => {
+                if let Some(child) = node.child(prop) {
+                    child.all(matched);
+                }
+            }
+            Target::From { index, .. } => {
+                for child in node.indices.range(index..).map(|(_, child)| child) {
+                    child.all(matched);
+                }
+            }
+            Target::None => {}
+        }
+    }
+
+    fn all(&self, matched: &mut Vec<SubscriptionId>) {
+        matched.extend(&self.subscribers);
+        for child in self.keys.values().chain(self.indices.values()) {
+            child.all(matched);
+        }
+    }
+}
+
+impl Target {
+    fn of(action: &PatchAction) -> Self {
+        match action {
+            PatchAction::PutMap { key, .. } | PatchAction::DeleteMap { key } => {
+                Target::Prop(Prop::Map(key.clone()))
+            }
+            PatchAction::PutSeq { index, .. } => Target::Prop(Prop::Seq(*index)),
+            PatchAction::Increment { prop, .. } | PatchAction::Conflict { prop } => {
+                Target::Prop(prop.clone())
+            }
+            PatchAction::Insert { index, .. } | PatchAction::SpliceText { index, .. } => {
+                Target::From {
+                    index: *index,
+                    removed: 0,
+                }
+            }
+            PatchAction::DeleteSeq { index, length } => Target::From {
+                index: *index,
+                removed: *length,
+            },
+            PatchAction::Mark { .. } => Target::None,
+        }
+    }
+
+    /// Whether this target could have removed an object which was at `prop`
+    ///
+    /// The index of a deleted element is where it would be if it were still visible, which
+    /// need not be the index at the time it was deleted, so any removal from the sequence counts
+    fn removes(&self, prop: &Prop) -> bool {
+        match (self, prop) {
+            (Target::Prop(p), prop) => p == prop,
+            (Target::From { removed, .. }, Prop::Seq(_)) => *removed > 0,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PatchSubscriptions;
+    use crate::patches::PatchAction;
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, ObjType, Prop, ROOT};
+
+    fn path(props: &[Prop]) -> Vec<Prop> {
+        props.to_vec()
+    }
+
+    #[test]
+    fn route_by_path() {
+        let mut doc = AutoCommit::new();
+        let todos = doc.put_object(ROOT, "todos", ObjType::List).unwrap();
+        let first = doc.insert_object(&todos, 0, ObjType::Map).unwrap();
+        let second = doc.insert_object(&todos, 1, ObjType::Map).unwrap();
+        doc.put(ROOT, "other", 1).unwrap();
+        doc.update_diff_cursor();
+
+        let mut subs = PatchSubscriptions::new();
+        let everything = subs.subscribe_path(vec![]);
+        let all_todos = subs.subscribe_path(path(&["todos".into()]));
+        let second_title = subs.subscribe_path(path(&["todos".into(), 1.into(), "title".into()]));
+        let other = subs.subscribe_path(path(&["other".into()]));
+
+        doc.put(&second, "title", "b").unwrap();
+        doc.put(&first, "title", "a").unwrap();
+        let routed = doc.diff_incremental_subscribed(&subs);
+        assert_eq!(routed[&everything].len(), 2);
+        assert_eq!(routed[&all_todos].len(), 2);
+        assert_eq!(routed[&second_title].len(), 1);
+        assert!(!routed.contains_key(&other));
+
+        // inserting before the second todo changes what the path refers to
+        doc.insert_object(&todos, 0, ObjType::Map).unwrap();
+        let routed = doc.diff_incremental_subscribed(&subs);
+        assert!(routed.contains_key(&second_title));
+        assert!(!routed.contains_key(&other));
+
+        // inserting after it does not
+        doc.insert_object(&todos, 3, ObjType::Map).unwrap();
+        let routed = doc.diff_incremental_subscribed(&subs);
+        assert!(!routed.contains_key(&second_title));
+
+        // replacing an ancestor does
+        doc.put_object(ROOT, "todos", ObjType::List).unwrap();
+        let routed = doc.diff_incremental_subscribed(&subs);
+        assert!(routed.contains_key(&second_title));
+        assert!(!routed.contains_key(&other));
+    }
+
+    #[test]
+    fn route_by_obj() {
+        let mut doc = AutoCommit::new();
+        let todos = doc.put_object(ROOT, "todos", ObjType::List).unwrap();
+        let first = doc.insert_object(&todos, 0, ObjType::Map).unwrap();
+        let second = doc.insert_object(&todos, 1, ObjType::Map).unwrap();
+        let tags = doc.put_object(&second, "tags", ObjType::List).unwrap();
+        doc.update_diff_cursor();
+
+        let mut subs = PatchSubscriptions::new();
+        let sub = subs.subscribe_obj(second.clone());
+
+        // the subscription follows the object as it moves
+        doc.delete(&todos, 0).unwrap();
+        doc.insert(&tags, 0, "urgent").unwrap();
+        doc.put(&first, "title", "a").unwrap();
+        let routed = doc.diff_incremental_subscribed(&subs);
+        assert_eq!(routed.len(), 1);
+        assert_eq!(routed[&sub].len(), 1);
+        assert_eq!(routed[&sub][0].obj, tags);
+
+        // and is told when the object is deleted
+        doc.delete(&todos, 0).unwrap();
+        let routed = doc.diff_incremental_subscribed(&subs);
+        assert!(matches!(
+            routed[&sub][0].action,
+            PatchAction::DeleteSeq { index: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn route_by_obj_when_an_ancestor_is_deleted() {
+        let mut doc = AutoCommit::new();
+        let todos = doc.put_object(ROOT, "todos", ObjType::List).unwrap();
+        let todo = doc.insert_object(&todos, 0, ObjType::Map).unwrap();
+        let tags = doc.put_object(&todo, "tags", ObjType::List).unwrap();
+        let other = doc.put_object(ROOT, "other", ObjType::Map).unwrap();
+        doc.update_diff_cursor();
+
+        let mut subs = PatchSubscriptions::new();
+        let tags_sub = subs.subscribe_obj(tags);
+        let todo_sub = subs.subscribe_obj(todo);
+        let other_sub = subs.subscribe_obj(other);
+
+        doc.delete(ROOT, "todos").unwrap();
+        let routed = doc.diff_incremental_subscribed(&subs);
+        for sub in [tags_sub, todo_sub] {
+            assert!(matches!(
+                &routed[&sub][..],
+                [patch] if patch.action == PatchAction::DeleteMap { key: "todos".into() }
+            ));
+        }
+        assert!(!routed.contains_key(&other_sub));
+    }
+
+    #[test]
+    fn unsubscribe() {
+        let mut doc = AutoCommit::new();
+        doc.update_diff_cursor();
+        let mut subs = PatchSubscriptions::new();
+        let a = subs.subscribe_path(path(&["a".into(), "b".into()]));
+        let b = subs.subscribe_obj(ROOT);
+        assert_eq!(subs.len(), 2);
+        assert!(subs.unsubscribe(a));
+        assert!(!subs.unsubscribe(a));
+        assert!(subs.paths.is_empty());
+        assert!(subs.unsubscribe(b));
+        assert!(subs.is_empty());
+
+        doc.put(ROOT, "a", 1).unwrap();
+        assert!(doc.diff_incremental_subscribed(&subs).is_empty());
+    }
+
+    #[test]
+    fn many_subscribers() {
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        let items = (0..1000)
+            .map(|i| doc.insert_object(&list, i, ObjType::Map).unwrap())
+            .collect::<Vec<_>>();
+        doc.update_diff_cursor();
+
+        let mut subs = PatchSubscriptions::new();
+        let by_path = (0..1000)
+            .map(|i| subs.subscribe_path(path(&["list".into(), i.into()])))
+            .collect::<Vec<_>>();
+        let by_obj = items
+            .iter()
+            .map(|item| subs.subscribe_obj(item.clone()))
+            .collect::<Vec<_>>();
+
+        for item in items.iter().step_by(10) {
+            doc.put(item, "done", true).unwrap();
+        }
+        let routed = doc.diff_incremental_subscribed(&subs);
+        assert_eq!(routed.len(), 200);
+        for i in (0..1000).step_by(10) {
+            assert_eq!(routed[&by_path[i]].len(), 1);
+            assert_eq!(routed[&by_obj[i]].len(), 1);
+        }
+    }
+}
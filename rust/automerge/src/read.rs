@@ -324,3 +324,48 @@ pub struct Stats {
     /// version of rustc used to compile this
     pub rustc_version: &'static str,
 }
+
+/// An estimate of the heap memory held by a document, in bytes
+///
+/// This is returned by [`crate::Automerge::memory_usage()`] and
+/// [`crate::AutoCommit::memory_usage()`]. Column data shared between a document and its forks is
+/// counted in full by each of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// The columns which store the ops
+    pub op_set: usize,
+    /// The indexes over the ops: text widths, visibility, counters, marks and the object table
+    pub indexes: usize,
+    /// The change graph, including change metadata and cached clocks
+    pub change_graph: usize,
+    /// The actor table
+    pub actors: usize,
+    /// Changes waiting for their dependencies to arrive
+    pub queue: usize,
+    /// Patches which have been logged or cached but not yet returned
+    pub patches: usize,
+}
+
+impl MemoryUsage {
+    /// The sum of all the parts
+    pub fn total(&self) -> usize {
+        self.op_set + self.indexes + self.change_graph + self.actors + self.queue + self.patches
+    }
+}
+
+/// Counts of the ops in a document by visibility and by object
+///
+/// This is returned by [`crate::Automerge::op_stats()`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpStats {
+    /// The number of ops which make up the current state of the document
+    pub num_visible_ops: u64,
+    /// The number of ops which have been overwritten or deleted
+    pub num_tombstones: u64,
+    /// The number of counter increments
+    pub num_increments: u64,
+    /// The number of UTF-8 bytes inserted into text objects, including deleted text
+    pub text_bytes: u64,
+    /// The number of ops in each object, in the order the objects are stored in
+    pub ops_per_object: Vec<(ExId, u64)>,
+}
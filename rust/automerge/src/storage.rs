@@ -4,6 +4,7 @@ pub(crate) mod change;
 mod chunk;
 pub(crate) mod columns;
 pub(crate) mod document;
+pub mod inspect;
 pub(crate) mod load;
 pub(crate) mod parse;
 
@@ -11,7 +12,7 @@ pub use load::VerificationMode;
 pub(crate) use {
     change::{AsChangeOp, Change, ChangeOp, Compressed, ReadChangeOpError},
     chunk::{CheckSum, Chunk, ChunkType, Header},
-    columns::{ColumnSpec, Columns, MismatchingColumn, RawColumn, RawColumns},
+    columns::{Codec, ColumnSpec, Columns, MismatchingColumn, RawColumn, RawColumns},
     document::{CompressConfig, DocChangeColumns, DocChangeMetadata, Document},
 };
 
@@ -125,6 +125,7 @@ pub(crate) struct ChangeOpsColumns {
     pred: OpIdListRange,
     expand: MaybeBooleanRange,
     mark_name: RleRange<smol_str::SmolStr>,
+    unknown: Vec<ColumnSpec>,
 }
 
 use crate::op_set2::change;
@@ -161,11 +162,18 @@ impl From<change::ChangeOpsColumns> for ChangeOpsColumns {
             pred,
             expand,
             mark_name,
+            unknown: Vec::new(),
         }
     }
 }
 
 impl ChangeOpsColumns {
+    /// The specifications of any columns in the change which this version of automerge doesn't
+    /// understand
+    pub(crate) fn unknown_columns(&self) -> &[ColumnSpec] {
+        &self.unknown
+    }
+
     pub(crate) fn iter<'a>(&self, data: &'a [u8]) -> ChangeOpsIter<'a> {
         ChangeOpsIter {
             failed: false,
@@ -222,6 +230,7 @@ impl ChangeOpsColumns {
             pred,
             expand,
             mark_name,
+            unknown: Vec::new(),
         }
     }
 
@@ -285,6 +294,7 @@ impl ChangeOpsColumns {
             pred,
             expand,
             mark_name,
+            unknown: Vec::new(),
         }
     }
 
@@ -557,6 +567,7 @@ impl TryFrom<Columns> for ChangeOpsColumns {
             pred,
             expand: expand.unwrap_or_else(|| (0..0).into()),
             mark_name: mark_name.unwrap_or_else(|| (0..0).into()),
+            unknown: other.specs().collect(),
         })
     }
 }
@@ -38,6 +38,10 @@ impl<'a> Compressed<'a> {
         self.bytes.clone()
     }
 
+    pub(crate) fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
     pub(crate) fn checksum(&self) -> CheckSum {
         self.checksum
     }
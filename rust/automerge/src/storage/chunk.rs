@@ -66,7 +66,7 @@ impl<'a> Chunk<'a> {
                 }
                 Chunk::Change(change)
             }
-            ChunkType::Document => {
+            ChunkType::Document | ChunkType::CompactedDocument => {
                 let (remaining, doc) =
                     Document::parse(chunk_input, header).map_err(|e| e.lift())?;
                 if !remaining.is_empty() {
@@ -115,6 +115,11 @@ pub(crate) enum ChunkType {
     Document,
     Change,
     Compressed,
+    /// A document some of whose changes have been compacted, see [`crate::Automerge::compact`].
+    /// This is laid out exactly like a document chunk but has its own type so that versions of
+    /// automerge which predate compaction reject it rather than loading a document with ops
+    /// missing.
+    CompactedDocument,
 }
 
 impl TryFrom<u8> for ChunkType {
@@ -125,6 +130,7 @@ impl TryFrom<u8> for ChunkType {
             0 => Ok(Self::Document),
             1 => Ok(Self::Change),
             2 => Ok(Self::Compressed),
+            3 => Ok(Self::CompactedDocument),
             other => Err(other),
         }
     }
@@ -136,6 +142,7 @@ impl From<ChunkType> for u8 {
             ChunkType::Document => 0,
             ChunkType::Change => 1,
             ChunkType::Compressed => 2,
+            ChunkType::CompactedDocument => 3,
         }
     }
 }
@@ -274,6 +281,10 @@ impl Header {
         self.hash
     }
 
+    pub(crate) fn chunk_type(&self) -> ChunkType {
+        self.chunk_type
+    }
+
     pub(crate) fn checksum_valid(&self) -> bool {
         CheckSum(self.hash.checksum()) == self.checksum
     }
@@ -34,7 +34,7 @@ pub(crate) use column_builder::{
 };
 
 pub(crate) mod raw_column;
-pub(crate) use raw_column::{RawColumn, RawColumns};
+pub(crate) use raw_column::{Codec, RawColumn, RawColumns};
 
 #[derive(Debug, thiserror::Error)]
 #[error("mismatching column at {index}.")]
@@ -49,7 +49,7 @@ pub(crate) mod compression {
     pub(crate) struct Uncompressed;
 
     /// A witness for what we know about whether or not a column is compressed
-    pub(crate) trait ColumnCompression {}
+    pub(crate) trait ColumnCompression: Send + Sync {}
     impl ColumnCompression for Unknown {}
     impl ColumnCompression for Uncompressed {}
 }
@@ -74,6 +74,10 @@ impl Columns {
         self.columns.push(col)
     }
 
+    pub(crate) fn specs(&self) -> impl Iterator<Item = ColumnSpec> + '_ {
+        self.columns.iter().map(|c| c.spec())
+    }
+
     pub(crate) fn parse2<'a, I: Iterator<Item = &'a RawColumn<compression::Uncompressed>>>(
         data_size: usize,
         cols: I,
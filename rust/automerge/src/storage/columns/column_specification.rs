@@ -17,14 +17,23 @@ impl Ord for ColumnSpec {
     }
 }
 
+/// The deflate bit of a column specification
+const DEFLATE_BIT: u32 = 0b00001000;
+
+/// Set along with the deflate bit on columns compressed with zstd rather than DEFLATE. Readers
+/// which predate zstd see a deflated column whose data is not a valid DEFLATE stream (the zstd
+/// frame magic number decodes as a stored block with mismatched lengths) and so reject the
+/// document rather than misreading it.
+const ZSTD_BIT: u32 = 1 << 31;
+
 impl ColumnSpec {
     pub(crate) const fn new(id: ColumnId, col_type: ColumnType, deflate: bool) -> Self {
         let mut raw = id.0 << 4;
         raw |= col_type.as_u8() as u32;
         if deflate {
-            raw |= 0b00001000;
+            raw |= DEFLATE_BIT;
         } else {
-            raw &= 0b11110111;
+            raw &= !DEFLATE_BIT;
         }
         ColumnSpec(raw)
     }
@@ -66,23 +75,34 @@ impl ColumnSpec {
     }
 
     pub(crate) fn id(&self) -> ColumnId {
-        ColumnId(self.0 >> 4)
+        ColumnId((self.0 & !ZSTD_BIT) >> 4)
     }
 
+    /// Whether the column is compressed, with either DEFLATE or zstd
     pub(crate) fn deflate(&self) -> bool {
-        self.0 & 0b00001000 > 0
+        self.0 & DEFLATE_BIT > 0
+    }
+
+    /// Whether the column is compressed with zstd
+    pub(crate) fn zstd(&self) -> bool {
+        self.deflate() && self.0 & ZSTD_BIT > 0
     }
 
     pub(crate) fn deflated(&self) -> Self {
         Self::new(self.id(), self.col_type(), true)
     }
 
+    #[cfg_attr(not(feature = "zstd"), allow(dead_code))]
+    pub(crate) fn zstd_compressed(&self) -> Self {
+        ColumnSpec(self.deflated().0 | ZSTD_BIT)
+    }
+
     pub(crate) fn inflated(&self) -> Self {
         Self::new(self.id(), self.col_type(), false)
     }
 
     pub(crate) fn normalize(&self) -> Normalized {
-        Normalized(self.0 & 0b11110111)
+        Normalized(self.0 & !(DEFLATE_BIT | ZSTD_BIT))
     }
 }
 
@@ -93,10 +113,11 @@ impl std::fmt::Debug for ColumnSpec {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "ColumnSpec(id: {:?}, type: {}, deflate: {})",
+            "ColumnSpec(id: {:?}, type: {}, deflate: {}, zstd: {})",
             self.id(),
             self.col_type(),
-            self.deflate()
+            self.deflate(),
+            self.zstd()
         )
     }
 }
@@ -116,6 +137,12 @@ impl From<u32> for ColumnId {
     }
 }
 
+impl From<ColumnId> for u32 {
+    fn from(id: ColumnId) -> Self {
+        id.0
+    }
+}
+
 impl std::fmt::Debug for ColumnId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.0.fmt(f)
@@ -335,6 +362,26 @@ mod tests {
             if deflated.normalize() != spec.normalize() {
                 panic!("Scenario {} failed normalize test", index + 1);
             }
+
+            if spec.zstd() || deflated.zstd() {
+                panic!(
+                    "Scenario {} failed: zstd set without the zstd bit",
+                    index + 1
+                );
+            }
+
+            let zstd = spec.zstd_compressed();
+            if !zstd.zstd() || !zstd.deflate() {
+                panic!("Scenario {} failed zstd bit test", index + 1);
+            }
+
+            if zstd.id() != spec.id() || zstd.col_type() != spec.col_type() {
+                panic!("Scenario {} failed zstd id test", index + 1);
+            }
+
+            if zstd.normalize() != spec.normalize() || zstd.inflated() != spec {
+                panic!("Scenario {} failed zstd normalize test", index + 1);
+            }
         }
     }
 }
@@ -46,17 +46,34 @@ impl<T: compression::ColumnCompression> RawColumn<T> {
         self.data.clone()
     }
 
-    fn compress(&self, input: &[u8], out: &mut Vec<u8>, threshold: usize) -> (ColumnSpec, usize) {
+    fn compress(
+        &self,
+        input: &[u8],
+        out: &mut Vec<u8>,
+        threshold: usize,
+        codec: Codec,
+    ) -> (ColumnSpec, usize) {
         let (spec, len) = if self.data.len() < threshold || self.spec.deflate() {
             out.extend(&input[self.data.clone()]);
             (self.spec, self.data.len())
         } else {
-            let mut deflater = flate2::bufread::DeflateEncoder::new(
-                &input[self.data.clone()],
-                flate2::Compression::default(),
-            );
-            //This unwrap should be okay as we're reading and writing to in memory buffers
-            (self.spec.deflated(), deflater.read_to_end(out).unwrap())
+            match codec {
+                Codec::Deflate => {
+                    let mut deflater = flate2::bufread::DeflateEncoder::new(
+                        &input[self.data.clone()],
+                        flate2::Compression::default(),
+                    );
+                    //This unwrap should be okay as we're reading and writing to in memory buffers
+                    (self.spec.deflated(), deflater.read_to_end(out).unwrap())
+                }
+                #[cfg(feature = "zstd")]
+                Codec::Zstd(level) => {
+                    let start = out.len();
+                    //This unwrap should be okay as we're reading and writing to in memory buffers
+                    zstd::stream::copy_encode(&input[self.data.clone()], &mut *out, level).unwrap();
+                    (self.spec.zstd_compressed(), out.len() - start)
+                }
+            }
         };
         (spec, len)
     }
@@ -78,7 +95,9 @@ impl<T: compression::ColumnCompression> RawColumn<T> {
         input: &[u8],
         out: &mut Vec<u8>,
     ) -> Result<(ColumnSpec, usize), ParseError> {
-        let len = if self.spec.deflate() {
+        let len = if self.spec.zstd() {
+            zstd_decompress(&input[self.data.clone()], out)?
+        } else if self.spec.deflate() {
             let mut inflater = flate2::bufread::DeflateDecoder::new(&input[self.data.clone()]);
             inflater.read_to_end(out).map_err(ParseError::Deflate)?
         } else {
@@ -89,6 +108,27 @@ impl<T: compression::ColumnCompression> RawColumn<T> {
     }
 }
 
+#[cfg(feature = "zstd")]
+fn zstd_decompress(input: &[u8], out: &mut Vec<u8>) -> Result<usize, ParseError> {
+    let start = out.len();
+    zstd::stream::copy_decode(input, &mut *out).map_err(ParseError::Zstd)?;
+    Ok(out.len() - start)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_decompress(_input: &[u8], _out: &mut [u8]) -> Result<usize, ParseError> {
+    Err(ParseError::UnsupportedZstd)
+}
+
+/// The algorithm used to compress columns which are over the compression threshold
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Codec {
+    Deflate,
+    /// zstd at the given compression level
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct RawColumns<T: compression::ColumnCompression>(pub(crate) Vec<RawColumn<T>>);
 
@@ -106,7 +146,8 @@ impl<T: compression::ColumnCompression> RawColumns<T> {
         Some(RawColumns(result))
     }
 
-    /// Write each column in `input` represented by `self` into `out`, possibly compressing.
+    /// Write each column in `input` represented by `self` into `out`, compressing columns which
+    /// are at least `threshold` bytes long with `codec`.
     ///
     /// # Returns
     /// The `RawColumns` corresponding to the data written to `out`
@@ -118,11 +159,14 @@ impl<T: compression::ColumnCompression> RawColumns<T> {
         input: &[u8],
         out: &mut Vec<u8>,
         threshold: usize,
+        codec: Codec,
     ) -> RawColumns<compression::Unknown> {
+        let compressed = crate::parallel::map_into(&self.0, out, |col, out| {
+            col.compress(input, out, threshold, codec)
+        });
         let mut result = Vec::with_capacity(self.0.len());
         let mut start = 0;
-        for col in &self.0 {
-            let (spec, len) = col.compress(input, out, threshold);
+        for (spec, len) in compressed {
             result.push(RawColumn {
                 spec,
                 data: start..(start + len),
@@ -145,15 +189,18 @@ impl<T: compression::ColumnCompression> RawColumns<T> {
         input: &[u8],
         out: &mut Vec<u8>,
     ) -> Result<RawColumns<compression::Uncompressed>, ParseError> {
-        let mut result = Vec::with_capacity(self.0.len());
-        let mut start = 0;
-        for col in &self.0 {
-            let (spec, len) = if let Some(decomp) = col.uncompressed() {
+        let decompressed = crate::parallel::map_into(&self.0, out, |col, out| {
+            if let Some(decomp) = col.uncompressed() {
                 out.extend(&input[decomp.data.clone()]);
-                (decomp.spec, decomp.data.len())
+                Ok((decomp.spec, decomp.data.len()))
             } else {
-                col.decompress(input, out)?
-            };
+                col.decompress(input, out)
+            }
+        });
+        let mut result = Vec::with_capacity(self.0.len());
+        let mut start = 0;
+        for col in decompressed {
+            let (spec, len) = col?;
             result.push(RawColumn {
                 spec,
                 data: start..(start + len),
@@ -199,6 +246,12 @@ pub(crate) enum ParseError {
     Leb128(#[from] parse::leb128::Error),
     #[error(transparent)]
     Deflate(#[from] std::io::Error),
+    #[cfg(feature = "zstd")]
+    #[error("invalid zstd column: {0}")]
+    Zstd(std::io::Error),
+    #[cfg(not(feature = "zstd"))]
+    #[error("column is compressed with zstd but the zstd feature is not enabled")]
+    UnsupportedZstd,
 }
 
 impl RawColumns<compression::Unknown> {
@@ -270,3 +323,55 @@ fn are_normal_sorted<T: compression::ColumnCompression>(cols: &[RawColumn<T>]) -
     }
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::columns::{ColumnId, ColumnType};
+
+    fn zstd_column(data: &[u8]) -> (RawColumns<compression::Unknown>, Vec<u8>) {
+        // the zstd frame magic number followed by some garbage
+        let mut bytes = vec![0x28, 0xb5, 0x2f, 0xfd];
+        bytes.extend(data);
+        let spec = ColumnSpec::new(ColumnId::new(1), ColumnType::String, false).zstd_compressed();
+        let cols = [(spec, 0..bytes.len())].into_iter().collect();
+        (cols, bytes)
+    }
+
+    #[test]
+    fn zstd_columns_are_not_valid_deflate_streams() {
+        // This is what readers which predate zstd do with a zstd column
+        let (_, bytes) = zstd_column(b"abcdefgh");
+        let mut inflater = flate2::bufread::DeflateDecoder::new(&bytes[..]);
+        assert!(inflater.read_to_end(&mut Vec::new()).is_err());
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn zstd_columns_are_rejected_without_the_zstd_feature() {
+        let (cols, bytes) = zstd_column(b"abcdefgh");
+        assert!(matches!(
+            cols.uncompress(&bytes, &mut Vec::new()),
+            Err(ParseError::UnsupportedZstd)
+        ));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_columns_round_trip() {
+        let data = b"hello hello hello hello hello hello hello hello".repeat(10);
+        let spec = ColumnSpec::new(ColumnId::new(1), ColumnType::String, false);
+        let cols = RawColumns(vec![RawColumn::new(spec, 0..data.len())]);
+        let mut compressed = Vec::new();
+        let zstd_cols = cols.compress(&data, &mut compressed, 64, Codec::Zstd(3));
+        assert!(zstd_cols.iter().all(|c| c.spec().zstd()));
+        assert!(compressed.len() < data.len());
+
+        let mut decompressed = Vec::new();
+        let uncompressed = zstd_cols
+            .uncompress(&compressed, &mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+        assert_eq!(uncompressed, cols);
+    }
+}
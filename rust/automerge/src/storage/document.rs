@@ -1,6 +1,6 @@
 use std::{borrow::Cow, ops::Range};
 
-use super::{parse, shift_range, ChunkType, Columns, Header, RawColumns};
+use super::{parse, shift_range, ChunkType, Codec, ColumnSpec, Columns, Header, RawColumns};
 
 use crate::change_graph::ChangeGraph;
 use crate::op_set2::OpSet;
@@ -17,7 +17,8 @@ mod compression;
 #[allow(dead_code)]
 pub(crate) enum CompressConfig {
     None,
-    Threshold(usize),
+    /// Compress columns which are at least this many bytes long with the given codec
+    Threshold(usize, Codec),
 }
 
 #[derive(Debug, Clone)]
@@ -33,6 +34,7 @@ pub(crate) struct Document<'a> {
     op_bytes: Range<usize>,
     change_metadata: DocChangeColumns,
     change_bytes: Range<usize>,
+    unknown_columns: Vec<ColumnSpec>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -50,6 +52,8 @@ pub(crate) enum ParseError {
     BadDocOps(#[from] doc_op_columns::Error),
     #[error(transparent)]
     BadDocChanges(#[from] doc_change_columns::ReadChangeError),
+    #[error("document chunk contains compacted changes but is not marked as compacted")]
+    UnmarkedCompaction,
 }
 
 impl<'a> Document<'a> {
@@ -173,6 +177,13 @@ impl<'a> Document<'a> {
         })?;
         let change_cols = DocChangeColumns::try_from(change_layout)
             .map_err(|e| parse::ParseError::Error(e.into()))?;
+        if change_cols.hash.is_some() && header.chunk_type() != ChunkType::CompactedDocument {
+            return Err(parse::ParseError::Error(ParseError::UnmarkedCompaction));
+        }
+        let unknown_columns = ops_cols
+            .unknown_columns()
+            .chain(change_cols.other.specs())
+            .collect();
 
         Ok((
             i,
@@ -186,6 +197,7 @@ impl<'a> Document<'a> {
                 op_bytes,
                 change_metadata: change_cols,
                 change_bytes,
+                unknown_columns,
             },
         ))
     }
@@ -233,7 +245,12 @@ impl<'a> Document<'a> {
             leb128::write::unsigned(&mut data, *index).unwrap();
         }
 
-        let header = Header::new(ChunkType::Document, &data);
+        let chunk_type = if change_meta.hash.is_some() {
+            ChunkType::CompactedDocument
+        } else {
+            ChunkType::Document
+        };
+        let header = Header::new(chunk_type, &data);
         let mut bytes = Vec::with_capacity(data.len() + header.len());
         header.write(&mut bytes);
         let header_len = bytes.len();
@@ -242,7 +259,7 @@ impl<'a> Document<'a> {
         let op_bytes = shift_range(ops_start..ops_end, header.len());
         let change_bytes = shift_range(change_start..change_end, header.len());
 
-        let compressed_bytes = if let CompressConfig::Threshold(threshold) = compress {
+        let compressed_bytes = if let CompressConfig::Threshold(threshold, codec) = compress {
             let compressed = Cow::Owned(compression::compress(compression::Args {
                 prefix: prefix_len + header.len(),
                 suffix: suffix_start + header.len(),
@@ -257,7 +274,9 @@ impl<'a> Document<'a> {
                 original: Cow::Borrowed(&bytes),
                 extra_args: compression::CompressArgs {
                     threshold,
+                    codec,
                     original_header_len: header_len,
+                    chunk_type,
                 },
             }));
             Some(compressed)
@@ -275,6 +294,7 @@ impl<'a> Document<'a> {
             op_bytes,
             change_metadata: change_meta,
             change_bytes,
+            unknown_columns: Vec::new(),
         }
     }
 
@@ -316,6 +336,12 @@ impl<'a> Document<'a> {
     pub(crate) fn heads(&self) -> &[ChangeHash] {
         &self.heads
     }
+
+    /// The specifications of any op or change columns in this document which this version of
+    /// automerge doesn't understand
+    pub(crate) fn unknown_columns(&self) -> &[ColumnSpec] {
+        &self.unknown_columns
+    }
 }
 
 /*
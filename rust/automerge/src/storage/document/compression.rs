@@ -22,13 +22,17 @@ pub(super) struct Args<'a, T: compression::ColumnCompression, DirArgs> {
 
 pub(super) struct CompressArgs {
     pub(super) threshold: usize,
+    pub(super) codec: raw_column::Codec,
     pub(super) original_header_len: usize,
+    pub(super) chunk_type: ChunkType,
 }
 
 /// Compress a document chunk returning the compressed bytes
 pub(super) fn compress(args: Args<'_, compression::Uncompressed, CompressArgs>) -> Vec<u8> {
     let header_len = args.extra_args.original_header_len;
     let threshold = args.extra_args.threshold;
+    let codec = args.extra_args.codec;
+    let chunk_type = args.extra_args.chunk_type;
     // Wrap in a closure so we can use `?` in the construction but still force the compiler
     // to check that the error type is `Infallible`
     let result: Result<_, Infallible> = (|| {
@@ -36,7 +40,9 @@ pub(super) fn compress(args: Args<'_, compression::Uncompressed, CompressArgs>)
             args,
             Compressing {
                 threshold,
+                codec,
                 header_len,
+                chunk_type,
             },
         )
         .changes()?
@@ -133,7 +139,9 @@ trait Direction: std::fmt::Debug {
 #[derive(Debug)]
 struct Compressing {
     threshold: usize,
+    codec: raw_column::Codec,
     header_len: usize,
+    chunk_type: ChunkType,
 }
 
 impl Direction for Compressing {
@@ -150,9 +158,9 @@ impl Direction for Compressing {
         meta_out: &mut Vec<u8>,
     ) -> Result<Cols<Self::Out>, Self::Error> {
         let start = out.len();
-        let raw_columns = cols
-            .raw_columns
-            .compress(&input[cols.data.clone()], out, self.threshold);
+        let raw_columns =
+            cols.raw_columns
+                .compress(&input[cols.data.clone()], out, self.threshold, self.codec);
         raw_columns.write(meta_out);
         Ok(Cols {
             data: start..out.len(),
@@ -353,7 +361,7 @@ impl Compression<'_, Compressing, Finished<Compressing>> {
     fn finish(self) -> Vec<u8> {
         let Finished { out, .. } = self.state;
         let headerless = &out[self.direction.header_len..];
-        let header = Header::new(ChunkType::Document, headerless);
+        let header = Header::new(self.direction.chunk_type, headerless);
         let mut result = Vec::with_capacity(header.len() + out.len());
         header.write(&mut result);
         result.extend(headerless);
@@ -14,7 +14,7 @@ use crate::{
         columns::{compression, ColumnId, ColumnSpec, ColumnType},
         Columns, MismatchingColumn, RawColumn, RawColumns,
     },
-    types::ScalarValue,
+    types::{ChangeHash, ScalarValue},
 };
 
 const ACTOR_COL_ID: ColumnId = ColumnId::new(0);
@@ -24,6 +24,7 @@ const TIME_COL_ID: ColumnId = ColumnId::new(2);
 const MESSAGE_COL_ID: ColumnId = ColumnId::new(3);
 const DEPS_COL_ID: ColumnId = ColumnId::new(4);
 const EXTRA_COL_ID: ColumnId = ColumnId::new(5);
+const HASH_COL_ID: ColumnId = ColumnId::new(6);
 
 #[derive(Debug, Clone)]
 pub(crate) struct DocChangeMetadata<'a> {
@@ -35,6 +36,8 @@ pub(crate) struct DocChangeMetadata<'a> {
     pub(crate) message: Option<Cow<'a, str>>,
     pub(crate) deps: Vec<u64>,
     pub(crate) extra: Cow<'a, [u8]>,
+    /// The hash of a change whose ops have been compacted away, see [`crate::Automerge::compact`]
+    pub(crate) hash: Option<ChangeHash>,
 }
 
 #[derive(Debug, Clone)]
@@ -46,6 +49,8 @@ pub(crate) struct DocChangeColumns {
     pub(crate) message: RleRange<smol_str::SmolStr>,
     pub(crate) deps: DepsRange,
     pub(crate) extra: ValueRange,
+    /// Only present in documents which have been compacted
+    pub(crate) hash: Option<ValueRange>,
     #[allow(dead_code)]
     pub(crate) other: Columns,
 }
@@ -69,6 +74,9 @@ impl DocChangeColumns {
             extra: ExtraDecoder {
                 val: self.extra.iter(data),
             },
+            hash: self.hash.as_ref().map(|hash| HashDecoder {
+                val: hash.iter(data),
+            }),
         }
     }
 
@@ -115,6 +123,18 @@ impl DocChangeColumns {
                 self.extra.raw_range().clone().into(),
             ))
         }
+        if let Some(hash) = &self.hash {
+            cols.push(RawColumn::new(
+                ColumnSpec::new(HASH_COL_ID, ColumnType::ValueMetadata, false),
+                hash.meta_range().clone().into(),
+            ));
+            if !hash.raw_range().is_empty() {
+                cols.push(RawColumn::new(
+                    ColumnSpec::new(HASH_COL_ID, ColumnType::Value, false),
+                    hash.raw_range().clone().into(),
+                ))
+            }
+        }
         cols.into_iter().collect()
     }
 }
@@ -127,6 +147,8 @@ pub(crate) enum ReadChangeError {
     MismatchingColumn { index: usize },
     #[error("incorrect value in extra bytes column")]
     InvalidExtraBytes,
+    #[error("incorrect value in hash column")]
+    InvalidHash,
     #[error("max_op is lower than start_op")]
     InvalidMaxOp,
     #[error(transparent)]
@@ -151,6 +173,7 @@ pub(crate) struct DocChangeColumnIter<'a> {
     message: CursorIter<'a, StrCursor>,
     deps: DepsIter<'a>,
     extra: ExtraDecoder<'a>,
+    hash: Option<HashDecoder<'a>>,
 }
 
 impl<'a> DocChangeColumnIter<'a> {
@@ -179,6 +202,10 @@ impl<'a> DocChangeColumnIter<'a> {
         let message = self.message.next().transpose()?.flatten();
         let deps = self.deps.next_in_col("deps")?;
         let extra = self.extra.next().transpose()?.unwrap_or(Cow::Borrowed(&[]));
+        let hash = match &mut self.hash {
+            Some(hash) => hash.next().transpose()?.flatten(),
+            None => None,
+        };
         Ok(Some(DocChangeMetadata {
             actor,
             seq,
@@ -187,6 +214,7 @@ impl<'a> DocChangeColumnIter<'a> {
             message,
             deps,
             extra,
+            hash,
         }))
     }
 }
@@ -228,6 +256,28 @@ impl<'a> Iterator for ExtraDecoder<'a> {
     }
 }
 
+#[derive(Clone)]
+struct HashDecoder<'a> {
+    val: ValueIter<'a>,
+}
+
+impl Iterator for HashDecoder<'_> {
+    type Item = Result<Option<ChangeHash>, ReadChangeError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.val.next() {
+            Some(Ok(ScalarValue::Bytes(b))) => Some(
+                ChangeHash::try_from(b.as_slice())
+                    .map(Some)
+                    .map_err(|_| ReadChangeError::InvalidHash),
+            ),
+            Some(Ok(ScalarValue::Null)) => Some(Ok(None)),
+            Some(Ok(_)) => Some(Err(ReadChangeError::InvalidHash)),
+            Some(Err(e)) => Some(Err(e.into())),
+            None => None,
+        }
+    }
+}
+
 impl TryFrom<Columns> for DocChangeColumns {
     type Error = ReadChangeError;
 
@@ -239,6 +289,7 @@ impl TryFrom<Columns> for DocChangeColumns {
         let mut message: Option<RleRange<smol_str::SmolStr>> = None;
         let mut deps: Option<DepsRange> = None;
         let mut extra: Option<ValueRange> = None;
+        let mut hash: Option<ValueRange> = None;
         let mut other = Columns::empty();
 
         for (index, col) in columns.into_iter().enumerate() {
@@ -278,6 +329,12 @@ impl TryFrom<Columns> for DocChangeColumns {
                     }
                     _ => return Err(ReadChangeError::MismatchingColumn { index }),
                 },
+                (HASH_COL_ID, ColumnType::ValueMetadata) => match col.into_ranges() {
+                    GenericColumnRange::Value(val) => {
+                        hash = Some(val);
+                    }
+                    _ => return Err(ReadChangeError::MismatchingColumn { index }),
+                },
                 (other_id, other_type) => {
                     tracing::warn!(id=?other_id, typ=?other_type, "unknown column");
                     other.append(col);
@@ -292,6 +349,7 @@ impl TryFrom<Columns> for DocChangeColumns {
             message: message.unwrap_or_else(|| (0..0).into()),
             deps: deps.unwrap_or_else(|| DepsRange::new((0..0).into(), (0..0).into())),
             extra: extra.unwrap_or_else(|| ValueRange::new((0..0).into(), (0..0).into())),
+            hash,
             other,
         })
     }
@@ -31,13 +31,17 @@ pub(crate) struct DocOpColumns {
     action: RleRange<u64>,
     val: ValueRange,
     succ: OpIdListRange,
-    #[allow(dead_code)]
     other: Columns,
     expand: MaybeBooleanRange,
     mark_name: RleRange<smol_str::SmolStr>,
 }
 
 impl DocOpColumns {
+    /// The specifications of any columns which this version of automerge doesn't understand
+    pub(crate) fn unknown_columns(&self) -> impl Iterator<Item = ColumnSpec> + '_ {
+        self.other.specs()
+    }
+
     pub(crate) fn raw_columns(&self) -> RawColumns<compression::Uncompressed> {
         let mut cols = vec![
             RawColumn::new(
@@ -0,0 +1,400 @@
+//! Inspect the chunks of a saved document without loading it
+//!
+//! [`crate::Automerge::load`] stops at the first chunk it can't load. [`inspect`] instead reports
+//! on every chunk in the data, which is useful for diagnosing and salvaging corrupt documents.
+//!
+//! ```
+//! # use automerge::{AutoCommit, ROOT, transaction::Transactable, inspect};
+//! let mut doc = AutoCommit::new();
+//! doc.put(ROOT, "key", "value").unwrap();
+//! let mut saved = doc.save();
+//! saved.extend_from_slice(b"garbage");
+//!
+//! let chunks = inspect::inspect(&saved);
+//! assert_eq!(chunks.len(), 2);
+//! assert_eq!(chunks[0].kind, Some(inspect::ChunkKind::Document));
+//! assert_eq!(chunks[0].changes.len(), 1);
+//! assert!(chunks[1].kind.is_none());
+//! ```
+use std::{collections::BTreeSet, ops::Range};
+
+use super::{
+    chunk,
+    columns::{compression, ColumnType},
+    document, load, parse, Chunk, ChunkType, ColumnSpec, Header, RawColumns, MAGIC_BYTES,
+};
+use crate::{change::Change, ChangeHash, LoadMode, TextEncoding, VerificationMode};
+
+/// The kinds of chunk a saved document is made of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkKind {
+    /// A compacted document containing many changes
+    Document,
+    /// A document from which the ops of old changes have been removed with
+    /// [`crate::Automerge::compact`]. Versions of automerge from before compaction can't load it.
+    CompactedDocument,
+    /// A single change
+    Change,
+    /// A single deflated change
+    CompressedChange,
+}
+
+/// Something wrong with a chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The chunk could not be parsed
+    Corrupt(String),
+    /// The checksum in the chunk header doesn't match the contents of the chunk
+    BadChecksum,
+    /// The heads stored in a document chunk are not the heads of the changes in it
+    MismatchedHeads {
+        expected: Vec<ChangeHash>,
+        derived: Vec<ChangeHash>,
+    },
+    /// The chunk contains a column which this version of automerge doesn't understand. The data
+    /// in the column is ignored when loading.
+    UnknownColumn { id: u32, col_type: String },
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Corrupt(e) => write!(f, "corrupt chunk: {}", e),
+            Self::BadChecksum => write!(f, "bad checksum"),
+            Self::MismatchedHeads { expected, derived } => write!(
+                f,
+                "mismatched heads: expected {:?}, derived {:?}",
+                expected, derived
+            ),
+            Self::UnknownColumn { id, col_type } => {
+                write!(f, "unknown column {} of type {}", id, col_type)
+            }
+        }
+    }
+}
+
+/// The parts of a chunk which columns belong to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Section {
+    /// The metadata of the changes in a document chunk
+    Changes,
+    /// The ops of a document or change chunk
+    Ops,
+}
+
+/// The size of one column of a chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnSize {
+    pub section: Section,
+    /// A name for the column such as "key string" or "value"
+    pub name: String,
+    /// The number of bytes the column occupies in the chunk
+    pub bytes: usize,
+    /// Whether the column is compressed with DEFLATE
+    pub deflated: bool,
+    /// Whether the column is compressed with zstd
+    pub zstd: bool,
+}
+
+/// What [`inspect`] found in one chunk
+#[derive(Debug, Clone)]
+pub struct ChunkReport {
+    /// The range of bytes in the input which the chunk occupies
+    pub range: Range<usize>,
+    /// The kind of the chunk, or `None` if even its header couldn't be parsed
+    pub kind: Option<ChunkKind>,
+    /// The changes which could be loaded from the chunk
+    pub changes: Vec<Change>,
+    /// The hashes of the changes whose ops a compacted document chunk no longer holds, these are
+    /// part of the document's history but can't be loaded as changes
+    pub compacted: Vec<ChangeHash>,
+    /// Everything which is wrong with the chunk
+    pub problems: Vec<Problem>,
+    /// The sizes of the columns in the chunk, if it could be parsed. The columns of compressed
+    /// change chunks are measured after decompressing the chunk.
+    pub columns: Vec<ColumnSize>,
+}
+
+/// Report on every chunk in `data`
+///
+/// A chunk whose header can be parsed is skipped using the length in the header if its contents
+/// are corrupt. If the header itself is corrupt then everything up to the next magic bytes is
+/// reported as one corrupt chunk.
+pub fn inspect(data: &[u8]) -> Vec<ChunkReport> {
+    let mut reports = Vec::new();
+    let mut start = 0;
+    while start < data.len() {
+        let rest = &data[start..];
+        let report = match Header::parse::<chunk::error::Header>(parse::Input::new(rest)) {
+            Ok((_, header)) => {
+                let range = start..start + header.data_bytes().end;
+                inspect_chunk(&data[range.clone()], range, &header)
+            }
+            Err(e) => {
+                let end = rest
+                    .windows(MAGIC_BYTES.len())
+                    .skip(1)
+                    .position(|w| w == MAGIC_BYTES)
+                    .map(|pos| start + pos + 1)
+                    .unwrap_or(data.len());
+                ChunkReport {
+                    range: start..end,
+                    kind: None,
+                    changes: Vec::new(),
+                    compacted: Vec::new(),
+                    problems: vec![Problem::Corrupt(e.to_string())],
+                    columns: Vec::new(),
+                }
+            }
+        };
+        start = report.range.end;
+        reports.push(report);
+    }
+    reports
+}
+
+fn inspect_chunk(bytes: &[u8], range: Range<usize>, header: &Header) -> ChunkReport {
+    let kind = match header.chunk_type() {
+        ChunkType::Document => ChunkKind::Document,
+        ChunkType::CompactedDocument => ChunkKind::CompactedDocument,
+        ChunkType::Change => ChunkKind::Change,
+        ChunkType::Compressed => ChunkKind::CompressedChange,
+    };
+    let mut report = ChunkReport {
+        range,
+        kind: Some(kind),
+        changes: Vec::new(),
+        compacted: Vec::new(),
+        problems: Vec::new(),
+        columns: Vec::new(),
+    };
+    let chunk = match Chunk::parse(parse::Input::new(bytes)) {
+        Ok((_, chunk)) => chunk,
+        Err(e) => {
+            report.problems.push(Problem::Corrupt(e.to_string()));
+            return report;
+        }
+    };
+    let unknown_columns = match &chunk {
+        Chunk::Document(d) => {
+            report.columns = stored_doc_columns(&bytes[header.data_bytes()]).unwrap_or_default();
+            d.unknown_columns()
+        }
+        Chunk::Change(c) | Chunk::CompressedChange(c, _) => {
+            report.columns = column_sizes(Section::Ops, &c.ops_meta.raw_columns());
+            c.ops_meta.unknown_columns()
+        }
+    };
+    report
+        .problems
+        .extend(unknown_columns.iter().map(unknown_column));
+    if !chunk.checksum_valid() {
+        report.problems.push(Problem::BadChecksum);
+        return report;
+    }
+    let loaded = match chunk {
+        Chunk::Document(d) => load::reconstruct_opset(
+            &d,
+            VerificationMode::DontCheck,
+            TextEncoding::default(),
+            LoadMode::Lazy,
+        )
+        .map_err(|e| e.to_string())
+        .map(|recon| {
+            let expected = d.heads().iter().copied().collect::<BTreeSet<_>>();
+            if expected != recon.heads {
+                report.problems.push(Problem::MismatchedHeads {
+                    expected: expected.into_iter().collect(),
+                    derived: recon.heads.into_iter().collect(),
+                });
+            }
+            let graph = &recon.change_graph;
+            report.compacted = (0..graph.len())
+                .filter(|&index| graph.is_compacted(index))
+                .filter_map(|index| graph.index_to_hash(index).copied())
+                .collect();
+            recon.changes
+        }),
+        Chunk::Change(c) => Change::new_from_unverified(c.into_owned(), None)
+            .map(|c| vec![c])
+            .map_err(|e| e.to_string()),
+        Chunk::CompressedChange(c, compressed) => {
+            Change::new_from_unverified(c.into_owned(), Some(compressed.into_owned()))
+                .map(|c| vec![c])
+                .map_err(|e| e.to_string())
+        }
+    };
+    match loaded {
+        Ok(changes) => report.changes = changes,
+        Err(e) => report.problems.push(Problem::Corrupt(e)),
+    }
+    report
+}
+
+/// The sizes of the columns of a document chunk as they are stored, before any decompression.
+/// `data` is the chunk without its header.
+fn stored_doc_columns(data: &[u8]) -> Option<Vec<ColumnSize>> {
+    let parsed = (|| -> parse::ParseResult<'_, _, document::ParseError> {
+        let i = parse::Input::new(data);
+        let (i, _actors) = parse::length_prefixed(parse::actor_id)(i)?;
+        let (i, _heads) = parse::length_prefixed(parse::change_hash)(i)?;
+        let (i, change_meta) = RawColumns::parse(i)?;
+        let (i, ops_meta) = RawColumns::parse(i)?;
+        Ok((i, (change_meta, ops_meta)))
+    })();
+    let (_, (change_meta, ops_meta)) = parsed.ok()?;
+    let mut sizes = column_sizes(Section::Changes, &change_meta);
+    sizes.extend(column_sizes(Section::Ops, &ops_meta));
+    Some(sizes)
+}
+
+fn column_sizes<T: compression::ColumnCompression>(
+    section: Section,
+    columns: &RawColumns<T>,
+) -> Vec<ColumnSize> {
+    columns
+        .iter()
+        .map(|col| ColumnSize {
+            section,
+            name: column_name(section, col.spec()),
+            bytes: col.data().len(),
+            deflated: col.spec().deflate() && !col.spec().zstd(),
+            zstd: col.spec().zstd(),
+        })
+        .collect()
+}
+
+/// Name a column after the field of the change or op it stores
+fn column_name(section: Section, spec: ColumnSpec) -> String {
+    let id = u32::from(spec.id());
+    let name = match (section, id, spec.col_type()) {
+        (Section::Changes, 0, ColumnType::Actor) => "actor",
+        (Section::Changes, 0, ColumnType::DeltaInteger) => "seq",
+        (Section::Changes, 1, ColumnType::DeltaInteger) => "max op",
+        (Section::Changes, 2, ColumnType::DeltaInteger) => "time",
+        (Section::Changes, 3, ColumnType::String) => "message",
+        (Section::Changes, 4, ColumnType::Group) => "deps count",
+        (Section::Changes, 4, ColumnType::DeltaInteger) => "deps index",
+        (Section::Changes, 5, ColumnType::ValueMetadata) => "extra metadata",
+        (Section::Changes, 5, ColumnType::Value) => "extra",
+        (Section::Ops, 0, ColumnType::Actor) => "obj actor",
+        (Section::Ops, 0, ColumnType::Integer) => "obj counter",
+        (Section::Ops, 1, ColumnType::Actor) => "key actor",
+        (Section::Ops, 1, ColumnType::DeltaInteger) => "key counter",
+        (Section::Ops, 1, ColumnType::String) => "key string",
+        (Section::Ops, 2, ColumnType::Actor) => "id actor",
+        (Section::Ops, 2, ColumnType::DeltaInteger) => "id counter",
+        (Section::Ops, 3, ColumnType::Boolean) => "insert",
+        (Section::Ops, 4, ColumnType::Integer) => "action",
+        (Section::Ops, 5, ColumnType::ValueMetadata) => "value metadata",
+        (Section::Ops, 5, ColumnType::Value) => "value",
+        (Section::Ops, 7, ColumnType::Group) => "pred count",
+        (Section::Ops, 7, ColumnType::Actor) => "pred actor",
+        (Section::Ops, 7, ColumnType::DeltaInteger) => "pred counter",
+        (Section::Ops, 8, ColumnType::Group) => "succ count",
+        (Section::Ops, 8, ColumnType::Actor) => "succ actor",
+        (Section::Ops, 8, ColumnType::DeltaInteger) => "succ counter",
+        (Section::Ops, 9, ColumnType::Boolean) => "expand",
+        (Section::Ops, 10, ColumnType::String) => "mark name",
+        (_, id, col_type) => return format!("column {} ({})", id, col_type),
+    };
+    name.to_string()
+}
+
+fn unknown_column(spec: &ColumnSpec) -> Problem {
+    Problem::UnknownColumn {
+        id: u32::from(spec.id()),
+        col_type: spec.col_type().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{transaction::Transactable, AutoCommit, ROOT};
+
+    fn doc_with_changes() -> (Vec<u8>, Vec<u8>) {
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, "a", 1).unwrap();
+        let saved = doc.save();
+        let heads = doc.get_heads();
+        doc.put(ROOT, "b", 2).unwrap();
+        (saved, doc.save_after(&heads))
+    }
+
+    #[test]
+    fn reports_every_chunk() {
+        let (saved, change) = doc_with_changes();
+        let mut data = saved.clone();
+        data.extend_from_slice(&change);
+        let reports = inspect(&data);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].range, 0..saved.len());
+        assert_eq!(reports[0].kind, Some(ChunkKind::Document));
+        assert_eq!(reports[1].kind, Some(ChunkKind::Change));
+        assert!(reports.iter().all(|r| r.problems.is_empty()));
+        assert!(reports.iter().all(|r| r.changes.len() == 1));
+
+        let value = |report: &ChunkReport| {
+            report
+                .columns
+                .iter()
+                .find(|c| c.section == Section::Ops && c.name == "value")
+                .map(|c| c.bytes)
+        };
+        assert_eq!(value(&reports[0]), Some(1));
+        assert_eq!(value(&reports[1]), Some(1));
+        assert!(reports[0]
+            .columns
+            .iter()
+            .any(|c| c.section == Section::Changes && c.name == "actor"));
+    }
+
+    #[test]
+    fn skips_past_corrupt_chunks() {
+        let (saved, change) = doc_with_changes();
+        // Corrupt the contents of the document chunk and put junk before the change chunk
+        let mut data = saved.clone();
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        data.extend_from_slice(&[1, 2, 3]);
+        data.extend_from_slice(&change);
+        let reports = inspect(&data);
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports[0].kind, Some(ChunkKind::Document));
+        assert!(reports[0].changes.is_empty());
+        assert!(!reports[0].problems.is_empty());
+        assert_eq!(reports[1].range, saved.len()..saved.len() + 3);
+        assert_eq!(reports[1].kind, None);
+        assert_eq!(reports[2].changes.len(), 1);
+        assert!(reports[2].problems.is_empty());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn names_zstd_columns() {
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(ROOT, "text", crate::ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, &"some text ".repeat(100))
+            .unwrap();
+        let saved = doc.save_with_options(
+            crate::SaveOptions::new()
+                .compression(crate::Compression::Zstd { level: 3 })
+                .compression_threshold(0),
+        );
+        let reports = inspect(&saved);
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].problems.is_empty());
+        let value = reports[0]
+            .columns
+            .iter()
+            .find(|c| c.section == Section::Ops && c.name == "value")
+            .unwrap();
+        assert!(value.zstd);
+        assert!(!value.deflated);
+        assert!(reports[0]
+            .columns
+            .iter()
+            .all(|c| !c.name.starts_with("column ")));
+    }
+}
@@ -1,6 +1,7 @@
 use tracing::instrument;
 
 use crate::{
+    automerge::LoadMode,
     change::Change,
     change_graph::ChangeGraph,
     storage::{self, parse},
@@ -89,9 +90,14 @@ fn load_next_change<'a>(
         storage::Chunk::Document(d) => {
             tracing::trace!("loading document chunk");
             if !d.heads().iter().all(|h| current.has_change(h)) {
-                let new_changes = reconstruct_opset(&d, VerificationMode::DontCheck, text_encoding)
-                    .map_err(|e| Error::InflateDocument(Box::new(e)))?
-                    .changes;
+                let new_changes = reconstruct_opset(
+                    &d,
+                    VerificationMode::DontCheck,
+                    text_encoding,
+                    LoadMode::Lazy,
+                )
+                .map_err(|e| Error::InflateDocument(Box::new(e)))?
+                .changes;
                 changes.extend(new_changes);
             }
         }
@@ -10,4 +10,6 @@ pub(crate) enum Error {
     MissingOps,
     #[error("missing ops")]
     MissingDep(#[from] crate::change_graph::MissingDep),
+    #[error("compacted change {0} is inconsistent with the rest of the history")]
+    InvalidCompactedChange(crate::ChangeHash),
 }
@@ -5,6 +5,7 @@ use std::collections::BTreeSet;
 
 use crate::types::TextEncoding;
 use crate::{
+    automerge::LoadMode,
     change::Change,
     op_set2::{OpSet, PackError, ReadOpError},
     storage::Document,
@@ -60,11 +61,15 @@ pub(crate) fn reconstruct_opset<'a>(
     doc: &'a Document<'a>,
     mode: VerificationMode,
     text_encoding: TextEncoding,
+    load_mode: LoadMode,
 ) -> Result<ReconOpSet, Error> {
     let mut op_set = OpSet::from_doc(doc, text_encoding)?;
     let mut change_collector = ChangeCollector::new(doc.iter_changes())?;
     let mut iter = op_set.iter();
-    let mut index_builder = op_set.index_builder();
+    let mut index_builder = match load_mode {
+        LoadMode::Eager => op_set.index_builder(),
+        LoadMode::Lazy => op_set.index_builder().lazy(),
+    };
     let mut stepper = Default::default();
     let mut _ordered = true;
 
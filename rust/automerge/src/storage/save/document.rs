@@ -3,7 +3,7 @@ use std::iter::Iterator;
 use crate::{
     change_graph::ChangeGraph,
     op_set2::OpSet,
-    storage::{change::DEFLATE_MIN_SIZE, CompressConfig, Document},
+    storage::{change::DEFLATE_MIN_SIZE, Codec, CompressConfig, Document},
 };
 
 /// # Panics
@@ -19,7 +19,7 @@ pub(crate) fn save_document(
 ) -> Vec<u8> {
     assert_eq!(op_set.actors.len(), change_graph.actor_ids().count());
 
-    let config = config.unwrap_or(CompressConfig::Threshold(DEFLATE_MIN_SIZE));
+    let config = config.unwrap_or(CompressConfig::Threshold(DEFLATE_MIN_SIZE, Codec::Deflate));
 
     let doc = Document::new(op_set, change_graph, config);
 
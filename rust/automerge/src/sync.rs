@@ -74,7 +74,7 @@ use std::collections::{HashMap, HashSet};
 use crate::{
     patches::{PatchLog, TextRepresentation},
     storage::{parse, ReadChangeOpError},
-    Automerge, AutomergeError, Change, ChangeHash, ReadDoc,
+    Automerge, AutomergeError, Change, ChangeHash, Compression, ReadDoc, SaveOptions,
 };
 
 mod bloom;
@@ -183,17 +183,14 @@ impl SyncDoc for Automerge {
                 if !first_have
                     .last_sync
                     .iter()
-                    .all(|hash| self.get_change_by_hash(hash).is_some())
+                    .all(|hash| self.has_change(hash))
                 {
                     let reset_msg = Message {
                         heads: our_heads,
                         need: Vec::new(),
                         have: vec![Have::default()],
                         changes: ChunkList::empty(),
-                        supported_capabilities: Some(vec![
-                            Capability::MessageV1,
-                            Capability::MessageV2,
-                        ]),
+                        supported_capabilities: Some(Capability::ours()),
                         version: MessageVersion::V1,
                     };
                     return Some(reset_msg);
@@ -205,12 +202,14 @@ impl SyncDoc for Automerge {
             sync_state.their_have.as_ref(),
             sync_state.their_need.as_ref(),
         ) {
+            // a peer with no changes can't be sent compacted changes one at a time, so it gets the
+            // whole document however far the sync has got
             let send_doc = sync_state
                 .their_heads
                 .as_ref()
                 .map(|h| h.is_empty())
                 .unwrap_or(false)
-                && !sync_state.have_responded
+                && (!sync_state.have_responded || self.change_graph.has_compacted())
                 && sync_state.supports_v2_messages();
 
             if send_doc {
@@ -219,7 +218,15 @@ impl SyncDoc for Automerge {
                     .iter()
                     .map(|c| c.hash())
                     .collect::<Vec<_>>();
-                (MessageBuilder::new_v2(self.save()), hashes)
+                // compress the document with the best algorithm the peer can load
+                let compression = Compression::negotiate(
+                    sync_state.their_capabilities.as_deref().unwrap_or_default(),
+                );
+                let options = SaveOptions::new().compression(compression);
+                (
+                    MessageBuilder::new_v2(self.save_with_options(options)),
+                    hashes,
+                )
             } else {
                 let all_changes = self
                     .get_changes_to_send(their_have, their_need)
@@ -267,7 +274,7 @@ impl SyncDoc for Automerge {
         let supported_capabilities = if sync_state.have_responded {
             None
         } else {
-            Some(vec![Capability::MessageV1, Capability::MessageV2])
+            Some(Capability::ours())
         };
 
         sync_state.have_responded = true;
@@ -306,8 +313,9 @@ impl SyncDoc for Automerge {
 
 impl Automerge {
     fn make_bloom_filter(&self, last_sync: Vec<ChangeHash>) -> Have {
-        let new_changes = self.get_changes(&last_sync);
-        let hashes = new_changes.iter().map(|change| change.hash());
+        // the metadata includes compacted changes, which `get_changes` leaves out
+        let new_changes = self.get_changes_meta(&last_sync);
+        let hashes = new_changes.iter().map(|change| change.hash);
         Have {
             last_sync,
             bloom: BloomFilter::from_hashes(hashes),
@@ -428,7 +436,7 @@ impl Automerge {
 
         let known_heads = message_heads
             .iter()
-            .filter(|head| self.get_change_by_hash(head).is_some())
+            .filter(|head| self.has_change(head))
             .collect::<Vec<_>>();
         if known_heads.len() == message_heads.len() {
             sync_state.shared_heads.clone_from(&message_heads);
@@ -448,6 +456,15 @@ impl Automerge {
                 .collect::<Vec<_>>();
         }
 
+        // a peer which is missing compacted changes and can't be sent the whole document was
+        // behind the heads passed to `compact`, there is no way to catch it up
+        let gets_doc = message_heads.is_empty() && sync_state.supports_v2_messages();
+        if let Some(hash) = message_need.iter().find(|h| self.is_compacted(h)) {
+            if !gets_doc {
+                return Err(AutomergeError::CompactedChange(*hash));
+            }
+        }
+
         sync_state.their_have = Some(message_have);
         sync_state.their_heads = Some(message_heads);
         sync_state.their_need = Some(message_need);
@@ -516,7 +533,8 @@ impl From<parse::ParseError<ReadMessageError>> for ReadMessageError {
 /// implementations this appended data is just ignored but new implementations read it and store
 /// the advertised capabilities on the sync state. This allows new implementations to discover if
 /// the remote peer supports the V2 message format (the `Capability::MessageV2` capability) and if
-/// so send a V2 message.
+/// so send a V2 message. When the V2 message carries the whole document it is compressed with zstd
+/// if the remote peer advertised the `Capability::Zstd` capability, and with DEFLATE otherwise.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Message {
     /// The heads of the sender.
@@ -670,14 +688,27 @@ pub enum Capability {
     #[default]
     MessageV1,
     MessageV2,
+    /// Can load documents whose columns are compressed with zstd, which is advertised by builds
+    /// with the `zstd` feature
+    Zstd,
     Unknown(u8),
 }
 
 impl Capability {
+    /// The capabilities this build of automerge advertises to its peers
+    pub fn ours() -> Vec<Capability> {
+        let mut caps = vec![Capability::MessageV1, Capability::MessageV2];
+        if cfg!(feature = "zstd") {
+            caps.push(Capability::Zstd);
+        }
+        caps
+    }
+
     fn encode(&self, out: &mut Vec<u8>) {
         match self {
             Capability::MessageV1 => out.push(0x01),
             Capability::MessageV2 => out.push(0x02),
+            Capability::Zstd => out.push(0x03),
             Capability::Unknown(v) => out.push(*v),
         }
     }
@@ -687,6 +718,7 @@ impl Capability {
         match v {
             0x01 => Ok((i, Self::MessageV1)),
             0x02 => Ok((i, Self::MessageV2)),
+            0x03 => Ok((i, Self::Zstd)),
             _ => Ok((i, Self::Unknown(v))),
         }
     }
@@ -1293,4 +1325,43 @@ mod tests {
         let (_, chunk) = Chunk::parse(Input::new(&changes.0[0])).unwrap();
         assert!(matches!(chunk, Chunk::Document(_)));
     }
+
+    fn whole_doc_response(their_capabilities: Vec<Capability>) -> Vec<u8> {
+        let mut doc1 = crate::AutoCommit::new();
+        let mut doc2 = crate::AutoCommit::new();
+        let text = doc2
+            .put_object(crate::ROOT, "text", crate::ObjType::Text)
+            .unwrap();
+        doc2.splice_text(&text, 0, 0, &"some text ".repeat(100))
+            .unwrap();
+
+        let mut s1 = State::new();
+        let mut s2 = State::new();
+        let mut outgoing = doc1.sync().generate_sync_message(&mut s1).unwrap();
+        outgoing.supported_capabilities = Some(their_capabilities);
+        doc2.sync().receive_sync_message(&mut s2, outgoing).unwrap();
+        let response = doc2.sync().generate_sync_message(&mut s2).unwrap();
+        let doc = response.changes.0[0].clone();
+
+        doc1.sync().receive_sync_message(&mut s1, response).unwrap();
+        assert_eq!(doc1.get_heads(), doc2.get_heads());
+        doc
+    }
+
+    #[test]
+    fn whole_doc_is_deflated_for_peers_without_zstd() {
+        let doc = whole_doc_response(vec![Capability::MessageV1, Capability::MessageV2]);
+        let reports = crate::storage::inspect::inspect(&doc);
+        assert!(reports[0].columns.iter().any(|c| c.deflated));
+        assert!(reports[0].columns.iter().all(|c| !c.zstd));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn whole_doc_is_zstd_compressed_for_peers_with_zstd() {
+        let doc = whole_doc_response(Capability::ours());
+        let reports = crate::storage::inspect::inspect(&doc);
+        assert!(reports[0].columns.iter().any(|c| c.zstd));
+        assert!(reports[0].columns.iter().all(|c| !c.deflated));
+    }
 }
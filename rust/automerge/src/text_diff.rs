@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use unicode_segmentation::UnicodeSegmentation;
 
 use crate::automerge::Automerge;
@@ -11,18 +13,121 @@ mod myers;
 mod replace;
 mod utils;
 
+/// The unit of text which [`crate::transaction::Transactable::update_text_with_options`] and
+/// [`crate::transaction::Transactable::update_spans_with_options`] diff over
+///
+/// Coarser granularities produce fewer, larger splices. These interleave better with concurrent
+/// edits when the new text is a substantial rewrite of the old text, at the cost of replacing
+/// some characters which did not actually change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextGranularity {
+    /// Diff extended grapheme clusters, this is the finest granularity
+    #[default]
+    Grapheme,
+    /// Diff words, as defined by the unicode word boundary rules
+    Word,
+    /// Diff lines, each line includes its terminating newline
+    Line,
+}
+
+impl TextGranularity {
+    fn coarser(self) -> Option<Self> {
+        match self {
+            Self::Grapheme => Some(Self::Word),
+            Self::Word => Some(Self::Line),
+            Self::Line => None,
+        }
+    }
+
+    fn segments(self, text: &str) -> Vec<&str> {
+        match self {
+            Self::Grapheme => text.graphemes(true).collect(),
+            Self::Word => text.split_word_bounds().collect(),
+            Self::Line => text.split_inclusive('\n').collect(),
+        }
+    }
+}
+
+/// Options controlling how a diff between the current and new text is converted into operations
+///
+/// The default is to diff graphemes without any limit on the size of the diff, which is what
+/// [`crate::transaction::Transactable::update_text`] does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UpdateTextOptions {
+    granularity: TextGranularity,
+    max_cost: Option<usize>,
+}
+
+impl UpdateTextOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The granularity to diff at
+    ///
+    /// The default is [`TextGranularity::Grapheme`]
+    pub fn granularity(self, granularity: TextGranularity) -> Self {
+        Self {
+            granularity,
+            ..self
+        }
+    }
+
+    /// The maximum number of inserted and deleted segments a diff may contain
+    ///
+    /// If the diff at the requested granularity is more expensive than this then the next coarser
+    /// granularity is tried. If even a line diff is too expensive then everything between the
+    /// common prefix and suffix of the old and new text is replaced in one go. Checking the cost
+    /// is `O((N+M) * max_cost)`, which also protects against the quadratic worst case of the
+    /// diff when the old and new text are completely different.
+    ///
+    /// The default is no limit
+    pub fn max_cost(self, max_cost: usize) -> Self {
+        Self {
+            max_cost: Some(max_cost),
+            ..self
+        }
+    }
+}
+
 pub(crate) fn myers_diff<'a, S: AsRef<str>>(
     doc: &'a mut Automerge,
     tx: &'a mut TransactionInner,
     patch_log: &mut PatchLog,
     text_obj: &ExId,
     new: S,
+    options: UpdateTextOptions,
 ) -> Result<(), crate::AutomergeError> {
     let old = doc.text_for(text_obj, tx.get_scope().clone())?;
     let new = new.as_ref();
+    let text_encoding = doc.text_encoding();
+    let mut granularity = Some(options.granularity);
+    while let Some(g) = granularity {
+        let old_segments = g.segments(&old);
+        let new_segments = g.segments(new);
+        if fits_cost(&old_segments, &new_segments, options.max_cost) {
+            let mut hook = TxHook {
+                tx,
+                doc,
+                patch_log,
+                obj: text_obj,
+                idx: 0,
+                old: &old_segments,
+                new: &new_segments,
+                text_encoding,
+            };
+            return myers::diff(
+                &mut hook,
+                &old_segments,
+                0..old_segments.len(),
+                &new_segments,
+                0..new_segments.len(),
+            );
+        }
+        granularity = g.coarser();
+    }
     let old_graphemes = old.graphemes(true).collect::<Vec<&str>>();
     let new_graphemes = new.graphemes(true).collect::<Vec<&str>>();
-    let text_encoding = doc.text_encoding();
     let mut hook = TxHook {
         tx,
         doc,
@@ -33,13 +138,40 @@ pub(crate) fn myers_diff<'a, S: AsRef<str>>(
         new: &new_graphemes,
         text_encoding,
     };
-    myers::diff(
-        &mut hook,
-        &old_graphemes,
-        0..old_graphemes.len(),
-        &new_graphemes,
-        0..new_graphemes.len(),
-    )
+    replace_changed_region(&mut hook, &old_graphemes, &new_graphemes)
+}
+
+fn fits_cost<T: PartialEq>(old: &[T], new: &[T], max_cost: Option<usize>) -> bool {
+    match max_cost {
+        Some(max_cost) => myers::within_cost(old, 0..old.len(), new, 0..new.len(), max_cost),
+        None => true,
+    }
+}
+
+/// Emit a diff which keeps the common prefix and suffix of `old` and `new` and replaces
+/// everything in between
+fn replace_changed_region<T: PartialEq, D: myers::DiffHook>(
+    hook: &mut D,
+    old: &[T],
+    new: &[T],
+) -> Result<(), D::Error> {
+    let prefix = utils::common_prefix_len(old, 0..old.len(), new, 0..new.len());
+    let suffix = utils::common_suffix_len(old, prefix..old.len(), new, prefix..new.len());
+    let old_end = old.len() - suffix;
+    let new_end = new.len() - suffix;
+    if prefix > 0 {
+        hook.equal(0, 0, prefix)?;
+    }
+    if old_end > prefix {
+        hook.delete(prefix, old_end - prefix, prefix)?;
+    }
+    if new_end > prefix {
+        hook.insert(old_end, prefix, new_end - prefix)?;
+    }
+    if suffix > 0 {
+        hook.equal(old_end, new_end, suffix)?;
+    }
+    hook.finish()
 }
 
 struct TxHook<'a> {
@@ -138,52 +270,73 @@ pub(crate) fn myers_block_diff<'a, 'b, I: IntoIterator<Item = BlockOrText<'b>>>(
     patch_log: &mut PatchLog,
     text_obj: &crate::ObjId,
     new: I,
+    options: UpdateTextOptions,
 ) -> Result<(), crate::AutomergeError> {
     let text_obj_meta = doc.exid_to_obj(text_obj)?;
-    let old = spans_as_grapheme(doc, &text_obj_meta.id, None)?;
-    let new = block_or_text_as_grapheme(new.into_iter());
-    let mut hook = replace::Replace::new(BlockDiffHook {
+    let old = spans_as_block_or_text(doc, &text_obj_meta.id, None)?;
+    let new = merge_text_runs(new);
+    let mut granularity = Some(options.granularity);
+    while let Some(g) = granularity {
+        let old_segments = block_or_text_as_segments(&old, g);
+        let new_segments = block_or_text_as_segments(&new, g);
+        if fits_cost(&old_segments, &new_segments, options.max_cost) {
+            let mut hook = replace::Replace::new(BlockDiffHook {
+                tx,
+                doc,
+                patch_log,
+                obj: text_obj,
+                idx: 0,
+                old: &old_segments,
+                new: &new_segments,
+            });
+            return myers::diff(
+                &mut hook,
+                &old_segments,
+                0..old_segments.len(),
+                &new_segments,
+                0..new_segments.len(),
+            );
+        }
+        granularity = g.coarser();
+    }
+    let old_segments = block_or_text_as_segments(&old, TextGranularity::Grapheme);
+    let new_segments = block_or_text_as_segments(&new, TextGranularity::Grapheme);
+    let mut hook = BlockDiffHook {
         tx,
         doc,
         patch_log,
         obj: text_obj,
         idx: 0,
-        old: &old,
-        new: &new,
-    });
-    //let mut hook = BlockDiffHook {
-    //tx,
-    //doc,
-    //patch_log,
-    //obj: text_obj,
-    //idx: 0,
-    //old: &old,
-    //new: &new,
-    //};
-    myers::diff(&mut hook, &old, 0..old.len(), &new, 0..new.len())
+        old: &old_segments,
+        new: &new_segments,
+    };
+    replace_changed_region(&mut hook, &old_segments, &new_segments)
 }
 
 struct BlockDiffHook<'a> {
     doc: &'a mut Automerge,
     tx: &'a mut TransactionInner,
     patch_log: &'a mut PatchLog,
-    old: &'a [BlockOrGrapheme],
-    new: &'a [BlockOrGrapheme],
+    old: &'a [BlockOrSegment],
+    new: &'a [BlockOrSegment],
     obj: &'a ExId,
     idx: usize,
 }
 
+/// The character a block occupies in the text
+const BLOCK_PLACEHOLDER: &str = "\u{fffc}";
+
 #[derive(Debug, Clone, PartialEq)]
-enum BlockOrGrapheme {
+enum BlockOrSegment {
     Block(crate::hydrate::Map),
-    Grapheme(String),
+    Segment(String),
 }
 
-impl BlockOrGrapheme {
+impl BlockOrSegment {
     fn width(&self, encoding: TextEncoding) -> usize {
         match self {
-            BlockOrGrapheme::Block(_) => 1,
-            BlockOrGrapheme::Grapheme(g) => encoding.width(g),
+            BlockOrSegment::Block(_) => encoding.width(BLOCK_PLACEHOLDER),
+            BlockOrSegment::Segment(g) => encoding.width(g),
         }
     }
 }
@@ -211,13 +364,20 @@ impl myers::DiffHook for BlockDiffHook<'_> {
     ) -> Result<(), Self::Error> {
         for i in old_index..old_index + old_len {
             match &self.old[i] {
-                BlockOrGrapheme::Block(_) => {
+                BlockOrSegment::Block(_) => {
                     self.tx
                         .join_block(self.doc, self.patch_log, self.obj, self.idx)?;
                 }
-                BlockOrGrapheme::Grapheme(_) => {
-                    self.tx
-                        .delete(self.doc, self.patch_log, self.obj, self.idx)?;
+                BlockOrSegment::Segment(g) => {
+                    let width = self.doc.text_encoding().width(g);
+                    self.tx.splice_text(
+                        self.doc,
+                        self.patch_log,
+                        self.obj,
+                        self.idx,
+                        width as isize,
+                        "",
+                    )?;
                 }
             }
         }
@@ -234,7 +394,7 @@ impl myers::DiffHook for BlockDiffHook<'_> {
         let mut run = String::new();
         for i in new_index..new_index + new_len {
             match &self.new[i] {
-                BlockOrGrapheme::Block(b) => {
+                BlockOrSegment::Block(b) => {
                     if !run.is_empty() {
                         self.tx.splice_text(
                             self.doc,
@@ -248,9 +408,9 @@ impl myers::DiffHook for BlockDiffHook<'_> {
                         run.clear();
                     }
                     split_block(self.doc, self.tx, self.patch_log, self.obj, self.idx, b)?;
-                    self.idx += 1;
+                    self.idx += self.doc.text_encoding().width(BLOCK_PLACEHOLDER);
                 }
-                BlockOrGrapheme::Grapheme(g) => {
+                BlockOrSegment::Segment(g) => {
                     run.push_str(g);
                 }
             }
@@ -288,12 +448,12 @@ impl myers::DiffHook for BlockDiffHook<'_> {
             match (old, new) {
                 (None, None) => {}
                 (None, Some(val)) => match val {
-                    BlockOrGrapheme::Block(b) => {
+                    BlockOrSegment::Block(b) => {
                         split_block(self.doc, self.tx, self.patch_log, self.obj, self.idx, b)?;
-                        self.idx += 1;
+                        self.idx += self.doc.text_encoding().width(BLOCK_PLACEHOLDER);
                         new_idx += 1;
                     }
-                    BlockOrGrapheme::Grapheme(g) => {
+                    BlockOrSegment::Segment(g) => {
                         self.tx
                             .splice_text(self.doc, self.patch_log, self.obj, self.idx, 0, g)?;
                         self.idx += self.doc.text_encoding().width(g);
@@ -301,36 +461,48 @@ impl myers::DiffHook for BlockDiffHook<'_> {
                     }
                 },
                 (Some(val), None) => match val {
-                    BlockOrGrapheme::Block(_) => {
+                    BlockOrSegment::Block(_) => {
                         self.tx
                             .join_block(self.doc, self.patch_log, self.obj, self.idx)?;
                         old_idx += 1;
                     }
-                    BlockOrGrapheme::Grapheme(_) => {
-                        self.tx
-                            .delete(self.doc, self.patch_log, self.obj, self.idx)?;
+                    BlockOrSegment::Segment(g) => {
+                        let width = self.doc.text_encoding().width(g);
+                        self.tx.splice_text(
+                            self.doc,
+                            self.patch_log,
+                            self.obj,
+                            self.idx,
+                            width as isize,
+                            "",
+                        )?;
                         old_idx += 1;
                     }
                 },
                 (Some(old), Some(new)) => match (old, new) {
-                    (BlockOrGrapheme::Block(b1), BlockOrGrapheme::Block(b2)) => {
+                    (BlockOrSegment::Block(b1), BlockOrSegment::Block(b2)) => {
                         if b1 != b2 {
                             update_block(self.doc, self.tx, self.patch_log, self.obj, self.idx, b2)?
                         }
-                        self.idx += 1;
+                        self.idx += self.doc.text_encoding().width(BLOCK_PLACEHOLDER);
                         old_idx += 1;
                         new_idx += 1;
                     }
-                    (BlockOrGrapheme::Grapheme(_g1), BlockOrGrapheme::Grapheme(g2)) => {
-                        self.tx
-                            .delete(self.doc, self.patch_log, self.obj, self.idx)?;
-                        self.tx
-                            .splice_text(self.doc, self.patch_log, self.obj, self.idx, 0, g2)?;
+                    (BlockOrSegment::Segment(g1), BlockOrSegment::Segment(g2)) => {
+                        let width = self.doc.text_encoding().width(g1);
+                        self.tx.splice_text(
+                            self.doc,
+                            self.patch_log,
+                            self.obj,
+                            self.idx,
+                            width as isize,
+                            g2,
+                        )?;
                         self.idx += self.doc.text_encoding().width(g2);
                         old_idx += 1;
                         new_idx += 1;
                     }
-                    (BlockOrGrapheme::Block(_), BlockOrGrapheme::Grapheme(g2)) => {
+                    (BlockOrSegment::Block(_), BlockOrSegment::Segment(g2)) => {
                         self.tx
                             .join_block(self.doc, self.patch_log, self.obj, self.idx)?;
                         self.tx
@@ -339,11 +511,18 @@ impl myers::DiffHook for BlockDiffHook<'_> {
                         old_idx += 1;
                         new_idx += 1;
                     }
-                    (BlockOrGrapheme::Grapheme(_g1), BlockOrGrapheme::Block(b2)) => {
-                        self.tx
-                            .delete(self.doc, self.patch_log, self.obj, self.idx)?;
+                    (BlockOrSegment::Segment(g1), BlockOrSegment::Block(b2)) => {
+                        let width = self.doc.text_encoding().width(g1);
+                        self.tx.splice_text(
+                            self.doc,
+                            self.patch_log,
+                            self.obj,
+                            self.idx,
+                            width as isize,
+                            "",
+                        )?;
                         split_block(self.doc, self.tx, self.patch_log, self.obj, self.idx, b2)?;
-                        self.idx += 1;
+                        self.idx += self.doc.text_encoding().width(BLOCK_PLACEHOLDER);
                         old_idx += 1;
                         new_idx += 1;
                     }
@@ -359,45 +538,49 @@ impl myers::DiffHook for BlockDiffHook<'_> {
     }
 }
 
-fn spans_as_grapheme(
+fn spans_as_block_or_text(
     doc: &Automerge,
     text: &crate::types::ObjId,
     clock: Option<Clock>,
-) -> Result<Vec<BlockOrGrapheme>, crate::AutomergeError> {
+) -> Result<Vec<BlockOrText<'static>>, crate::AutomergeError> {
     let range = doc.ops.scope_to_obj(text);
     let spans_internal = SpansInternal::new(doc.ops(), range, clock.clone(), doc.text_encoding());
-    let mut result = Vec::with_capacity(spans_internal.size_hint().0);
-    for span in spans_internal {
-        match span {
-            SpanInternal::Obj(b, _) => {
-                let crate::hydrate::Value::Map(map) = doc.hydrate_map(&b.into(), clock.as_ref())
-                else {
-                    tracing::warn!("unexpected non map object in text");
-                    result.push(BlockOrGrapheme::Block(crate::hydrate::Map::new()));
-                    continue;
-                };
-                result.push(BlockOrGrapheme::Block(map));
-            }
-            SpanInternal::Text(t, _, _) => {
-                for g in t.graphemes(true) {
-                    result.push(BlockOrGrapheme::Grapheme(g.to_string()));
-                }
-            }
+    let spans = spans_internal.map(|span| match span {
+        SpanInternal::Obj(b, _) => {
+            let crate::hydrate::Value::Map(map) = doc.hydrate_map(&b.into(), clock.as_ref()) else {
+                tracing::warn!("unexpected non map object in text");
+                return BlockOrText::Block(crate::hydrate::Map::new());
+            };
+            BlockOrText::Block(map)
         }
-    }
-    Ok(result)
+        SpanInternal::Text(t, _, _) => BlockOrText::Text(Cow::Owned(t)),
+    });
+    Ok(merge_text_runs(spans))
 }
 
-fn block_or_text_as_grapheme<'a, I: Iterator<Item = BlockOrText<'a>>>(
-    iter: I,
-) -> Vec<BlockOrGrapheme> {
-    let mut result = Vec::with_capacity(iter.size_hint().0);
+/// Concatenate adjacent text runs so that segments which straddle a change in marks are not split
+fn merge_text_runs<'a, I: IntoIterator<Item = BlockOrText<'a>>>(iter: I) -> Vec<BlockOrText<'a>> {
+    let mut result: Vec<BlockOrText<'a>> = Vec::new();
     for b in iter {
+        match (result.last_mut(), b) {
+            (Some(BlockOrText::Text(run)), BlockOrText::Text(t)) => run.to_mut().push_str(&t),
+            (_, b) => result.push(b),
+        }
+    }
+    result
+}
+
+fn block_or_text_as_segments(
+    spans: &[BlockOrText<'_>],
+    granularity: TextGranularity,
+) -> Vec<BlockOrSegment> {
+    let mut result = Vec::with_capacity(spans.len());
+    for b in spans {
         match b {
-            BlockOrText::Block(b) => result.push(BlockOrGrapheme::Block(b)),
+            BlockOrText::Block(b) => result.push(BlockOrSegment::Block(b.clone())),
             BlockOrText::Text(t) => {
-                for g in t.graphemes(true) {
-                    result.push(BlockOrGrapheme::Grapheme(g.to_string()));
+                for g in granularity.segments(t) {
+                    result.push(BlockOrSegment::Segment(g.to_string()));
                 }
             }
         }
@@ -74,6 +74,60 @@ where
     d.finish()
 }
 
+/// Check whether the shortest edit script between `old` and `new` contains at most `max_cost`
+/// insertions and deletions.
+///
+/// This runs the greedy forward pass of Myers' algorithm but gives up once `D` exceeds
+/// `max_cost`, so it takes `O((N+M) * max_cost)` time regardless of how different the inputs are.
+pub(super) fn within_cost<Old, New>(
+    old: &Old,
+    old_range: Range<usize>,
+    new: &New,
+    new_range: Range<usize>,
+    max_cost: usize,
+) -> bool
+where
+    Old: Index<usize> + ?Sized,
+    New: Index<usize> + ?Sized,
+    New::Output: PartialEq<Old::Output>,
+{
+    let n = old_range.len() as isize;
+    let m = new_range.len() as isize;
+    if n.abs_diff(m) > max_cost {
+        return false;
+    }
+    let max = max_cost as isize;
+    let mut v = V {
+        offset: max + 1,
+        v: vec![0; 2 * max_cost + 3],
+    };
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[k - 1] < v[k + 1]) {
+                v[k + 1]
+            } else {
+                v[k - 1] + 1
+            } as isize;
+            let mut y = x - k;
+            if y >= 0 && x < n && y < m {
+                let advance = common_prefix_len(
+                    old,
+                    old_range.start + x as usize..old_range.end,
+                    new,
+                    new_range.start + y as usize..new_range.end,
+                ) as isize;
+                x += advance;
+                y += advance;
+            }
+            v[k] = x as usize;
+            if x >= n && y >= m {
+                return true;
+            }
+        }
+    }
+    false
+}
+
 // A D-path is a path which starts at (0,0) that has exactly D non-diagonal
 // edges. All D-paths consist of a (D - 1)-path followed by a non-diagonal edge
 // and then a possibly empty sequence of diagonal edges called a snake.
@@ -330,3 +384,15 @@ fn test_find_middle_snake() {
     assert_eq!(x_start, 4);
     assert_eq!(y_start, 1);
 }
+
+#[test]
+fn test_within_cost() {
+    let a = &b"ABCABBA"[..];
+    let b = &b"CBABAC"[..];
+    // The shortest edit script for these sequences has five edits
+    assert!(!within_cost(a, 0..a.len(), b, 0..b.len(), 4));
+    assert!(within_cost(a, 0..a.len(), b, 0..b.len(), 5));
+    assert!(within_cost(a, 0..a.len(), a, 0..a.len(), 0));
+    assert!(!within_cost(&b""[..], 0..0, a, 0..a.len(), 6));
+    assert!(within_cost(&b""[..], 0..0, a, 0..a.len(), 7));
+}
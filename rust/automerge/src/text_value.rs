@@ -300,7 +300,8 @@ impl ConcreteTextValue {
         }
     }
 
-    pub(crate) fn len(&self) -> usize {
+    /// The length of the text in the units of the [`TextRepresentation`] it was created with
+    pub fn len(&self) -> usize {
         match self {
             Self::Utf8CodeUnit(u) => u.len(),
             Self::Utf16CodeUnit(u) => u.len(),
@@ -310,6 +311,10 @@ impl ConcreteTextValue {
         }
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub(crate) fn remove(&mut self, index: usize) {
         match self {
             Self::Utf8CodeUnit(u) => u.remove(index),
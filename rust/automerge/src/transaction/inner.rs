@@ -13,7 +13,7 @@ use crate::op_set2::{Op, OpSet, OpSetCheckpoint, PropRef, SuccInsert, TxOp};
 use crate::patches::{PatchLog, TextRepresentation};
 use crate::types::{Clock, ElemId, ListEncoding, ObjMeta, OpId, ScalarValue, TextEncoding};
 use crate::Automerge;
-use crate::{AutomergeError, ObjType, OpType, ReadDoc};
+use crate::{AutomergeError, ObjType, OpType, ReadDoc, UpdateTextOptions};
 use crate::{Change, ChangeHash, Prop};
 
 #[derive(Debug, Clone)]
@@ -901,6 +901,7 @@ impl TransactionInner {
                     patch_log,
                     obj,
                     new_text.to_string().as_str(),
+                    UpdateTextOptions::default(),
                 )?)
             }
             _ => Err(crate::error::UpdateObjectError::ChangeType),
@@ -1022,7 +1023,14 @@ impl TransactionInner {
                 self.update_list(doc, patch_log, &id, new)
             }
             (Some((id, crate::Value::Object(ObjType::Text))), crate::hydrate::Value::Text(new)) => {
-                crate::text_diff::myers_diff(doc, self, patch_log, &id, new.to_string().as_str())
+                crate::text_diff::myers_diff(
+                    doc,
+                    self,
+                    patch_log,
+                    &id,
+                    new.to_string().as_str(),
+                    UpdateTextOptions::default(),
+                )
             }
             (old, new) => {
                 // Here we are either changing the type of the existing object, or inserting an
@@ -8,7 +8,7 @@ use crate::marks::{ExpandMark, Mark, MarkSet};
 use crate::patches::{PatchLog, TextRepresentation};
 use crate::types::{Clock, ScalarValue};
 use crate::{hydrate, AutomergeError};
-use crate::{ChangeHash, Cursor, ObjType, Prop, Value};
+use crate::{ChangeHash, Cursor, ObjType, Prop, UpdateTextOptions, Value};
 
 use super::{CommitOptions, Transactable, TransactionArgs, TransactionInner};
 
@@ -40,6 +40,7 @@ impl<'a> Transaction<'a> {
         mut patch_log: PatchLog,
     ) -> Self {
         patch_log.migrate_actors(&doc.ops().actors).unwrap(); // we forked and merged so there will be no mismatch
+        patch_log.record_before(|| doc.get_heads());
         Self {
             inner: Some(TransactionInner::new(args)),
             doc,
@@ -511,16 +512,40 @@ impl Transactable for Transaction<'_> {
         obj: &ExId,
         new_text: S,
     ) -> Result<(), AutomergeError> {
-        self.do_tx(|tx, doc, hist| crate::text_diff::myers_diff(doc, tx, hist, obj, new_text))
+        self.update_text_with_options(obj, new_text, UpdateTextOptions::default())
+    }
+
+    fn update_text_with_options<S: AsRef<str>>(
+        &mut self,
+        obj: &ExId,
+        new_text: S,
+        options: UpdateTextOptions,
+    ) -> Result<(), AutomergeError> {
+        self.do_tx(|tx, doc, hist| {
+            crate::text_diff::myers_diff(doc, tx, hist, obj, new_text, options)
+        })
     }
 
     fn update_spans<'b, O: AsRef<ExId>, I: IntoIterator<Item = crate::BlockOrText<'b>>>(
         &mut self,
         text: O,
         new_text: I,
+    ) -> Result<(), AutomergeError> {
+        self.update_spans_with_options(text, new_text, UpdateTextOptions::default())
+    }
+
+    fn update_spans_with_options<
+        'b,
+        O: AsRef<ExId>,
+        I: IntoIterator<Item = crate::BlockOrText<'b>>,
+    >(
+        &mut self,
+        text: O,
+        new_text: I,
+        options: UpdateTextOptions,
     ) -> Result<(), AutomergeError> {
         self.do_tx(move |tx, doc, hist| {
-            crate::text_diff::myers_block_diff(doc, tx, hist, text.as_ref(), new_text)
+            crate::text_diff::myers_block_diff(doc, tx, hist, text.as_ref(), new_text, options)
         })
     }
 
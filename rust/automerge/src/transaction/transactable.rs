@@ -2,7 +2,7 @@ use std::borrow::Cow;
 
 use crate::exid::ExId;
 use crate::marks::{ExpandMark, Mark};
-use crate::{AutomergeError, ChangeHash, ObjType, Prop, ReadDoc, ScalarValue};
+use crate::{AutomergeError, ChangeHash, ObjType, Prop, ReadDoc, ScalarValue, UpdateTextOptions};
 
 /// A way of mutating a document within a single change.
 pub trait Transactable: ReadDoc {
@@ -142,6 +142,20 @@ pub trait Transactable: ReadDoc {
         new_text: I,
     ) -> Result<(), AutomergeError>;
 
+    /// Like [`Self::update_spans`] but with control over how the diff is calculated
+    ///
+    /// The default implementation ignores `options` and calls [`Self::update_spans`], the
+    /// implementations in this crate all override it.
+    fn update_spans_with_options<'a, O: AsRef<ExId>, I: IntoIterator<Item = BlockOrText<'a>>>(
+        &mut self,
+        text: O,
+        new_text: I,
+        options: UpdateTextOptions,
+    ) -> Result<(), AutomergeError> {
+        let _ = options;
+        self.update_spans(text, new_text)
+    }
+
     /// The heads this transaction will be based on
     fn base_heads(&self) -> Vec<ChangeHash>;
 
@@ -155,6 +169,23 @@ pub trait Transactable: ReadDoc {
     fn update_text<S: AsRef<str>>(&mut self, obj: &ExId, new_text: S)
         -> Result<(), AutomergeError>;
 
+    /// Like [`Self::update_text`] but with control over how the diff is calculated
+    ///
+    /// Diffing at a coarser [`crate::TextGranularity`] produces fewer, larger splices which
+    /// interleave better with concurrent edits when the new text is a substantial rewrite.
+    ///
+    /// The default implementation ignores `options` and calls [`Self::update_text`], the
+    /// implementations in this crate all override it.
+    fn update_text_with_options<S: AsRef<str>>(
+        &mut self,
+        obj: &ExId,
+        new_text: S,
+        options: UpdateTextOptions,
+    ) -> Result<(), AutomergeError> {
+        let _ = options;
+        self.update_text(obj, new_text)
+    }
+
     fn update_object<O: AsRef<ExId>>(
         &mut self,
         obj: O,
@@ -62,6 +62,14 @@ impl Distribution<ActorId> for Standard {
 }
 
 impl ActorId {
+    pub(crate) fn heap_size(&self) -> usize {
+        if self.0.is_heap() {
+            self.0.capacity()
+        } else {
+            0
+        }
+    }
+
     pub fn random() -> ActorId {
         let mut buf = [0u8; 16];
         // getrandom 0.3 breaks node v18
@@ -508,8 +516,9 @@ pub(crate) enum Key {
 /// A property of an object
 ///
 /// This is either a string representing a property in a map, or an integer
-/// which is the index into a sequence
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone)]
+/// which is the index into a sequence. It serializes as a string or a number respectively.
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
 pub enum Prop {
     /// A property in a map
     Map(String),
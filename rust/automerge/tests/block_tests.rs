@@ -5,7 +5,8 @@ use automerge::{
     iter::Span,
     marks::{ExpandMark, Mark},
     transaction::Transactable,
-    BlockOrText, ObjType, ReadDoc, ScalarValue, ROOT,
+    BlockOrText, ObjType, PatchAction, ReadDoc, ScalarValue, TextEncoding, TextGranularity,
+    UpdateTextOptions, ROOT,
 };
 use test_log::test;
 
@@ -333,3 +334,67 @@ fn marks_on_spans_respect_heads() {
         ]
     );
 }
+
+#[test]
+fn update_spans_by_word() {
+    let mut doc = automerge::AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    let block = doc.split_block(&text, 0).unwrap();
+    doc.update_object(
+        &block,
+        &hydrate_map! {
+            "parents" => hydrate_list![],
+            "type" => "paragraph",
+            "attrs" => hydrate_map!{}
+        }
+        .into(),
+    )
+    .unwrap();
+    doc.splice_text(&text, 1, 0, "hello bold world").unwrap();
+    // Split the text into several runs, the word diff should not see the run boundaries
+    doc.mark(
+        &text,
+        Mark::new("bold".to_string(), true, 7, 9),
+        ExpandMark::None,
+    )
+    .unwrap();
+
+    doc.update_diff_cursor();
+
+    doc.update_spans_with_options(
+        &text,
+        [
+            BlockOrText::Block(hydrate_map! {
+                "parents" => hydrate_list![],
+                "type" => "paragraph",
+                "attrs" => hydrate_map!{}
+            }),
+            BlockOrText::Text("hello brave world".into()),
+        ],
+        UpdateTextOptions::new().granularity(TextGranularity::Word),
+    )
+    .unwrap();
+
+    assert_eq!(doc.text(&text).unwrap(), "\u{fffc}hello brave world");
+    let prefix = "\u{fffc}hello ";
+    let index = match doc.text_encoding() {
+        TextEncoding::Utf8CodeUnit => prefix.len(),
+        TextEncoding::Utf16CodeUnit => prefix.encode_utf16().count(),
+        _ => prefix.chars().count(),
+    };
+    let actions = doc
+        .diff_incremental()
+        .into_iter()
+        .map(|p| p.action)
+        .collect::<Vec<_>>();
+    assert_eq!(actions[0], PatchAction::DeleteSeq { index, length: 4 });
+    assert!(matches!(
+        actions[1],
+        PatchAction::SpliceText {
+            index: i,
+            marks: None,
+            ..
+        } if i == index
+    ));
+    assert_eq!(actions.len(), 2);
+}
@@ -1263,6 +1263,34 @@ fn test_compressed_doc_cols() {
     );
 }
 
+#[cfg(feature = "zstd")]
+#[test]
+fn test_zstd_doc_cols() {
+    let mut doc = new_doc();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    for i in 0..200 {
+        doc.splice_text(&text, i * 6, 0, "hello ").unwrap();
+    }
+    doc.commit();
+    let deflated = doc.save();
+    let zstd = doc.save_with_options(
+        automerge::SaveOptions::new()
+            .compression(automerge::Compression::Zstd { level: 19 })
+            .compression_threshold(64),
+    );
+    assert_ne!(zstd, deflated);
+    let columns = automerge::inspect::inspect(&zstd)
+        .into_iter()
+        .flat_map(|report| report.columns)
+        .collect::<Vec<_>>();
+    assert!(columns.iter().any(|c| c.zstd));
+    assert!(columns.iter().all(|c| !c.deflated));
+
+    let loaded = Automerge::load(&zstd).unwrap();
+    assert_eq!(loaded.text(&text).unwrap(), "hello ".repeat(200));
+    assert_eq!(loaded.save(), deflated);
+}
+
 #[test]
 fn test_change_encoding_expanded_change_round_trip() {
     let change_bytes: Vec<u8> = vec![
@@ -2317,6 +2345,421 @@ fn stats_smoke_test() {
     assert_eq!(stats.num_ops, 2);
 }
 
+#[test]
+fn op_stats_smoke_test() {
+    let mut doc = AutoCommit::new();
+    let text = doc
+        .put_object(&automerge::ROOT, "text", ObjType::Text)
+        .unwrap();
+    doc.splice_text(&text, 0, 0, "héllo").unwrap();
+    doc.put(&automerge::ROOT, "a", 1).unwrap();
+    doc.commit();
+    doc.put(&automerge::ROOT, "a", 2).unwrap();
+    doc.splice_text(&text, 0, 1, "").unwrap();
+    let stats = doc.op_stats();
+    assert_eq!(stats.num_visible_ops, 6);
+    assert_eq!(stats.num_tombstones, 2);
+    assert_eq!(stats.num_increments, 0);
+    assert_eq!(stats.text_bytes, 6);
+    assert_eq!(
+        stats.ops_per_object,
+        vec![(automerge::ROOT, 3), (text.clone(), 5)]
+    );
+}
+
+#[test]
+fn memory_usage_smoke_test() {
+    let mut doc = AutoCommit::new();
+    let empty = doc.memory_usage();
+    assert_eq!(empty.queue, 0);
+    assert_eq!(empty.patches, 0);
+
+    doc.update_diff_cursor();
+    let text = doc.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, &"hello ".repeat(1000))
+        .unwrap();
+    doc.commit();
+    let usage = doc.memory_usage();
+    assert!(usage.op_set > empty.op_set);
+    assert!(usage.indexes > empty.indexes);
+    assert!(usage.change_graph > empty.change_graph);
+    assert!(usage.actors > 0);
+    assert!(usage.patches > 0);
+    assert_eq!(
+        usage.total(),
+        usage.op_set
+            + usage.indexes
+            + usage.change_graph
+            + usage.actors
+            + usage.queue
+            + usage.patches
+    );
+
+    // a change whose dependency is missing is held in the queue
+    let mut other = doc.fork();
+    other.put(&ROOT, "a", 1).unwrap();
+    other.commit();
+    other.put(&ROOT, "a", 2).unwrap();
+    let orphan = other.get_last_local_change().unwrap().clone();
+    doc.apply_changes(vec![orphan]).unwrap();
+    assert!(doc.memory_usage().queue > 0);
+
+    // so are the patches recorded for an observer
+    let mut observed = AutoCommit::new();
+    observed.set_observer_log(Some(PatchLog::active(TextRepresentation::String(
+        TextEncoding::default(),
+    ))));
+    observed.put(&ROOT, "a", 1).unwrap();
+    observed.commit();
+    assert!(observed.memory_usage().patches > 0);
+}
+
+fn sync_with_states(
+    a: &mut AutoCommit,
+    a_state: &mut automerge::sync::State,
+    b: &mut AutoCommit,
+    b_state: &mut automerge::sync::State,
+) {
+    for _ in 0..100 {
+        let a_msg = a.sync().generate_sync_message(a_state);
+        let b_msg = b.sync().generate_sync_message(b_state);
+        if a_msg.is_none() && b_msg.is_none() {
+            return;
+        }
+        if let Some(msg) = a_msg {
+            b.sync().receive_sync_message(b_state, msg).unwrap();
+        }
+        if let Some(msg) = b_msg {
+            a.sync().receive_sync_message(a_state, msg).unwrap();
+        }
+    }
+    panic!("the documents did not finish syncing");
+}
+
+#[test]
+fn compact_removes_dead_ops_and_keeps_syncing() {
+    let mut doc1 = AutoCommit::new();
+    let text = doc1.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    doc1.splice_text(&text, 0, 0, "hello world").unwrap();
+    doc1.put(&ROOT, "key", 1).unwrap();
+    doc1.commit();
+    let mut doc2 = doc1.fork();
+    doc2.splice_text(&text, 5, 6, "").unwrap();
+    doc2.put(&ROOT, "key", 2).unwrap();
+    doc2.commit();
+
+    let mut state1 = automerge::sync::State::new();
+    let mut state2 = automerge::sync::State::new();
+    sync_with_states(&mut doc1, &mut state1, &mut doc2, &mut state2);
+
+    let checkpoint = doc1.get_heads();
+    let num_ops = doc1.stats().num_ops;
+    // " world" and the overwritten value of "key"
+    assert_eq!(doc1.compact(&checkpoint).unwrap(), 7);
+    assert_eq!(doc1.stats().num_ops, num_ops - 7);
+    assert_eq!(doc1.stats().num_changes, 2);
+    assert_eq!(doc1.text(&text).unwrap(), "hello");
+    assert_eq!(doc1.get(&ROOT, "key").unwrap().unwrap().0, Value::int(2));
+    assert_eq!(doc1.get_heads(), checkpoint);
+    assert_eq!(doc1.compact(&checkpoint).unwrap(), 0);
+
+    // the changes before the checkpoint can no longer be rebuilt
+    assert!(doc1.get_change_by_hash(&checkpoint[0]).is_none());
+    assert!(doc1.get_changes(&[]).is_empty());
+    assert!(doc1.get_change_meta_by_hash(&checkpoint[0]).is_some());
+
+    doc1.splice_text(&text, 5, 0, " there").unwrap();
+    doc1.commit();
+    doc2.splice_text(&text, 0, 1, "J").unwrap();
+    doc2.commit();
+    sync_with_states(&mut doc1, &mut state1, &mut doc2, &mut state2);
+    assert_eq!(doc1.text(&text).unwrap(), "Jello there");
+    assert_eq!(doc2.text(&text).unwrap(), "Jello there");
+    assert_eq!(doc1.get_heads(), doc2.get_heads());
+
+    // a saved compacted document loads without the removed ops and carries on syncing
+    let mut loaded = AutoCommit::load(&doc1.save()).unwrap();
+    assert_eq!(loaded.get_heads(), doc1.get_heads());
+    assert_eq!(loaded.stats().num_ops, doc1.stats().num_ops);
+    assert_eq!(loaded.text(&text).unwrap(), "Jello there");
+    assert_eq!(
+        loaded.get_changes(&checkpoint),
+        doc1.get_changes(&checkpoint)
+    );
+    assert!(loaded.get_change_by_hash(&checkpoint[0]).is_none());
+
+    doc2.splice_text(&text, 11, 0, "!").unwrap();
+    doc2.commit();
+    loaded.put(&ROOT, "key", 3).unwrap();
+    loaded.commit();
+    sync_with_states(&mut loaded, &mut state1, &mut doc2, &mut state2);
+    assert_eq!(loaded.text(&text).unwrap(), "Jello there!");
+    assert_eq!(doc2.get(&ROOT, "key").unwrap().unwrap().0, Value::int(3));
+    assert_eq!(loaded.get_heads(), doc2.get_heads());
+
+    let heads = loaded.get_heads();
+    assert_eq!(loaded.compact(&heads).unwrap(), 2);
+    let mut reloaded = AutoCommit::load(&loaded.save()).unwrap();
+    assert_eq!(reloaded.text(&text).unwrap(), "Jello there!");
+    assert_eq!(reloaded.get_heads(), heads);
+}
+
+#[test]
+fn compact_keeps_elements_referenced_above_the_checkpoint() {
+    let mut doc1 = AutoCommit::new();
+    let text = doc1.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    doc1.splice_text(&text, 0, 0, "abc").unwrap();
+    doc1.commit();
+    let mut doc2 = doc1.fork();
+    doc1.splice_text(&text, 1, 2, "").unwrap();
+    doc1.commit();
+    let checkpoint = doc1.get_heads();
+
+    // concurrently insert after the "b" which doc1 deleted
+    doc2.splice_text(&text, 2, 0, "X").unwrap();
+    doc2.commit();
+    let change = doc2.get_last_local_change().unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    assert_eq!(doc1.text(&text).unwrap(), "aX");
+
+    // the deleted "c" goes but "b" stays as "X" was inserted after it above the checkpoint
+    assert_eq!(doc1.compact(&checkpoint).unwrap(), 1);
+    assert_eq!(doc1.text(&text).unwrap(), "aX");
+    let rebuilt = doc1.get_change_by_hash(&change.hash()).unwrap();
+    assert_eq!(rebuilt.raw_bytes(), change.raw_bytes());
+
+    let mut loaded = AutoCommit::load(&doc1.save()).unwrap();
+    assert_eq!(loaded.text(&text).unwrap(), "aX");
+    assert_eq!(loaded.get_changes(&checkpoint), vec![change]);
+}
+
+#[test]
+fn compact_rejects_unknown_heads() {
+    let mut doc = AutoCommit::new();
+    doc.put(&ROOT, "a", 1).unwrap();
+    doc.commit();
+    let mut other = AutoCommit::new();
+    other.put(&ROOT, "a", 2).unwrap();
+    other.commit();
+    assert!(matches!(
+        doc.compact(&other.get_heads()),
+        Err(AutomergeError::InvalidHash(_))
+    ));
+}
+
+#[test]
+fn compacted_documents_are_rejected_by_older_readers() {
+    let mut doc = AutoCommit::new();
+    doc.put(&ROOT, "a", 1).unwrap();
+    doc.put(&ROOT, "a", 2).unwrap();
+    doc.commit();
+    // the chunk type follows the magic bytes and the checksum
+    assert_eq!(doc.save()[8], 0);
+
+    let heads = doc.get_heads();
+    doc.compact(&heads).unwrap();
+    let saved = doc.save();
+    assert_eq!(saved[8], 3);
+    assert_eq!(
+        automerge::inspect::inspect(&saved)[0].kind,
+        Some(automerge::inspect::ChunkKind::CompactedDocument)
+    );
+    assert_eq!(AutoCommit::load(&saved).unwrap().get_heads(), heads);
+
+    // readers which predate compaction only know chunk types 0 to 2 and reject the document the
+    // same way as this one rejects a chunk type it doesn't know
+    let mut unknown = saved.clone();
+    unknown[8] = 4;
+    assert!(AutoCommit::load(&unknown).is_err());
+}
+
+#[test]
+fn compacted_documents_sync_to_new_peers() {
+    let mut doc1 = AutoCommit::new();
+    let text = doc1.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    doc1.splice_text(&text, 0, 0, "hello world").unwrap();
+    doc1.commit();
+    doc1.splice_text(&text, 5, 6, "").unwrap();
+    doc1.commit();
+    let heads = doc1.get_heads();
+    doc1.compact(&heads).unwrap();
+    doc1.splice_text(&text, 5, 0, "!").unwrap();
+    doc1.commit();
+
+    // the new peer gets the whole document rather than changes it can't apply
+    let mut doc2 = AutoCommit::new();
+    let mut state1 = automerge::sync::State::new();
+    let mut state2 = automerge::sync::State::new();
+    sync_with_states(&mut doc1, &mut state1, &mut doc2, &mut state2);
+    assert_eq!(doc2.text(&text).unwrap(), "hello!");
+    assert_eq!(doc2.get_heads(), doc1.get_heads());
+}
+
+#[test]
+fn syncing_with_a_peer_behind_the_compaction_fails() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(&ROOT, "a", 1).unwrap();
+    doc1.commit();
+    let mut doc2 = doc1.fork();
+    doc1.put(&ROOT, "a", 2).unwrap();
+    doc1.commit();
+    let heads = doc1.get_heads();
+    doc1.compact(&heads).unwrap();
+    doc1.put(&ROOT, "a", 3).unwrap();
+    doc1.commit();
+
+    // doc2 never saw the second change so it can't be caught up
+    let mut state1 = automerge::sync::State::new();
+    let mut state2 = automerge::sync::State::new();
+    let result = (0..10).try_for_each(|_| {
+        if let Some(msg) = doc2.sync().generate_sync_message(&mut state2) {
+            doc1.sync().receive_sync_message(&mut state1, msg)?;
+        }
+        if let Some(msg) = doc1.sync().generate_sync_message(&mut state1) {
+            doc2.sync().receive_sync_message(&mut state2, msg)?;
+        }
+        Ok(())
+    });
+    assert!(
+        matches!(result, Err(AutomergeError::CompactedChange(hash)) if hash == heads[0]),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn autocommit_log_patches_methods_log_only_the_new_changes() {
+    let mut doc = AutoCommit::new();
+    doc.put(&ROOT, "existing", 0).unwrap();
+    doc.update_diff_cursor();
+    let mut other = doc.fork();
+    let keys = |patches: Vec<Patch>| {
+        let mut keys = patches
+            .into_iter()
+            .map(|p| match p.action {
+                PatchAction::PutMap { key, .. } => key,
+                other => panic!("unexpected patch {:?}", other),
+            })
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys
+    };
+    let new_log = || PatchLog::active(TextRepresentation::String(TextEncoding::default()));
+
+    other.put(&ROOT, "merged", 1).unwrap();
+    let mut log = new_log();
+    doc.merge_and_log_patches(&mut other, &mut log).unwrap();
+    assert_eq!(keys(doc.make_patches(&mut log)), vec!["merged"]);
+
+    other.put(&ROOT, "applied", 2).unwrap();
+    let change = other.get_last_local_change().unwrap();
+    let mut log = new_log();
+    doc.apply_changes_log_patches([change], &mut log).unwrap();
+    assert_eq!(keys(doc.make_patches(&mut log)), vec!["applied"]);
+
+    other.put(&ROOT, "loaded", 3).unwrap();
+    let mut log = new_log();
+    doc.load_incremental_log_patches(&other.save_incremental(), &mut log)
+        .unwrap();
+    assert_eq!(keys(doc.make_patches(&mut log)), vec!["loaded"]);
+
+    other.put(&ROOT, "synced", 4).unwrap();
+    let mut log = new_log();
+    let mut doc_state = automerge::sync::State::new();
+    let mut other_state = automerge::sync::State::new();
+    loop {
+        let mut progress = false;
+        if let Some(msg) = other.sync().generate_sync_message(&mut other_state) {
+            doc.sync()
+                .receive_sync_message_log_patches(&mut doc_state, msg, &mut log)
+                .unwrap();
+            progress = true;
+        }
+        if let Some(msg) = doc.sync().generate_sync_message(&mut doc_state) {
+            other
+                .sync()
+                .receive_sync_message(&mut other_state, msg)
+                .unwrap();
+            progress = true;
+        }
+        if !progress {
+            break;
+        }
+    }
+    assert_eq!(keys(doc.make_patches(&mut log)), vec!["synced"]);
+}
+
+#[test]
+fn autocommit_observer_log_records_local_and_remote_changes() {
+    let mut doc = AutoCommit::new();
+    doc.update_diff_cursor();
+    doc.set_observer_log(Some(PatchLog::active(TextRepresentation::String(
+        TextEncoding::default(),
+    ))));
+    let mut other = doc.fork();
+
+    doc.put(&ROOT, "local", 1).unwrap();
+    doc.commit();
+    other.put(&ROOT, "merged", 2).unwrap();
+    doc.merge(&mut other).unwrap();
+    other.put(&ROOT, "applied", 3).unwrap();
+    let change = other.get_last_local_change().unwrap();
+    doc.apply_changes([change]).unwrap();
+
+    // the diff cursor's log is unaffected by the observer log and vice versa
+    let keys = |patches: Vec<Patch>| {
+        let mut keys = patches
+            .into_iter()
+            .map(|p| match p.action {
+                PatchAction::PutMap { key, .. } => key,
+                other => panic!("unexpected patch {:?}", other),
+            })
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys
+    };
+    assert_eq!(
+        keys(doc.diff_incremental()),
+        vec!["applied", "local", "merged"]
+    );
+    assert_eq!(
+        keys(doc.take_observed_patches()),
+        vec!["applied", "local", "merged"]
+    );
+    assert!(doc.take_observed_patches().is_empty());
+
+    // changes which only add to a caller's log are still seen by the observer
+    other.put(&ROOT, "synced", 4).unwrap();
+    let mut log = PatchLog::active(TextRepresentation::String(TextEncoding::default()));
+    let mut doc_state = automerge::sync::State::new();
+    let mut other_state = automerge::sync::State::new();
+    loop {
+        let mut progress = false;
+        if let Some(msg) = other.sync().generate_sync_message(&mut other_state) {
+            doc.sync()
+                .receive_sync_message_log_patches(&mut doc_state, msg, &mut log)
+                .unwrap();
+            progress = true;
+        }
+        if let Some(msg) = doc.sync().generate_sync_message(&mut doc_state) {
+            other
+                .sync()
+                .receive_sync_message(&mut other_state, msg)
+                .unwrap();
+            progress = true;
+        }
+        if !progress {
+            break;
+        }
+    }
+    assert_eq!(keys(doc.make_patches(&mut log)), vec!["synced"]);
+    assert_eq!(keys(doc.take_observed_patches()), vec!["synced"]);
+
+    doc.set_observer_log(None);
+    doc.put(&ROOT, "unobserved", 5).unwrap();
+    assert!(doc.take_observed_patches().is_empty());
+}
+
 #[test]
 fn invalid_index() {
     let mut doc = AutoCommit::new();
@@ -7,7 +7,7 @@ use automerge::{
     patches::TextRepresentation,
     transaction::Transactable,
     ActorId, AutoCommit, ConcreteTextValue, ObjType, Patch, PatchAction, Prop, ReadDoc,
-    ScalarValue, TextEncoding, Value, ROOT,
+    ScalarValue, TextEncoding, TextGranularity, UpdateTextOptions, Value, ROOT,
 };
 const B: usize = 16;
 
@@ -669,6 +669,126 @@ fn update_text_change_at() {
     assert_eq!(doc.text(&text).unwrap(), "a\nc\nb\n");
 }
 
+#[test]
+fn update_text_by_word() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "the quick brown fox").unwrap();
+    doc.update_diff_cursor();
+
+    doc.update_text_with_options(
+        &text,
+        "the quack brown fox",
+        UpdateTextOptions::new().granularity(TextGranularity::Word),
+    )
+    .unwrap();
+
+    assert_eq!(doc.text(&text).unwrap(), "the quack brown fox");
+    let patches = doc.diff_incremental();
+    assert_eq!(
+        patches.into_iter().map(|p| p.action).collect::<Vec<_>>(),
+        vec![
+            PatchAction::SpliceText {
+                index: 4,
+                value: ConcreteTextValue::new(
+                    "quack",
+                    TextRepresentation::String(TextEncoding::default())
+                ),
+                marks: None,
+            },
+            PatchAction::DeleteSeq {
+                index: 9,
+                length: 5
+            },
+        ]
+    );
+}
+
+#[test]
+fn update_text_by_line() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "one\ntwo\nthree\n").unwrap();
+    let initial_heads = doc.get_heads();
+
+    doc.update_text_with_options(
+        &text,
+        "one\ntoo\nthree\n",
+        UpdateTextOptions::new().granularity(TextGranularity::Line),
+    )
+    .unwrap();
+    doc.isolate(&initial_heads);
+    doc.update_text(&text, "one\ntwo\nthree\nfour\n").unwrap();
+    doc.integrate();
+
+    assert_eq!(doc.text(&text).unwrap(), "one\ntoo\nthree\nfour\n");
+}
+
+#[test]
+fn update_text_falls_back_to_coarser_diff_when_too_expensive() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "start abcdef end").unwrap();
+    doc.update_diff_cursor();
+
+    // A grapheme diff needs six edits and a word diff needs two, so with a maximum cost of one
+    // we replace everything between the common prefix and suffix
+    doc.update_text_with_options(
+        &text,
+        "start badcfe end",
+        UpdateTextOptions::new().max_cost(1),
+    )
+    .unwrap();
+
+    assert_eq!(doc.text(&text).unwrap(), "start badcfe end");
+    let patches = doc.diff_incremental();
+    assert_eq!(
+        patches.into_iter().map(|p| p.action).collect::<Vec<_>>(),
+        vec![
+            PatchAction::DeleteSeq {
+                index: 6,
+                length: 6
+            },
+            PatchAction::SpliceText {
+                index: 6,
+                value: ConcreteTextValue::new(
+                    "badcfe",
+                    TextRepresentation::String(TextEncoding::default())
+                ),
+                marks: None,
+            },
+        ]
+    );
+
+    // With a large enough budget we get the same result as a plain grapheme diff
+    let mut doc2 = AutoCommit::new();
+    let text2 = doc2.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc2.splice_text(&text2, 0, 0, "start abcdef end").unwrap();
+    doc2.update_diff_cursor();
+    doc2.update_text_with_options(
+        &text2,
+        "start badcfe end",
+        UpdateTextOptions::new().max_cost(100),
+    )
+    .unwrap();
+    let patches = doc2.diff_incremental();
+    assert_eq!(doc2.text(&text2).unwrap(), "start badcfe end");
+
+    let mut doc3 = AutoCommit::new();
+    let text3 = doc3.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc3.splice_text(&text3, 0, 0, "start abcdef end").unwrap();
+    doc3.update_diff_cursor();
+    doc3.update_text(&text3, "start badcfe end").unwrap();
+    let grapheme_patches = doc3.diff_incremental();
+    assert_eq!(
+        patches.into_iter().map(|p| p.action).collect::<Vec<_>>(),
+        grapheme_patches
+            .into_iter()
+            .map(|p| p.action)
+            .collect::<Vec<_>>()
+    );
+}
+
 proptest::proptest! {
     #[test]
     fn marks_are_okay(scenario in arb_scenario()) {
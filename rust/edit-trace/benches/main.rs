@@ -1,4 +1,7 @@
-use automerge::{transaction::Transactable, AutoCommit, Automerge, ObjType, ROOT};
+use automerge::{
+    transaction::Transactable, AutoCommit, Automerge, ObjType, ReadDoc, TextGranularity,
+    UpdateTextOptions, ROOT,
+};
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use std::fs;
 
@@ -39,6 +42,15 @@ fn load_trace_autotx(bytes: &[u8]) {
     AutoCommit::load(bytes).unwrap();
 }
 
+fn update_text_trace(
+    (mut doc, text, new_text): (AutoCommit, automerge::ObjId, String),
+    options: UpdateTextOptions,
+) {
+    doc.update_text_with_options(&text, new_text, options)
+        .unwrap();
+    doc.commit();
+}
+
 fn bench(c: &mut Criterion) {
     let contents = fs::read_to_string("edits.json").expect("cannot read edits file");
     let edits = jzon::parse(&contents).expect("cant parse edits");
@@ -95,7 +107,7 @@ fn bench(c: &mut Criterion) {
     );
 
     let commands_len = commands.len();
-    let mut doc = replay_trace_autotx(commands);
+    let mut doc = replay_trace_autotx(commands.clone());
     group.bench_with_input(
         BenchmarkId::new("save autotx", commands_len),
         &doc,
@@ -116,6 +128,41 @@ fn bench(c: &mut Criterion) {
     );
 
     group.finish();
+
+    // Replay the first half of the trace and then diff the document against the final text, this
+    // is a large rewrite of the kind which `update_text` sees when it is given a whole document
+    let mut group = c.benchmark_group("edit trace update_text");
+    let final_text = doc.text(doc.get(ROOT, "text").unwrap().unwrap().1).unwrap();
+    let half = replay_trace_autotx(commands[..commands_len / 2].to_vec());
+    let half_text = half.get(ROOT, "text").unwrap().unwrap().1;
+    let granularities = [
+        ("grapheme", TextGranularity::Grapheme),
+        ("word", TextGranularity::Word),
+        ("line", TextGranularity::Line),
+    ];
+    for (name, granularity) in granularities {
+        for max_cost in [None, Some(1000)] {
+            // An unbounded grapheme diff of half the trace takes tens of seconds per iteration
+            if granularity == TextGranularity::Grapheme && max_cost.is_none() {
+                continue;
+            }
+            let mut options = UpdateTextOptions::new().granularity(granularity);
+            let id = if let Some(max_cost) = max_cost {
+                options = options.max_cost(max_cost);
+                format!("{} max_cost={}", name, max_cost)
+            } else {
+                name.to_string()
+            };
+            group.bench_function(BenchmarkId::new(id, commands_len), |b| {
+                b.iter_batched(
+                    || (half.clone(), half_text.clone(), final_text.clone()),
+                    |input| update_text_trace(input, options),
+                    criterion::BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+    group.finish();
 }
 
 criterion_group!(benches, bench);
@@ -75,3 +75,23 @@ fn seek_raw(bencher: Bencher) {
         col.get(pos);
     });
 }
+
+#[inline(never)]
+#[divan::bench(max_time = Duration::from_secs(3))]
+fn find_uint_runs(bencher: Bencher) {
+    let col: ColumnData<UIntCursor> = (0..N).map(|i| i / 100).collect();
+    bencher.bench_local(|| {
+        let value = rand_u64() % (N / 100);
+        col.find_by_value(value).count();
+    });
+}
+
+#[inline(never)]
+#[divan::bench(max_time = Duration::from_secs(3))]
+fn find_uint(bencher: Bencher) {
+    let col: ColumnData<UIntCursor> = (0..N).map(|_| rand_u64()).collect();
+    bencher.bench_local(|| {
+        let value = rand_u64();
+        col.find_by_value(value).count();
+    });
+}
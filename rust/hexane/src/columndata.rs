@@ -1,13 +1,14 @@
 use super::aggregate::Acc;
 use super::aggregate::Agg;
 use super::cursor::{
-    ColumnCursor, HasAcc, HasMinMax, HasPos, Run, RunIter, ScanMeta, SpliceResult,
+    ColumnCursor, HasAcc, HasMinMax, HasPos, Run, RunIter, RunIterContaining1, RunIterContaining2,
+    ScanMeta, SpliceResult,
 };
 use super::encoder::Encoder;
 use super::pack::{MaybePackable, PackError, Packable};
 use super::raw::RawReader;
 use super::slab;
-use super::slab::{Slab, SlabTree, SpanTree};
+use super::slab::{ColumnIndex, SharedBytes, Slab, SlabStats, SlabTree, SpanTree};
 use super::Cow;
 
 use std::borrow::Borrow;
@@ -44,6 +45,13 @@ impl<C: ColumnCursor> ColumnData<C> {
         self.slabs.iter().map(|s| s.as_slice().len()).sum()
     }
 
+    /// An estimate of the heap memory held by the column: the slab data and the tree it is
+    /// stored in. Slab data is shared between clones of a column but is counted in full by each
+    /// of them.
+    pub fn heap_size(&self) -> usize {
+        self.slabs.heap_size() + self.slabs.iter().map(|s| s.heap_size()).sum::<usize>()
+    }
+
     pub fn get(&self, index: usize) -> Option<Option<Cow<'_, C::Item>>> {
         let range = index..(index + 1);
         let mut iter = self.iter_range(range);
@@ -733,6 +741,83 @@ impl<C: ColumnCursor> ColumnData<C> {
         data
     }
 
+    /// Write the bytes of every slab as they are, returning an index of where they are
+    ///
+    /// Unlike [`ColumnData::save`] runs are not merged across slab boundaries so the output is
+    /// not the canonical encoding of the column, but it can be opened again with
+    /// [`ColumnData::load_with_index`] in time proportional to the number of slabs.
+    pub fn save_with_index(&self) -> (Vec<u8>, ColumnIndex) {
+        let mut data = Vec::with_capacity(self.slabs.iter().map(|s| s.byte_len()).sum());
+        for s in &self.slabs {
+            data.extend_from_slice(s.as_slice());
+        }
+        (data, ColumnIndex::new(self.slabs.iter()))
+    }
+
+    /// Per slab statistics, kept up to date as the column is spliced
+    pub fn slab_stats(&self) -> impl Iterator<Item = SlabStats> + '_ {
+        self.slabs.iter().map(|s| s.stats())
+    }
+
+    /// The index of the slabs of this column, see [`ColumnData::save_with_index`]
+    pub fn index(&self) -> ColumnIndex {
+        ColumnIndex::new(self.slabs.iter())
+    }
+
+    /// Open a column written by [`ColumnData::save_with_index`]
+    ///
+    /// The slabs share `data` rather than copying it, so it can be any buffer of bytes, such as a
+    /// memory mapped file. Each slab is decoded once to check it
+    /// against the index and its min, max and distinct counts are recomputed rather than taken
+    /// from the index. The value a delta slab starts from can't be checked, like the bytes of
+    /// the runs it is part of what the column holds.
+    pub fn load_with_index(data: SharedBytes, index: &ColumnIndex) -> Result<Self, PackError> {
+        let mut slabs = index.slabs(data)?;
+        for (i, slab) in slabs.iter().enumerate() {
+            let invalid = |error: &str| {
+                PackError::invalid_value("column index", format!("slab {} {}", i, error))
+            };
+            let mut cursor = C::new(slab);
+            let data = slab.as_slice();
+            let mut len = 0;
+            while let Some(run) = cursor.try_next(data)? {
+                len += run.count;
+            }
+            if len != slab.len() {
+                return Err(invalid("has a different number of items to the data"));
+            }
+            if cursor.acc() != slab.acc() {
+                return Err(invalid("has a different total to the data"));
+            }
+        }
+        for slab in &mut slabs {
+            if slab.is_empty() {
+                slab.set_min_max(Agg::default(), Agg::default());
+                slab.set_distinct(0, 0);
+            } else {
+                C::compute_stats(std::slice::from_mut(slab));
+            }
+        }
+        Ok(Self::init(index.items(), SlabTree::load(slabs)))
+    }
+
+    /// Open a column written by [`ColumnData::save_with_index`] without decoding it
+    ///
+    /// This takes time proportional to the number of slabs but trusts the index as it is, so
+    /// should only be used for an index and data which came from this process or a store it
+    /// trusts. A corrupt `data` is not detected until it is read and wrong stats in the index
+    /// give wrong answers to lookups.
+    pub fn load_with_index_unverified(
+        data: SharedBytes,
+        index: &ColumnIndex,
+    ) -> Result<Self, PackError> {
+        let mut slabs = index.slabs(data)?;
+        for (slab, s) in slabs.iter_mut().zip(&index.slabs) {
+            slab.set_distinct(s.distinct, s.nulls);
+        }
+        Ok(Self::init(index.items(), SlabTree::load(slabs)))
+    }
+
     pub fn push<'b, M>(&mut self, value: M) -> Acc
     where
         M: MaybePackable<'b, C::Item> + Clone,
@@ -795,8 +880,12 @@ impl<C: ColumnCursor> ColumnData<C> {
                 mut slabs,
             } => {
                 acc += group;
-                C::compute_min_max(&mut slabs); // this should be handled by slabwriter.finish
+                C::compute_stats(&mut slabs); // this should be handled by slabwriter.finish
                 self.len = self.len + add - del;
+                if slabs.is_empty() && self.slabs.len() == 1 {
+                    // everything was deleted
+                    slabs.push(Slab::default());
+                }
                 self.slabs.splice(cursor.index..(cursor.index + 1), slabs);
                 assert!(!self.slabs.is_empty());
             }
@@ -834,7 +923,11 @@ impl<C: ColumnCursor> ColumnData<C> {
     }
 
     pub fn init_empty(len: usize) -> Self {
-        let new_slab = C::init_empty(len);
+        let mut new_slab = [C::init_empty(len)];
+        if len > 0 {
+            C::compute_stats(&mut new_slab);
+        }
+        let [new_slab] = new_slab;
         let mut slabs = SlabTree::default();
         slabs.push(new_slab);
         assert!(!slabs.is_empty());
@@ -876,10 +969,14 @@ where
             .iter_where(move |_, s| s.intersects(start..end))
             .flat_map(move |cursor| {
                 let pos = cursor.weight.pos();
-                cursor
-                    .element
-                    .run_iter::<C>()
-                    .containing_range(pos, start..end)
+                let slab = cursor.element;
+                let uniform = slab.uniform_agg();
+                if uniform.is_some() && (start..end).contains(&uniform.as_usize()) {
+                    // every item is in range, no need to decode the runs
+                    RunIterContaining2::whole(pos..pos + slab.len())
+                } else {
+                    slab.run_iter::<C>().containing_range(pos, start..end)
+                }
             })
     }
 
@@ -890,7 +987,12 @@ where
             .iter_where(move |_, s| agg.is_some() && agg >= s.min() && agg <= s.max())
             .flat_map(move |cursor| {
                 let pos = cursor.weight.pos();
-                cursor.element.run_iter::<C>().containing_agg(pos, agg)
+                let slab = cursor.element;
+                if slab.uniform_agg() == agg {
+                    RunIterContaining1::whole(pos..pos + slab.len())
+                } else {
+                    slab.run_iter::<C>().containing_agg(pos, agg)
+                }
             })
     }
 }
@@ -941,7 +1043,7 @@ where
 
 #[cfg(test)]
 pub(crate) mod tests {
-    use super::super::boolean::BooleanCursor;
+    use super::super::boolean::{BooleanCursor, BooleanCursorInternal};
     use super::super::delta::{DeltaCursor, DeltaCursorInternal};
     use super::super::rle::{ByteCursor, RleCursor, StrCursor, UIntCursor};
     use super::super::test::ColExport;
@@ -949,6 +1051,7 @@ pub(crate) mod tests {
     use rand::prelude::*;
     use rand::rngs::SmallRng;
     use std::cmp::{max, min};
+    use std::sync::Arc;
 
     const FUZZ_SIZE: usize = 1_000;
 
@@ -966,6 +1069,7 @@ pub(crate) mod tests {
             let (_, c) = C::seek(slab.len(), slab);
             assert_eq!(c.min(), slab.min());
             assert_eq!(c.max(), slab.max());
+            assert_eq!(C::count_distinct(slab), (slab.distinct(), slab.nulls()));
         }
         assert_eq!(vec, &col.to_vec());
     }
@@ -1604,6 +1708,211 @@ pub(crate) mod tests {
         assert_eq!(range, 23..25);
     }
 
+    fn test_index_round_trip<C: ColumnCursor>(col: &ColumnData<C>) -> ColumnData<C> {
+        let (data, index) = col.save_with_index();
+        let data: SharedBytes = Arc::new(data);
+        let index = ColumnIndex::load(&index.save()).unwrap();
+        assert_eq!(index, col.index());
+        assert_eq!(index.len(), col.slabs.len());
+        let unverified = ColumnData::<C>::load_with_index_unverified(data.clone(), &index).unwrap();
+        let copy = ColumnData::<C>::load_with_index(data, &index).unwrap();
+        assert_eq!(copy.to_vec(), col.to_vec());
+        assert_eq!(copy.save(), col.save());
+        for ((a, b), c) in copy
+            .slabs
+            .iter()
+            .zip(col.slabs.iter())
+            .zip(unverified.slabs.iter())
+        {
+            assert_eq!(a, b);
+            assert_eq!(a, c);
+        }
+        copy
+    }
+
+    #[test]
+    fn column_index_round_trip() {
+        let mut rng = make_rng();
+
+        let mut col = ColumnData::<RleCursor<16, u64>>::new();
+        let mut data: Vec<Option<u64>> = vec![];
+        for _ in 0..100 {
+            let (index, values) = generate_splice(data.len(), &mut rng);
+            test_splice(&mut data, &mut col, index, values);
+        }
+        let mut copy = test_index_round_trip(&col);
+        copy.splice(3, 0, vec![7, 7, 7]);
+        data.splice(3..3, vec![Some(7), Some(7), Some(7)]);
+        assert_eq!(copy.to_vec(), data);
+        assert_eq!(
+            copy.find_by_value(7).count(),
+            col.find_by_value(7).count() + 3
+        );
+
+        let mut col = ColumnData::<DeltaCursorInternal<8>>::new();
+        let mut data: Vec<Option<i64>> = vec![];
+        for _ in 0..100 {
+            let (index, values) = generate_splice(data.len(), &mut rng);
+            test_splice(&mut data, &mut col, index, values);
+        }
+        test_index_round_trip(&col);
+
+        let mut col = ColumnData::<BooleanCursorInternal<4>>::new();
+        col.splice(0, 0, bool::rand_vec(&mut rng));
+        col.splice(0, 0, vec![true, false, true, true]);
+        test_index_round_trip(&col);
+
+        let col: ColumnData<StrCursor> = ColumnData::new();
+        let copy = test_index_round_trip(&col);
+        assert!(copy.is_empty());
+    }
+
+    #[test]
+    fn column_index_must_match_the_data() {
+        let mut col = ColumnData::<RleCursor<8, u64>>::new();
+        col.splice(0, 0, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        let (data, index) = col.save_with_index();
+        let shorter = Arc::new(data[1..].to_vec());
+        assert!(ColumnData::<UIntCursor>::load_with_index(shorter, &index).is_err());
+        let mut longer = data.clone();
+        longer.push(0);
+        assert!(ColumnData::<UIntCursor>::load_with_index(Arc::new(longer), &index).is_err());
+        let data: SharedBytes = Arc::new(data);
+        let mut saved = index.save();
+        saved.push(0);
+        assert!(ColumnIndex::load(&saved).is_err());
+        assert!(ColumnIndex::load(&saved[..saved.len() - 3]).is_err());
+
+        // the format is versioned
+        let mut saved = index.save();
+        assert_eq!(saved[0], 1);
+        saved[0] = 2;
+        assert!(ColumnIndex::load(&saved).is_err());
+        assert!(ColumnIndex::load(&[]).is_err());
+
+        // slabs whose bytes don't hold the items the index claims are rejected
+        let mut short = index.clone();
+        short.slabs[0].len -= 1;
+        assert!(ColumnData::<UIntCursor>::load_with_index(data.clone(), &short).is_err());
+        let mut wrong_acc = index.clone();
+        wrong_acc.slabs[0].acc += Acc::from(1);
+        assert!(ColumnData::<UIntCursor>::load_with_index(data.clone(), &wrong_acc).is_err());
+
+        // the stats in the index are recomputed rather than trusted
+        let mut lying = index.clone();
+        lying.slabs[0].min = Agg::from(100);
+        lying.slabs[0].max = Agg::from(100);
+        lying.slabs[0].distinct = 1;
+        let copy = ColumnData::<UIntCursor>::load_with_index(data.clone(), &lying).unwrap();
+        assert_eq!(copy.find_by_value(1).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(copy.find_by_value(100).count(), 0);
+        assert_eq!(copy.index(), index);
+    }
+
+    #[test]
+    fn column_index_slabs_borrow_any_bytes() {
+        // bytes held by something other than a Vec, as a memory mapped file would be
+        struct Mapped(Box<[u8]>);
+        impl AsRef<[u8]> for Mapped {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+        let mut col = ColumnData::<RleCursor<8, u64>>::new();
+        col.splice(0, 0, (0..100).map(|i| Some(i % 7)));
+        let (data, index) = col.save_with_index();
+        let mapped = Arc::new(Mapped(data.into_boxed_slice()));
+        let mut copy = ColumnData::<UIntCursor>::load_with_index(mapped.clone(), &index).unwrap();
+        assert_eq!(copy.to_vec(), col.to_vec());
+        assert_eq!(Arc::strong_count(&mapped), copy.slabs.len() + 1);
+        copy.splice(0, 1, [Some(7)]);
+        assert_eq!(copy.to_vec()[0], Some(7));
+        assert_eq!(copy.to_vec()[1..], col.to_vec()[1..]);
+    }
+
+    #[test]
+    fn heap_size_covers_slab_data() {
+        let mut col = ColumnData::<RleCursor<8, u64>>::new();
+        let empty = col.heap_size();
+        col.splice(0, 0, (0..1000).map(Some));
+        assert!(col.slabs.len() > 1);
+        assert!(col.heap_size() > empty + col.byte_len());
+        let slabs = col.slabs.iter().map(|s| s.heap_size()).sum::<usize>();
+        assert!(col.heap_size() > slabs);
+    }
+
+    #[test]
+    fn slab_stats_prune_value_lookups() {
+        let mut rng = make_rng();
+        let mut col = ColumnData::<RleCursor<8, u64>>::new();
+        let mut data: Vec<Option<u64>> = vec![];
+        for _ in 0..20 {
+            let value = rng.gen_range(1..4);
+            let values = vec![Some(value); rng.gen_range(1..40)];
+            let end = data.len();
+            test_splice(&mut data, &mut col, end, values);
+            let index = rng.gen_range(0..data.len());
+            test_splice(&mut data, &mut col, index, vec![None]);
+        }
+        let stats = col.slab_stats().collect::<Vec<_>>();
+        assert_eq!(stats.iter().map(|s| s.len).sum::<usize>(), data.len());
+        assert_eq!(stats.iter().map(|s| s.nulls).sum::<usize>(), 20);
+        assert!(stats.iter().all(|s| s.distinct <= 3));
+
+        for value in 1..4 {
+            let expected = data
+                .iter()
+                .enumerate()
+                .filter_map(|(i, v)| (*v == Some(value)).then_some(i))
+                .collect::<Vec<_>>();
+            assert_eq!(col.find_by_value(value).collect::<Vec<_>>(), expected);
+            let range = value as usize..value as usize + 1;
+            assert_eq!(col.find_by_range(range).collect::<Vec<_>>(), expected);
+        }
+
+        let mut col = ColumnData::<RleCursor<8, u64>>::new();
+        col.splice(0, 0, vec![7; 100]);
+        assert_eq!(
+            col.slabs.get(0).map(|s| s.uniform_agg()),
+            Some(Agg::from(7))
+        );
+        assert_eq!(
+            col.find_by_value(7).collect::<Vec<_>>(),
+            (0..100).collect::<Vec<_>>()
+        );
+        assert_eq!(col.find_by_range(6..8).count(), 100);
+        col.splice(50, 0, vec![None::<u64>]);
+        assert_eq!(
+            col.slabs.get(0).map(|s| s.uniform_agg()),
+            Some(Agg::default())
+        );
+        assert_eq!(col.find_by_value(7).count(), 100);
+
+        let mut col = ColumnData::<DeltaCursorInternal<8>>::new();
+        col.splice(0, 0, vec![5; 30]);
+        col.splice(10, 0, vec![1, 2, 3]);
+        assert_eq!(
+            col.find_by_value(5).collect::<Vec<_>>(),
+            (0..10).chain(13..33).collect::<Vec<_>>()
+        );
+        assert_eq!(col.find_by_value(2).collect::<Vec<_>>(), vec![11]);
+        let copy = test_index_round_trip(&col);
+        assert_eq!(
+            copy.slab_stats().collect::<Vec<_>>(),
+            col.slab_stats().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn splice_deleting_everything() {
+        let mut col = ColumnData::<UIntCursor>::new();
+        col.splice(0, 0, vec![1, 2, 3]);
+        col.splice::<u64, _>(0, 3, vec![]);
+        assert_eq!(col.len(), 0);
+        col.splice(0, 0, vec![4]);
+        assert_eq!(col.to_vec(), vec![Some(4)]);
+    }
+
     #[test]
     fn splice_on_boundary() {
         let data = vec![1, 2, 3, 4, 5, 6];
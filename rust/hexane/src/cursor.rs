@@ -204,6 +204,44 @@ pub trait ColumnCursor: Debug + Clone + Copy + PartialEq + Default {
         }
     }
 
+    /// Counts the distinct non-null values and the nulls in a slab
+    ///
+    /// An over count is allowed as lookups only rely on a slab holding a
+    /// single distinct value.
+    fn count_distinct(slab: &Slab) -> (usize, usize) {
+        let (_, distinct, nulls) = Self::scan_distinct(slab);
+        (distinct, nulls)
+    }
+
+    /// Reads a slab once, counting its distinct values and nulls as in
+    /// [`ColumnCursor::count_distinct`] and returning the cursor at its end
+    fn scan_distinct(slab: &Slab) -> (Self, usize, usize) {
+        let mut values = vec![];
+        let mut nulls = 0;
+        let mut cursor = Self::new(slab);
+        let data = slab.as_slice();
+        while let Some(run) = cursor.next(data) {
+            match run.value {
+                Some(v) => values.push(v),
+                None => nulls += run.count,
+            }
+        }
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        values.dedup();
+        (cursor, values.len(), nulls)
+    }
+
+    /// Sets the min, max and distinct counts of slabs which have just been
+    /// written, reading each slab once
+    fn compute_stats(slabs: &mut [Slab]) {
+        for s in slabs {
+            let (cursor, distinct, nulls) = Self::scan_distinct(s);
+            debug_assert_eq!(cursor.index(), s.len());
+            s.set_min_max(cursor.min(), cursor.max());
+            s.set_distinct(distinct, nulls);
+        }
+    }
+
     fn is_empty(v: Option<Cow<'_, Self::Item>>) -> bool {
         v.is_none()
     }
@@ -310,7 +348,8 @@ pub trait ColumnCursor: Debug + Clone + Copy + PartialEq + Default {
             return (None, Self::new(slab));
         } else {
             let mut cursor = Self::new(slab);
-            while let Some(val) = cursor.next(slab.as_slice()) {
+            let data = slab.as_slice();
+            while let Some(val) = cursor.next(data) {
                 if cursor.index() >= index {
                     return (Some(val), cursor);
                 }
@@ -375,7 +414,7 @@ pub trait ColumnCursor: Debug + Clone + Copy + PartialEq + Default {
                 slab.len() + add
             );
         }
-        if slabs.is_empty() {
+        if slabs.is_empty() && del == 0 {
             SpliceResult::Noop
         } else {
             SpliceResult::Replace {
@@ -549,6 +588,28 @@ where
     pub(crate) range: Range<usize>,
 }
 
+impl<C: ColumnCursor> RunIterContaining1<'_, C> {
+    pub(crate) fn whole(range: Range<usize>) -> Self {
+        RunIterContaining1 {
+            iter: RunIter::empty(),
+            pos: range.end,
+            target: Agg::default(),
+            range,
+        }
+    }
+}
+
+impl<C: ColumnCursor> RunIterContaining2<'_, C> {
+    pub(crate) fn whole(range: Range<usize>) -> Self {
+        RunIterContaining2 {
+            iter: RunIter::empty(),
+            pos: range.end,
+            target: 0..0,
+            range,
+        }
+    }
+}
+
 impl<C: ColumnCursor> Iterator for RunIterContaining1<'_, C> {
     type Item = usize;
 
@@ -252,6 +252,27 @@ impl<const B: usize> ColumnCursor for DeltaCursorInternal<B> {
         }
     }
 
+    // runs with a zero step hold a single value, any other run is counted as
+    // `count` distinct values which may over count
+    fn scan_distinct(slab: &Slab) -> (Self, usize, usize) {
+        let mut values = vec![];
+        let mut stepped = 0;
+        let mut nulls = 0;
+        let mut cursor = Self::new(slab);
+        let data = slab.as_slice();
+        while let Some(run) = cursor.next(data) {
+            match run.value.as_deref() {
+                None => nulls += run.count,
+                Some(0) => values.push(cursor.abs),
+                Some(_) => stepped += run.count,
+            }
+        }
+        values.sort_unstable();
+        values.dedup();
+        let distinct = (values.len() + stepped).min(slab.len() - nulls);
+        (cursor, distinct, nulls)
+    }
+
     fn min(&self) -> Agg {
         self.min
     }
@@ -0,0 +1,374 @@
+use super::aggregate::Acc;
+use super::columndata::ColumnData;
+use super::cursor::{ColumnCursor, Run, ScanMeta};
+use super::encoder::{Encoder, EncoderState, SpliceEncoder};
+use super::pack::{PackError, Packable};
+use super::rle::RleCursor;
+use super::slab::{Slab, SlabWeight, SlabWriter};
+use super::Cow;
+
+use std::ops::Range;
+
+// A dictionary encoded string column is a sequence of self contained blocks
+//
+//   block := uleb(entries) entry* uleb(body_len) body
+//   entry := uleb(len) utf8
+//   body  := rle encoded nullable uleb indexes into the entries
+//
+// A block is never larger than about `B` bytes so a splice only ever
+// re-encodes the blocks of a single slab and slabs can be saved by copying
+// their bytes
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DictCursorInternal<const B: usize> {
+    index: usize,
+    offset: usize,
+    block: Option<Block<B>>,
+}
+
+pub type DictCursor = DictCursorInternal<256>;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Block<const B: usize> {
+    dict: usize,
+    entries: usize,
+    body_start: usize,
+    body_end: usize,
+    rle: RleCursor<B, u64>,
+}
+
+impl<const B: usize> Block<B> {
+    fn parse(slab: &[u8], offset: usize) -> Result<Self, PackError> {
+        let (header, entries) = usize::unpack(&slab[offset..])?;
+        let dict = offset + header;
+        let mut pos = dict;
+        for _ in 0..*entries {
+            let (width, _) = str::unpack(&slab[pos..])?;
+            pos += width;
+        }
+        let (header, body_len) = usize::unpack(&slab[pos..])?;
+        let body_start = pos + header;
+        let body_end = body_start + *body_len;
+        if body_end > slab.len() {
+            return Err(PackError::BadFormat);
+        }
+        Ok(Block {
+            dict,
+            entries: *entries,
+            body_start,
+            body_end,
+            rle: RleCursor::default(),
+        })
+    }
+
+    fn entry<'a>(&self, slab: &'a [u8], index: u64) -> Result<Cow<'a, str>, PackError> {
+        if index as usize >= self.entries {
+            return Err(PackError::invalid_value(
+                "dictionary index",
+                format!("{} of {}", index, self.entries),
+            ));
+        }
+        let mut pos = self.dict;
+        for _ in 0..index {
+            let (width, _) = str::unpack(&slab[pos..])?;
+            pos += width;
+        }
+        let (_, value) = str::unpack(&slab[pos..])?;
+        Ok(value)
+    }
+}
+
+/// The block being encoded: its dictionary and runs of indexes into it
+#[derive(Debug, Default, Clone)]
+pub struct DictState<'a, const B: usize> {
+    pub(crate) dict: Vec<Cow<'a, str>>,
+    pub(crate) runs: Vec<(usize, Option<u64>)>,
+    pub(crate) width: usize,
+}
+
+impl<'a, const B: usize> DictState<'a, B> {
+    pub(crate) fn lookup(&mut self, value: Cow<'a, str>) -> u64 {
+        if let Some(index) = self.dict.iter().position(|v| *v == value) {
+            index as u64
+        } else {
+            self.width += str::width(&value);
+            self.dict.push(value);
+            self.dict.len() as u64 - 1
+        }
+    }
+}
+
+impl<const B: usize> ColumnCursor for DictCursorInternal<B> {
+    type Item = str;
+    type State<'a> = DictState<'a, B>;
+    type PostState<'a> = Vec<Run<'a, str>>;
+    type Export = Option<String>;
+    type SlabIndex = SlabWeight;
+
+    fn empty() -> Self {
+        Self::default()
+    }
+
+    fn finish<'a>(_slab: &'a Slab, _writer: &mut SlabWriter<'a, str>, _cursor: Self) {}
+
+    fn finalize_state<'a>(
+        _slab: &'a Slab,
+        encoder: &mut Encoder<'a, Self>,
+        post: Self::PostState<'a>,
+        _cursor: Self,
+    ) -> Option<Self> {
+        for run in post {
+            encoder.append_chunk(run);
+        }
+        encoder.flush();
+        None
+    }
+
+    fn copy_between<'a>(
+        _slab: &'a [u8],
+        _writer: &mut SlabWriter<'a, str>,
+        _c0: Self,
+        _c1: Self,
+        _run: Run<'a, str>,
+        _size: usize,
+    ) -> Self::State<'a> {
+        // only called from copy_slab and we override that
+        DictState::default()
+    }
+
+    fn slab_size() -> usize {
+        B
+    }
+
+    fn splice_encoder(index: usize, del: usize, slab: &Slab) -> SpliceEncoder<'_, Self> {
+        let mut current = SlabWriter::new(B, false);
+        let mut state = DictState::default();
+        let mut post = vec![];
+        let mut deleted = 0;
+        let mut pos = 0;
+        let mut cursor = Self::empty();
+        let data = slab.as_slice();
+        while let Some(run) = cursor.next(data) {
+            let start = pos;
+            pos += run.count;
+            let keep = index.clamp(start, pos) - start;
+            let skip = (index + del).clamp(start, pos) - start;
+            if keep > 0 {
+                state.append_chunk(&mut current, Run::new(keep, run.value.clone()));
+            }
+            deleted += skip - keep;
+            if run.count > skip {
+                post.push(Run::new(run.count - skip, run.value));
+            }
+        }
+
+        SpliceEncoder {
+            encoder: Encoder::init(current, state),
+            slab,
+            post,
+            acc: Acc::new(),
+            deleted,
+            overflow: del - deleted,
+            cursor,
+        }
+    }
+
+    fn export_splice<'a, I>(data: &mut Vec<Self::Export>, range: Range<usize>, values: I)
+    where
+        I: Iterator<Item = Option<Cow<'a, str>>>,
+    {
+        data.splice(range, values.map(|e| e.map(|i| i.into_owned())));
+    }
+
+    fn try_next<'a>(&mut self, slab: &'a [u8]) -> Result<Option<Run<'a, str>>, PackError> {
+        loop {
+            if let Some(block) = &mut self.block {
+                let body = &slab[block.body_start..block.body_end];
+                if let Some(run) = block.rle.try_next(body)? {
+                    let value = match run.value {
+                        Some(index) => Some(block.entry(slab, *index)?),
+                        None => None,
+                    };
+                    self.index += run.count;
+                    self.offset = block.body_start + block.rle.offset;
+                    return Ok(Some(Run::new(run.count, value)));
+                }
+                self.offset = block.body_end;
+                self.block = None;
+            }
+            if self.offset >= slab.len() {
+                return Ok(None);
+            }
+            self.block = Some(Block::parse(slab, self.offset)?);
+        }
+    }
+
+    fn index(&self) -> usize {
+        self.index
+    }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn load_with(data: &[u8], m: &ScanMeta) -> Result<ColumnData<Self>, PackError> {
+        let mut writer = SlabWriter::<str>::new(B, true);
+        let mut offset = 0;
+        let mut slab_start = 0;
+        let mut items = 0;
+        let mut len = 0;
+        while offset < data.len() {
+            let mut block = Block::<B>::parse(data, offset)?;
+            let body = &data[block.body_start..block.body_end];
+            while let Some(run) = block.rle.try_next(body)? {
+                let value = match run.value {
+                    Some(index) => Some(block.entry(data, *index)?),
+                    None => None,
+                };
+                str::validate(value.as_deref(), m)?;
+                items += run.count;
+            }
+            offset = block.body_end;
+            if offset - slab_start >= B {
+                writer.copy(data, slab_start..offset, 0, items, Acc::new(), None);
+                writer.manual_slab_break();
+                slab_start = offset;
+                len += items;
+                items = 0;
+            }
+        }
+        writer.copy(data, slab_start..offset, 0, items, Acc::new(), None);
+        len += items;
+        Ok(writer.into_column(len))
+    }
+
+    fn init_empty(len: usize) -> Slab {
+        let mut writer = SlabWriter::<str>::new(usize::MAX, false);
+        let mut state = DictState::<B>::default();
+        state.append_chunk(&mut writer, Run::new(len, None));
+        state.flush(&mut writer);
+        writer.finish().pop().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::super::columndata::ColumnData;
+    use super::super::rle::StrCursor;
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn dict_column_stores_each_string_once_per_block() {
+        let mut col: ColumnData<DictCursor> = ColumnData::new();
+        col.splice(0, 0, vec!["title", "body", "title", "body", "title"]);
+        col.splice(2, 0, vec![None::<String>, None]);
+        assert_eq!(
+            col.to_vec(),
+            vec![
+                Some("title".to_string()),
+                Some("body".to_string()),
+                None,
+                None,
+                Some("title".to_string()),
+                Some("body".to_string()),
+                Some("title".to_string()),
+            ]
+        );
+        let data = col.save();
+        assert_eq!(data[..12], *b"\x02\x05title\x04body");
+        let copy = DictCursor::load(&data).unwrap();
+        assert_eq!(copy.to_vec(), col.to_vec());
+
+        let inline: ColumnData<StrCursor> = col.iter().collect();
+        assert!(data.len() < inline.save().len());
+    }
+
+    #[test]
+    fn dict_column_splits_into_blocks() {
+        let mut col: ColumnData<DictCursorInternal<16>> = ColumnData::new();
+        let values = (0..100)
+            .map(|i| format!("key{}", i % 7))
+            .collect::<Vec<_>>();
+        col.splice(0, 0, values.clone());
+        assert!(col.slabs.len() > 1);
+        col.splice::<String, _>(10, 1, vec![]);
+        col.splice(50, 0, vec!["key2".to_string()]);
+        let mut expected = values;
+        expected.remove(10);
+        expected.insert(50, "key2".to_string());
+        let expected = expected.into_iter().map(Some).collect::<Vec<_>>();
+        assert_eq!(col.to_vec(), expected);
+        let copy = DictCursorInternal::<16>::load(&col.save()).unwrap();
+        assert_eq!(copy.to_vec(), expected);
+        assert_eq!(copy.get(50).flatten().as_deref(), Some("key2"));
+    }
+
+    #[test]
+    fn dict_column_value_lookups() {
+        let values = ["a", "a", "b", "c", "c", "c", "d"];
+        let dict: ColumnData<DictCursorInternal<8>> = values.iter().copied().collect();
+        let inline: ColumnData<StrCursor> = values.iter().copied().collect();
+        for value in ["a", "b", "c", "d", "e"] {
+            assert_eq!(
+                dict.scope_to_value(Some(value), ..),
+                inline.scope_to_value(Some(value), ..)
+            );
+        }
+        assert_eq!(
+            dict.find_by_value(1).collect::<Vec<_>>(),
+            inline.find_by_value(1).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn dict_column_init_empty_and_bad_data() {
+        let col = ColumnData::<DictCursor>::init_empty(5);
+        assert_eq!(col.to_vec(), vec![None; 5]);
+        assert_eq!(DictCursor::load(&col.save()).unwrap().len(), 5);
+
+        assert!(DictCursor::load(&[1, 1, b'a', 2, 2, 5]).is_err());
+        assert!(DictCursor::load(&[0, 9]).is_err());
+    }
+
+    fn arb_splices() -> impl Strategy<Value = Vec<(usize, usize, Vec<Option<String>>)>> {
+        let value = prop_oneof![
+            Just(None),
+            prop::sample::select(vec!["a", "bb", "ccc", "a much longer key"])
+                .prop_map(|s| Some(s.to_string())),
+            "[a-z]{0,12}".prop_map(Some),
+        ];
+        proptest::collection::vec(
+            (
+                any::<usize>(),
+                0..4_usize,
+                proptest::collection::vec(value, 0..20),
+            ),
+            1..40,
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn proptest_dict_splice(splices in arb_splices()) {
+            let mut col = ColumnData::<DictCursorInternal<32>>::new();
+            let mut vec: Vec<Option<String>> = vec![];
+            for (index, del, values) in splices {
+                let index = index % (vec.len() + 1);
+                let del = del.min(vec.len() - index);
+                vec.splice(index..index + del, values.clone());
+                // a single splice can't delete across a slab boundary
+                for _ in 0..del {
+                    col.splice::<String, _>(index, 1, vec![]);
+                }
+                col.splice(index, 0, values);
+                assert_eq!(col.to_vec(), vec);
+            }
+            let copy = DictCursorInternal::<32>::load(&col.save()).unwrap();
+            assert_eq!(copy.to_vec(), vec);
+            for (index, value) in vec.iter().enumerate() {
+                assert_eq!(col.get(index).flatten().as_deref(), value.as_deref());
+            }
+        }
+    }
+}
@@ -3,6 +3,7 @@ use super::boolean::BooleanState;
 use super::columndata::ColumnData;
 use super::cursor::{ColumnCursor, Run};
 use super::delta::DeltaState;
+use super::dict::DictState;
 use super::pack::{MaybePackable, Packable};
 use super::rle::RleState;
 use super::slab::{Slab, SlabWriter};
@@ -18,6 +19,7 @@ pub trait Writer<'a, P: Packable + ?Sized> {
     fn flush_run(&mut self, count: i64, value: Cow<'a, P>);
     fn flush_bool_run(&mut self, count: usize, value: bool);
     fn flush_bytes(&mut self, bytes: Cow<'a, [u8]>);
+    fn flush_block(&mut self, block: Vec<u8>, items: usize);
 }
 
 impl<'a, P: Packable + ?Sized> Writer<'a, P> for Vec<u8> {
@@ -43,6 +45,10 @@ impl<'a, P: Packable + ?Sized> Writer<'a, P> for Vec<u8> {
     fn flush_bytes(&mut self, bytes: Cow<'a, [u8]>) {
         self.extend_from_slice(bytes.as_ref())
     }
+
+    fn flush_block(&mut self, block: Vec<u8>, _items: usize) {
+        self.extend_from_slice(&block)
+    }
 }
 
 pub trait EncoderState<'a, P: Packable + ?Sized + 'a>: Debug + Default + Clone {
@@ -265,6 +271,66 @@ impl<'a> EncoderState<'a, [u8]> for () {
     fn flush<W: Writer<'a, [u8]>>(&mut self, _writer: &mut W) {}
 }
 
+impl<'a, const B: usize> EncoderState<'a, str> for DictState<'a, B> {
+    fn is_empty(&self) -> bool {
+        self.dict.is_empty()
+    }
+
+    fn append_chunk<W: Writer<'a, str>>(&mut self, writer: &mut W, run: Run<'a, str>) -> usize {
+        let count = run.count;
+        if count == 0 {
+            return 0;
+        }
+        let index = run.value.map(|value| self.lookup(value));
+        match self.runs.last_mut() {
+            Some((n, last)) if *last == index => *n += count,
+            _ => {
+                self.width += 2;
+                self.runs.push((count, index));
+            }
+        }
+        if self.width >= B {
+            self.flush(writer);
+        }
+        count
+    }
+
+    fn copy_slab<C: ColumnCursor<State<'a> = Self, Item = str>>(
+        &mut self,
+        writer: &mut SlabWriter<'a, str>,
+        slab: &'a Slab,
+    ) {
+        // blocks are self contained so slabs can be copied as is
+        self.flush(writer);
+        let bytes = slab.as_slice();
+        writer.copy(bytes, 0..bytes.len(), 0, slab.len(), slab.acc(), None);
+    }
+
+    fn flush<W: Writer<'a, str>>(&mut self, writer: &mut W) {
+        if self.runs.is_empty() {
+            return;
+        }
+        let mut block = vec![];
+        leb128::write::unsigned(&mut block, self.dict.len() as u64).unwrap();
+        for value in &self.dict {
+            str::pack(value, &mut block);
+        }
+        let mut body = vec![];
+        let mut rle = RleState::<'_, u64>::default();
+        let mut items = 0;
+        for (count, index) in std::mem::take(&mut self.runs) {
+            items += count;
+            let value = index.map(Cow::Owned);
+            rle.append_chunk(&mut body, Run { count, value });
+        }
+        rle.flush(&mut body);
+        leb128::write::unsigned(&mut block, body.len() as u64).unwrap();
+        block.extend(body);
+        writer.flush_block(block, items);
+        *self = Self::default();
+    }
+}
+
 impl<'a, P: Packable + ?Sized> EncoderState<'a, P> for RleState<'a, P> {
     fn is_empty(&self) -> bool {
         match self {
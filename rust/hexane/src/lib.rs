@@ -32,6 +32,7 @@ pub(crate) mod boolean;
 pub(crate) mod columndata;
 pub(crate) mod cursor;
 pub(crate) mod delta;
+pub(crate) mod dict;
 pub(crate) mod encoder;
 pub(crate) mod leb128;
 pub(crate) mod pack;
@@ -47,11 +48,17 @@ pub use boolean::BooleanCursor;
 pub use columndata::{ColAccIter, ColGroupItem, ColGroupIter, ColumnData, ColumnDataIter};
 pub use cursor::{ColumnCursor, CursorIter, HasAcc, HasPos, Run, ScanMeta, SpliceDel};
 pub use delta::DeltaCursor;
+pub use dict::DictCursor;
 pub use encoder::{Encoder, EncoderState};
 pub use leb128::{lebsize, ulebsize};
-pub use pack::{MaybePackable, PackError, Packable};
+pub use pack::{F64Bits, MaybePackable, PackError, Packable};
 pub use raw::{RawCursor, RawReader, ReadRawError};
-pub use rle::{ByteCursor, IntCursor, RleCursor, StrCursor, UIntCursor};
-pub use slab::{tree, Slab, SlabTree, SlabWeight, SlabWriter, SpanTree, SpanWeight, WriteOp};
+pub use rle::{
+    ByteCursor, F64Cursor, FixedWidthCursor, IntCursor, RleCursor, StrCursor, UIntCursor,
+};
+pub use slab::{
+    tree, ColumnIndex, SharedBytes, Slab, SlabStats, SlabTree, SlabWeight, SlabWriter, SpanTree,
+    SpanWeight, WriteOp,
+};
 
 pub(crate) use std::borrow::Cow;
@@ -173,6 +173,65 @@ impl Packable for usize {
     }
 }
 
+/// An `f64` which compares by its bit pattern, so that `NaN`s form runs and round trip exactly
+#[derive(Debug, Clone, Copy, Default)]
+pub struct F64Bits(pub f64);
+
+impl PartialEq for F64Bits {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl PartialOrd for F64Bits {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.0.total_cmp(&other.0))
+    }
+}
+
+impl From<f64> for F64Bits {
+    fn from(f: f64) -> Self {
+        F64Bits(f)
+    }
+}
+
+impl From<F64Bits> for f64 {
+    fn from(f: F64Bits) -> Self {
+        f.0
+    }
+}
+
+impl Packable for F64Bits {
+    fn width(_item: &F64Bits) -> usize {
+        8
+    }
+
+    fn pack(item: &F64Bits, out: &mut Vec<u8>) {
+        out.extend_from_slice(&item.0.to_le_bytes());
+    }
+
+    fn unpack(buff: &[u8]) -> Result<(usize, Cow<'static, F64Bits>), PackError> {
+        let bytes = buff.get(..8).ok_or(PackError::BadFormat)?;
+        let val = f64::from_le_bytes(bytes.try_into().unwrap());
+        Ok((8, Cow::Owned(F64Bits(val))))
+    }
+}
+
+impl<const N: usize> Packable for [u8; N] {
+    fn width(_item: &[u8; N]) -> usize {
+        N
+    }
+
+    fn pack(item: &[u8; N], out: &mut Vec<u8>) {
+        out.extend_from_slice(item);
+    }
+
+    fn unpack(buff: &[u8]) -> Result<(usize, Cow<'_, Self>), PackError> {
+        let bytes = buff.get(..N).ok_or(PackError::BadFormat)?;
+        Ok((N, Cow::Borrowed(bytes.try_into().unwrap())))
+    }
+}
+
 impl Packable for bool {
     fn agg(item: &bool) -> Agg {
         if *item {
@@ -245,6 +304,24 @@ impl<'a, T: Packable> MaybePackable<'a, T> for T {
     }
 }
 
+impl<'a> MaybePackable<'a, F64Bits> for f64 {
+    fn maybe_packable(self) -> Option<Cow<'a, F64Bits>> {
+        Some(Cow::Owned(F64Bits(self)))
+    }
+    fn agg(&self) -> Agg {
+        Agg::default()
+    }
+}
+
+impl<'a> MaybePackable<'a, F64Bits> for Option<f64> {
+    fn maybe_packable(self) -> Option<Cow<'a, F64Bits>> {
+        self.map(|f| Cow::Owned(F64Bits(f)))
+    }
+    fn agg(&self) -> Agg {
+        Agg::default()
+    }
+}
+
 impl<'a> MaybePackable<'a, str> for Option<String> {
     fn maybe_packable(self) -> Option<Cow<'a, str>> {
         self.map(Cow::Owned)
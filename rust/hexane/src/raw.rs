@@ -102,6 +102,12 @@ impl<const B: usize> ColumnCursor for RawCursorInternal<B> {
         data.splice(range, total);
     }
 
+    // raw bytes are not values, don't bother counting them
+    fn scan_distinct(slab: &Slab) -> (Self, usize, usize) {
+        let (_, cursor) = Self::seek(slab.len(), slab);
+        (cursor, 0, 0)
+    }
+
     fn try_next<'a>(&mut self, slab: &'a [u8]) -> Result<Option<Run<'a, Self::Item>>, PackError> {
         let next_offset = self.offset + 1;
         if next_offset > slab.len() {
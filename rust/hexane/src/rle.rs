@@ -3,7 +3,7 @@ use super::columndata::ColumnData;
 use super::cursor::{ColumnCursor, HasAcc, HasPos, Run, ScanMeta, SpliceDel};
 use super::encoder::{Encoder, EncoderState, SpliceEncoder};
 use super::leb128::lebsize;
-use super::pack::{PackError, Packable};
+use super::pack::{F64Bits, PackError, Packable};
 use super::slab::{Slab, SlabWeight, SlabWriter, SpanWeight};
 use super::Cow;
 
@@ -508,6 +508,10 @@ pub type ByteCursor = RleCursor<128, [u8]>;
 pub type StrCursor = RleCursor<128, str>;
 pub type UIntCursor = RleCursor<64, u64>;
 pub type IntCursor = RleCursor<64, i64>;
+/// Floats stored as their little endian bit pattern, runs are of identical bits
+pub type F64Cursor = RleCursor<64, F64Bits>;
+/// Values of exactly `N` bytes, stored without a length prefix
+pub type FixedWidthCursor<const N: usize> = RleCursor<64, [u8; N]>;
 
 #[derive(Debug, Default)]
 pub enum RleState<'a, P: Packable + ?Sized>
@@ -856,4 +860,44 @@ pub(crate) mod tests {
         let col = IntCursor::load(&[]).unwrap();
         assert!(col.is_empty());
     }
+
+    #[test]
+    fn f64_and_fixed_width_runs() {
+        let mut col: ColumnData<F64Cursor> = ColumnData::new();
+        col.splice(0, 0, vec![1.5, 1.5, 1.5, -0.0, f64::INFINITY]);
+        col.splice(3, 0, vec![None::<f64>, None]);
+        assert_eq!(
+            col.test_dump(),
+            vec![vec![
+                ColExport::run(3, F64Bits(1.5)),
+                ColExport::Null(2),
+                ColExport::litrun(vec![F64Bits(-0.0), F64Bits(f64::INFINITY)]),
+            ]]
+        );
+        assert_eq!(col.save()[..2], [3, 0]);
+
+        let mut col: ColumnData<FixedWidthCursor<2>> = ColumnData::new();
+        col.splice(0, 0, vec![[1, 2], [1, 2], [3, 4]]);
+        assert_eq!(col.save(), vec![2, 1, 2, 127, 3, 4]);
+        assert_eq!(
+            FixedWidthCursor::<2>::load(&col.save()).unwrap().to_vec(),
+            col.to_vec()
+        );
+        assert!(FixedWidthCursor::<2>::load(&[2, 1]).is_err());
+    }
+
+    #[test]
+    fn f64_nan_forms_runs() {
+        let mut col: ColumnData<F64Cursor> = ColumnData::new();
+        col.splice(0, 0, vec![f64::NAN, f64::NAN, 2.0]);
+        assert_eq!(
+            col.test_dump(),
+            vec![vec![
+                ColExport::run(2, F64Bits(f64::NAN)),
+                ColExport::litrun(vec![F64Bits(2.0)]),
+            ]]
+        );
+        let copy = F64Cursor::load(&col.save()).unwrap();
+        assert!(copy.get(1).unwrap().unwrap().0.is_nan());
+    }
 }
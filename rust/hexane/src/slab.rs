@@ -1,5 +1,6 @@
 use super::aggregate::{Acc, Agg};
 use super::cursor::{ColumnCursor, HasAcc, HasPos, RunIter};
+use super::pack::PackError;
 use super::Cow;
 
 pub mod tree;
@@ -15,35 +16,115 @@ use std::fmt::Debug;
 use std::ops::{Index, Range};
 use std::sync::Arc;
 
-#[derive(Debug, Default, Clone, PartialEq)]
+/// Bytes which slabs can be cut from without copying them, such as a memory mapped file
+///
+/// See [`ColumnData::load_with_index`].
+///
+/// [`ColumnData::load_with_index`]: crate::ColumnData::load_with_index
+pub type SharedBytes = Arc<dyn AsRef<[u8]> + Send + Sync>;
+
+#[derive(Clone)]
+enum SlabData {
+    /// Bytes written for this slab
+    Owned(Arc<Vec<u8>>),
+    /// Bytes this slab shares with the other slabs of a column opened with an index
+    Shared(SharedBytes),
+}
+
+impl SlabData {
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            SlabData::Owned(data) => data,
+            SlabData::Shared(data) => (**data).as_ref(),
+        }
+    }
+}
+
+impl Default for SlabData {
+    fn default() -> Self {
+        SlabData::Owned(Arc::default())
+    }
+}
+
+impl Debug for SlabData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SlabData::Owned(data) => f.debug_tuple("Owned").field(data).finish(),
+            SlabData::Shared(data) => f.debug_tuple("Shared").field(&(**data).as_ref()).finish(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct Slab {
-    data: Arc<Vec<u8>>,
+    data: SlabData,
     //data: Vec<u8>,
+    // the part of `data` which is this slab's, slabs opened with an index share one buffer
+    range: Range<usize>,
     len: usize,
     acc: Acc,
     min: Agg,
     max: Agg,
     abs: i64,
+    distinct: usize,
+    nulls: usize,
+}
+
+/// Statistics about a single slab of a column, see [`ColumnData::slab_stats`]
+///
+/// [`ColumnData::slab_stats`]: crate::ColumnData::slab_stats
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SlabStats {
+    /// The number of items in the slab
+    pub len: usize,
+    /// The number of encoded bytes
+    pub bytes: usize,
+    pub min: Agg,
+    pub max: Agg,
+    /// The number of distinct non-null values, for delta encoded columns this
+    /// is an upper bound
+    pub distinct: usize,
+    pub nulls: usize,
+}
+
+impl PartialEq for Slab {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+            && self.len == other.len
+            && self.acc == other.acc
+            && self.min == other.min
+            && self.max == other.max
+            && self.abs == other.abs
+            && self.distinct == other.distinct
+            && self.nulls == other.nulls
+    }
 }
 
 impl Index<Range<usize>> for Slab {
     type Output = [u8];
 
     fn index(&self, index: Range<usize>) -> &Self::Output {
-        &self.data[index]
+        &self.as_slice()[index]
     }
 }
 
 impl Slab {
     pub(crate) fn new(data: Vec<u8>, len: usize, acc: Acc, abs: i64) -> Self {
-        let data = Arc::new(data);
+        let range = 0..data.len();
+        Self::with_data(SlabData::Owned(Arc::new(data)), range, len, acc, abs)
+    }
+
+    fn with_data(data: SlabData, range: Range<usize>, len: usize, acc: Acc, abs: i64) -> Self {
         Slab {
             data,
+            range,
             len,
             acc,
             abs,
             min: Agg::default(),
             max: Agg::default(),
+            distinct: 0,
+            nulls: 0,
         }
     }
 
@@ -52,6 +133,39 @@ impl Slab {
         self.max = new_max;
     }
 
+    pub(crate) fn set_distinct(&mut self, distinct: usize, nulls: usize) {
+        self.distinct = distinct;
+        self.nulls = nulls;
+    }
+
+    pub fn distinct(&self) -> usize {
+        self.distinct
+    }
+
+    pub fn nulls(&self) -> usize {
+        self.nulls
+    }
+
+    pub fn stats(&self) -> SlabStats {
+        SlabStats {
+            len: self.len,
+            bytes: self.byte_len(),
+            min: self.min,
+            max: self.max,
+            distinct: self.distinct,
+            nulls: self.nulls,
+        }
+    }
+
+    // the aggregate every item in the slab shares, if there is one
+    pub(crate) fn uniform_agg(&self) -> Agg {
+        if self.nulls == 0 && self.distinct == 1 {
+            self.min
+        } else {
+            Agg::default()
+        }
+    }
+
     pub fn abs(&self) -> i64 {
         self.abs
     }
@@ -81,11 +195,31 @@ impl Slab {
     }
 
     pub fn as_slice(&self) -> &[u8] {
-        &self.data
+        &self.data.as_slice()[self.range.clone()]
     }
 
     pub fn byte_len(&self) -> usize {
-        self.data.len()
+        self.range.len()
+    }
+
+    /// The heap memory held by the slab's data, including the allocation for its reference counts
+    ///
+    /// Slabs which share a buffer each count their share of it. Bytes shared from a
+    /// [`SharedBytes`] are counted as the slab's bytes, whatever holds them.
+    pub fn heap_size(&self) -> usize {
+        match &self.data {
+            SlabData::Owned(data) => {
+                let size = 2 * std::mem::size_of::<usize>()
+                    + std::mem::size_of::<Vec<u8>>()
+                    + data.capacity();
+                if data.is_empty() {
+                    size
+                } else {
+                    size * self.range.len() / data.len()
+                }
+            }
+            SlabData::Shared(_) => self.range.len(),
+        }
     }
 
     pub fn is_empty(&self) -> bool {
@@ -101,6 +235,177 @@ impl Slab {
     }
 }
 
+/// The boundaries and aggregates of the slabs of a column
+///
+/// Together with the slab bytes written by [`ColumnData::save_with_index`] this is enough to
+/// rebuild the column's [`SlabTree`] without decoding a single run, see
+/// [`ColumnData::load_with_index`].
+///
+/// [`ColumnData::save_with_index`]: crate::ColumnData::save_with_index
+/// [`ColumnData::load_with_index`]: crate::ColumnData::load_with_index
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ColumnIndex {
+    pub(crate) slabs: Vec<SlabSummary>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct SlabSummary {
+    pub(crate) bytes: usize,
+    pub(crate) len: usize,
+    pub(crate) acc: Acc,
+    pub(crate) min: Agg,
+    pub(crate) max: Agg,
+    pub(crate) abs: i64,
+    pub(crate) distinct: usize,
+    pub(crate) nulls: usize,
+}
+
+/// The version of the format written by [`ColumnIndex::save`]
+const INDEX_VERSION: u8 = 1;
+
+impl ColumnIndex {
+    pub(crate) fn new<'a, I: Iterator<Item = &'a Slab>>(slabs: I) -> Self {
+        let slabs = slabs
+            .map(|s| SlabSummary {
+                bytes: s.byte_len(),
+                len: s.len(),
+                acc: s.acc(),
+                min: s.min(),
+                max: s.max(),
+                abs: s.abs(),
+                distinct: s.distinct(),
+                nulls: s.nulls(),
+            })
+            .collect();
+        ColumnIndex { slabs }
+    }
+
+    /// The number of slabs
+    pub fn len(&self) -> usize {
+        self.slabs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slabs.is_empty()
+    }
+
+    /// The number of items in the column
+    pub fn items(&self) -> usize {
+        self.slabs.iter().map(|s| s.len).sum()
+    }
+
+    /// The number of bytes of slab data the index describes
+    pub fn byte_len(&self) -> usize {
+        self.slabs.iter().map(|s| s.bytes).sum()
+    }
+
+    pub fn save(&self) -> Vec<u8> {
+        let mut out = vec![];
+        self.save_to(&mut out);
+        out
+    }
+
+    pub fn save_to(&self, out: &mut Vec<u8>) -> Range<usize> {
+        let start = out.len();
+        out.push(INDEX_VERSION);
+        leb128::write::unsigned(out, self.slabs.len() as u64).unwrap();
+        for s in &self.slabs {
+            leb128::write::unsigned(out, s.bytes as u64).unwrap();
+            leb128::write::unsigned(out, s.len as u64).unwrap();
+            leb128::write::unsigned(out, s.acc.as_u64()).unwrap();
+            leb128::write::unsigned(out, s.min.as_u64()).unwrap();
+            leb128::write::unsigned(out, s.max.as_u64()).unwrap();
+            leb128::write::signed(out, s.abs).unwrap();
+            leb128::write::unsigned(out, s.distinct as u64).unwrap();
+            leb128::write::unsigned(out, s.nulls as u64).unwrap();
+        }
+        start..out.len()
+    }
+
+    pub fn load(mut data: &[u8]) -> Result<Self, PackError> {
+        match data.split_first() {
+            Some((&INDEX_VERSION, rest)) => data = rest,
+            Some((version, _)) => {
+                return Err(PackError::invalid_value(
+                    "column index",
+                    format!("unknown version {}", version),
+                ))
+            }
+            None => return Err(PackError::BadFormat),
+        }
+        let num_slabs = leb128::read::unsigned(&mut data)?;
+        let mut slabs = Vec::new();
+        for _ in 0..num_slabs {
+            slabs.push(SlabSummary {
+                bytes: leb128::read::unsigned(&mut data)? as usize,
+                len: leb128::read::unsigned(&mut data)? as usize,
+                acc: Acc::from(leb128::read::unsigned(&mut data)?),
+                min: Agg::from(leb128::read::unsigned(&mut data)?),
+                max: Agg::from(leb128::read::unsigned(&mut data)?),
+                abs: leb128::read::signed(&mut data)?,
+                distinct: leb128::read::unsigned(&mut data)? as usize,
+                nulls: leb128::read::unsigned(&mut data)? as usize,
+            });
+        }
+        if !data.is_empty() {
+            return Err(PackError::BadFormat);
+        }
+        Ok(ColumnIndex { slabs })
+    }
+
+    /// Cut the slabs the index describes out of `data` without copying it
+    ///
+    /// The offsets and lengths are checked against `data` and each other but the runs in the
+    /// slabs are not decoded, so their min and max are as the index gives them.
+    pub(crate) fn slabs(&self, shared: SharedBytes) -> Result<Vec<Slab>, PackError> {
+        let data = (*shared).as_ref();
+        let invalid = |error: String| PackError::invalid_value("column index", error);
+        let mut offset = 0_usize;
+        let mut slabs = Vec::with_capacity(self.slabs.len());
+        for (i, s) in self.slabs.iter().enumerate() {
+            let end = offset
+                .checked_add(s.bytes)
+                .filter(|end| *end <= data.len())
+                .ok_or_else(|| invalid(format!("slab {} runs past the end of the data", i)))?;
+            if s.len > 0 && s.bytes == 0 {
+                return Err(invalid(format!(
+                    "slab {} has {} items but no bytes",
+                    i, s.len
+                )));
+            }
+            if s.distinct.saturating_add(s.nulls) > s.len {
+                return Err(invalid(format!("slab {} has more values than items", i)));
+            }
+            let mut slab = Slab::with_data(
+                SlabData::Shared(shared.clone()),
+                offset..end,
+                s.len,
+                s.acc,
+                s.abs,
+            );
+            slab.set_min_max(s.min, s.max);
+            slabs.push(slab);
+            offset = end;
+        }
+        if offset != data.len() {
+            return Err(invalid(format!(
+                "{} bytes indexed but {} given",
+                offset,
+                data.len()
+            )));
+        }
+        if self
+            .slabs
+            .iter()
+            .try_fold(0_usize, |items, s| items.checked_add(s.len))
+            .is_none()
+        {
+            return Err(invalid("too many items".to_string()));
+        }
+        Ok(slabs)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub struct SlabWeight {
     pub(crate) pos: usize,
@@ -220,4 +525,32 @@ pub(crate) mod tests {
         assert!(!baseline.clone().maybe_sub(&min_lt));
         assert!(baseline.clone().maybe_sub(&min_none));
     }
+
+    #[test]
+    fn distinct_counts_follow_splices() {
+        use crate::{ColumnData, UIntCursor};
+
+        let mut col = ColumnData::<UIntCursor>::new();
+        col.splice(0, 0, vec![7; 20]);
+        assert_eq!(
+            col.slabs.get(0).map(|s| (s.distinct(), s.nulls())),
+            Some((1, 0))
+        );
+        col.splice(5, 0, vec![Some(1), None, Some(2), Some(7)]);
+        assert_eq!(
+            col.slabs.get(0).map(|s| (s.distinct(), s.nulls())),
+            Some((3, 1))
+        );
+        col.splice(5, 4, Vec::<u64>::new());
+        assert_eq!(
+            col.slabs.get(0).map(|s| (s.distinct(), s.nulls())),
+            Some((1, 0))
+        );
+        for slab in col.slabs.iter() {
+            assert_eq!(
+                UIntCursor::count_distinct(slab),
+                (slab.distinct(), slab.nulls())
+            );
+        }
+    }
 }
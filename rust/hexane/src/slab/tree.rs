@@ -97,6 +97,12 @@ impl<T: Clone + Debug + Default, W: SpanWeight<T>> SpanTree<T, W> {
         self.len() == 0
     }
 
+    /// The heap memory held by the nodes of the tree, not counting anything the elements
+    /// themselves point to
+    pub fn heap_size(&self) -> usize {
+        self.root_node.as_ref().map_or(0, |n| n.heap_size())
+    }
+
     pub fn to_vec(&self) -> Vec<T> {
         self.iter().cloned().collect()
     }
@@ -317,6 +323,12 @@ impl<T: Clone + Debug + Default, W: SpanWeight<T>> SpanTree<T, W> {
 }
 
 impl<T: Clone + Debug + Default, W: SpanWeight<T>> TreeNode<T, W> {
+    fn heap_size(&self) -> usize {
+        self.elements.capacity() * mem::size_of::<T>()
+            + self.children.capacity() * mem::size_of::<Self>()
+            + self.children.iter().map(|c| c.heap_size()).sum::<usize>()
+    }
+
     fn new() -> Self {
         Self {
             elements: Vec::new(),
@@ -281,6 +281,11 @@ impl<'a, P: Packable + ?Sized> Writer<'a, P> for SlabWriter<'a, P> {
         self.push(WriteAction::Raw(data), items, items);
     }
 
+    fn flush_block(&mut self, block: Vec<u8>, items: usize) {
+        let width = block.len();
+        self.push(WriteAction::Raw(Cow::Owned(block)), items, width);
+    }
+
     fn flush_null(&mut self, count: usize) {
         let width = 1 + ulebsize(count as u64) as usize;
         self.push(WriteAction::NullRun(count as u64), count, width);
@@ -454,7 +459,7 @@ impl<'a, P: Packable + ?Sized> SlabWriter<'a, P> {
 
     pub fn into_column<C: ColumnCursor>(self, length: usize) -> ColumnData<C> {
         let mut slabs = self.finish();
-        C::compute_min_max(&mut slabs);
+        C::compute_stats(&mut slabs);
         ColumnData::init(length, SlabTree::load(slabs))
     }
 
@@ -106,3 +106,132 @@ impl<P: Packable + ?Sized> ColExport<P> {
         Self::Run(count, item.borrow().to_owned())
     }
 }
+
+mod proptests {
+    use super::super::pack::F64Bits;
+    use super::super::rle::RleCursor;
+    use crate::{ColumnCursor, ColumnData, MaybePackable};
+    use proptest::prelude::*;
+
+    fn arb_f64() -> impl Strategy<Value = Option<F64Bits>> {
+        // a small pool of values so that runs are common
+        prop_oneof![
+            Just(None),
+            prop::sample::select(vec![0.0, -0.0, 0.1, f64::NAN, f64::MAX, f64::MIN_POSITIVE])
+                .prop_map(|f| Some(F64Bits(f))),
+            any::<f64>().prop_map(|f| Some(F64Bits(f))),
+        ]
+    }
+
+    fn arb_uint() -> impl Strategy<Value = Option<u64>> {
+        prop_oneof![
+            Just(None),
+            (0..3_u64).prop_map(Some),
+            (0..1000_u64).prop_map(Some)
+        ]
+    }
+
+    fn arb_fixed() -> impl Strategy<Value = Option<[u8; 3]>> {
+        prop_oneof![
+            Just(None),
+            (0..3_u8).prop_map(|b| Some([b; 3])),
+            any::<[u8; 3]>().prop_map(Some),
+        ]
+    }
+
+    /// Splices of `(index, del, values, seek)`, where `seek` picks a position to seek to
+    /// afterwards. Indexes and positions are reduced modulo the length of the column.
+    #[allow(clippy::type_complexity)]
+    fn arb_splices<T: std::fmt::Debug + Clone>(
+        values: impl Strategy<Value = T>,
+    ) -> impl Strategy<Value = Vec<(usize, usize, Vec<T>, usize)>> {
+        proptest::collection::vec(
+            (
+                any::<usize>(),
+                // deletes long enough to span several slabs
+                prop_oneof![0..4_usize, 0..40_usize],
+                proptest::collection::vec(values, 0..20),
+                any::<usize>(),
+            ),
+            1..40,
+        )
+    }
+
+    /// Splice into `col`, deleting slab by slab since a single splice can only delete within
+    /// one slab
+    fn splice<C, T>(col: &mut ColumnData<C>, index: usize, mut del: usize, values: Vec<Option<T>>)
+    where
+        C: ColumnCursor,
+        for<'a> Option<T>: MaybePackable<'a, C::Item>,
+    {
+        loop {
+            let mut start = 0;
+            let mut in_slab = del;
+            for slab in col.slabs.iter() {
+                if index < start + slab.len() {
+                    in_slab = start + slab.len() - index;
+                    break;
+                }
+                start += slab.len();
+            }
+            if in_slab >= del {
+                col.splice(index, del, values);
+                return;
+            }
+            col.splice::<Option<T>, _>(index, in_slab, vec![]);
+            del -= in_slab;
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn check_splices<C, T>(splices: Vec<(usize, usize, Vec<Option<T>>, usize)>)
+    where
+        C: ColumnCursor<Export = Option<T>>,
+        C::Item: ToOwned<Owned = T>,
+        T: Clone + PartialEq + std::fmt::Debug,
+        for<'a> Option<T>: MaybePackable<'a, C::Item>,
+    {
+        let mut col = ColumnData::<C>::new();
+        let mut vec: Vec<Option<T>> = vec![];
+        for (index, del, values, seek) in splices {
+            let index = index % (vec.len() + 1);
+            let del = del.min(vec.len() - index);
+            vec.splice(index..index + del, values.clone());
+            splice(&mut col, index, del, values);
+            assert_eq!(col.to_vec(), vec);
+            assert_eq!(col.len(), vec.len());
+
+            // seeking from the start and iterating a range both land on the right value
+            let seek = seek % (vec.len() + 1);
+            let mut iter = col.iter();
+            iter.advance_to(seek);
+            assert_eq!(iter.pos(), seek);
+            assert_eq!(iter.to_vec(), vec[seek..]);
+            let end = seek + (vec.len() - seek) / 2;
+            assert_eq!(col.iter_range(seek..end).to_vec(), vec[seek..end]);
+        }
+        let copy = C::load(&col.save()).unwrap();
+        assert_eq!(copy.to_vec(), vec);
+        for (index, value) in vec.iter().enumerate() {
+            let found = col.get(index).unwrap().map(|v| v.into_owned());
+            assert_eq!(found.as_ref(), value.as_ref());
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn proptest_f64_splice(splices in arb_splices(arb_f64())) {
+            check_splices::<RleCursor<16, F64Bits>, F64Bits>(splices);
+        }
+
+        #[test]
+        fn proptest_uint_splice(splices in arb_splices(arb_uint())) {
+            check_splices::<RleCursor<16, u64>, u64>(splices);
+        }
+
+        #[test]
+        fn proptest_fixed_width_splice(splices in arb_splices(arb_fixed())) {
+            check_splices::<RleCursor<16, [u8; 3]>, [u8; 3]>(splices);
+        }
+    }
+}